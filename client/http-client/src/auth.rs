@@ -0,0 +1,272 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Bearer token authentication.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use futures_util::future::{BoxFuture, FutureExt, Shared};
+use http_body_util::{BodyExt, Full};
+use hyper::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+use jsonrpsee_core::BoxError;
+use jsonrpsee_core::http_helpers::HttpError;
+use tower::{Layer, Service, ServiceExt};
+
+use crate::transport::Error;
+use crate::{HttpBody, HttpRequest, HttpResponse};
+
+type TokenFuture = Pin<Box<dyn Future<Output = Result<String, BoxError>> + Send>>;
+type TokenFn = dyn Fn() -> TokenFuture + Send + Sync;
+type SharedFetch = Shared<BoxFuture<'static, Result<CachedToken, FetchError>>>;
+
+/// Layer that applies [`BearerAuth`] which sends a bearer token in the `Authorization` header of each request.
+///
+/// The token is fetched with the provided closure and cached until it's about to expire,
+/// according to the `exp` claim when the token is a JWT, or until the server rejects it with
+/// `401 Unauthorized`, in which case a new token is fetched and the request is sent once more.
+/// Concurrent requests wait for the same fetch rather than fetching a token each.
+///
+/// Clones of the layer share the cached token, so the same layer can be used to set the
+/// headers of the WebSocket handshake with [`BearerAuthLayer::header_value`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use jsonrpsee_http_client::{BearerAuthLayer, HttpClient};
+///
+/// async fn fetch_token() -> Result<String, jsonrpsee_core::BoxError> {
+///     Ok("eyJhbGciOiJIUzI1NiJ9...".to_string())
+/// }
+///
+/// let auth = BearerAuthLayer::new(fetch_token);
+/// let client = HttpClient::builder()
+///     .set_http_middleware(tower::ServiceBuilder::new().layer(auth))
+///     .build("http://localhost:9944")
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct BearerAuthLayer {
+	fetch: Arc<TokenFn>,
+	state: Arc<Mutex<TokenState>>,
+	refresh_before: Duration,
+}
+
+impl std::fmt::Debug for BearerAuthLayer {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("BearerAuthLayer").field("refresh_before", &self.refresh_before).finish_non_exhaustive()
+	}
+}
+
+impl BearerAuthLayer {
+	/// Create a new [`BearerAuthLayer`] which fetches the tokens with `fetch`.
+	pub fn new<F, Fut>(fetch: F) -> Self
+	where
+		F: Fn() -> Fut + Send + Sync + 'static,
+		Fut: Future<Output = Result<String, BoxError>> + Send + 'static,
+	{
+		Self {
+			fetch: Arc::new(move || Box::pin(fetch())),
+			state: Arc::new(Mutex::new(TokenState::Empty)),
+			refresh_before: Duration::from_secs(30),
+		}
+	}
+
+	/// Fetch a new token when the cached one expires within `margin`.
+	///
+	/// Default: 30 seconds.
+	pub fn refresh_before(mut self, margin: Duration) -> Self {
+		self.refresh_before = margin;
+		self
+	}
+
+	/// Get the value of the `Authorization` header, fetching a new token if needed.
+	pub async fn header_value(&self) -> Result<HeaderValue, BoxError> {
+		let fetch = {
+			let mut state = self.state.lock().expect("lock poisoned; qed");
+			match &*state {
+				TokenState::Ready(cached)
+					if cached.expires_at.is_none_or(|exp| SystemTime::now() + self.refresh_before < exp) =>
+				{
+					return Ok(cached.header.clone());
+				}
+				TokenState::Fetching(fetch) => fetch.clone(),
+				_ => {
+					let fetch = (self.fetch)();
+					let fetch = async move {
+						let token = fetch.await.map_err(|e| FetchError(Arc::new(e)))?;
+						let mut header = HeaderValue::try_from(format!("Bearer {token}"))
+							.map_err(|e| FetchError(Arc::new(e.into())))?;
+						header.set_sensitive(true);
+						Ok(CachedToken { header, expires_at: jwt_expiry(&token) })
+					}
+					.boxed()
+					.shared();
+					*state = TokenState::Fetching(fetch.clone());
+					fetch
+				}
+			}
+		};
+
+		// The lock isn't held while fetching, the requests made meanwhile wait for the same fetch.
+		let result = fetch.clone().await;
+
+		let mut state = self.state.lock().expect("lock poisoned; qed");
+		if matches!(&*state, TokenState::Fetching(current) if current.ptr_eq(&fetch)) {
+			*state = match &result {
+				Ok(cached) => TokenState::Ready(cached.clone()),
+				Err(_) => TokenState::Empty,
+			};
+		}
+
+		result.map(|cached| cached.header).map_err(Into::into)
+	}
+
+	/// Drop the cached token if it's still `rejected`.
+	fn invalidate(&self, rejected: &HeaderValue) {
+		let mut state = self.state.lock().expect("lock poisoned; qed");
+		if matches!(&*state, TokenState::Ready(cached) if cached.header == rejected) {
+			*state = TokenState::Empty;
+		}
+	}
+}
+
+impl<S> Layer<S> for BearerAuthLayer {
+	type Service = BearerAuth<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		BearerAuth { inner, auth: self.clone() }
+	}
+}
+
+/// Middleware that sends a bearer token with each request, see [`BearerAuthLayer`] for more details.
+#[derive(Debug, Clone)]
+pub struct BearerAuth<S> {
+	inner: S,
+	auth: BearerAuthLayer,
+}
+
+impl<S, B> Service<HttpRequest> for BearerAuth<S>
+where
+	S: Service<HttpRequest, Response = HttpResponse<B>, Error = Error> + Clone + Send + 'static,
+	S::Future: Send,
+	B: Send + 'static,
+{
+	type Response = S::Response;
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx)
+	}
+
+	fn call(&mut self, request: HttpRequest) -> Self::Future {
+		// The service that was polled ready must be the one that is called.
+		let clone = self.inner.clone();
+		let mut inner = std::mem::replace(&mut self.inner, clone);
+		let auth = self.auth.clone();
+
+		Box::pin(async move {
+			// The body is buffered to send the request again if the token is rejected.
+			let (mut parts, body) = request.into_parts();
+			let body = body.collect().await.map_err(|e| Error::Http(HttpError::Stream(e)))?.to_bytes();
+
+			let header = auth.header_value().await.map_err(|e| Error::Http(HttpError::Stream(e)))?;
+			parts.headers.insert(AUTHORIZATION, header.clone());
+
+			let request = HttpRequest::from_parts(parts.clone(), HttpBody::new(Full::new(body.clone())));
+			let response = inner.call(request).await?;
+			if response.status() != StatusCode::UNAUTHORIZED {
+				return Ok(response);
+			}
+
+			auth.invalidate(&header);
+			let header = auth.header_value().await.map_err(|e| Error::Http(HttpError::Stream(e)))?;
+			parts.headers.insert(AUTHORIZATION, header);
+
+			let request = HttpRequest::from_parts(parts, HttpBody::new(Full::new(body)));
+			inner.ready().await?.call(request).await
+		})
+	}
+}
+
+#[derive(Debug)]
+enum TokenState {
+	Empty,
+	Fetching(SharedFetch),
+	Ready(CachedToken),
+}
+
+/// Error of a token fetch, which is shared by the requests that wait for it.
+#[derive(Debug, Clone)]
+struct FetchError(Arc<BoxError>);
+
+impl std::fmt::Display for FetchError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		self.0.fmt(f)
+	}
+}
+
+impl std::error::Error for FetchError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		self.0.source()
+	}
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+	header: HeaderValue,
+	expires_at: Option<SystemTime>,
+}
+
+/// Read the `exp` claim of a JWT, without verifying the token.
+fn jwt_expiry(token: &str) -> Option<SystemTime> {
+	let payload = token.split('.').nth(1)?;
+	let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+	let claims: serde_json::Value = serde_json::from_slice(&payload).ok()?;
+
+	Some(UNIX_EPOCH + Duration::from_secs(claims.get("exp")?.as_u64()?))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::jwt_expiry;
+	use std::time::{Duration, UNIX_EPOCH};
+
+	#[test]
+	fn jwt_expiry_works() {
+		// {"alg":"HS256"}.{"exp":1700000000}
+		let token = "eyJhbGciOiJIUzI1NiJ9.eyJleHAiOjE3MDAwMDAwMDB9.c2ln";
+		assert_eq!(jwt_expiry(token), Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)));
+
+		assert_eq!(jwt_expiry("opaque-token"), None);
+		assert_eq!(jwt_expiry("a.bm90IGpzb24.c"), None);
+	}
+}
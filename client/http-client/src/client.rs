@@ -30,7 +30,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use crate::rpc_service::RpcService;
-use crate::transport::{self, Error as TransportError, HttpBackend, HttpTransportClientBuilder};
+use crate::transport::{self, Error as TransportError, Http2Config, HttpBackend, HttpTransportClientBuilder};
 use crate::{HttpRequest, HttpResponse};
 use hyper::body::Bytes;
 use hyper::http::{Extensions, HeaderMap};
@@ -50,7 +50,7 @@ use tower::layer::util::Identity;
 use tower::{Layer, Service};
 
 #[cfg(feature = "tls")]
-use crate::{CertificateStore, CustomCertStore};
+use crate::{CertificateStore, ClientCertificate, CustomCertStore};
 
 type Logger = tower::layer::util::Stack<RpcLoggerLayer, tower::layer::util::Identity>;
 
@@ -84,12 +84,17 @@ pub struct HttpClientBuilder<HttpMiddleware = Identity, RpcMiddleware = Logger>
 	request_timeout: Duration,
 	#[cfg(feature = "tls")]
 	certificate_store: CertificateStore,
+	#[cfg(feature = "tls")]
+	client_certificate: Option<ClientCertificate>,
 	id_kind: IdKind,
 	headers: HeaderMap,
 	service_builder: tower::ServiceBuilder<HttpMiddleware>,
 	rpc_middleware: RpcServiceBuilder<RpcMiddleware>,
 	tcp_no_delay: bool,
 	max_concurrent_requests: Option<usize>,
+	http2_config: Option<Http2Config>,
+	pool_max_idle_per_host: Option<usize>,
+	pool_idle_timeout: Option<Duration>,
 }
 
 impl<HttpMiddleware, RpcMiddleware> HttpClientBuilder<HttpMiddleware, RpcMiddleware> {
@@ -188,6 +193,34 @@ impl<HttpMiddleware, RpcMiddleware> HttpClientBuilder<HttpMiddleware, RpcMiddlew
 		self
 	}
 
+	/// Authenticate with a client certificate when the server requests one (mutual TLS).
+	///
+	/// The certificate is used with both the native and a custom certificate store
+	/// and replaces the client authentication of the custom store.
+	///
+	/// If the server rejects the certificate, the request fails with
+	/// [`transport::Error::ClientCertificateRejected`](crate::transport::Error::ClientCertificateRejected).
+	///
+	/// # Optional
+	///
+	/// This requires the optional `tls` feature.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jsonrpsee_http_client::{ClientCertificate, HttpClientBuilder};
+	///
+	/// let cert = std::fs::read("client.pem").unwrap();
+	/// let key = std::fs::read("client.key").unwrap();
+	///
+	/// let client_builder = HttpClientBuilder::new().with_client_certificate(ClientCertificate::from_pem(&cert, &key).unwrap());
+	/// ```
+	#[cfg(feature = "tls")]
+	pub fn with_client_certificate(mut self, cert: ClientCertificate) -> Self {
+		self.client_certificate = Some(cert);
+		self
+	}
+
 	/// Configure the data type of the request object ID (default is number).
 	pub fn id_format(mut self, id_kind: IdKind) -> Self {
 		self.id_kind = id_kind;
@@ -210,11 +243,53 @@ impl<HttpMiddleware, RpcMiddleware> HttpClientBuilder<HttpMiddleware, RpcMiddlew
 		self
 	}
 
+	/// Use HTTP/2 with the given settings.
+	///
+	/// For `http` targets the client talks HTTP/2 with prior knowledge (h2c) and
+	/// the server must support it. For `https` targets the protocol is negotiated with ALPN
+	/// and the settings apply when the server selects HTTP/2.
+	///
+	/// Concurrent requests are multiplexed on the same connection with HTTP/2.
+	///
+	/// Default: HTTP/1.1 for `http` targets.
+	///
+	/// # Example
+	///
+	/// ```no_run
+	/// use std::time::Duration;
+	/// use jsonrpsee_http_client::{HttpClientBuilder, Http2Config};
+	///
+	/// let http2_cfg = Http2Config::new().keep_alive_interval(Duration::from_secs(30)).initial_max_send_streams(256);
+	/// let client = HttpClientBuilder::default().enable_http2(http2_cfg).build("http://localhost").unwrap();
+	/// ```
+	pub fn enable_http2(mut self, config: Http2Config) -> Self {
+		self.http2_config = Some(config);
+		self
+	}
+
+	/// Set the maximum number of idle connections per host kept in the connection pool.
+	///
+	/// Default: unlimited.
+	pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+		self.pool_max_idle_per_host = Some(max);
+		self
+	}
+
+	/// Set the duration after which idle connections in the pool are closed.
+	///
+	/// Default: 90 seconds.
+	pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+		self.pool_idle_timeout = Some(timeout);
+		self
+	}
+
 	/// Set the RPC middleware.
 	pub fn set_rpc_middleware<T>(self, rpc_builder: RpcServiceBuilder<T>) -> HttpClientBuilder<HttpMiddleware, T> {
 		HttpClientBuilder {
 			#[cfg(feature = "tls")]
 			certificate_store: self.certificate_store,
+			#[cfg(feature = "tls")]
+			client_certificate: self.client_certificate,
 			id_kind: self.id_kind,
 			headers: self.headers,
 			max_request_size: self.max_request_size,
@@ -224,6 +299,9 @@ impl<HttpMiddleware, RpcMiddleware> HttpClientBuilder<HttpMiddleware, RpcMiddlew
 			request_timeout: self.request_timeout,
 			tcp_no_delay: self.tcp_no_delay,
 			max_concurrent_requests: self.max_concurrent_requests,
+			http2_config: self.http2_config,
+			pool_max_idle_per_host: self.pool_max_idle_per_host,
+			pool_idle_timeout: self.pool_idle_timeout,
 		}
 	}
 
@@ -235,6 +313,8 @@ impl<HttpMiddleware, RpcMiddleware> HttpClientBuilder<HttpMiddleware, RpcMiddlew
 		HttpClientBuilder {
 			#[cfg(feature = "tls")]
 			certificate_store: self.certificate_store,
+			#[cfg(feature = "tls")]
+			client_certificate: self.client_certificate,
 			id_kind: self.id_kind,
 			headers: self.headers,
 			max_request_size: self.max_request_size,
@@ -244,6 +324,9 @@ impl<HttpMiddleware, RpcMiddleware> HttpClientBuilder<HttpMiddleware, RpcMiddlew
 			request_timeout: self.request_timeout,
 			tcp_no_delay: self.tcp_no_delay,
 			max_concurrent_requests: self.max_concurrent_requests,
+			http2_config: self.http2_config,
+			pool_max_idle_per_host: self.pool_max_idle_per_host,
+			pool_idle_timeout: self.pool_idle_timeout,
 		}
 	}
 }
@@ -266,11 +349,16 @@ where
 			request_timeout,
 			#[cfg(feature = "tls")]
 			certificate_store,
+			#[cfg(feature = "tls")]
+			client_certificate,
 			id_kind,
 			headers,
 			service_builder,
 			tcp_no_delay,
 			rpc_middleware,
+			http2_config,
+			pool_max_idle_per_host,
+			pool_idle_timeout,
 			..
 		} = self;
 
@@ -279,9 +367,14 @@ where
 			max_response_size,
 			headers,
 			tcp_no_delay,
+			http2_config,
+			pool_max_idle_per_host,
+			pool_idle_timeout,
 			service_builder,
 			#[cfg(feature = "tls")]
 			certificate_store,
+			#[cfg(feature = "tls")]
+			client_certificate,
 		}
 		.build(target)
 		.map_err(|e| Error::Transport(e.into()))?;
@@ -307,12 +400,17 @@ impl Default for HttpClientBuilder {
 			request_timeout: Duration::from_secs(60),
 			#[cfg(feature = "tls")]
 			certificate_store: CertificateStore::Native,
+			#[cfg(feature = "tls")]
+			client_certificate: None,
 			id_kind: IdKind::Number,
 			headers: HeaderMap::new(),
 			service_builder: tower::ServiceBuilder::new(),
 			rpc_middleware: RpcServiceBuilder::default().rpc_logger(1024),
 			tcp_no_delay: true,
 			max_concurrent_requests: None,
+			http2_config: None,
+			pool_max_idle_per_host: None,
+			pool_idle_timeout: None,
 		}
 	}
 }
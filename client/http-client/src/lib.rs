@@ -35,6 +35,7 @@
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+mod auth;
 mod client;
 mod rpc_service;
 
@@ -44,9 +45,11 @@ pub mod transport;
 #[cfg(test)]
 mod tests;
 
+pub use auth::{BearerAuth, BearerAuthLayer};
 pub use client::{HttpClient, HttpClientBuilder};
 pub use hyper::http::{HeaderMap, HeaderValue};
 pub use jsonrpsee_types as types;
+pub use transport::Http2Config;
 
 /// Default HTTP body for the client.
 pub type HttpBody = jsonrpsee_core::http_helpers::Body;
@@ -59,6 +62,9 @@ pub type HttpResponse<T = HttpBody> = jsonrpsee_core::http_helpers::Response<T>;
 #[cfg(feature = "tls")]
 pub type CustomCertStore = rustls::ClientConfig;
 
+#[cfg(feature = "tls")]
+pub use jsonrpsee_client_transport::tls::ClientCertificate;
+
 #[cfg(feature = "tls")]
 // rustls needs the concrete `ClientConfig` type so we can't Box it here.
 #[allow(clippy::large_enum_variant)]
@@ -4,7 +4,7 @@ use hyper::body::Bytes;
 use jsonrpsee_core::{
 	BoxError, JsonRawValue,
 	client::{Error, MiddlewareBatchResponse, MiddlewareMethodResponse, MiddlewareNotifResponse},
-	middleware::{Batch, Notification, Request, RpcServiceT},
+	middleware::{Batch, Notification, Request, RpcServiceT, layer::TraceContext},
 };
 use jsonrpsee_types::Response;
 use tower::Service;
@@ -43,7 +43,8 @@ where
 
 		async move {
 			let raw = serde_json::to_string(&request)?;
-			let bytes = service.send_and_read_body(raw).await.map_err(|e| Error::Transport(e.into()))?;
+			let trace_context = request.extensions.get::<TraceContext>();
+			let bytes = service.send_and_read_body(raw, trace_context).await.map_err(|e| Error::Transport(e.into()))?;
 			let mut rp: Response<Box<JsonRawValue>> = serde_json::from_slice(&bytes)?;
 			rp.extensions = request.extensions;
 
@@ -51,12 +52,13 @@ where
 		}
 	}
 
-	fn batch<'a>(&self, batch: Batch<'a>) -> impl Future<Output = Self::BatchResponse> + Send + 'a {
+	fn batch<'a>(&self, mut batch: Batch<'a>) -> impl Future<Output = Self::BatchResponse> + Send + 'a {
 		let service = self.service.clone();
 
 		async move {
 			let raw = serde_json::to_string(&batch)?;
-			let bytes = service.send_and_read_body(raw).await.map_err(|e| Error::Transport(e.into()))?;
+			let trace_context = batch.extensions().get::<TraceContext>();
+			let bytes = service.send_and_read_body(raw, trace_context).await.map_err(|e| Error::Transport(e.into()))?;
 			let rp: Vec<_> = serde_json::from_slice::<Vec<Response<Box<JsonRawValue>>>>(&bytes)?
 				.into_iter()
 				.map(|r| r.into_owned().into())
@@ -74,7 +76,7 @@ where
 
 		async move {
 			let raw = serde_json::to_string(&notif)?;
-			service.send(raw).await.map_err(|e| Error::Transport(e.into()))?;
+			service.send(raw, notif.extensions.get::<TraceContext>()).await.map_err(|e| Error::Transport(e.into()))?;
 			Ok(notif.extensions.into())
 		}
 	}
@@ -9,17 +9,20 @@
 use base64::Engine;
 use hyper::body::Bytes;
 use hyper::http::{HeaderMap, HeaderValue};
-use hyper_util::client::legacy::Client;
 use hyper_util::client::legacy::connect::HttpConnector;
-use hyper_util::rt::TokioExecutor;
+use hyper_util::client::legacy::{Builder as ClientBuilder, Client};
+use hyper_util::rt::{TokioExecutor, TokioTimer};
 use jsonrpsee_core::BoxError;
 use jsonrpsee_core::{
 	TEN_MB_SIZE_BYTES,
 	http_helpers::{self, HttpError},
+	middleware::layer::{TRACEPARENT, TraceContext},
 };
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use thiserror::Error;
 use tower::layer::util::Identity;
 use tower::{Layer, Service, ServiceExt};
@@ -28,7 +31,7 @@ use url::Url;
 use crate::{HttpBody, HttpRequest, HttpResponse};
 
 #[cfg(feature = "tls")]
-use crate::{CertificateStore, CustomCertStore};
+use crate::{CertificateStore, ClientCertificate, CustomCertStore};
 
 const CONTENT_TYPE_JSON: &str = "application/json";
 
@@ -68,7 +71,7 @@ where
 			#[cfg(feature = "tls")]
 			Self::Https(inner) => inner.poll_ready(ctx),
 		}
-		.map_err(|e| Error::Http(HttpError::Stream(e.into())))
+		.map_err(backend_error)
 	}
 
 	fn call(&mut self, req: HttpRequest<B>) -> Self::Future {
@@ -78,7 +81,95 @@ where
 			Self::Https(inner) => inner.call(req),
 		};
 
-		Box::pin(async move { resp.await.map_err(|e| Error::Http(HttpError::Stream(e.into()))) })
+		Box::pin(async move { resp.await.map_err(backend_error) })
+	}
+}
+
+fn backend_error(err: hyper_util::client::legacy::Error) -> Error {
+	// The rejection shows up when connecting or, with TLS 1.3, once the response is read
+	// because the server verifies the client certificate after the client completed the handshake.
+	#[cfg(feature = "tls")]
+	if let Some(alert) = jsonrpsee_client_transport::tls::find_rejected_client_certificate(&err) {
+		return Error::ClientCertificateRejected(alert);
+	}
+	Error::Http(HttpError::Stream(err.into()))
+}
+
+/// HTTP/2 settings of the client.
+///
+/// Plaintext `http` targets use HTTP/2 with prior knowledge (h2c) which requires that the server
+/// supports it, whereas `https` targets negotiate the protocol version with ALPN.
+#[derive(Debug, Clone, Copy)]
+pub struct Http2Config {
+	/// Interval of the keep-alive pings.
+	pub(crate) keep_alive_interval: Option<Duration>,
+	/// Timeout to receive a keep-alive pong.
+	pub(crate) keep_alive_timeout: Duration,
+	/// Send keep-alive pings when there are no open streams.
+	pub(crate) keep_alive_while_idle: bool,
+	/// Initial maximum number of concurrent streams per connection.
+	pub(crate) initial_max_send_streams: usize,
+	/// Adaptive flow control.
+	pub(crate) adaptive_window: bool,
+}
+
+impl Default for Http2Config {
+	fn default() -> Self {
+		Self {
+			keep_alive_interval: None,
+			keep_alive_timeout: Duration::from_secs(20),
+			keep_alive_while_idle: false,
+			initial_max_send_streams: 100,
+			adaptive_window: false,
+		}
+	}
+}
+
+impl Http2Config {
+	/// Create a new [`Http2Config`] with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Send HTTP/2 keep-alive pings at the given interval.
+	///
+	/// Default: pings are disabled.
+	pub fn keep_alive_interval(mut self, interval: Duration) -> Self {
+		self.keep_alive_interval = Some(interval);
+		self
+	}
+
+	/// Close the connection if a keep-alive pong isn't received within the timeout.
+	///
+	/// Default: 20 seconds.
+	pub fn keep_alive_timeout(mut self, timeout: Duration) -> Self {
+		self.keep_alive_timeout = timeout;
+		self
+	}
+
+	/// Send keep-alive pings even if there are no open streams on the connection.
+	///
+	/// Default: false.
+	pub fn keep_alive_while_idle(mut self, enabled: bool) -> Self {
+		self.keep_alive_while_idle = enabled;
+		self
+	}
+
+	/// Set the initial maximum number of concurrent requests multiplexed on a connection,
+	/// which is replaced by the limit announced by the server once it has been received.
+	///
+	/// Default: 100.
+	pub fn initial_max_send_streams(mut self, max: usize) -> Self {
+		self.initial_max_send_streams = max;
+		self
+	}
+
+	/// Use adaptive flow control instead of the fixed default window sizes.
+	///
+	/// Default: false.
+	pub fn adaptive_window(mut self, enabled: bool) -> Self {
+		self.adaptive_window = enabled;
+		self
 	}
 }
 
@@ -88,6 +179,9 @@ pub struct HttpTransportClientBuilder<L> {
 	/// Certificate store.
 	#[cfg(feature = "tls")]
 	pub(crate) certificate_store: CertificateStore,
+	/// Certificate to authenticate with to the server.
+	#[cfg(feature = "tls")]
+	pub(crate) client_certificate: Option<ClientCertificate>,
 	/// Configurable max request body size
 	pub(crate) max_request_size: u32,
 	/// Configurable max response body size
@@ -98,6 +192,12 @@ pub struct HttpTransportClientBuilder<L> {
 	pub(crate) service_builder: tower::ServiceBuilder<L>,
 	/// TCP_NODELAY
 	pub(crate) tcp_no_delay: bool,
+	/// HTTP/2 settings, HTTP/1.1 is used for plaintext connections if not set.
+	pub(crate) http2_config: Option<Http2Config>,
+	/// Maximum number of idle connections per host.
+	pub(crate) pool_max_idle_per_host: Option<usize>,
+	/// Timeout after which idle connections are closed.
+	pub(crate) pool_idle_timeout: Option<Duration>,
 }
 
 impl Default for HttpTransportClientBuilder<Identity> {
@@ -112,11 +212,16 @@ impl HttpTransportClientBuilder<Identity> {
 		Self {
 			#[cfg(feature = "tls")]
 			certificate_store: CertificateStore::Native,
+			#[cfg(feature = "tls")]
+			client_certificate: None,
 			max_request_size: TEN_MB_SIZE_BYTES,
 			max_response_size: TEN_MB_SIZE_BYTES,
 			headers: HeaderMap::new(),
 			service_builder: tower::ServiceBuilder::new(),
 			tcp_no_delay: true,
+			http2_config: None,
+			pool_max_idle_per_host: None,
+			pool_idle_timeout: None,
 		}
 	}
 }
@@ -129,6 +234,13 @@ impl<L> HttpTransportClientBuilder<L> {
 		self
 	}
 
+	/// See docs [`crate::HttpClientBuilder::with_client_certificate`] for more information.
+	#[cfg(feature = "tls")]
+	pub fn with_client_certificate(mut self, cert: ClientCertificate) -> Self {
+		self.client_certificate = Some(cert);
+		self
+	}
+
 	/// Set the maximum size of a request body in bytes. Default is 10 MiB.
 	pub fn max_request_size(mut self, size: u32) -> Self {
 		self.max_request_size = size;
@@ -157,16 +269,39 @@ impl<L> HttpTransportClientBuilder<L> {
 		self
 	}
 
+	/// See docs [`crate::HttpClientBuilder::enable_http2`] for more information.
+	pub fn enable_http2(mut self, config: Http2Config) -> Self {
+		self.http2_config = Some(config);
+		self
+	}
+
+	/// See docs [`crate::HttpClientBuilder::pool_max_idle_per_host`] for more information.
+	pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+		self.pool_max_idle_per_host = Some(max);
+		self
+	}
+
+	/// See docs [`crate::HttpClientBuilder::pool_idle_timeout`] for more information.
+	pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+		self.pool_idle_timeout = Some(timeout);
+		self
+	}
+
 	/// Configure a tower service.
 	pub fn set_service<T>(self, service: tower::ServiceBuilder<T>) -> HttpTransportClientBuilder<T> {
 		HttpTransportClientBuilder {
 			#[cfg(feature = "tls")]
 			certificate_store: self.certificate_store,
+			#[cfg(feature = "tls")]
+			client_certificate: self.client_certificate,
 			headers: self.headers,
 			max_request_size: self.max_request_size,
 			max_response_size: self.max_response_size,
 			service_builder: service,
 			tcp_no_delay: self.tcp_no_delay,
+			http2_config: self.http2_config,
+			pool_max_idle_per_host: self.pool_max_idle_per_host,
+			pool_idle_timeout: self.pool_idle_timeout,
 		}
 	}
 
@@ -182,11 +317,16 @@ impl<L> HttpTransportClientBuilder<L> {
 		let Self {
 			#[cfg(feature = "tls")]
 			certificate_store,
+			#[cfg(feature = "tls")]
+			client_certificate,
 			max_request_size,
 			max_response_size,
 			headers,
 			service_builder,
 			tcp_no_delay,
+			http2_config,
+			pool_max_idle_per_host,
+			pool_idle_timeout,
 		} = self;
 		let mut url = Url::parse(target.as_ref()).map_err(|e| Error::Url(format!("Invalid URL: {e}")))?;
 
@@ -195,11 +335,24 @@ impl<L> HttpTransportClientBuilder<L> {
 		}
 		url.set_fragment(None);
 
+		let mut client_builder = Client::builder(TokioExecutor::new());
+		if let Some(max) = pool_max_idle_per_host {
+			client_builder.pool_max_idle_per_host(max);
+		}
+		if let Some(timeout) = pool_idle_timeout {
+			client_builder.pool_idle_timeout(timeout).pool_timer(TokioTimer::new());
+		}
+		if let Some(cfg) = http2_config {
+			configure_http2(&mut client_builder, cfg);
+		}
+
 		let client = match url.scheme() {
 			"http" => {
 				let mut connector = HttpConnector::new();
 				connector.set_nodelay(tcp_no_delay);
-				HttpBackend::Http(Client::builder(TokioExecutor::new()).build(connector))
+				// There is no protocol negotiation without TLS, the server must support h2c.
+				client_builder.http2_only(http2_config.is_some());
+				HttpBackend::Http(client_builder.build(connector))
 			}
 			#[cfg(feature = "tls")]
 			"https" => {
@@ -213,25 +366,26 @@ impl<L> HttpTransportClientBuilder<L> {
 				http_conn.set_nodelay(tcp_no_delay);
 				http_conn.enforce_http(false);
 
-				let https_conn = match certificate_store {
+				let mut tls_config = match certificate_store {
 					CertificateStore::Native => {
 						use rustls_platform_verifier::ConfigVerifierExt;
 
-						hyper_rustls::HttpsConnectorBuilder::new()
-							.with_tls_config(rustls::ClientConfig::with_platform_verifier())
-							.https_or_http()
-							.enable_all_versions()
-							.wrap_connector(http_conn)
+						rustls::ClientConfig::with_platform_verifier()
 					}
-
-					CertificateStore::Custom(tls_config) => hyper_rustls::HttpsConnectorBuilder::new()
-						.with_tls_config(tls_config)
-						.https_or_http()
-						.enable_all_versions()
-						.wrap_connector(http_conn),
+					CertificateStore::Custom(tls_config) => tls_config,
 				};
 
-				HttpBackend::Https(Client::builder(TokioExecutor::new()).build(https_conn))
+				if let Some(cert) = client_certificate {
+					tls_config.client_auth_cert_resolver = Arc::new(cert);
+				}
+
+				let https_conn = hyper_rustls::HttpsConnectorBuilder::new()
+					.with_tls_config(tls_config)
+					.https_or_http()
+					.enable_all_versions()
+					.wrap_connector(http_conn);
+
+				HttpBackend::Https(client_builder.build(https_conn))
 			}
 			_ => {
 				#[cfg(feature = "tls")]
@@ -275,6 +429,24 @@ impl<L> HttpTransportClientBuilder<L> {
 	}
 }
 
+fn configure_http2(builder: &mut ClientBuilder, cfg: Http2Config) {
+	let Http2Config {
+		keep_alive_interval,
+		keep_alive_timeout,
+		keep_alive_while_idle,
+		initial_max_send_streams,
+		adaptive_window,
+	} = cfg;
+
+	builder
+		.timer(TokioTimer::new())
+		.http2_keep_alive_interval(keep_alive_interval)
+		.http2_keep_alive_timeout(keep_alive_timeout)
+		.http2_keep_alive_while_idle(keep_alive_while_idle)
+		.http2_initial_max_send_streams(initial_max_send_streams)
+		.http2_adaptive_window(adaptive_window);
+}
+
 /// HTTP Transport Client.
 #[derive(Debug, Clone)]
 pub struct HttpTransportClient<S> {
@@ -297,7 +469,7 @@ where
 	B::Data: Send,
 	B::Error: Into<BoxError>,
 {
-	async fn inner_send(&self, body: String) -> Result<HttpResponse<B>, Error> {
+	async fn inner_send(&self, body: String, trace_context: Option<&TraceContext>) -> Result<HttpResponse<B>, Error> {
 		if body.len() > self.max_request_size as usize {
 			return Err(Error::RequestTooLarge);
 		}
@@ -305,6 +477,10 @@ where
 		let mut req = HttpRequest::post(&self.target);
 		if let Some(headers) = req.headers_mut() {
 			*headers = self.headers.clone();
+			if let Some(cx) = trace_context {
+				let value = HeaderValue::try_from(cx.to_traceparent()).expect("traceparent is valid ASCII; qed");
+				headers.insert(TRACEPARENT, value);
+			}
 		}
 
 		let req = req.body(body.into()).expect("URI and request headers are valid; qed");
@@ -318,8 +494,14 @@ where
 	}
 
 	/// Send serialized message and wait until all bytes from the HTTP message body have been read.
-	pub(crate) async fn send_and_read_body(&self, body: String) -> Result<Vec<u8>, Error> {
-		let response = self.inner_send(body).await?;
+	///
+	/// The `trace_context` is sent in the `traceparent` header.
+	pub(crate) async fn send_and_read_body(
+		&self,
+		body: String,
+		trace_context: Option<&TraceContext>,
+	) -> Result<Vec<u8>, Error> {
+		let response = self.inner_send(body, trace_context).await?;
 
 		let (parts, body) = response.into_parts();
 		let (body, _is_single) = http_helpers::read_body(&parts.headers, body, self.max_response_size).await?;
@@ -328,8 +510,8 @@ where
 	}
 
 	/// Send serialized message without reading the HTTP message body.
-	pub(crate) async fn send(&self, body: String) -> Result<(), Error> {
-		self.inner_send(body).await?;
+	pub(crate) async fn send(&self, body: String, trace_context: Option<&TraceContext>) -> Result<(), Error> {
+		self.inner_send(body, trace_context).await?;
 		Ok(())
 	}
 }
@@ -359,6 +541,11 @@ pub enum Error {
 	/// Invalid certificate store.
 	#[error("Invalid certificate store")]
 	InvalidCertficateStore,
+
+	/// Server rejected the client certificate or required one and none was provided.
+	#[cfg(feature = "tls")]
+	#[error("Server rejected the client certificate: {0:?}")]
+	ClientCertificateRejected(rustls::AlertDescription),
 }
 
 #[cfg(test)]
@@ -447,7 +634,7 @@ mod tests {
 
 		let body = "a".repeat(81);
 		assert_eq!(body.len(), 81);
-		let response = client.send(body).await.unwrap_err();
+		let response = client.send(body, None).await.unwrap_err();
 		assert!(matches!(response, Error::RequestTooLarge));
 	}
 }
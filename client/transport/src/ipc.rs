@@ -0,0 +1,195 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Transport for newline-delimited JSON-RPC over Unix domain sockets.
+//!
+//! This is compatible with servers built by `jsonrpsee_server::ServerBuilder::build_ipc`
+//! and other IPC endpoints which terminate every JSON-RPC message with `\n`, such as geth.
+//!
+//! The transport is used with the async client by passing the [`Sender`](crate::ipc::Sender) and [`Receiver`](crate::ipc::Receiver)
+//! to `jsonrpsee_core::client::ClientBuilder::build_with_tokio`.
+//!
+//! ```no_run
+//! use jsonrpsee_client_transport::ipc::{IpcError, IpcTransportClientBuilder};
+//!
+//! async fn connect() -> Result<(), IpcError> {
+//!     let (sender, receiver) = IpcTransportClientBuilder::default().build("/tmp/jsonrpsee.ipc").await?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use jsonrpsee_core::TEN_MB_SIZE_BYTES;
+use jsonrpsee_core::client::{ReceivedMessage, TransportReceiverT, TransportSenderT};
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use tokio::net::UnixStream;
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio_util::codec::{FramedRead, LinesCodec, LinesCodecError};
+
+const LOG_TARGET: &str = "jsonrpsee-client";
+
+/// Sending end of IPC transport.
+#[derive(Debug)]
+pub struct Sender {
+	inner: OwnedWriteHalf,
+	max_request_size: u32,
+}
+
+/// Receiving end of IPC transport.
+#[derive(Debug)]
+pub struct Receiver {
+	inner: FramedRead<OwnedReadHalf, LinesCodec>,
+}
+
+/// Builder for an IPC transport [`Sender`] and [`Receiver`] pair.
+#[derive(Debug, Clone, Copy)]
+pub struct IpcTransportClientBuilder {
+	/// Timeout for the connection.
+	pub connection_timeout: Duration,
+	/// Max request payload size
+	pub max_request_size: u32,
+	/// Max response payload size
+	pub max_response_size: u32,
+}
+
+impl Default for IpcTransportClientBuilder {
+	fn default() -> Self {
+		Self {
+			connection_timeout: Duration::from_secs(10),
+			max_request_size: TEN_MB_SIZE_BYTES,
+			max_response_size: TEN_MB_SIZE_BYTES,
+		}
+	}
+}
+
+impl IpcTransportClientBuilder {
+	/// Set max request size.
+	pub fn max_request_size(mut self, size: u32) -> Self {
+		self.max_request_size = size;
+		self
+	}
+
+	/// Set max response size.
+	pub fn max_response_size(mut self, size: u32) -> Self {
+		self.max_response_size = size;
+		self
+	}
+
+	/// Set connection timeout.
+	pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+		self.connection_timeout = timeout;
+		self
+	}
+
+	/// Try to connect to the Unix domain socket at `path`.
+	pub async fn build(self, path: impl AsRef<Path>) -> Result<(Sender, Receiver), IpcError> {
+		let path = path.as_ref();
+		tracing::debug!(target: LOG_TARGET, "Connecting to IPC socket: {}", path.display());
+
+		let socket = match tokio::time::timeout(self.connection_timeout, UnixStream::connect(path)).await {
+			Ok(socket) => socket.map_err(IpcError::Io)?,
+			Err(_) => return Err(IpcError::Timeout(self.connection_timeout)),
+		};
+
+		Ok(self.build_with_stream(socket))
+	}
+
+	/// Use an already connected Unix domain socket.
+	pub fn build_with_stream(self, socket: UnixStream) -> (Sender, Receiver) {
+		let (read, write) = socket.into_split();
+		let codec = LinesCodec::new_with_max_length(self.max_response_size as usize);
+
+		(
+			Sender { inner: write, max_request_size: self.max_request_size },
+			Receiver { inner: FramedRead::new(read, codec) },
+		)
+	}
+}
+
+/// Error that can occur when connecting or on an established IPC connection.
+#[derive(Debug, Error)]
+pub enum IpcError {
+	/// Error on the Unix domain socket.
+	#[error("Error on the Unix socket: {0}")]
+	Io(io::Error),
+	/// Timeout while trying to connect.
+	#[error("Connection timeout exceeded: {0:?}")]
+	Timeout(Duration),
+	/// Message was too large.
+	#[error("The message was too large")]
+	MessageTooLarge,
+	/// Connection was closed.
+	#[error("Connection was closed")]
+	Closed,
+}
+
+impl TransportSenderT for Sender {
+	type Error = IpcError;
+
+	/// Sends out a request. Returns a `Future` that finishes when the request has been
+	/// successfully sent.
+	fn send(&mut self, mut body: String) -> impl Future<Output = Result<(), Self::Error>> + Send {
+		async move {
+			if body.len() > self.max_request_size as usize {
+				return Err(IpcError::MessageTooLarge);
+			}
+
+			body.push('\n');
+			self.inner.write_all(body.as_bytes()).await.map_err(IpcError::Io)?;
+			self.inner.flush().await.map_err(IpcError::Io)
+		}
+	}
+
+	/// Shut down the write half of the connection.
+	fn close(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send {
+		async { self.inner.shutdown().await.map_err(IpcError::Io) }
+	}
+}
+
+impl TransportReceiverT for Receiver {
+	type Error = IpcError;
+
+	/// Returns a `Future` resolving when the server sent us something back.
+	fn receive(&mut self) -> impl Future<Output = Result<ReceivedMessage, Self::Error>> + Send {
+		async {
+			loop {
+				match self.inner.next().await {
+					// Empty lines are treated as keep-alive and ignored.
+					Some(Ok(line)) if line.trim().is_empty() => continue,
+					Some(Ok(line)) => break Ok(ReceivedMessage::Text(line)),
+					Some(Err(LinesCodecError::MaxLineLengthExceeded)) => break Err(IpcError::MessageTooLarge),
+					Some(Err(LinesCodecError::Io(e))) => break Err(IpcError::Io(e)),
+					None => break Err(IpcError::Closed),
+				}
+			}
+		}
+	}
+}
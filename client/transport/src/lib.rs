@@ -34,6 +34,16 @@
 #[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
 pub mod ws;
 
+/// TLS helpers shared by the HTTP and WebSocket clients.
+#[cfg(feature = "tls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+pub mod tls;
+
+/// IPC transport over Unix domain sockets.
+#[cfg(all(feature = "ipc", unix))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "ipc", unix))))]
+pub mod ipc;
+
 /// Websocket transport via web-sys.
 #[cfg(all(feature = "web", target_arch = "wasm32"))]
 #[cfg_attr(docsrs, doc(cfg(feature = "web")))]
@@ -0,0 +1,153 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! TLS helpers shared by the HTTP and WebSocket clients.
+
+use std::sync::Arc;
+
+use rustls::AlertDescription;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::sign::CertifiedKey;
+
+/// Certificate chain and private key the client authenticates with to the server (mutual TLS).
+#[derive(Debug, Clone)]
+pub struct ClientCertificate(Arc<CertifiedKey>);
+
+impl ClientCertificate {
+	/// Create a new [`ClientCertificate`] from a PEM encoded certificate chain and private key.
+	///
+	/// The first certificate of the chain must be the certificate of the client.
+	pub fn from_pem(cert_chain: &[u8], key: &[u8]) -> Result<Self, rustls::Error> {
+		let invalid_pem = |e: rustls::pki_types::pem::Error| rustls::Error::General(format!("Invalid PEM: {e}"));
+		let cert_chain =
+			CertificateDer::pem_slice_iter(cert_chain).collect::<Result<Vec<_>, _>>().map_err(invalid_pem)?;
+		let key = PrivateKeyDer::from_pem_slice(key).map_err(invalid_pem)?;
+
+		Self::from_der(cert_chain, key)
+	}
+
+	/// Create a new [`ClientCertificate`] from a DER encoded certificate chain and private key.
+	///
+	/// The first certificate of the chain must be the certificate of the client.
+	pub fn from_der(
+		cert_chain: Vec<CertificateDer<'static>>,
+		key: PrivateKeyDer<'static>,
+	) -> Result<Self, rustls::Error> {
+		let key = CertifiedKey::from_der(cert_chain, key, &rustls::crypto::ring::default_provider())?;
+		Ok(Self(Arc::new(key)))
+	}
+}
+
+impl rustls::client::ResolvesClientCert for ClientCertificate {
+	fn resolve(&self, _: &[&[u8]], _: &[rustls::SignatureScheme]) -> Option<Arc<CertifiedKey>> {
+		Some(self.0.clone())
+	}
+
+	fn has_certs(&self) -> bool {
+		true
+	}
+}
+
+/// Alerts the server sends if it rejects the client certificate or requires one and none was provided.
+const REJECTED_CERTIFICATE_ALERTS: [AlertDescription; 8] = [
+	AlertDescription::CertificateRequired,
+	AlertDescription::BadCertificate,
+	AlertDescription::UnsupportedCertificate,
+	AlertDescription::CertificateRevoked,
+	AlertDescription::CertificateExpired,
+	AlertDescription::CertificateUnknown,
+	AlertDescription::UnknownCA,
+	AlertDescription::AccessDenied,
+];
+
+/// Returns the alert if `err` is the server rejecting the client certificate
+/// or requiring one when none was provided.
+pub fn rejected_client_certificate(err: &rustls::Error) -> Option<AlertDescription> {
+	match err {
+		rustls::Error::AlertReceived(alert) if REJECTED_CERTIFICATE_ALERTS.contains(alert) => Some(*alert),
+		_ => None,
+	}
+}
+
+/// Get the TLS error in the source chain of `err`, if any.
+///
+/// `tokio-rustls` wraps the TLS errors in I/O errors which don't expose them as their source.
+pub fn find_rustls_error<'a>(err: &'a (dyn std::error::Error + 'static)) -> Option<&'a rustls::Error> {
+	source_chain(err).find_map(as_rustls_error)
+}
+
+/// Returns the alert if the source chain of `err` contains the server rejecting the client certificate
+/// or requiring one when none was provided, see [`rejected_client_certificate`].
+pub fn find_rejected_client_certificate(err: &(dyn std::error::Error + 'static)) -> Option<AlertDescription> {
+	source_chain(err).find_map(|err| {
+		if let Some(err) = as_rustls_error(err) {
+			return rejected_client_certificate(err);
+		}
+
+		// HTTP/2 only keeps the message of the I/O errors of the connection.
+		let msg = err.downcast_ref::<std::io::Error>()?.get_ref()?.to_string();
+		REJECTED_CERTIFICATE_ALERTS.into_iter().find(|alert| rustls::Error::AlertReceived(*alert).to_string() == msg)
+	})
+}
+
+fn source_chain<'a>(
+	err: &'a (dyn std::error::Error + 'static),
+) -> impl Iterator<Item = &'a (dyn std::error::Error + 'static)> {
+	std::iter::successors(Some(err), |err| err.source())
+}
+
+fn as_rustls_error<'a>(err: &'a (dyn std::error::Error + 'static)) -> Option<&'a rustls::Error> {
+	match err.downcast_ref::<std::io::Error>() {
+		Some(err) => err.get_ref()?.downcast_ref(),
+		None => err.downcast_ref(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn finds_rejected_certificate_in_io_error() {
+		let alert = rustls::Error::AlertReceived(AlertDescription::CertificateRequired);
+		let err = std::io::Error::new(std::io::ErrorKind::InvalidData, alert);
+
+		let rustls_err = find_rustls_error(&err).unwrap();
+		assert_eq!(rejected_client_certificate(rustls_err), Some(AlertDescription::CertificateRequired));
+		assert_eq!(rejected_client_certificate(&rustls::Error::DecryptError), None);
+	}
+
+	#[test]
+	fn finds_rejected_certificate_in_message() {
+		let alert = rustls::Error::AlertReceived(AlertDescription::UnknownCA);
+		let err = std::io::Error::new(std::io::ErrorKind::InvalidData, alert.to_string());
+		assert_eq!(find_rejected_client_certificate(&err), Some(AlertDescription::UnknownCA));
+
+		let err = std::io::Error::new(std::io::ErrorKind::InvalidData, "connection reset");
+		assert_eq!(find_rejected_client_certificate(&err), None);
+	}
+}
@@ -0,0 +1,126 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! WebSocket `permessage-deflate` extension.
+
+use jsonrpsee_core::deflate::Inflater;
+use soketto::base::Header;
+use soketto::extension::{Extension, Param};
+use soketto::{BoxedError, Mode, Storage};
+
+/// Configuration of the WebSocket `permessage-deflate` extension.
+#[derive(Debug, Copy, Clone)]
+pub struct DeflateConfig {
+	max_window_bits: u8,
+	threshold: usize,
+}
+
+impl Default for DeflateConfig {
+	fn default() -> Self {
+		Self { max_window_bits: 15, threshold: 1024 }
+	}
+}
+
+impl DeflateConfig {
+	/// Create a new DeflateConfig.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Maximum size of the LZ77 sliding window, as base-2 logarithm, used to compress messages
+	/// sent by the client. A smaller window uses less memory at the cost of a worse compression ratio.
+	///
+	/// Default: 15 (32 KiB).
+	///
+	/// # Panics
+	///
+	/// This method panics if `bits` isn't within `9..=15`.
+	pub fn max_window_bits(mut self, bits: u8) -> Self {
+		assert!((9..=15).contains(&bits), "max window bits must be within 9..=15");
+		self.max_window_bits = bits;
+		self
+	}
+
+	/// Messages smaller than `bytes` are sent uncompressed because
+	/// compressing them isn't worth the overhead.
+	///
+	/// Default: 1024 bytes.
+	pub fn threshold(mut self, bytes: usize) -> Self {
+		self.threshold = bytes;
+		self
+	}
+}
+
+/// `permessage-deflate` extension which doesn't compress small messages.
+#[derive(Debug)]
+pub(crate) struct Deflate {
+	inner: soketto::extension::deflate::Deflate,
+	threshold: usize,
+	inflater: Inflater,
+}
+
+impl Deflate {
+	pub(crate) fn new(cfg: DeflateConfig, max_message_size: usize) -> Self {
+		let mut inner = soketto::extension::deflate::Deflate::new(Mode::Client);
+		inner.set_max_client_window_bits(cfg.max_window_bits);
+		Self { inner, threshold: cfg.threshold, inflater: Inflater::new(max_message_size) }
+	}
+}
+
+impl Extension for Deflate {
+	fn is_enabled(&self) -> bool {
+		self.inner.is_enabled()
+	}
+
+	fn name(&self) -> &str {
+		self.inner.name()
+	}
+
+	fn params(&self) -> &[Param<'_>] {
+		self.inner.params()
+	}
+
+	fn configure(&mut self, params: &[Param]) -> Result<(), BoxedError> {
+		self.inner.configure(params)
+	}
+
+	fn encode(&mut self, header: &mut Header, data: &mut Storage) -> Result<(), BoxedError> {
+		// Uncompressed messages are always allowed (RFC 7692, 6).
+		if data.as_ref().len() < self.threshold {
+			return Ok(());
+		}
+
+		self.inner.encode(header, data)
+	}
+
+	fn decode(&mut self, header: &mut Header, data: &mut Vec<u8>) -> Result<(), BoxedError> {
+		self.inflater.decode(header, data)
+	}
+
+	fn reserved_bits(&self) -> (bool, bool, bool) {
+		self.inner.reserved_bits()
+	}
+}
@@ -24,6 +24,8 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+#[cfg(feature = "deflate")]
+mod deflate;
 mod stream;
 
 use std::io;
@@ -44,12 +46,17 @@ use thiserror::Error;
 use tokio::net::TcpStream;
 use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
 
+#[cfg(feature = "deflate")]
+pub use deflate::DeflateConfig;
 pub use http::{HeaderMap, HeaderValue, Uri, uri::InvalidUri};
 pub use soketto::handshake::client::Header;
 pub use stream::EitherStream;
 pub use tokio::io::{AsyncRead, AsyncWrite};
 pub use url::Url;
 
+#[cfg(feature = "tls")]
+pub use crate::tls::ClientCertificate;
+
 const LOG_TARGET: &str = "jsonrpsee-client";
 
 /// Custom TLS configuration.
@@ -87,6 +94,9 @@ pub struct WsTransportClientBuilder {
 	#[cfg(feature = "tls")]
 	/// What certificate store to use
 	pub certificate_store: CertificateStore,
+	#[cfg(feature = "tls")]
+	/// Certificate to authenticate with to the server.
+	pub client_certificate: Option<ClientCertificate>,
 	/// Timeout for the connection.
 	pub connection_timeout: Duration,
 	/// Custom headers to pass during the HTTP handshake.
@@ -99,6 +109,9 @@ pub struct WsTransportClientBuilder {
 	pub max_redirections: usize,
 	/// TCP no delay.
 	pub tcp_no_delay: bool,
+	/// Offer the `permessage-deflate` extension during the handshake.
+	#[cfg(feature = "deflate")]
+	pub deflate_config: Option<DeflateConfig>,
 }
 
 impl Default for WsTransportClientBuilder {
@@ -106,12 +119,16 @@ impl Default for WsTransportClientBuilder {
 		Self {
 			#[cfg(feature = "tls")]
 			certificate_store: CertificateStore::Native,
+			#[cfg(feature = "tls")]
+			client_certificate: None,
 			max_request_size: TEN_MB_SIZE_BYTES,
 			max_response_size: TEN_MB_SIZE_BYTES,
 			connection_timeout: Duration::from_secs(10),
 			headers: http::HeaderMap::new(),
 			max_redirections: 5,
 			tcp_no_delay: true,
+			#[cfg(feature = "deflate")]
+			deflate_config: None,
 		}
 	}
 }
@@ -128,6 +145,20 @@ impl WsTransportClientBuilder {
 		self
 	}
 
+	/// Authenticate with a client certificate when the server requests one (mutual TLS).
+	///
+	/// The certificate is used with both the native and a custom certificate store
+	/// and replaces the client authentication of the custom store.
+	///
+	/// # Optional
+	///
+	/// This requires the optional `tls` feature.
+	#[cfg(feature = "tls")]
+	pub fn with_client_certificate(mut self, cert: ClientCertificate) -> Self {
+		self.client_certificate = Some(cert);
+		self
+	}
+
 	/// Set the maximum size of a request in bytes. Default is 10 MiB.
 	pub fn max_request_size(mut self, size: u32) -> Self {
 		self.max_request_size = size;
@@ -160,6 +191,15 @@ impl WsTransportClientBuilder {
 		self.max_redirections = redirect;
 		self
 	}
+
+	/// Offer the `permessage-deflate` extension to the server during the handshake (default is disabled).
+	///
+	/// Messages are only compressed if the server accepts the extension.
+	#[cfg(feature = "deflate")]
+	pub fn enable_ws_compression(mut self, cfg: DeflateConfig) -> Self {
+		self.deflate_config = Some(cfg);
+		self
+	}
 }
 
 /// Stream mode, either plain TCP or TLS.
@@ -220,6 +260,16 @@ pub enum WsHandshakeError {
 	/// Couldn't find any IP address for this hostname.
 	#[error("No IP address found for this hostname: {0}")]
 	NoAddressFound(String),
+
+	/// The TLS handshake failed.
+	#[cfg(feature = "tls")]
+	#[error("TLS handshake failed: {0}")]
+	Tls(#[source] rustls::Error),
+
+	/// Server rejected the client certificate or required one and none was provided.
+	#[cfg(feature = "tls")]
+	#[error("Server rejected the client certificate: {0:?}")]
+	ClientCertificateRejected(rustls::AlertDescription),
 }
 
 /// Error that can occur when reading or sending messages on an established connection.
@@ -335,7 +385,7 @@ impl WsTransportClientBuilder {
 		let _ = rustls::crypto::ring::default_provider().install_default();
 
 		let connector = match target._mode {
-			Mode::Tls => Some(build_tls_config(&self.certificate_store)?),
+			Mode::Tls => Some(build_tls_config(&self.certificate_store, self.client_certificate.as_ref())?),
 			Mode::Plain => None,
 		};
 		Ok(connector)
@@ -413,7 +463,10 @@ impl WsTransportClientBuilder {
 								#[cfg(feature = "tls")]
 								match target._mode {
 									Mode::Tls if connector.is_none() => {
-										connector = Some(build_tls_config(&self.certificate_store)?);
+										connector = Some(build_tls_config(
+											&self.certificate_store,
+											self.client_certificate.as_ref(),
+										)?);
 									}
 									Mode::Tls => (),
 									// Drop connector if it was configured previously.
@@ -492,6 +545,11 @@ impl WsTransportClientBuilder {
 
 		client.set_headers(&headers);
 
+		#[cfg(feature = "deflate")]
+		if let Some(cfg) = self.deflate_config {
+			client.add_extension(Box::new(deflate::Deflate::new(cfg, self.max_response_size as usize)));
+		}
+
 		// Perform the initial handshake.
 		match client.handshake().await {
 			Ok(ServerResponse::Accepted { .. }) => {
@@ -564,19 +622,48 @@ async fn connect(sockaddr: SocketAddr, timeout_dur: Duration) -> Result<EitherSt
 
 impl From<io::Error> for WsHandshakeError {
 	fn from(err: io::Error) -> WsHandshakeError {
+		#[cfg(feature = "tls")]
+		if let Some(err) = crate::tls::find_rustls_error(&err).cloned() {
+			return err.into();
+		}
 		WsHandshakeError::Io(err)
 	}
 }
 
 impl From<soketto::handshake::Error> for WsHandshakeError {
 	fn from(err: soketto::handshake::Error) -> WsHandshakeError {
+		// With TLS 1.3 the server verifies the client certificate after the TLS handshake
+		// completed on the client side, so the rejection shows up in the WebSocket handshake.
+		#[cfg(feature = "tls")]
+		if let Some(err) = match &err {
+			soketto::handshake::Error::Io(err) => crate::tls::find_rustls_error(err).cloned(),
+			_ => None,
+		} {
+			return err.into();
+		}
 		WsHandshakeError::Transport(err)
 	}
 }
 
+#[cfg(feature = "tls")]
+impl From<rustls::Error> for WsHandshakeError {
+	fn from(err: rustls::Error) -> WsHandshakeError {
+		match crate::tls::rejected_client_certificate(&err) {
+			Some(alert) => WsHandshakeError::ClientCertificateRejected(alert),
+			None => WsHandshakeError::Tls(err),
+		}
+	}
+}
+
 impl From<soketto::connection::Error> for WsError {
 	fn from(err: soketto::connection::Error) -> Self {
-		WsError::Connection(err)
+		match err {
+			#[cfg(feature = "deflate")]
+			soketto::connection::Error::Extension(err) if err.is::<jsonrpsee_core::deflate::MessageTooLarge>() => {
+				WsError::MessageTooLarge
+			}
+			err => WsError::Connection(err),
+		}
 	}
 }
 
@@ -637,8 +724,11 @@ impl TryFrom<url::Url> for Target {
 
 // NOTE: this is slow and should be used sparingly.
 #[cfg(feature = "tls")]
-fn build_tls_config(cert_store: &CertificateStore) -> Result<tokio_rustls::TlsConnector, WsHandshakeError> {
-	let config = match cert_store {
+fn build_tls_config(
+	cert_store: &CertificateStore,
+	client_cert: Option<&ClientCertificate>,
+) -> Result<tokio_rustls::TlsConnector, WsHandshakeError> {
+	let mut config = match cert_store {
 		#[cfg(feature = "tls-rustls-platform-verifier")]
 		CertificateStore::Native => {
 			use rustls_platform_verifier::ConfigVerifierExt;
@@ -655,6 +745,10 @@ fn build_tls_config(cert_store: &CertificateStore) -> Result<tokio_rustls::TlsCo
 		CertificateStore::Custom(cfg) => cfg.clone(),
 	};
 
+	if let Some(cert) = client_cert {
+		config.client_auth_cert_resolver = std::sync::Arc::new(cert.clone());
+	}
+
 	Ok(std::sync::Arc::new(config).into())
 }
 
@@ -38,6 +38,8 @@
 #[cfg(test)]
 mod tests;
 
+mod reconnect;
+
 pub use http::{HeaderMap, HeaderValue};
 pub use jsonrpsee_core::client::Client as WsClient;
 pub use jsonrpsee_core::client::async_client::PingConfig;
@@ -45,6 +47,7 @@ pub use jsonrpsee_core::client::async_client::RpcService;
 pub use jsonrpsee_core::middleware::RpcServiceBuilder;
 use jsonrpsee_core::middleware::layer::RpcLoggerLayer;
 pub use jsonrpsee_types as types;
+pub use reconnect::{CallRetryPolicy, ReconnectPolicy, ReconnectingWsClient};
 
 use jsonrpsee_client_transport::ws::{AsyncRead, AsyncWrite, WsTransportClientBuilder};
 use jsonrpsee_core::TEN_MB_SIZE_BYTES;
@@ -55,7 +58,10 @@ use url::Url;
 type Logger = tower::layer::util::Stack<RpcLoggerLayer, tower::layer::util::Identity>;
 
 #[cfg(feature = "tls")]
-pub use jsonrpsee_client_transport::ws::CustomCertStore;
+pub use jsonrpsee_client_transport::ws::{ClientCertificate, CustomCertStore};
+
+#[cfg(feature = "deflate")]
+pub use jsonrpsee_client_transport::ws::DeflateConfig;
 
 #[cfg(feature = "tls")]
 use jsonrpsee_client_transport::ws::CertificateStore;
@@ -89,6 +95,8 @@ use jsonrpsee_client_transport::ws::CertificateStore;
 pub struct WsClientBuilder<RpcMiddleware = Logger> {
 	#[cfg(feature = "tls")]
 	certificate_store: CertificateStore,
+	#[cfg(feature = "tls")]
+	client_certificate: Option<ClientCertificate>,
 	max_request_size: u32,
 	max_response_size: u32,
 	request_timeout: Duration,
@@ -100,6 +108,10 @@ pub struct WsClientBuilder<RpcMiddleware = Logger> {
 	max_redirections: usize,
 	id_kind: IdKind,
 	tcp_no_delay: bool,
+	#[cfg(feature = "deflate")]
+	deflate_config: Option<DeflateConfig>,
+	#[cfg(feature = "bidirectional")]
+	methods: jsonrpsee_core::server::Methods,
 	service_builder: RpcServiceBuilder<RpcMiddleware>,
 }
 
@@ -108,6 +120,8 @@ impl Default for WsClientBuilder {
 		Self {
 			#[cfg(feature = "tls")]
 			certificate_store: CertificateStore::Native,
+			#[cfg(feature = "tls")]
+			client_certificate: None,
 			max_request_size: TEN_MB_SIZE_BYTES,
 			max_response_size: TEN_MB_SIZE_BYTES,
 			request_timeout: Duration::from_secs(60),
@@ -119,6 +133,10 @@ impl Default for WsClientBuilder {
 			max_redirections: 5,
 			id_kind: IdKind::Number,
 			tcp_no_delay: true,
+			#[cfg(feature = "deflate")]
+			deflate_config: None,
+			#[cfg(feature = "bidirectional")]
+			methods: jsonrpsee_core::server::Methods::new(),
 			service_builder: RpcServiceBuilder::default().rpc_logger(1024),
 		}
 	}
@@ -200,6 +218,31 @@ impl<RpcMiddleware> WsClientBuilder<RpcMiddleware> {
 		self
 	}
 
+	/// Authenticate with a client certificate when the server requests one (mutual TLS).
+	///
+	/// If the server rejects the certificate, the connection fails with
+	/// [`WsHandshakeError::ClientCertificateRejected`](jsonrpsee_client_transport::ws::WsHandshakeError::ClientCertificateRejected).
+	///
+	/// # Optional
+	///
+	/// This requires the optional `tls` feature.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jsonrpsee_ws_client::{ClientCertificate, WsClientBuilder};
+	///
+	/// let cert = std::fs::read("client.pem").unwrap();
+	/// let key = std::fs::read("client.key").unwrap();
+	///
+	/// let client_builder = WsClientBuilder::new().with_client_certificate(ClientCertificate::from_pem(&cert, &key).unwrap());
+	/// ```
+	#[cfg(feature = "tls")]
+	pub fn with_client_certificate(mut self, cert: ClientCertificate) -> Self {
+		self.client_certificate = Some(cert);
+		self
+	}
+
 	/// See documentation [`WsTransportClientBuilder::max_request_size`] (default is 10 MB).
 	pub fn max_request_size(mut self, size: u32) -> Self {
 		self.max_request_size = size;
@@ -272,11 +315,76 @@ impl<RpcMiddleware> WsClientBuilder<RpcMiddleware> {
 		self
 	}
 
+	/// Offer the WebSocket `permessage-deflate` extension to the server (default is disabled).
+	///
+	/// Messages are only compressed if the server accepts the extension.
+	///
+	/// # Optional
+	///
+	/// This requires the optional `deflate` feature.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jsonrpsee_ws_client::{WsClientBuilder, DeflateConfig};
+	///
+	/// #[tokio::main]
+	/// async fn main() {
+	///     // Only compress requests which are larger than 512 bytes.
+	///     let client = WsClientBuilder::default()
+	///          .enable_ws_compression(DeflateConfig::new().threshold(512))
+	///          .build("wss://localhost:443")
+	///          .await
+	///          .unwrap();
+	/// }
+	/// ```
+	#[cfg(feature = "deflate")]
+	pub fn enable_ws_compression(mut self, cfg: DeflateConfig) -> Self {
+		self.deflate_config = Some(cfg);
+		self
+	}
+
+	/// Answer the requests of the server with the methods of a local [`RpcModule`](jsonrpsee_core::server::RpcModule).
+	///
+	/// The server sends these requests with a `ClientHandle` when it's configured to do so.
+	/// Requests to unknown methods are answered with a `method not found` error.
+	///
+	/// # Optional
+	///
+	/// This requires the optional `bidirectional` feature.
+	///
+	/// # Example
+	///
+	/// ```no_run
+	/// use jsonrpsee_core::server::RpcModule;
+	/// use jsonrpsee_ws_client::WsClientBuilder;
+	///
+	/// #[tokio::main]
+	/// async fn main() {
+	///     let mut module = RpcModule::new(());
+	///     module.register_method("confirm", |params, _, _| params.one::<String>().is_ok()).unwrap();
+	///
+	///     let client = WsClientBuilder::default()
+	///          .set_methods(module)
+	///          .build("wss://localhost:443")
+	///          .await
+	///          .unwrap();
+	/// }
+	/// ```
+	#[cfg(feature = "bidirectional")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "bidirectional")))]
+	pub fn set_methods(mut self, methods: impl Into<jsonrpsee_core::server::Methods>) -> Self {
+		self.methods = methods.into();
+		self
+	}
+
 	/// Set the RPC service builder.
 	pub fn set_rpc_middleware<T>(self, service_builder: RpcServiceBuilder<T>) -> WsClientBuilder<T> {
 		WsClientBuilder {
 			#[cfg(feature = "tls")]
 			certificate_store: self.certificate_store,
+			#[cfg(feature = "tls")]
+			client_certificate: self.client_certificate,
 			max_request_size: self.max_request_size,
 			max_response_size: self.max_response_size,
 			request_timeout: self.request_timeout,
@@ -288,6 +396,10 @@ impl<RpcMiddleware> WsClientBuilder<RpcMiddleware> {
 			max_redirections: self.max_redirections,
 			id_kind: self.id_kind,
 			tcp_no_delay: self.tcp_no_delay,
+			#[cfg(feature = "deflate")]
+			deflate_config: self.deflate_config,
+			#[cfg(feature = "bidirectional")]
+			methods: self.methods,
 			service_builder,
 		}
 	}
@@ -311,6 +423,8 @@ impl<RpcMiddleware> WsClientBuilder<RpcMiddleware> {
 			id_kind,
 			tcp_no_delay,
 			service_builder,
+			#[cfg(feature = "bidirectional")]
+			methods,
 			..
 		} = self;
 
@@ -326,6 +440,11 @@ impl<RpcMiddleware> WsClientBuilder<RpcMiddleware> {
 			client = client.enable_ws_ping(cfg);
 		}
 
+		#[cfg(feature = "bidirectional")]
+		{
+			client = client.set_methods(methods);
+		}
+
 		client.build_with_tokio(sender, receiver)
 	}
 
@@ -342,12 +461,16 @@ impl<RpcMiddleware> WsClientBuilder<RpcMiddleware> {
 		let transport_builder = WsTransportClientBuilder {
 			#[cfg(feature = "tls")]
 			certificate_store: self.certificate_store.clone(),
+			#[cfg(feature = "tls")]
+			client_certificate: self.client_certificate.clone(),
 			connection_timeout: self.connection_timeout,
 			headers: self.headers.clone(),
 			max_request_size: self.max_request_size,
 			max_response_size: self.max_response_size,
 			max_redirections: self.max_redirections,
 			tcp_no_delay: self.tcp_no_delay,
+			#[cfg(feature = "deflate")]
+			deflate_config: self.deflate_config,
 		};
 
 		let uri = Url::parse(url.as_ref()).map_err(|e| Error::Transport(e.into()))?;
@@ -371,12 +494,16 @@ impl<RpcMiddleware> WsClientBuilder<RpcMiddleware> {
 		let transport_builder = WsTransportClientBuilder {
 			#[cfg(feature = "tls")]
 			certificate_store: self.certificate_store.clone(),
+			#[cfg(feature = "tls")]
+			client_certificate: self.client_certificate.clone(),
 			connection_timeout: self.connection_timeout,
 			headers: self.headers.clone(),
 			max_request_size: self.max_request_size,
 			max_response_size: self.max_response_size,
 			max_redirections: self.max_redirections,
 			tcp_no_delay: self.tcp_no_delay,
+			#[cfg(feature = "deflate")]
+			deflate_config: self.deflate_config,
 		};
 
 		let uri = Url::parse(url.as_ref()).map_err(|e| Error::Transport(e.into()))?;
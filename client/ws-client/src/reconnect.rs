@@ -0,0 +1,622 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! WebSocket client which automatically reconnects when the connection is lost.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use jsonrpsee_core::client::resubscribe::{
+	DetachedSender, RawParams, Resubscribe, SubscriptionEnd, UnsubscribeSignal, detached_subscription,
+	forward_notifications,
+};
+use jsonrpsee_core::client::{
+	BatchResponse, ClientT, Error, MiddlewareBatchResponse, MiddlewareMethodResponse, MiddlewareNotifResponse,
+	Subscription, SubscriptionClientT,
+};
+use jsonrpsee_core::middleware::RpcServiceT;
+use jsonrpsee_core::params::BatchRequestBuilder;
+use jsonrpsee_core::traits::ToRpcParams;
+use jsonrpsee_core::{DeserializeOwned, JsonRawValue};
+use tokio::sync::{oneshot, watch};
+
+use crate::{RpcService, WsClient, WsClientBuilder};
+
+const NOT_POISONED: &str = "Not poisoned; qed";
+
+/// What to do with calls that are in flight when the connection is lost.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CallRetryPolicy {
+	/// Fail the call with [`Error::DisconnectedWillReconnect`].
+	///
+	/// Calls made while the client is reconnecting fail with the same error.
+	Drop,
+	/// Wait until the client has reconnected and send the call again.
+	///
+	/// The call fails with [`Error::RequestTimeout`] if the client hasn't reconnected
+	/// within the request timeout of the [`WsClientBuilder`].
+	///
+	/// This should only be used if the methods are safe to call more than once.
+	Retry,
+}
+
+/// Configuration for how [`ReconnectingWsClient`] redials the server.
+///
+/// The delay between two reconnection attempts starts at `initial_delay` and is multiplied
+/// by `factor` after each failed attempt, but never exceeds `max_delay`.
+///
+/// Default: initial delay 100 ms, factor 2, max delay 30 seconds, unlimited retries and
+/// [`CallRetryPolicy::Drop`].
+#[derive(Debug, Copy, Clone)]
+pub struct ReconnectPolicy {
+	initial_delay: Duration,
+	max_delay: Duration,
+	factor: u32,
+	max_retries: Option<usize>,
+	call_retry_policy: CallRetryPolicy,
+}
+
+impl Default for ReconnectPolicy {
+	fn default() -> Self {
+		Self {
+			initial_delay: Duration::from_millis(100),
+			max_delay: Duration::from_secs(30),
+			factor: 2,
+			max_retries: None,
+			call_retry_policy: CallRetryPolicy::Drop,
+		}
+	}
+}
+
+impl ReconnectPolicy {
+	/// Create a new reconnect policy with the default exponential backoff.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Exponential backoff which starts at `initial_delay` and doubles after each failed attempt.
+	pub fn exponential_backoff(initial_delay: Duration) -> Self {
+		Self { initial_delay, ..Default::default() }
+	}
+
+	/// Wait `delay` between each reconnection attempt.
+	pub fn fixed_interval(delay: Duration) -> Self {
+		Self { initial_delay: delay, max_delay: delay, factor: 1, ..Default::default() }
+	}
+
+	/// Configure the maximum delay between two reconnection attempts (default is 30 seconds).
+	pub fn max_delay(mut self, max: Duration) -> Self {
+		self.max_delay = max;
+		self
+	}
+
+	/// Configure the factor the delay is multiplied with after each failed attempt (default is 2).
+	///
+	/// # Panics
+	///
+	/// This method panics if `factor` == 0.
+	pub fn factor(mut self, factor: u32) -> Self {
+		assert!(factor > 0);
+		self.factor = factor;
+		self
+	}
+
+	/// Configure how many times the client tries to reconnect before it gives up
+	/// (default is unlimited).
+	///
+	/// With `0` the client gives up as soon as the connection is lost.
+	pub fn max_retries(mut self, max: usize) -> Self {
+		self.max_retries = Some(max);
+		self
+	}
+
+	/// Configure what happens with calls that are in flight when the connection is lost
+	/// (default is [`CallRetryPolicy::Drop`]).
+	pub fn call_retry_policy(mut self, policy: CallRetryPolicy) -> Self {
+		self.call_retry_policy = policy;
+		self
+	}
+
+	fn delays(&self) -> Backoff {
+		Backoff {
+			next: self.initial_delay.min(self.max_delay),
+			max_delay: self.max_delay,
+			factor: self.factor,
+			// The first attempt is made without a delay.
+			remaining: self.max_retries.map(|max| max.saturating_sub(1)),
+		}
+	}
+}
+
+/// Iterator over the delays between reconnection attempts.
+#[derive(Debug)]
+struct Backoff {
+	next: Duration,
+	max_delay: Duration,
+	factor: u32,
+	remaining: Option<usize>,
+}
+
+impl Iterator for Backoff {
+	type Item = Duration;
+
+	fn next(&mut self) -> Option<Duration> {
+		if let Some(remaining) = self.remaining.as_mut() {
+			*remaining = remaining.checked_sub(1)?;
+		}
+
+		let delay = self.next;
+		self.next = self.next.saturating_mul(self.factor).min(self.max_delay);
+		Some(delay)
+	}
+}
+
+#[derive(Debug, Clone)]
+enum ConnectionStatus {
+	/// Connected where the number is how many times the client has reconnected.
+	Connected(u64),
+	Reconnecting,
+	/// The reconnection attempts were exhausted.
+	Closed(Arc<Error>),
+}
+
+#[derive(Debug)]
+struct Shared<Svc> {
+	client: RwLock<Arc<WsClient<Svc>>>,
+	status: watch::Sender<ConnectionStatus>,
+	/// How many times the client has reconnected.
+	reconnects: AtomicU64,
+	max_buffer_capacity_per_subscription: usize,
+	request_timeout: Duration,
+}
+
+impl<Svc> Shared<Svc> {
+	fn client(&self) -> Arc<WsClient<Svc>> {
+		self.client.read().expect(NOT_POISONED).clone()
+	}
+
+	/// Wait until the client is connected.
+	///
+	/// If `newer_than` is provided, a connection established before or at
+	/// that reconnection is not regarded as connected.
+	async fn connected(&self, newer_than: Option<u64>) -> Result<(u64, Arc<WsClient<Svc>>), Error> {
+		let mut rx = self.status.subscribe();
+		let status = rx
+			.wait_for(|s| match s {
+				ConnectionStatus::Connected(n) => newer_than.is_none_or(|prev| *n > prev),
+				ConnectionStatus::Reconnecting => false,
+				ConnectionStatus::Closed(_) => true,
+			})
+			.await
+			.map(|s| s.clone())
+			.unwrap_or_else(|_| ConnectionStatus::Closed(Arc::new(Error::Custom("Client dropped".into()))));
+
+		match status {
+			ConnectionStatus::Connected(n) => Ok((n, self.client())),
+			ConnectionStatus::Closed(err) => Err(Error::RestartNeeded(err)),
+			ConnectionStatus::Reconnecting => unreachable!("filtered out by `wait_for`; qed"),
+		}
+	}
+
+	/// Get the current connection if it's not being re-established.
+	fn connected_now(&self) -> Result<(u64, Arc<WsClient<Svc>>), Error> {
+		match &*self.status.borrow() {
+			ConnectionStatus::Connected(n) => Ok((*n, self.client())),
+			ConnectionStatus::Reconnecting => Err(Error::DisconnectedWillReconnect),
+			ConnectionStatus::Closed(err) => Err(Error::RestartNeeded(err.clone())),
+		}
+	}
+}
+
+/// WebSocket client which redials the same URL with a backoff policy
+/// once the connection is lost.
+///
+/// Calls that are in flight when the connection is lost are either replayed
+/// on the new connection or failed with [`Error::DisconnectedWillReconnect`],
+/// see [`CallRetryPolicy`].
+///
+/// Subscriptions are re-established on the new connection and the notifications are
+/// forwarded to the same [`Subscription`], such that the consumer doesn't notice the
+/// reconnect except for the notifications that were emitted while the client was disconnected.
+/// The `params` of the subscription are sent again as-is when re-subscribing.
+///
+/// The client is created by [`WsClientBuilder::build_with_reconnect`].
+#[derive(Debug)]
+pub struct ReconnectingWsClient<Svc> {
+	shared: Arc<Shared<Svc>>,
+	call_retry_policy: CallRetryPolicy,
+	/// When the client is dropped a message is sent to the reconnect task.
+	_on_exit: oneshot::Sender<()>,
+}
+
+impl<Svc> ReconnectingWsClient<Svc> {
+	/// Checks if the client is currently connected to the target.
+	pub fn is_connected(&self) -> bool {
+		matches!(*self.shared.status.borrow(), ConnectionStatus::Connected(_)) && self.shared.client().is_connected()
+	}
+
+	/// Returns how many times the client has reconnected.
+	pub fn reconnect_count(&self) -> u64 {
+		self.shared.reconnects.load(Ordering::Relaxed)
+	}
+
+	/// Completes when the client has given up to reconnect, see [`ReconnectPolicy::max_retries`].
+	///
+	/// # Cancel safety
+	///
+	/// This method is cancel safe.
+	pub async fn on_disconnect(&self) -> Error {
+		let mut rx = self.shared.status.subscribe();
+		match rx.wait_for(|s| matches!(s, ConnectionStatus::Closed(_))).await.map(|s| s.clone()) {
+			Ok(ConnectionStatus::Closed(err)) => Error::RestartNeeded(err),
+			_ => Error::Custom("Client dropped".into()),
+		}
+	}
+
+	async fn client(&self, newer_than: Option<u64>) -> Result<(u64, Arc<WsClient<Svc>>), Error> {
+		match self.call_retry_policy {
+			CallRetryPolicy::Retry => {
+				tokio::time::timeout(self.shared.request_timeout, self.shared.connected(newer_than))
+					.await
+					.map_err(|_| Error::RequestTimeout)?
+			}
+			CallRetryPolicy::Drop => self.shared.connected_now(),
+		}
+	}
+
+	/// Run a call until it succeeds or fails with an error that isn't caused by the connection being lost.
+	async fn call<T, F, Fut>(&self, mut f: F) -> Result<T, Error>
+	where
+		F: FnMut(u64, Arc<WsClient<Svc>>) -> Fut,
+		Fut: Future<Output = Result<T, Error>>,
+	{
+		let mut newer_than = None;
+
+		loop {
+			let (n, client) = self.client(newer_than).await?;
+
+			match f(n, client).await {
+				Err(Error::RestartNeeded(_)) if self.call_retry_policy == CallRetryPolicy::Retry => {
+					newer_than = Some(n);
+				}
+				Err(Error::RestartNeeded(_)) => return Err(Error::DisconnectedWillReconnect),
+				res => return res,
+			}
+		}
+	}
+}
+
+impl<Svc> ReconnectingWsClient<Svc>
+where
+	Svc: RpcServiceT<
+			MethodResponse = Result<MiddlewareMethodResponse, Error>,
+			BatchResponse = Result<MiddlewareBatchResponse, Error>,
+			NotificationResponse = Result<MiddlewareNotifResponse, Error>,
+		> + Send
+		+ Sync
+		+ 'static,
+{
+	async fn start_subscription<Notif>(&self, kind: Resubscribe) -> Result<Subscription<Notif>, Error> {
+		let (reconnects, sub) = self
+			.call(|n, client| {
+				let kind = &kind;
+				async move { kind.subscribe(&*client).await.map(|sub| (n, sub)) }
+			})
+			.await?;
+
+		let (tx, unsubscribed, front) =
+			detached_subscription(self.shared.max_buffer_capacity_per_subscription, sub.kind().clone());
+
+		tokio::spawn(subscription_task(SubscriptionTaskParams {
+			shared: self.shared.clone(),
+			kind,
+			sub,
+			reconnects,
+			tx,
+			unsubscribed,
+		}));
+
+		Ok(front)
+	}
+}
+
+impl<Svc> ClientT for ReconnectingWsClient<Svc>
+where
+	Svc: RpcServiceT<
+			MethodResponse = Result<MiddlewareMethodResponse, Error>,
+			BatchResponse = Result<MiddlewareBatchResponse, Error>,
+			NotificationResponse = Result<MiddlewareNotifResponse, Error>,
+		> + Send
+		+ Sync,
+{
+	fn notification<Params>(&self, method: &str, params: Params) -> impl Future<Output = Result<(), Error>> + Send
+	where
+		Params: ToRpcParams + Send,
+	{
+		async move {
+			let params = params.to_rpc_params()?;
+			self.call(|_, client| {
+				let params = RawParams(params.clone());
+				async move { client.notification(method, params).await }
+			})
+			.await
+		}
+	}
+
+	fn request<R, Params>(&self, method: &str, params: Params) -> impl Future<Output = Result<R, Error>> + Send
+	where
+		R: DeserializeOwned,
+		Params: ToRpcParams + Send,
+	{
+		async move {
+			let params = params.to_rpc_params()?;
+			self.call(|_, client| {
+				let params = RawParams(params.clone());
+				async move { client.request(method, params).await }
+			})
+			.await
+		}
+	}
+
+	fn batch_request<'a, R>(
+		&self,
+		batch: BatchRequestBuilder<'a>,
+	) -> impl Future<Output = Result<BatchResponse<'a, R>, Error>> + Send
+	where
+		R: DeserializeOwned + std::fmt::Debug + 'a,
+	{
+		async move {
+			self.call(|_, client| {
+				let batch = batch.clone();
+				async move { client.batch_request(batch).await }
+			})
+			.await
+		}
+	}
+}
+
+impl<Svc> SubscriptionClientT for ReconnectingWsClient<Svc>
+where
+	Svc: RpcServiceT<
+			MethodResponse = Result<MiddlewareMethodResponse, Error>,
+			BatchResponse = Result<MiddlewareBatchResponse, Error>,
+			NotificationResponse = Result<MiddlewareNotifResponse, Error>,
+		> + Send
+		+ Sync
+		+ 'static,
+{
+	fn subscribe<'a, Notif, Params>(
+		&self,
+		subscribe_method: &'a str,
+		params: Params,
+		unsubscribe_method: &'a str,
+	) -> impl Future<Output = Result<Subscription<Notif>, Error>> + Send
+	where
+		Params: ToRpcParams + Send,
+		Notif: DeserializeOwned,
+	{
+		async move {
+			let kind = Resubscribe::Subscription {
+				subscribe_method: subscribe_method.to_owned(),
+				unsubscribe_method: unsubscribe_method.to_owned(),
+				params: params.to_rpc_params()?,
+			};
+			self.start_subscription(kind).await
+		}
+	}
+
+	fn subscribe_to_method<Notif>(
+		&self,
+		method: &str,
+	) -> impl Future<Output = Result<Subscription<Notif>, Error>> + Send
+	where
+		Notif: DeserializeOwned,
+	{
+		self.start_subscription(Resubscribe::Method(method.to_owned()))
+	}
+}
+
+struct SubscriptionTaskParams<Svc> {
+	shared: Arc<Shared<Svc>>,
+	kind: Resubscribe,
+	sub: Subscription<Box<JsonRawValue>>,
+	reconnects: u64,
+	tx: DetachedSender,
+	/// Completes when the [`Subscription`] is unsubscribed or dropped.
+	unsubscribed: UnsubscribeSignal,
+}
+
+/// Forwards the notifications to the [`Subscription`] and
+/// re-subscribes once the client has reconnected.
+async fn subscription_task<Svc>(params: SubscriptionTaskParams<Svc>)
+where
+	Svc: RpcServiceT<
+			MethodResponse = Result<MiddlewareMethodResponse, Error>,
+			BatchResponse = Result<MiddlewareBatchResponse, Error>,
+			NotificationResponse = Result<MiddlewareNotifResponse, Error>,
+		> + Send
+		+ Sync,
+{
+	let SubscriptionTaskParams { shared, kind, mut sub, mut reconnects, tx, mut unsubscribed } = params;
+
+	loop {
+		let send = |notif| std::future::ready(tx.send(notif));
+
+		match forward_notifications(sub, unsubscribed.recv(), send).await {
+			SubscriptionEnd::Unsubscribed => return,
+			SubscriptionEnd::Lagged => {
+				tx.set_lagged();
+				return;
+			}
+			SubscriptionEnd::Closed => return,
+			SubscriptionEnd::ConnectionLost => (),
+		}
+
+		loop {
+			let connected = tokio::select! {
+				_ = unsubscribed.recv() => return,
+				c = shared.connected(Some(reconnects)) => c,
+			};
+
+			// The client has given up to reconnect.
+			let Ok((n, client)) = connected else {
+				return;
+			};
+
+			reconnects = n;
+
+			match kind.subscribe(&*client).await {
+				Ok(s) => {
+					sub = s;
+					break;
+				}
+				// The connection was lost again before the subscription was re-established.
+				Err(Error::RestartNeeded(_)) => continue,
+				Err(_) => return,
+			}
+		}
+	}
+}
+
+impl<RpcMiddleware> WsClientBuilder<RpcMiddleware> {
+	/// Build a [`ReconnectingWsClient`] which connects to the specified URL and redials it
+	/// according to `policy` whenever the connection is lost.
+	///
+	/// Fails if the initial connection can't be established.
+	///
+	/// ## Panics
+	///
+	/// Panics if being called outside of `tokio` runtime context.
+	pub async fn build_with_reconnect<S>(
+		self,
+		url: impl Into<String>,
+		policy: ReconnectPolicy,
+	) -> Result<ReconnectingWsClient<S>, Error>
+	where
+		RpcMiddleware: tower::Layer<RpcService, Service = S> + Clone + Send + Sync + 'static,
+		S: Send + Sync + 'static,
+	{
+		let url = url.into();
+		let max_buffer_capacity_per_subscription = self.max_buffer_capacity_per_subscription;
+		let request_timeout = self.request_timeout;
+		let client = self.clone().build(&url).await?;
+		let (status, _) = watch::channel(ConnectionStatus::Connected(0));
+		let (on_exit, exit) = oneshot::channel();
+
+		let shared = Arc::new(Shared {
+			client: RwLock::new(Arc::new(client)),
+			status,
+			reconnects: AtomicU64::new(0),
+			max_buffer_capacity_per_subscription,
+			request_timeout,
+		});
+
+		tokio::spawn(reconnect_task(shared.clone(), self, url, policy, exit));
+
+		Ok(ReconnectingWsClient { shared, call_retry_policy: policy.call_retry_policy, _on_exit: on_exit })
+	}
+}
+
+async fn reconnect_task<S, RpcMiddleware>(
+	shared: Arc<Shared<S>>,
+	builder: WsClientBuilder<RpcMiddleware>,
+	url: String,
+	policy: ReconnectPolicy,
+	mut exit: oneshot::Receiver<()>,
+) where
+	RpcMiddleware: tower::Layer<RpcService, Service = S> + Clone + Send + Sync + 'static,
+{
+	let mut reconnects = 0;
+
+	loop {
+		let client = shared.client();
+		let err = tokio::select! {
+			err = client.on_disconnect() => err,
+			_ = &mut exit => return,
+		};
+		drop(client);
+
+		if policy.max_retries == Some(0) {
+			shared.status.send_replace(ConnectionStatus::Closed(Arc::new(err)));
+			return;
+		}
+
+		shared.status.send_replace(ConnectionStatus::Reconnecting);
+		let mut delays = policy.delays();
+
+		loop {
+			let res = tokio::select! {
+				res = builder.clone().build(&url) => res,
+				_ = &mut exit => return,
+			};
+
+			let err = match res {
+				Ok(client) => {
+					reconnects += 1;
+					*shared.client.write().expect(NOT_POISONED) = Arc::new(client);
+					shared.reconnects.store(reconnects, Ordering::Relaxed);
+					shared.status.send_replace(ConnectionStatus::Connected(reconnects));
+					break;
+				}
+				Err(err) => err,
+			};
+
+			let Some(delay) = delays.next() else {
+				shared.status.send_replace(ConnectionStatus::Closed(Arc::new(err)));
+				return;
+			};
+
+			tokio::select! {
+				_ = tokio::time::sleep(delay) => (),
+				_ = &mut exit => return,
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn max_retries_limits_the_attempts() {
+		// The first attempt is made right away, then one delay per retry.
+		let policy = ReconnectPolicy::fixed_interval(Duration::from_millis(10)).max_retries(3);
+		assert_eq!(policy.delays().count(), 2);
+
+		let policy =
+			ReconnectPolicy::exponential_backoff(Duration::from_millis(10)).max_delay(Duration::from_millis(30));
+		let delays: Vec<_> = policy.delays().take(4).map(|d| d.as_millis()).collect();
+		assert_eq!(delays, [10, 20, 30, 30]);
+	}
+
+	#[test]
+	fn calls_are_not_retried_by_default() {
+		assert_eq!(ReconnectPolicy::default().call_retry_policy, CallRetryPolicy::Drop);
+	}
+}
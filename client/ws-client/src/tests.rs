@@ -24,8 +24,8 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::WsClientBuilder;
 use crate::types::error::{ErrorCode, ErrorObject};
+use crate::{CallRetryPolicy, ReconnectPolicy, WsClientBuilder};
 
 use jsonrpsee_core::client::{
 	BatchResponse, ClientT, Error, IdKind, Subscription, SubscriptionClientT, SubscriptionCloseReason,
@@ -38,6 +38,7 @@ use jsonrpsee_test_utils::mocks::{Id, WebSocketTestServer};
 use jsonrpsee_types::error::ErrorObjectOwned;
 use jsonrpsee_types::{Notification, SubscriptionId, SubscriptionPayload, SubscriptionResponse};
 use serde_json::Value as JsonValue;
+use std::time::Duration;
 
 fn init_logger() {
 	let _ = tracing_subscriber::FmtSubscriber::builder()
@@ -501,3 +502,159 @@ async fn redirections() {
 	let response: String = client.request("anything", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
 	assert_eq!(response, String::from(expected));
 }
+
+#[tokio::test]
+async fn reconnect_works() {
+	init_logger();
+
+	let response = ok_response("hello".into(), Id::Num(0));
+	let mut server = WebSocketTestServer::with_hardcoded_response("127.0.0.1:0".parse().unwrap(), response.clone())
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let addr = server.local_addr();
+	let policy = ReconnectPolicy::fixed_interval(Duration::from_millis(50)).call_retry_policy(CallRetryPolicy::Retry);
+	let client = WsClientBuilder::default()
+		.build_with_reconnect(to_ws_uri_string(addr), policy)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	let rp: String = client.request("say_hello", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(rp, "hello");
+	assert_eq!(client.reconnect_count(), 0);
+
+	server.close().await;
+	// give the server some time to release the address.
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	let _server = WebSocketTestServer::with_hardcoded_response(addr, response).with_default_timeout().await.unwrap();
+
+	// The call is retried once the client has reconnected.
+	let rp: String = client.request("say_hello", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(rp, "hello");
+	assert_eq!(client.reconnect_count(), 1);
+	assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn reconnect_with_drop_policy_fails_calls() {
+	init_logger();
+
+	let mut server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0)),
+	)
+	.with_default_timeout()
+	.await
+	.unwrap();
+	let policy = ReconnectPolicy::fixed_interval(Duration::from_millis(50)).call_retry_policy(CallRetryPolicy::Drop);
+	let client = WsClientBuilder::default()
+		.build_with_reconnect(to_ws_uri_string(server.local_addr()), policy)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	server.close().await;
+	tokio::time::sleep(Duration::from_millis(100)).await;
+
+	let err = client.request::<String, _>("say_hello", rpc_params![]).with_default_timeout().await.unwrap();
+	assert!(matches!(err, Err(Error::DisconnectedWillReconnect)));
+	assert!(!client.is_connected());
+}
+
+#[tokio::test]
+async fn reconnect_retried_calls_time_out() {
+	init_logger();
+
+	let mut server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0)),
+	)
+	.with_default_timeout()
+	.await
+	.unwrap();
+	let policy = ReconnectPolicy::fixed_interval(Duration::from_millis(50)).call_retry_policy(CallRetryPolicy::Retry);
+	let client = WsClientBuilder::default()
+		.request_timeout(Duration::from_millis(200))
+		.build_with_reconnect(to_ws_uri_string(server.local_addr()), policy)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	server.close().await;
+	tokio::time::sleep(Duration::from_millis(100)).await;
+
+	// The server is down, so the call waits for the reconnect no longer than the request timeout.
+	let err = client.request::<String, _>("say_hello", rpc_params![]).with_default_timeout().await.unwrap();
+	assert!(matches!(err, Err(Error::RequestTimeout)));
+}
+
+#[tokio::test]
+async fn reconnect_gives_up_after_max_retries() {
+	init_logger();
+
+	let mut server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0)),
+	)
+	.with_default_timeout()
+	.await
+	.unwrap();
+	let policy = ReconnectPolicy::exponential_backoff(Duration::from_millis(10)).max_retries(2);
+	let client = WsClientBuilder::default()
+		.build_with_reconnect(to_ws_uri_string(server.local_addr()), policy)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	server.close().await;
+
+	assert!(matches!(client.on_disconnect().with_default_timeout().await.unwrap(), Error::RestartNeeded(_)));
+	let err = client.request::<String, _>("say_hello", rpc_params![]).with_default_timeout().await.unwrap();
+	assert!(matches!(err, Err(Error::RestartNeeded(_))));
+}
+
+#[tokio::test]
+async fn subscription_survives_reconnect() {
+	init_logger();
+
+	let sub_id = server_subscription_id_response(Id::Num(0));
+	let notif = server_subscription_response("subscribe_hello", "hello my friend".into());
+	let mut server =
+		WebSocketTestServer::with_hardcoded_subscription("127.0.0.1:0".parse().unwrap(), sub_id.clone(), notif.clone())
+			.with_default_timeout()
+			.await
+			.unwrap();
+	let addr = server.local_addr();
+	let client = WsClientBuilder::default()
+		.build_with_reconnect(to_ws_uri_string(addr), ReconnectPolicy::fixed_interval(Duration::from_millis(50)))
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	let mut sub = client
+		.subscribe::<String, _>("subscribe_hello", rpc_params![], "unsubscribe_hello")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(sub.next().with_default_timeout().await.unwrap().unwrap().unwrap(), "hello my friend");
+
+	server.close().await;
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	let _server =
+		WebSocketTestServer::with_hardcoded_subscription(addr, sub_id, notif).with_default_timeout().await.unwrap();
+
+	// Drain the notifications that were buffered before the connection was lost.
+	while client.reconnect_count() == 0 {
+		let _ = sub.next().with_default_timeout().await.unwrap().unwrap().unwrap();
+	}
+
+	assert_eq!(sub.next().with_default_timeout().await.unwrap().unwrap().unwrap(), "hello my friend");
+	assert!(sub.close_reason().is_none());
+}
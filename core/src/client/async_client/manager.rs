@@ -102,6 +102,18 @@ impl RequestManager {
 		Self::default()
 	}
 
+	/// Record that the active subscriptions are closed because the connection was lost.
+	pub(crate) fn connection_closed(&self) {
+		let subscriptions = self.requests.values().filter_map(|kind| match kind {
+			Kind::Subscription((_, sink, _)) => Some(sink),
+			_ => None,
+		});
+
+		for sink in subscriptions.chain(self.notification_handlers.values()) {
+			sink.close_state.set_connection_closed();
+		}
+	}
+
 	/// Tries to insert a new pending request.
 	///
 	/// Returns `Ok` if the pending request was successfully inserted otherwise `Err`.
@@ -58,6 +58,8 @@ use helpers::{
 use http::Extensions;
 use jsonrpsee_types::response::SubscriptionError;
 use jsonrpsee_types::{InvalidRequestId, ResponseSuccess, TwoPointZero};
+#[cfg(feature = "server")]
+use jsonrpsee_types::{ErrorCode, ErrorObject};
 use jsonrpsee_types::{Response, SubscriptionResponse};
 use manager::RequestManager;
 use serde::de::DeserializeOwned;
@@ -192,6 +194,8 @@ pub struct ClientBuilder<L = Logger> {
 	id_kind: IdKind,
 	ping_config: Option<PingConfig>,
 	tcp_no_delay: bool,
+	#[cfg(feature = "server")]
+	methods: crate::server::Methods,
 	service_builder: RpcServiceBuilder<L>,
 }
 
@@ -204,6 +208,8 @@ impl Default for ClientBuilder {
 			id_kind: IdKind::Number,
 			ping_config: None,
 			tcp_no_delay: true,
+			#[cfg(feature = "server")]
+			methods: crate::server::Methods::new(),
 			service_builder: RpcServiceBuilder::default().rpc_logger(1024),
 		}
 	}
@@ -224,6 +230,8 @@ impl<L> ClientBuilder<L> {
 	}
 
 	/// Set max concurrent requests (default is 256).
+	///
+	/// This also limits how many requests of the server are answered concurrently, see `set_methods`.
 	pub fn max_concurrent_requests(mut self, max: usize) -> Self {
 		self.max_concurrent_requests = max;
 		self
@@ -280,6 +288,20 @@ impl<L> ClientBuilder<L> {
 		self
 	}
 
+	/// Answer the requests of the server with the local `methods`.
+	///
+	/// Requests to unknown methods are answered with a `method not found` error
+	/// and subscriptions are not supported. Once `max_concurrent_requests` requests are
+	/// being answered, further requests are answered with a `server is busy` error.
+	///
+	/// Default: no methods.
+	#[cfg(feature = "server")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+	pub fn set_methods(mut self, methods: impl Into<crate::server::Methods>) -> Self {
+		self.methods = methods.into();
+		self
+	}
+
 	/// Configure the client to a specific RPC middleware which
 	/// runs for every JSON-RPC call.
 	///
@@ -292,6 +314,8 @@ impl<L> ClientBuilder<L> {
 			id_kind: self.id_kind,
 			ping_config: self.ping_config,
 			tcp_no_delay: self.tcp_no_delay,
+			#[cfg(feature = "server")]
+			methods: self.methods,
 			service_builder,
 		}
 	}
@@ -356,6 +380,10 @@ impl<L> ClientBuilder<L> {
 			max_buffer_capacity_per_subscription: self.max_buffer_capacity_per_subscription,
 			inactivity_check,
 			inactivity_stream,
+			#[cfg(feature = "server")]
+			local_methods: LocalMethods::new(self.methods, to_back.clone(), self.max_concurrent_requests),
+			#[cfg(not(feature = "server"))]
+			local_methods: LocalMethods,
 		}));
 
 		tokio::spawn(wait_for_shutdown(send_receive_task_sync_rx, client_dropped_rx, disconnect_reason.clone()));
@@ -411,6 +439,10 @@ impl<L> ClientBuilder<L> {
 			max_buffer_capacity_per_subscription: self.max_buffer_capacity_per_subscription,
 			inactivity_check,
 			inactivity_stream,
+			#[cfg(feature = "server")]
+			local_methods: LocalMethods::new(self.methods, to_back.clone(), self.max_concurrent_requests),
+			#[cfg(not(feature = "server"))]
+			local_methods: LocalMethods,
 		}));
 
 		wasm_bindgen_futures::spawn_local(wait_for_shutdown(
@@ -632,7 +664,12 @@ where
 				.await?
 				.into_subscription()
 				.expect("Extensions set to subscription, must return subscription; qed");
-			Ok(Subscription::new(self.to_back.clone(), sub.stream, SubscriptionKind::Subscription(sub.sub_id)))
+			Ok(Subscription::new(
+				self.to_back.clone(),
+				sub.stream,
+				SubscriptionKind::Subscription(sub.sub_id),
+				Some(sub.on_close),
+			))
 		}
 	}
 
@@ -664,7 +701,7 @@ where
 				Err(_) => return Err(self.on_disconnect().await),
 			};
 
-			Ok(Subscription::new(self.to_back.clone(), rx, SubscriptionKind::Method(method)))
+			Ok(Subscription::new(self.to_back.clone(), rx, SubscriptionKind::Method(method), None))
 		}
 	}
 }
@@ -676,12 +713,14 @@ fn handle_backend_messages<R: TransportReceiverT>(
 	message: Option<Result<ReceivedMessage, R::Error>>,
 	manager: &ThreadSafeRequestManager,
 	max_buffer_capacity_per_subscription: usize,
+	local_methods: &LocalMethods,
 ) -> Result<Vec<FrontToBack>, Error> {
 	// Handle raw messages of form `ReceivedMessage::Bytes` (Vec<u8>) or ReceivedMessage::Data` (String).
 	fn handle_recv_message(
 		raw: &[u8],
 		manager: &ThreadSafeRequestManager,
 		max_buffer_capacity_per_subscription: usize,
+		local_methods: &LocalMethods,
 	) -> Result<Vec<FrontToBack>, Error> {
 		let first_non_whitespace = raw.iter().find(|byte| !byte.is_ascii_whitespace());
 		let mut messages = Vec::new();
@@ -712,7 +751,11 @@ fn handle_backend_messages<R: TransportReceiverT>(
 				else if let Ok(response) = serde_json::from_slice::<SubscriptionError<_>>(raw) {
 					process_subscription_close_response(&mut manager.lock(), response);
 				}
-				// Incoming Notification
+				// Incoming request of the server.
+				else if local_methods.answer(raw) {
+					// Answered in the background.
+				}
+				// Incoming Notification.
 				else if let Ok(notif) = serde_json::from_slice::<Notification>(raw) {
 					process_notification(&mut manager.lock(), notif);
 				} else {
@@ -782,10 +825,10 @@ fn handle_backend_messages<R: TransportReceiverT>(
 			Ok(vec![])
 		}
 		Some(Ok(ReceivedMessage::Bytes(raw))) => {
-			handle_recv_message(raw.as_ref(), manager, max_buffer_capacity_per_subscription)
+			handle_recv_message(raw.as_ref(), manager, max_buffer_capacity_per_subscription, local_methods)
 		}
 		Some(Ok(ReceivedMessage::Text(raw))) => {
-			handle_recv_message(raw.as_ref(), manager, max_buffer_capacity_per_subscription)
+			handle_recv_message(raw.as_ref(), manager, max_buffer_capacity_per_subscription, local_methods)
 		}
 		Some(Err(e)) => Err(Error::Transport(e.into())),
 		None => Err(Error::Custom("TransportReceiver dropped".into())),
@@ -813,6 +856,11 @@ async fn handle_frontend_messages<S: TransportSenderT>(
 		FrontToBack::Notification(notif) => {
 			sender.send(notif).await?;
 		}
+		// A local method answered a request of the server.
+		#[cfg(feature = "server")]
+		FrontToBack::Response(response) => {
+			sender.send(response).await?;
+		}
 		// User called `request` on the front-end
 		FrontToBack::Request(request) => {
 			if let Err(send_back) = manager.lock().insert_pending_call(request.id.clone(), request.send_back) {
@@ -943,6 +991,8 @@ where
 		}
 	};
 
+	// The subscriptions are dropped with the manager once both tasks are closed.
+	manager.lock().connection_closed();
 	from_frontend.close();
 	let _ = sender.close().await;
 	let _ = close_tx.send(res).await;
@@ -956,6 +1006,7 @@ struct ReadTaskParams<R: TransportReceiverT, S> {
 	max_buffer_capacity_per_subscription: usize,
 	inactivity_check: InactivityCheck,
 	inactivity_stream: IntervalStream<S>,
+	local_methods: LocalMethods,
 }
 
 async fn read_task<R, S>(params: ReadTaskParams<R, S>)
@@ -971,6 +1022,7 @@ where
 		max_buffer_capacity_per_subscription,
 		mut inactivity_check,
 		mut inactivity_stream,
+		local_methods,
 	} = params;
 
 	let backend_event = futures_util::stream::unfold(receiver, |mut receiver| async {
@@ -1000,7 +1052,7 @@ where
 				inactivity_check.mark_as_active();
 				let Some(msg) = maybe_msg else { break Ok(()) };
 
-				match handle_backend_messages::<R>(Some(msg), &manager, max_buffer_capacity_per_subscription) {
+				match handle_backend_messages::<R>(Some(msg), &manager, max_buffer_capacity_per_subscription, &local_methods) {
 					Ok(messages) => {
 						for msg in messages {
 							pending_unsubscribes.push(to_send_task.send(msg));
@@ -1020,9 +1072,72 @@ where
 		}
 	};
 
+	manager.lock().connection_closed();
 	let _ = close_tx.send(res).await;
 }
 
+/// Local methods which answer the requests of the server, see `ClientBuilder::set_methods`.
+#[cfg(feature = "server")]
+struct LocalMethods {
+	methods: crate::server::Methods,
+	to_send_task: mpsc::Sender<FrontToBack>,
+	/// Limits the number of requests which are answered concurrently.
+	permits: Arc<tokio::sync::Semaphore>,
+}
+
+/// Without the `server` feature the requests of the server are treated as notifications.
+#[cfg(not(feature = "server"))]
+struct LocalMethods;
+
+#[cfg(feature = "server")]
+impl LocalMethods {
+	fn new(methods: crate::server::Methods, to_send_task: mpsc::Sender<FrontToBack>, max_concurrent: usize) -> Self {
+		Self { methods, to_send_task, permits: Arc::new(tokio::sync::Semaphore::new(max_concurrent)) }
+	}
+
+	/// Answer the message in the background if it's a request.
+	///
+	/// Returns whether the message was a request.
+	fn answer(&self, raw: &[u8]) -> bool {
+		let Ok(raw) = std::str::from_utf8(raw) else {
+			return false;
+		};
+		let Ok(request) = serde_json::from_str::<jsonrpsee_types::Request>(raw) else {
+			return false;
+		};
+
+		let Ok(permit) = self.permits.clone().try_acquire_owned() else {
+			tracing::debug!(target: LOG_TARGET, "Too many concurrent requests of the server, rejecting `{}`", request.method);
+			let busy = crate::server::MethodResponse::error(request.id, ErrorObject::from(ErrorCode::ServerIsBusy));
+			let _ = self.to_send_task.try_send(FrontToBack::Response(busy.into_json().get().to_owned()));
+			return true;
+		};
+		let request = raw.to_owned();
+		let methods = self.methods.clone();
+		let to_send_task = self.to_send_task.clone();
+
+		tokio::spawn(async move {
+			let _permit = permit;
+
+			match methods.raw_json_request(&request, 1).await {
+				Ok((response, _)) => {
+					let _ = to_send_task.send(FrontToBack::Response(response.get().to_owned())).await;
+				}
+				Err(e) => tracing::debug!(target: LOG_TARGET, "Failed to answer the request of the server: {e}"),
+			}
+		});
+
+		true
+	}
+}
+
+#[cfg(not(feature = "server"))]
+impl LocalMethods {
+	fn answer(&self, _raw: &[u8]) -> bool {
+		false
+	}
+}
+
 async fn wait_for_shutdown(
 	mut close_rx: mpsc::Receiver<Result<(), Error>>,
 	client_dropped: oneshot::Receiver<()>,
@@ -3,10 +3,12 @@ use crate::{
 		BatchMessage, Error, FrontToBack, MiddlewareBatchResponse, MiddlewareMethodResponse, MiddlewareNotifResponse,
 		RequestMessage, SubscriptionMessage, SubscriptionResponse,
 	},
+	middleware::layer::{SubscriptionCloseHook, TraceContext, WithTraceParent},
 	middleware::{Batch, IsBatch, IsSubscription, Notification, Request, RpcServiceT},
 };
 
 use jsonrpsee_types::{Response, ResponsePayload};
+use serde::Serialize;
 use tokio::sync::{mpsc, oneshot};
 
 impl From<mpsc::error::SendError<FrontToBack>> for Error {
@@ -43,7 +45,7 @@ impl RpcServiceT for RpcService {
 		let tx = self.0.clone();
 
 		async move {
-			let raw = serde_json::to_string(&request)?;
+			let raw = to_json_with_traceparent(&request, request.extensions.get::<TraceContext>())?;
 
 			match request.extensions.get::<IsSubscription>() {
 				Some(sub) => {
@@ -65,7 +67,7 @@ impl RpcServiceT for RpcService {
 
 					Ok(MiddlewareMethodResponse::subscription_response(
 						Response::new(ResponsePayload::success(rp), request.id.clone().into_owned()).into(),
-						SubscriptionResponse { sub_id, stream: subscribe_rx },
+						SubscriptionResponse { sub_id, stream: subscribe_rx, on_close: SubscriptionCloseHook::new().1 },
 					))
 				}
 				None => {
@@ -93,7 +95,18 @@ impl RpcServiceT for RpcService {
 		async move {
 			let (send_back_tx, send_back_rx) = oneshot::channel();
 
-			let raw = serde_json::to_string(&batch)?;
+			let raw = match batch.extensions().get::<TraceContext>() {
+				Some(_) => {
+					// The client doesn't put errors into the batches it sends.
+					let entries = batch
+						.iter()
+						.flatten()
+						.map(|entry| to_json_with_traceparent(entry, entry.extensions().get::<TraceContext>()))
+						.collect::<Result<Vec<_>, _>>()?;
+					format!("[{}]", entries.join(","))
+				}
+				None => serde_json::to_string(&batch)?,
+			};
 			let id_range = batch
 				.extensions()
 				.get::<IsBatch>()
@@ -111,9 +124,20 @@ impl RpcServiceT for RpcService {
 		let tx = self.0.clone();
 
 		async move {
-			let raw = serde_json::to_string(&n)?;
+			let raw = to_json_with_traceparent(&n, n.extensions.get::<TraceContext>())?;
 			tx.send(FrontToBack::Notification(raw)).await?;
 			Ok(MiddlewareNotifResponse::from(n.extensions))
 		}
 	}
 }
+
+/// Serialize the message with the reserved `traceparent` member, if there is a trace context.
+fn to_json_with_traceparent<T: Serialize>(
+	message: &T,
+	trace_context: Option<&TraceContext>,
+) -> Result<String, serde_json::Error> {
+	match trace_context {
+		Some(cx) => serde_json::to_string(&WithTraceParent { message, traceparent: cx.to_traceparent() }),
+		None => serde_json::to_string(message),
+	}
+}
@@ -32,6 +32,7 @@ use std::sync::Arc;
 
 /// Error type.
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
 	/// JSON-RPC error which can occur when a JSON-RPC call fails.
 	#[error("{0}")]
@@ -66,6 +67,13 @@ pub enum Error {
 	/// The error returned when registering a method or subscription failed.
 	#[error(transparent)]
 	RegisterMethod(#[from] RegisterMethodError),
+	/// The connection was lost while the call was in flight and the client
+	/// is reconnecting, but the call is not replayed on the new connection.
+	#[error("The client was disconnected and is reconnecting; the call was dropped")]
+	DisconnectedWillReconnect,
+	/// None of the endpoints of the pool is healthy.
+	#[error("No healthy endpoint available")]
+	NoHealthyEndpoint,
 	/// An internal state when the underlying RpcService
 	/// got disconnected and the error must be fetched
 	/// from the backend.
@@ -31,6 +31,17 @@ cfg_async_client! {
 	pub use async_client::{Client, ClientBuilder};
 }
 
+#[cfg(feature = "async-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async-client")))]
+pub mod pool;
+#[cfg(feature = "async-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async-client")))]
+pub use pool::{LoadBalancing, PoolClient, PoolClientBuilder};
+// Shared with the reconnecting WebSocket client.
+#[cfg(feature = "async-client")]
+#[doc(hidden)]
+pub mod resubscribe;
+
 pub mod error;
 
 pub use error::Error;
@@ -39,10 +50,13 @@ use std::fmt;
 use std::future::Future;
 use std::ops::{Deref, Range};
 use std::pin::Pin;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
 use std::task::{self, Poll};
 
+use crate::middleware::layer::{
+	MetricsResponse, Outcome, ResponseOutcome, RetryResponse, SubscriptionCloseGuard, SubscriptionCloseHook, serialized_size,
+};
 use crate::params::BatchRequestBuilder;
 use crate::traits::{ToJson, ToRpcParams};
 
@@ -56,27 +70,37 @@ use serde_json::value::RawValue;
 use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::{mpsc, oneshot};
 
-/// Shared state whether a subscription has lagged or not.
+/// Shared state why a subscription was closed by the client, if it was.
 #[derive(Debug, Clone)]
-pub(crate) struct SubscriptionLagged(Arc<RwLock<bool>>);
+pub(crate) struct SubscriptionCloseState(Arc<RwLock<Option<SubscriptionCloseReason>>>);
 
 /// Owned version of [`RawResponse`].
 pub type RawResponseOwned = RawResponse<'static>;
 
-impl SubscriptionLagged {
-	/// Create a new [`SubscriptionLagged`].
+impl SubscriptionCloseState {
+	/// Create a new [`SubscriptionCloseState`].
 	pub(crate) fn new() -> Self {
-		Self(Arc::new(RwLock::new(false)))
+		Self(Arc::new(RwLock::new(None)))
 	}
 
 	/// A message has been missed.
 	pub(crate) fn set_lagged(&self) {
-		*self.0.write().expect("RwLock not poised; qed") = true;
+		*self.0.write().expect("RwLock not poised; qed") = Some(SubscriptionCloseReason::Lagged);
+	}
+
+	/// The connection was lost, which is only recorded if the subscription didn't lag before.
+	pub(crate) fn set_connection_closed(&self) {
+		self.0.write().expect("RwLock not poised; qed").get_or_insert(SubscriptionCloseReason::ConnectionClosed);
 	}
 
 	/// Check whether the subscription has missed a message.
 	pub(crate) fn has_lagged(&self) -> bool {
-		*self.0.read().expect("RwLock not poised; qed")
+		matches!(*self.0.read().expect("RwLock not poised; qed"), Some(SubscriptionCloseReason::Lagged))
+	}
+
+	/// Check whether the subscription was closed because the connection was lost.
+	pub(crate) fn connection_closed(&self) -> bool {
+		matches!(*self.0.read().expect("RwLock not poised; qed"), Some(SubscriptionCloseReason::ConnectionClosed))
 	}
 }
 
@@ -280,6 +304,8 @@ pub struct Subscription<Notif> {
 	rx: SubscriptionReceiver,
 	/// Callback kind.
 	kind: Option<SubscriptionKind>,
+	/// Runs the hooks of the middleware once the subscription is dropped.
+	_on_close: Option<SubscriptionCloseGuard>,
 	/// Marker in order to pin the `Notif` parameter.
 	marker: PhantomData<Notif>,
 }
@@ -290,8 +316,13 @@ impl<Notif> std::marker::Unpin for Subscription<Notif> {}
 
 impl<Notif> Subscription<Notif> {
 	/// Create a new subscription.
-	fn new(to_back: mpsc::Sender<FrontToBack>, rx: SubscriptionReceiver, kind: SubscriptionKind) -> Self {
-		Self { to_back, rx, kind: Some(kind), marker: PhantomData, is_closed: false }
+	fn new(
+		to_back: mpsc::Sender<FrontToBack>,
+		rx: SubscriptionReceiver,
+		kind: SubscriptionKind,
+		on_close: Option<SubscriptionCloseGuard>,
+	) -> Self {
+		Self { to_back, rx, kind: Some(kind), _on_close: on_close, marker: PhantomData, is_closed: false }
 	}
 
 	/// Return the subscription type and, if applicable, ID.
@@ -319,7 +350,7 @@ impl<Notif> Subscription<Notif> {
 	/// Returns Some(reason) is the subscription was closed otherwise
 	/// None is returned.
 	pub fn close_reason(&self) -> Option<SubscriptionCloseReason> {
-		let lagged = self.rx.lagged.has_lagged();
+		let lagged = self.rx.close_state.has_lagged();
 
 		// `is_closed` is only set if the subscription has been polled
 		// and that is why lagged is checked here as well.
@@ -329,6 +360,12 @@ impl<Notif> Subscription<Notif> {
 
 		if lagged { Some(SubscriptionCloseReason::Lagged) } else { Some(SubscriptionCloseReason::ConnectionClosed) }
 	}
+
+	/// Returns whether the background task of the client closed the subscription because the connection was lost,
+	/// as opposed to the server closing the subscription.
+	pub(crate) fn connection_lost(&self) -> bool {
+		self.rx.close_state.connection_closed()
+	}
 }
 
 /// Batch request message.
@@ -388,6 +425,9 @@ enum FrontToBack {
 	Batch(BatchMessage),
 	/// Send a notification to the server.
 	Notification(String),
+	/// Send the response to a request of the server.
+	#[cfg(feature = "server")]
+	Response(String),
 	/// Send a request to the server.
 	Request(RequestMessage),
 	/// Send a subscription request to the server.
@@ -594,6 +634,11 @@ impl<'a, R: fmt::Debug + 'a> BatchResponse<'a, R> {
 	pub fn iter(&self) -> impl Iterator<Item = &BatchEntry<'_, R>> {
 		self.responses.iter()
 	}
+
+	/// Get the response of the call at `index` in the batch.
+	pub fn get(&self, index: usize) -> Option<&BatchEntry<'a, R>> {
+		self.responses.get(index)
+	}
 }
 
 impl<'a, R> IntoIterator for BatchResponse<'a, R> {
@@ -605,6 +650,109 @@ impl<'a, R> IntoIterator for BatchResponse<'a, R> {
 	}
 }
 
+/// Identity of a typed batch request, which ties the [`BatchCall`] handles to the response of their batch.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BatchId(u64);
+
+impl BatchId {
+	/// Create a new unique batch ID.
+	pub fn unique() -> Self {
+		static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+		Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+	}
+}
+
+/// Response of a typed batch request, which dereferences to the [`BatchResponse`].
+#[derive(Debug)]
+pub struct TypedBatchResponse {
+	id: BatchId,
+	response: BatchResponse<'static, Box<RawValue>>,
+}
+
+impl TypedBatchResponse {
+	/// Create the response of the batch request `id`.
+	pub fn new(id: BatchId, response: BatchResponse<'static, Box<RawValue>>) -> Self {
+		Self { id, response }
+	}
+
+	/// Get the ID of the batch request.
+	pub fn id(&self) -> BatchId {
+		self.id
+	}
+
+	/// Consume the response and return the untyped batch response.
+	pub fn into_inner(self) -> BatchResponse<'static, Box<RawValue>> {
+		self.response
+	}
+}
+
+impl Deref for TypedBatchResponse {
+	type Target = BatchResponse<'static, Box<RawValue>>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.response
+	}
+}
+
+/// Handle to the response of a call in a typed batch request.
+///
+/// The typed batch builders are generated by the `#[rpc(client, batch)]` macro, every call inserted
+/// into the builder returns a handle which deserializes the response of that call.
+pub struct BatchCall<R> {
+	batch: BatchId,
+	index: usize,
+	_marker: PhantomData<fn() -> R>,
+}
+
+impl<R> BatchCall<R> {
+	/// Create a handle to the call at `index` in the batch request `batch`.
+	pub fn new(batch: BatchId, index: usize) -> Self {
+		Self { batch, index, _marker: PhantomData }
+	}
+
+	/// Get the ID of the batch request.
+	pub fn batch_id(&self) -> BatchId {
+		self.batch
+	}
+
+	/// Get the index of the call in the batch.
+	pub fn index(&self) -> usize {
+		self.index
+	}
+}
+
+impl<R: DeserializeOwned> BatchCall<R> {
+	/// Deserialize the response of the call from the response of the batch.
+	///
+	/// Fails with [`Error::Call`] if the call returned a JSON-RPC error
+	/// and with [`Error::Custom`] if `batch` is the response of another batch request.
+	pub fn response(&self, batch: &TypedBatchResponse) -> Result<R, Error> {
+		if batch.id != self.batch {
+			return Err(Error::Custom("The batch response belongs to another batch request".into()));
+		}
+
+		match batch.get(self.index) {
+			Some(Ok(raw)) => serde_json::from_str(raw.get()).map_err(Error::ParseError),
+			Some(Err(err)) => Err(Error::Call(err.clone().into_owned())),
+			None => Err(Error::Custom(format!("Batch response has no entry for call {}", self.index))),
+		}
+	}
+}
+
+impl<R> Clone for BatchCall<R> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<R> Copy for BatchCall<R> {}
+
+impl<R> fmt::Debug for BatchCall<R> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("BatchCall").field("batch", &self.batch).field("index", &self.index).finish()
+	}
+}
+
 #[derive(thiserror::Error, Debug)]
 enum TrySubscriptionSendError {
 	#[error("The subscription is closed")]
@@ -616,7 +764,7 @@ enum TrySubscriptionSendError {
 #[derive(Debug)]
 pub(crate) struct SubscriptionSender {
 	inner: mpsc::Sender<Box<RawValue>>,
-	lagged: SubscriptionLagged,
+	pub(crate) close_state: SubscriptionCloseState,
 }
 
 impl SubscriptionSender {
@@ -625,7 +773,7 @@ impl SubscriptionSender {
 			Ok(_) => Ok(()),
 			Err(TrySendError::Closed(_)) => Err(TrySubscriptionSendError::Closed),
 			Err(TrySendError::Full(m)) => {
-				self.lagged.set_lagged();
+				self.close_state.set_lagged();
 				Err(TrySubscriptionSendError::TooSlow(m))
 			}
 		}
@@ -635,7 +783,7 @@ impl SubscriptionSender {
 #[derive(Debug)]
 pub(crate) struct SubscriptionReceiver {
 	inner: mpsc::Receiver<Box<RawValue>>,
-	lagged: SubscriptionLagged,
+	close_state: SubscriptionCloseState,
 }
 
 impl Stream for SubscriptionReceiver {
@@ -648,10 +796,12 @@ impl Stream for SubscriptionReceiver {
 
 fn subscription_channel(max_buf_size: usize) -> (SubscriptionSender, SubscriptionReceiver) {
 	let (tx, rx) = mpsc::channel(max_buf_size);
-	let lagged_tx = SubscriptionLagged::new();
-	let lagged_rx = lagged_tx.clone();
+	let close_state = SubscriptionCloseState::new();
 
-	(SubscriptionSender { inner: tx, lagged: lagged_tx }, SubscriptionReceiver { inner: rx, lagged: lagged_rx })
+	(
+		SubscriptionSender { inner: tx, close_state: close_state.clone() },
+		SubscriptionReceiver { inner: rx, close_state },
+	)
 }
 
 /// Represents an active subscription returned by the server.
@@ -662,6 +812,8 @@ pub struct SubscriptionResponse {
 	// The receiver is used to receive notifications from the server and shouldn't be exposed to the user
 	// from the middleware.
 	stream: SubscriptionReceiver,
+	/// Runs the [`SubscriptionCloseHook`] once the subscription is dropped.
+	on_close: SubscriptionCloseGuard,
 }
 
 impl SubscriptionResponse {
@@ -819,6 +971,60 @@ impl MiddlewareNotifResponse {
 	}
 }
 
+impl ResponseOutcome for Result<MiddlewareMethodResponse, Error> {
+	fn outcome(&self) -> Outcome {
+		match self {
+			Ok(rp) => rp.as_error().map_or(Outcome::Success, |err| Outcome::Error(err.code())),
+			Err(_) => Outcome::Failed,
+		}
+	}
+
+	fn size(&self) -> usize {
+		self.as_ref().map_or(0, serialized_size)
+	}
+}
+
+impl MetricsResponse for Result<MiddlewareMethodResponse, Error> {
+	fn subscription_id(&self) -> Option<String> {
+		let sub = self.as_ref().ok()?.subscription.as_ref()?;
+		serde_json::to_string(sub.subscription_id()).ok()
+	}
+
+	fn subscription_close_hook(&self) -> Option<SubscriptionCloseHook> {
+		Some(self.as_ref().ok()?.subscription.as_ref()?.on_close.hook())
+	}
+}
+
+impl RetryResponse for Result<MiddlewareMethodResponse, Error> {
+	fn error_code(&self) -> Option<i32> {
+		match self {
+			Ok(rp) => rp.as_error().map(|err| err.code()),
+			Err(Error::Call(err)) => Some(err.code()),
+			Err(_) => None,
+		}
+	}
+
+	fn is_transport_error(&self) -> bool {
+		matches!(self, Err(Error::Transport(_) | Error::RequestTimeout))
+	}
+}
+
+impl ResponseOutcome for Result<MiddlewareBatchResponse, Error> {
+	fn outcome(&self) -> Outcome {
+		if self.is_ok() { Outcome::Success } else { Outcome::Failed }
+	}
+
+	fn size(&self) -> usize {
+		self.as_ref().map_or(0, serialized_size)
+	}
+}
+
+impl MetricsResponse for Result<MiddlewareBatchResponse, Error> {
+	fn subscription_id(&self) -> Option<String> {
+		None
+	}
+}
+
 impl<T: Serialize> ToJson for Result<T, Error> {
 	fn to_json(&self) -> Result<Box<RawValue>, serde_json::Error> {
 		match self {
@@ -0,0 +1,811 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Client which balances the calls over a pool of identical endpoints.
+
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use futures_util::future::join_all;
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+use tokio::sync::{oneshot, watch};
+
+use super::resubscribe::{
+	DetachedSender, RawParams, Resubscribe, SubscriptionEnd, UnsubscribeSignal, detached_subscription,
+	forward_notifications,
+};
+use super::{BatchResponse, ClientT, Error, Subscription, SubscriptionClientT};
+use crate::params::BatchRequestBuilder;
+use crate::traits::ToRpcParams;
+
+const NOT_POISONED: &str = "Not poisoned; qed";
+
+/// Creates a client which is connected to an endpoint of the [`PoolClient`].
+///
+/// This is implemented for closures such as
+/// `|url: String| async move { WsClientBuilder::default().build(url).await }`.
+pub trait Connect: Send + Sync + 'static {
+	/// The client type.
+	type Client: SubscriptionClientT + Send + Sync + 'static;
+
+	/// Connect to `endpoint`.
+	fn connect(&self, endpoint: String) -> impl Future<Output = Result<Self::Client, Error>> + Send;
+}
+
+impl<F, Fut, C> Connect for F
+where
+	F: Fn(String) -> Fut + Send + Sync + 'static,
+	Fut: Future<Output = Result<C, Error>> + Send,
+	C: SubscriptionClientT + Send + Sync + 'static,
+{
+	type Client = C;
+
+	fn connect(&self, endpoint: String) -> impl Future<Output = Result<C, Error>> + Send {
+		self(endpoint)
+	}
+}
+
+/// How the [`PoolClient`] distributes the calls over the healthy endpoints.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LoadBalancing {
+	/// Use the healthy endpoints in turn.
+	RoundRobin,
+	/// Use the healthy endpoint with the lowest average response time.
+	///
+	/// Endpoints which haven't been measured yet are preferred.
+	LeastLatency,
+}
+
+/// Builder for [`PoolClient`].
+#[derive(Debug, Clone)]
+pub struct PoolClientBuilder {
+	load_balancing: LoadBalancing,
+	health_check_interval: Duration,
+	health_check_timeout: Duration,
+	health_check_method: Option<String>,
+	max_buffer_capacity_per_subscription: usize,
+	idempotent_methods: BTreeSet<String>,
+	all_idempotent: bool,
+}
+
+impl Default for PoolClientBuilder {
+	fn default() -> Self {
+		Self {
+			load_balancing: LoadBalancing::RoundRobin,
+			health_check_interval: Duration::from_secs(10),
+			health_check_timeout: Duration::from_secs(5),
+			health_check_method: None,
+			max_buffer_capacity_per_subscription: 1024,
+			idempotent_methods: BTreeSet::new(),
+			all_idempotent: false,
+		}
+	}
+}
+
+impl PoolClientBuilder {
+	/// Create a new builder.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Configure how the calls are distributed over the endpoints (default is [`LoadBalancing::RoundRobin`]).
+	pub fn load_balancing(mut self, load_balancing: LoadBalancing) -> Self {
+		self.load_balancing = load_balancing;
+		self
+	}
+
+	/// Configure how often the endpoints are health-checked (default is 10 seconds).
+	///
+	/// Endpoints which are down are reconnected at the same interval. The endpoints which are
+	/// regarded as healthy are only probed if [`PoolClientBuilder::health_check_method`] is set.
+	pub fn health_check_interval(mut self, interval: Duration) -> Self {
+		self.health_check_interval = interval;
+		self
+	}
+
+	/// Configure how long connecting to an endpoint or a health check call may take before
+	/// the endpoint is regarded as down (default is 5 seconds).
+	pub fn health_check_timeout(mut self, timeout: Duration) -> Self {
+		self.health_check_timeout = timeout;
+		self
+	}
+
+	/// Call `method` without parameters on every endpoint to check that it is alive.
+	///
+	/// Any response, including a JSON-RPC error, counts as healthy. The endpoints aren't probed
+	/// by default, thus they are only regarded as down when a call fails with a transport error
+	/// and an endpoint whose connection was lost is only noticed by the next call or subscription on it.
+	pub fn health_check_method(mut self, method: impl Into<String>) -> Self {
+		self.health_check_method = Some(method.into());
+		self
+	}
+
+	/// See documentation [`super::ClientBuilder::max_buffer_capacity_per_subscription`] (default is 1024).
+	pub fn max_buffer_capacity_per_subscription(mut self, max: usize) -> Self {
+		assert!(max > 0);
+		self.max_buffer_capacity_per_subscription = max;
+		self
+	}
+
+	/// Mark the `methods` as idempotent such that calls to them which fail with a transport
+	/// error are sent again to another endpoint.
+	///
+	/// The client traits generated by the `rpc` macro provide the methods marked with
+	/// `#[method(idempotent)]` as the `IDEMPOTENT_METHODS` constant.
+	pub fn idempotent_methods<T: Into<String>>(mut self, methods: impl IntoIterator<Item = T>) -> Self {
+		self.idempotent_methods.extend(methods.into_iter().map(Into::into));
+		self
+	}
+
+	/// Regard all methods as idempotent.
+	pub fn all_idempotent(mut self) -> Self {
+		self.all_idempotent = true;
+		self
+	}
+
+	/// Connect to all `endpoints` with `connector` and build the [`PoolClient`].
+	///
+	/// Fails if none of the endpoints could be connected to, the remaining endpoints
+	/// are reconnected in the background.
+	///
+	/// ## Panics
+	///
+	/// Panics if being called outside of `tokio` runtime context.
+	pub async fn build<C, T>(
+		self,
+		endpoints: impl IntoIterator<Item = T>,
+		connector: C,
+	) -> Result<PoolClient<C::Client>, Error>
+	where
+		C: Connect,
+		T: Into<String>,
+	{
+		let urls: Vec<String> = endpoints.into_iter().map(Into::into).collect();
+		let connected = join_all(urls.iter().map(|url| connect(&connector, url, self.health_check_timeout))).await;
+
+		let mut last_err = Error::NoHealthyEndpoint;
+		let endpoints: Vec<_> = urls
+			.into_iter()
+			.zip(connected)
+			.map(|(url, res)| {
+				let client = res.map_err(|err| last_err = err).ok().map(Arc::new);
+				Endpoint { url, state: RwLock::new(EndpointState { client, latency: None }) }
+			})
+			.collect();
+
+		let healthy = endpoints.iter().filter(|e| e.client().is_some()).count();
+		if healthy == 0 {
+			return Err(last_err);
+		}
+
+		let (healthy, _) = watch::channel(healthy);
+		let shared = Arc::new(Shared {
+			endpoints,
+			healthy,
+			next: AtomicUsize::new(0),
+			load_balancing: self.load_balancing,
+			max_buffer_capacity_per_subscription: self.max_buffer_capacity_per_subscription,
+			idempotent_methods: self.idempotent_methods.clone(),
+			all_idempotent: self.all_idempotent,
+		});
+		let (on_exit, exit) = oneshot::channel();
+
+		tokio::spawn(health_check_task(shared.clone(), connector, self, exit));
+
+		Ok(PoolClient { shared, _on_exit: on_exit })
+	}
+}
+
+async fn connect<C: Connect>(connector: &C, url: &str, timeout: Duration) -> Result<C::Client, Error> {
+	tokio::time::timeout(timeout, connector.connect(url.to_owned())).await.map_err(|_| Error::RequestTimeout)?
+}
+
+#[derive(Debug)]
+struct EndpointState<C> {
+	/// The client if the endpoint is healthy.
+	client: Option<Arc<C>>,
+	/// Moving average of the response time.
+	latency: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct Endpoint<C> {
+	url: String,
+	state: RwLock<EndpointState<C>>,
+}
+
+impl<C> Endpoint<C> {
+	fn client(&self) -> Option<Arc<C>> {
+		self.state.read().expect(NOT_POISONED).client.clone()
+	}
+
+	fn latency(&self) -> Option<Duration> {
+		self.state.read().expect(NOT_POISONED).latency
+	}
+}
+
+#[derive(Debug)]
+struct Shared<C> {
+	endpoints: Vec<Endpoint<C>>,
+	/// Number of healthy endpoints.
+	healthy: watch::Sender<usize>,
+	/// Round-robin counter.
+	next: AtomicUsize,
+	load_balancing: LoadBalancing,
+	max_buffer_capacity_per_subscription: usize,
+	idempotent_methods: BTreeSet<String>,
+	all_idempotent: bool,
+}
+
+impl<C> Shared<C> {
+	fn is_idempotent(&self, method: &str) -> bool {
+		self.all_idempotent || self.idempotent_methods.contains(method)
+	}
+
+	/// The healthy endpoints in the order they should be tried.
+	fn candidates(&self) -> Vec<(usize, Arc<C>)> {
+		let len = self.endpoints.len();
+		let mut candidates: Vec<_> = match self.load_balancing {
+			LoadBalancing::RoundRobin => {
+				let start = self.next.fetch_add(1, Ordering::Relaxed);
+				(0..len).map(|i| (start + i) % len).collect()
+			}
+			LoadBalancing::LeastLatency => (0..len).collect(),
+		};
+
+		if self.load_balancing == LoadBalancing::LeastLatency {
+			candidates.sort_by_key(|&idx| self.endpoints[idx].latency().unwrap_or_default());
+		}
+
+		candidates.into_iter().filter_map(|idx| self.endpoints[idx].client().map(|c| (idx, c))).collect()
+	}
+
+	/// Wait until an endpoint is healthy.
+	async fn healthy_endpoint(&self) -> (usize, Arc<C>) {
+		let mut rx = self.healthy.subscribe();
+
+		loop {
+			if let Some(candidate) = self.candidates().into_iter().next() {
+				return candidate;
+			}
+			// The sender is kept alive by `self`.
+			let _ = rx.changed().await;
+		}
+	}
+
+	/// Mark the endpoint as healthy with the connected `client`.
+	fn set_client(&self, idx: usize, client: Arc<C>) {
+		let was_healthy = self.endpoints[idx].state.write().expect(NOT_POISONED).client.replace(client).is_some();
+
+		if !was_healthy {
+			self.healthy.send_modify(|n| *n += 1);
+		}
+	}
+
+	/// Mark the endpoint as down after `client` failed.
+	///
+	/// Nothing happens if the endpoint was reconnected with another client in the meantime.
+	fn mark_down(&self, idx: usize, client: &Arc<C>) {
+		let mut state = self.endpoints[idx].state.write().expect(NOT_POISONED);
+		if !state.client.as_ref().is_some_and(|c| Arc::ptr_eq(c, client)) {
+			return;
+		}
+		state.client = None;
+		state.latency = None;
+		drop(state);
+
+		self.healthy.send_modify(|n| *n -= 1);
+	}
+
+	fn record_latency(&self, idx: usize, elapsed: Duration) {
+		let mut state = self.endpoints[idx].state.write().expect(NOT_POISONED);
+		state.latency = Some(match state.latency {
+			Some(avg) => (avg * 4 + elapsed) / 5,
+			None => elapsed,
+		});
+	}
+}
+
+/// Client which distributes the calls over a pool of identical endpoints.
+///
+/// The endpoint which a call failed on with a transport error is marked as down until it has been
+/// reconnected by the health check. Healthy endpoints are only probed by the health check if
+/// [`PoolClientBuilder::health_check_method`] is set, which is off by default. Calls to methods marked as idempotent with
+/// [`PoolClientBuilder::idempotent_methods`] are sent again to the next healthy endpoint, other calls
+/// fail because they may have been executed by the endpoint anyway.
+///
+/// Subscriptions are migrated to another healthy endpoint when the endpoint they were made on
+/// goes down, such that the consumer doesn't notice it except for the notifications that were
+/// emitted while the subscription was being re-established. The [`super::SubscriptionKind`] of
+/// such a subscription keeps the ID that was assigned by the first endpoint.
+///
+/// The client is created by [`PoolClientBuilder::build`].
+#[derive(Debug)]
+pub struct PoolClient<C> {
+	shared: Arc<Shared<C>>,
+	/// When the client is dropped a message is sent to the health check task.
+	_on_exit: oneshot::Sender<()>,
+}
+
+impl<C> PoolClient<C> {
+	/// Create a builder for the client.
+	pub fn builder() -> PoolClientBuilder {
+		PoolClientBuilder::new()
+	}
+
+	/// Returns the endpoints which are currently healthy.
+	pub fn healthy_endpoints(&self) -> Vec<&str> {
+		self.shared.endpoints.iter().filter(|e| e.client().is_some()).map(|e| e.url.as_str()).collect()
+	}
+}
+
+impl<C> PoolClient<C>
+where
+	C: Send + Sync,
+{
+	/// Run a call on the healthy endpoints until one of them doesn't fail with a transport error.
+	///
+	/// The call is only sent to the next endpoint if it may be `retried`.
+	async fn call<T, F, Fut>(&self, retry: bool, mut f: F) -> Result<(usize, Arc<C>, T), Error>
+	where
+		F: FnMut(Arc<C>) -> Fut,
+		Fut: Future<Output = Result<T, Error>>,
+	{
+		let mut last_err = Error::NoHealthyEndpoint;
+
+		for (idx, client) in self.shared.candidates() {
+			let started = Instant::now();
+
+			match f(client.clone()).await {
+				Err(err) if is_transport_error(&err) => {
+					self.shared.mark_down(idx, &client);
+					if !retry {
+						return Err(err);
+					}
+					last_err = err;
+				}
+				res => {
+					self.shared.record_latency(idx, started.elapsed());
+					return res.map(|res| (idx, client, res));
+				}
+			}
+		}
+
+		Err(last_err)
+	}
+}
+
+impl<C> ClientT for PoolClient<C>
+where
+	C: ClientT + Send + Sync,
+{
+	fn notification<Params>(&self, method: &str, params: Params) -> impl Future<Output = Result<(), Error>> + Send
+	where
+		Params: ToRpcParams + Send,
+	{
+		async move {
+			let params = params.to_rpc_params()?;
+			self.call(self.shared.is_idempotent(method), |client| {
+				let params = RawParams(params.clone());
+				async move { client.notification(method, params).await }
+			})
+			.await
+			.map(|(_, _, res)| res)
+		}
+	}
+
+	fn request<R, Params>(&self, method: &str, params: Params) -> impl Future<Output = Result<R, Error>> + Send
+	where
+		R: DeserializeOwned,
+		Params: ToRpcParams + Send,
+	{
+		async move {
+			let params = params.to_rpc_params()?;
+			self.call(self.shared.is_idempotent(method), |client| {
+				let params = RawParams(params.clone());
+				async move { client.request(method, params).await }
+			})
+			.await
+			.map(|(_, _, res)| res)
+		}
+	}
+
+	fn batch_request<'a, R>(
+		&self,
+		batch: BatchRequestBuilder<'a>,
+	) -> impl Future<Output = Result<BatchResponse<'a, R>, Error>> + Send
+	where
+		R: DeserializeOwned + std::fmt::Debug + 'a,
+	{
+		async move {
+			let retry = batch.iter().all(|(method, _)| self.shared.is_idempotent(method));
+			self.call(retry, |client| {
+				let batch = batch.clone();
+				async move { client.batch_request(batch).await }
+			})
+			.await
+			.map(|(_, _, res)| res)
+		}
+	}
+}
+
+impl<C> SubscriptionClientT for PoolClient<C>
+where
+	C: SubscriptionClientT + Send + Sync + 'static,
+{
+	fn subscribe<'a, Notif, Params>(
+		&self,
+		subscribe_method: &'a str,
+		params: Params,
+		unsubscribe_method: &'a str,
+	) -> impl Future<Output = Result<Subscription<Notif>, Error>> + Send
+	where
+		Params: ToRpcParams + Send,
+		Notif: DeserializeOwned,
+	{
+		async move {
+			let kind = Resubscribe::Subscription {
+				subscribe_method: subscribe_method.to_owned(),
+				unsubscribe_method: unsubscribe_method.to_owned(),
+				params: params.to_rpc_params()?,
+			};
+			self.start_subscription(kind).await
+		}
+	}
+
+	fn subscribe_to_method<Notif>(
+		&self,
+		method: &str,
+	) -> impl Future<Output = Result<Subscription<Notif>, Error>> + Send
+	where
+		Notif: DeserializeOwned,
+	{
+		self.start_subscription(Resubscribe::Method(method.to_owned()))
+	}
+}
+
+impl<C> PoolClient<C>
+where
+	C: SubscriptionClientT + Send + Sync + 'static,
+{
+	async fn start_subscription<Notif>(&self, kind: Resubscribe) -> Result<Subscription<Notif>, Error> {
+		// Subscriptions are made again on another endpoint when their endpoint goes down anyway.
+		let (endpoint, client, sub) = self
+			.call(true, |client| {
+				let kind = &kind;
+				async move { kind.subscribe(&*client).await }
+			})
+			.await?;
+
+		let (tx, unsubscribed, front) =
+			detached_subscription(self.shared.max_buffer_capacity_per_subscription, sub.kind().clone());
+
+		tokio::spawn(subscription_task(SubscriptionTaskParams {
+			shared: self.shared.clone(),
+			kind,
+			sub,
+			endpoint,
+			client,
+			tx,
+			unsubscribed,
+		}));
+
+		Ok(front)
+	}
+}
+
+struct SubscriptionTaskParams<C> {
+	shared: Arc<Shared<C>>,
+	kind: Resubscribe,
+	sub: Subscription<Box<RawValue>>,
+	endpoint: usize,
+	/// Client of the endpoint which the subscription was made on.
+	client: Arc<C>,
+	tx: DetachedSender,
+	/// Completes when the [`Subscription`] is unsubscribed or dropped.
+	unsubscribed: UnsubscribeSignal,
+}
+
+/// Forwards the notifications to the [`Subscription`] and migrates the subscription to
+/// another endpoint once its endpoint goes down.
+async fn subscription_task<C>(params: SubscriptionTaskParams<C>)
+where
+	C: SubscriptionClientT + Send + Sync,
+{
+	let SubscriptionTaskParams { shared, kind, mut sub, mut endpoint, mut client, tx, mut unsubscribed } = params;
+
+	loop {
+		let send = |notif| std::future::ready(tx.send(notif));
+
+		match forward_notifications(sub, unsubscribed.recv(), send).await {
+			SubscriptionEnd::Unsubscribed => return,
+			SubscriptionEnd::Lagged => {
+				tx.set_lagged();
+				return;
+			}
+			// The subscription ends but the endpoint is still healthy.
+			SubscriptionEnd::Closed => return,
+			SubscriptionEnd::ConnectionLost => shared.mark_down(endpoint, &client),
+		}
+
+		loop {
+			let (idx, candidate) = tokio::select! {
+				_ = unsubscribed.recv() => return,
+				c = shared.healthy_endpoint() => c,
+			};
+
+			match kind.subscribe(&*candidate).await {
+				Ok(s) => {
+					sub = s;
+					endpoint = idx;
+					client = candidate;
+					break;
+				}
+				Err(err) if is_transport_error(&err) => shared.mark_down(idx, &candidate),
+				Err(_) => return,
+			}
+		}
+	}
+}
+
+/// Reconnects the endpoints which are down and checks the health of the others.
+async fn health_check_task<C: Connect>(
+	shared: Arc<Shared<C::Client>>,
+	connector: C,
+	config: PoolClientBuilder,
+	mut exit: oneshot::Receiver<()>,
+) {
+	let mut interval = tokio::time::interval(config.health_check_interval);
+	// The first tick completes immediately and all endpoints were just connected to.
+	interval.tick().await;
+
+	loop {
+		tokio::select! {
+			_ = interval.tick() => (),
+			_ = &mut exit => return,
+		}
+
+		let checks = (0..shared.endpoints.len()).map(|idx| health_check(&shared, &connector, &config, idx));
+
+		tokio::select! {
+			_ = join_all(checks) => (),
+			_ = &mut exit => return,
+		}
+	}
+}
+
+async fn health_check<C: Connect>(shared: &Shared<C::Client>, connector: &C, config: &PoolClientBuilder, idx: usize) {
+	let endpoint = &shared.endpoints[idx];
+
+	let client = match endpoint.client() {
+		Some(client) => client,
+		None => match connect(connector, &endpoint.url, config.health_check_timeout).await {
+			Ok(client) => Arc::new(client),
+			Err(err) => {
+				tracing::debug!(target: "jsonrpsee-client", "Failed to connect to {}: {err}", endpoint.url);
+				return;
+			}
+		},
+	};
+
+	if let Some(method) = &config.health_check_method {
+		let started = Instant::now();
+		let res = tokio::time::timeout(
+			config.health_check_timeout,
+			client.request::<Box<RawValue>, _>(method, RawParams(None)),
+		)
+		.await;
+
+		match res {
+			Ok(Ok(_) | Err(Error::Call(_))) => shared.record_latency(idx, started.elapsed()),
+			Ok(Err(err)) => {
+				tracing::debug!(target: "jsonrpsee-client", "Health check of {} failed: {err}", endpoint.url);
+				shared.mark_down(idx, &client);
+				return;
+			}
+			Err(_) => {
+				tracing::debug!(target: "jsonrpsee-client", "Health check of {} timed out", endpoint.url);
+				shared.mark_down(idx, &client);
+				return;
+			}
+		}
+	}
+
+	if endpoint.client().is_none() {
+		shared.set_client(idx, client);
+	}
+}
+
+/// Whether the call failed because the endpoint couldn't be reached.
+fn is_transport_error(err: &Error) -> bool {
+	matches!(err, Error::Transport(_) | Error::RestartNeeded(_) | Error::DisconnectedWillReconnect)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::atomic::AtomicBool;
+
+	use super::*;
+
+	/// Client of an endpoint whose calls fail with a transport error while the endpoint is down.
+	#[derive(Debug)]
+	struct MockClient {
+		up: Arc<AtomicBool>,
+	}
+
+	impl MockClient {
+		fn check(&self) -> Result<(), Error> {
+			if self.up.load(Ordering::SeqCst) { Ok(()) } else { Err(Error::Transport("endpoint is down".into())) }
+		}
+	}
+
+	impl ClientT for MockClient {
+		fn notification<Params>(&self, _: &str, _: Params) -> impl Future<Output = Result<(), Error>> + Send
+		where
+			Params: ToRpcParams + Send,
+		{
+			async move { self.check() }
+		}
+
+		fn request<R, Params>(&self, _: &str, _: Params) -> impl Future<Output = Result<R, Error>> + Send
+		where
+			R: DeserializeOwned,
+			Params: ToRpcParams + Send,
+		{
+			async move {
+				self.check()?;
+				serde_json::from_str("null").map_err(Into::into)
+			}
+		}
+
+		fn batch_request<'a, R>(
+			&self,
+			_: BatchRequestBuilder<'a>,
+		) -> impl Future<Output = Result<BatchResponse<'a, R>, Error>> + Send
+		where
+			R: DeserializeOwned + std::fmt::Debug + 'a,
+		{
+			async { Err(Error::HttpNotImplemented) }
+		}
+	}
+
+	impl SubscriptionClientT for MockClient {
+		fn subscribe<'a, Notif, Params>(
+			&self,
+			_: &'a str,
+			_: Params,
+			_: &'a str,
+		) -> impl Future<Output = Result<Subscription<Notif>, Error>> + Send
+		where
+			Params: ToRpcParams + Send,
+			Notif: DeserializeOwned,
+		{
+			async { Err(Error::HttpNotImplemented) }
+		}
+
+		fn subscribe_to_method<Notif>(&self, _: &str) -> impl Future<Output = Result<Subscription<Notif>, Error>> + Send
+		where
+			Notif: DeserializeOwned,
+		{
+			async { Err(Error::HttpNotImplemented) }
+		}
+	}
+
+	/// Builds a pool of two endpoints named `"0"` and `"1"` which can be taken down with the returned flags.
+	async fn mock_pool(builder: PoolClientBuilder) -> (PoolClient<MockClient>, [Arc<AtomicBool>; 2]) {
+		let up = [Arc::new(AtomicBool::new(true)), Arc::new(AtomicBool::new(true))];
+		let flags = up.clone();
+		let connector = move |url: String| {
+			let up = flags[url.parse::<usize>().unwrap()].clone();
+			async move {
+				let client = MockClient { up };
+				client.check().map(|_| client)
+			}
+		};
+
+		let client = builder.health_check_interval(Duration::from_millis(10)).build(["0", "1"], connector).await.unwrap();
+		(client, up)
+	}
+
+	async fn wait_for_healthy(client: &PoolClient<MockClient>, expected: &[&str]) {
+		let wait = async {
+			while client.healthy_endpoints() != expected {
+				tokio::time::sleep(Duration::from_millis(5)).await;
+			}
+		};
+		tokio::time::timeout(Duration::from_secs(5), wait).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn unhealthy_endpoint_is_evicted_and_recovered() {
+		let (client, up) = mock_pool(PoolClientBuilder::new().health_check_method("health")).await;
+		assert_eq!(client.healthy_endpoints(), ["0", "1"]);
+
+		up[0].store(false, Ordering::SeqCst);
+		wait_for_healthy(&client, &["1"]).await;
+		// Non-idempotent calls aren't sent to the evicted endpoint.
+		for _ in 0..4 {
+			client.request::<(), _>("say_hello", RawParams(None)).await.unwrap();
+		}
+
+		up[0].store(true, Ordering::SeqCst);
+		wait_for_healthy(&client, &["0", "1"]).await;
+	}
+
+	#[tokio::test]
+	async fn endpoints_are_not_probed_by_default() {
+		let (client, up) = mock_pool(PoolClientBuilder::new().all_idempotent()).await;
+
+		up[0].store(false, Ordering::SeqCst);
+		tokio::time::sleep(Duration::from_millis(50)).await;
+		assert_eq!(client.healthy_endpoints(), ["0", "1"]);
+
+		// The endpoint is evicted by the failed call which is sent again to the other endpoint.
+		for _ in 0..2 {
+			client.request::<(), _>("say_hello", RawParams(None)).await.unwrap();
+		}
+		assert_eq!(client.healthy_endpoints(), ["1"]);
+
+		up[0].store(true, Ordering::SeqCst);
+		wait_for_healthy(&client, &["0", "1"]).await;
+	}
+
+	#[test]
+	fn failures_of_replaced_clients_are_ignored() {
+		let (healthy, _) = watch::channel(1);
+		let old = Arc::new(());
+		let shared = Shared {
+			endpoints: vec![Endpoint {
+				url: "ws://127.0.0.1".into(),
+				state: RwLock::new(EndpointState { client: Some(old.clone()), latency: None }),
+			}],
+			healthy,
+			next: AtomicUsize::new(0),
+			load_balancing: LoadBalancing::RoundRobin,
+			max_buffer_capacity_per_subscription: 1,
+			idempotent_methods: BTreeSet::new(),
+			all_idempotent: false,
+		};
+
+		shared.mark_down(0, &old);
+		assert!(shared.endpoints[0].client().is_none());
+		assert_eq!(*shared.healthy.borrow(), 0);
+
+		// The health check reconnected the endpoint before a call on the old client failed.
+		let new = Arc::new(());
+		shared.set_client(0, new.clone());
+		shared.mark_down(0, &old);
+		assert!(Arc::ptr_eq(&shared.endpoints[0].client().unwrap(), &new));
+		assert_eq!(*shared.healthy.borrow(), 1);
+
+		shared.mark_down(0, &new);
+		assert_eq!(*shared.healthy.borrow(), 0);
+	}
+}
@@ -0,0 +1,184 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Shared helpers for clients which re-establish subscriptions on another connection.
+
+use std::future::Future;
+
+use serde_json::value::RawValue;
+use tokio::sync::mpsc;
+
+use super::{
+	Error, FrontToBack, Subscription, SubscriptionClientT, SubscriptionCloseReason, SubscriptionKind,
+	SubscriptionSender, subscription_channel,
+};
+use crate::traits::ToRpcParams;
+
+/// How to re-establish a subscription.
+#[derive(Debug)]
+pub enum Resubscribe {
+	/// Subscription made by [`SubscriptionClientT::subscribe`].
+	Subscription {
+		/// Subscribe method.
+		subscribe_method: String,
+		/// Unsubscribe method.
+		unsubscribe_method: String,
+		/// Serialized parameters which are sent again as-is.
+		params: Option<Box<RawValue>>,
+	},
+	/// Subscription made by [`SubscriptionClientT::subscribe_to_method`].
+	Method(String),
+}
+
+impl Resubscribe {
+	/// Make the subscription on `client`.
+	pub async fn subscribe<C>(&self, client: &C) -> Result<Subscription<Box<RawValue>>, Error>
+	where
+		C: SubscriptionClientT + Send + Sync,
+	{
+		match self {
+			Self::Subscription { subscribe_method, unsubscribe_method, params } => {
+				client.subscribe(subscribe_method, RawParams(params.clone()), unsubscribe_method).await
+			}
+			Self::Method(method) => client.subscribe_to_method(method).await,
+		}
+	}
+}
+
+/// Create a [`Subscription`] whose notifications are sent by a task of the client
+/// instead of the connection, such that the task can re-establish the subscription
+/// on another connection without the consumer noticing.
+pub fn detached_subscription<Notif>(
+	max_buffer_capacity: usize,
+	kind: SubscriptionKind,
+) -> (DetachedSender, UnsubscribeSignal, Subscription<Notif>) {
+	let (to_back, from_front) = mpsc::channel(1);
+	let (tx, rx) = subscription_channel(max_buffer_capacity);
+
+	(DetachedSender(tx), UnsubscribeSignal(from_front), Subscription::new(to_back, rx, kind, None))
+}
+
+/// Sends the notifications of a [`Subscription`] created by [`detached_subscription`].
+#[derive(Debug)]
+pub struct DetachedSender(SubscriptionSender);
+
+impl DetachedSender {
+	/// Send a notification to the [`Subscription`].
+	///
+	/// Returns `false` if the subscription was dropped or if it couldn't keep up,
+	/// in which case it's closed with [`SubscriptionCloseReason::Lagged`].
+	pub fn send(&self, notif: Box<RawValue>) -> bool {
+		self.0.send(notif).is_ok()
+	}
+
+	/// Close the [`Subscription`] with [`SubscriptionCloseReason::Lagged`].
+	pub fn set_lagged(&self) {
+		self.0.close_state.set_lagged();
+	}
+}
+
+/// Completes when a [`Subscription`] created by [`detached_subscription`] is unsubscribed or dropped.
+#[derive(Debug)]
+pub struct UnsubscribeSignal(mpsc::Receiver<FrontToBack>);
+
+impl UnsubscribeSignal {
+	/// Wait until the [`Subscription`] is unsubscribed or dropped.
+	///
+	/// # Cancel safety
+	///
+	/// This method is cancel safe.
+	pub async fn recv(&mut self) {
+		let _ = self.0.recv().await;
+	}
+}
+
+/// Why [`forward_notifications`] returned.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SubscriptionEnd {
+	/// The consumer went away and the subscription was unsubscribed.
+	Unsubscribed,
+	/// The subscription could not keep up with the server.
+	Lagged,
+	/// The server closed the subscription while the connection is still up.
+	Closed,
+	/// The connection the subscription was made on was lost.
+	ConnectionLost,
+}
+
+/// Forward the notifications of `sub` with `send` until the subscription ends.
+///
+/// `send` returns `false` if the consumer went away, and `closed` completes when
+/// the consumer unsubscribed. In both cases `sub` is unsubscribed.
+pub async fn forward_notifications<Closed, F, Fut>(
+	mut sub: Subscription<Box<RawValue>>,
+	closed: Closed,
+	mut send: F,
+) -> SubscriptionEnd
+where
+	Closed: Future<Output = ()>,
+	F: FnMut(Box<RawValue>) -> Fut,
+	Fut: Future<Output = bool>,
+{
+	tokio::pin!(closed);
+
+	loop {
+		let notif = tokio::select! {
+			_ = &mut closed => {
+				let _ = sub.unsubscribe().await;
+				return SubscriptionEnd::Unsubscribed;
+			}
+			notif = sub.next() => notif,
+		};
+
+		match notif {
+			Some(Ok(notif)) => {
+				if !send(notif).await {
+					let _ = sub.unsubscribe().await;
+					return SubscriptionEnd::Unsubscribed;
+				}
+			}
+			// `Box<RawValue>` can't fail to deserialize from valid JSON.
+			Some(Err(_)) => continue,
+			None => break,
+		}
+	}
+
+	match sub.close_reason() {
+		Some(SubscriptionCloseReason::Lagged) => SubscriptionEnd::Lagged,
+		_ if sub.connection_lost() => SubscriptionEnd::ConnectionLost,
+		_ => SubscriptionEnd::Closed,
+	}
+}
+
+/// Parameters that were already serialized.
+#[derive(Debug)]
+pub struct RawParams(pub Option<Box<RawValue>>);
+
+impl ToRpcParams for RawParams {
+	fn to_rpc_params(self) -> Result<Option<Box<RawValue>>, serde_json::Error> {
+		Ok(self.0)
+	}
+}
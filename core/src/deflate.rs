@@ -0,0 +1,200 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Shared parts of the WebSocket `permessage-deflate` extension of the client and the server.
+
+use std::io::{self, Write};
+
+use flate2::{Decompress, FlushDecompress, Status};
+use soketto::BoxedError;
+use soketto::base::{Header, OpCode};
+
+/// Inflates the compressed messages received from the peer.
+///
+/// The state of the decompressor is kept between messages because the peer may use the
+/// LZ77 sliding window of the previous messages unless `no_context_takeover` was negotiated (RFC 7692, 7.1.1).
+#[derive(Debug)]
+pub struct Inflater {
+	decompress: Decompress,
+	max_message_size: usize,
+	await_last_fragment: bool,
+}
+
+impl Inflater {
+	/// Create a new inflater which fails if an inflated message exceeds `max_message_size` bytes.
+	pub fn new(max_message_size: usize) -> Self {
+		Self { decompress: Decompress::new(false), max_message_size, await_last_fragment: false }
+	}
+
+	/// Inflate the message if it's compressed, to be called from [`soketto::extension::Extension::decode`].
+	///
+	/// soketto only checks the size of the compressed message, thus the message
+	/// is inflated here such that it can't exceed the maximum message size.
+	pub fn decode(&mut self, header: &mut Header, data: &mut Vec<u8>) -> Result<(), BoxedError> {
+		if data.is_empty() {
+			return Ok(());
+		}
+
+		match header.opcode() {
+			OpCode::Binary | OpCode::Text if header.is_rsv1() => {
+				if !header.is_fin() {
+					self.await_last_fragment = true;
+					return Ok(());
+				}
+			}
+			OpCode::Continue if header.is_fin() && self.await_last_fragment => {
+				self.await_last_fragment = false;
+			}
+			_ => return Ok(()),
+		}
+
+		// Restore LEN and NLEN (RFC 7692, 7.2.2).
+		data.extend_from_slice(&[0, 0, 0xFF, 0xFF]);
+
+		let mut writer = LimitedWriter { buf: Vec::new(), max: self.max_message_size };
+		let mut input = &data[..];
+		let mut chunk = [0; 8 * 1024];
+
+		loop {
+			let (total_in, total_out) = (self.decompress.total_in(), self.decompress.total_out());
+			let status = self.decompress.decompress(input, &mut chunk, FlushDecompress::Sync)?;
+			let consumed = (self.decompress.total_in() - total_in) as usize;
+			let produced = (self.decompress.total_out() - total_out) as usize;
+
+			input = &input[consumed..];
+			writer.write_all(&chunk[..produced]).map_err(|err| match err.downcast::<MessageTooLarge>() {
+				Ok(err) => Box::new(err) as BoxedError,
+				Err(err) => Box::new(err),
+			})?;
+
+			match status {
+				// The peer finished the DEFLATE stream, the next message starts a new one.
+				Status::StreamEnd => {
+					self.decompress.reset(false);
+					break;
+				}
+				Status::BufError => break,
+				Status::Ok if input.is_empty() && produced < chunk.len() => break,
+				Status::Ok => (),
+			}
+		}
+
+		*data = writer.buf;
+
+		header.set_rsv1(false);
+		header.set_payload_len(data.len());
+
+		Ok(())
+	}
+}
+
+/// The inflated message exceeds the maximum message size.
+#[derive(Debug, Copy, Clone)]
+pub struct MessageTooLarge {
+	/// Maximum message size in bytes.
+	pub maximum: usize,
+}
+
+impl std::fmt::Display for MessageTooLarge {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "Inflated message exceeds the maximum message size of {} bytes", self.maximum)
+	}
+}
+
+impl std::error::Error for MessageTooLarge {}
+
+/// Buffer which fails to grow beyond `max` bytes.
+struct LimitedWriter {
+	buf: Vec<u8>,
+	max: usize,
+}
+
+impl Write for LimitedWriter {
+	fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+		if self.buf.len() + data.len() > self.max {
+			return Err(io::Error::other(MessageTooLarge { maximum: self.max }));
+		}
+		self.buf.extend_from_slice(data);
+		Ok(data.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use flate2::{Compress, Compression, FlushCompress};
+
+	/// Compress the messages like a peer which uses context takeover.
+	fn compress(msgs: &[&[u8]]) -> Vec<Vec<u8>> {
+		let mut compress = Compress::new(Compression::default(), false);
+		msgs.iter()
+			.map(|msg| {
+				let mut out = Vec::with_capacity(msg.len() + 64);
+				compress.compress_vec(msg, &mut out, FlushCompress::Sync).unwrap();
+				assert!(out.ends_with(&[0, 0, 0xFF, 0xFF]));
+				out.truncate(out.len() - 4);
+				out
+			})
+			.collect()
+	}
+
+	fn decode(inflater: &mut Inflater, data: &[u8]) -> Result<Vec<u8>, BoxedError> {
+		let mut header = Header::new(OpCode::Text);
+		header.set_rsv1(true);
+		let mut data = data.to_vec();
+		inflater.decode(&mut header, &mut data)?;
+		assert!(!header.is_rsv1());
+		Ok(data)
+	}
+
+	#[test]
+	fn context_takeover_works() {
+		let msg = br#"{"jsonrpc":"2.0","method":"say_hello","params":["hello"],"id":1}"#;
+		let compressed = compress(&[msg, msg, msg]);
+		// The later messages refer to the previous ones.
+		assert!(compressed[1].len() < compressed[0].len());
+
+		let mut inflater = Inflater::new(usize::MAX);
+		for data in compressed {
+			assert_eq!(decode(&mut inflater, &data).unwrap(), msg);
+		}
+	}
+
+	#[test]
+	fn inflated_size_is_limited() {
+		let msg = vec![b'0'; 1024 * 1024];
+		let compressed = compress(&[&msg]).remove(0);
+
+		assert_eq!(decode(&mut Inflater::new(msg.len()), &compressed).unwrap(), msg);
+
+		let err = decode(&mut Inflater::new(msg.len() - 1), &compressed).unwrap_err();
+		assert!(err.is::<MessageTooLarge>());
+	}
+}
@@ -42,10 +42,17 @@ pub mod traits;
 /// RPC Parameters.
 pub mod params;
 
+/// OpenRPC service discovery.
+pub mod openrpc;
+
 cfg_http_helpers! {
 	pub mod http_helpers;
 }
 
+cfg_deflate! {
+	pub mod deflate;
+}
+
 cfg_server! {
 	pub mod id_providers;
 	pub mod server;
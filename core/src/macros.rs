@@ -35,6 +35,12 @@ macro_rules! cfg_http_helpers {
 	};
 }
 
+macro_rules! cfg_deflate {
+	($($item:item)*) => {
+		cfg_feature!("deflate", $($item)*);
+	};
+}
+
 macro_rules! cfg_async_client {
 	($($item:item)*) => {
 		$(
@@ -0,0 +1,653 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+
+//! RPC metrics layer.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::middleware::layer::{Outcome, ResponseOutcome};
+use crate::middleware::{Batch, Extensions, IsHttpRequest, Notification, RpcServiceT};
+
+use futures_util::Future;
+use jsonrpsee_types::Request;
+use jsonrpsee_types::error::METHOD_NOT_FOUND_CODE;
+use serde::Serialize;
+
+/// Kind of call recorded by the [`RpcMetrics`] middleware.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CallKind {
+	/// Method call, which includes subscription calls.
+	MethodCall,
+	/// Notification.
+	Notification,
+	/// Batch request.
+	Batch,
+}
+
+impl CallKind {
+	/// Get the kind as a label value.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::MethodCall => "method_call",
+			Self::Notification => "notification",
+			Self::Batch => "batch",
+		}
+	}
+}
+
+/// Metrics of a completed call.
+#[derive(Debug, Clone)]
+pub struct CallMetrics<'a> {
+	/// Method name or `batch` for batch requests.
+	pub method: &'a str,
+	/// Kind of the call.
+	pub kind: CallKind,
+	/// Outcome of the call.
+	pub outcome: Outcome,
+	/// Time it took to complete the call.
+	pub latency: Duration,
+	/// Size of the serialized request in bytes.
+	pub request_size: usize,
+	/// Size of the serialized response in bytes.
+	pub response_size: usize,
+}
+
+/// Recorder of the metrics collected by the [`RpcMetrics`] middleware.
+///
+/// See [`PrometheusRecorder`] for a recorder that keeps the metrics in memory
+/// and renders them in the Prometheus text exposition format.
+pub trait MetricsRecorder: Send + Sync + 'static {
+	/// A call has been completed.
+	fn on_call(&self, call: &CallMetrics<'_>);
+
+	/// A connection has made its first call.
+	fn on_connect(&self) {}
+
+	/// A connection has been closed.
+	fn on_disconnect(&self) {}
+
+	/// A subscription has been opened by the subscription method `method`.
+	fn on_subscribe(&self, _method: &str) {}
+
+	/// A subscription opened by the subscription method `method` has been closed.
+	fn on_unsubscribe(&self, _method: &str) {}
+}
+
+/// Response which can be inspected by the [`RpcMetrics`] middleware.
+pub trait MetricsResponse: ResponseOutcome {
+	/// Get the serialized subscription ID if the response accepted a subscription.
+	fn subscription_id(&self) -> Option<String>;
+
+	/// Get the hook which runs once the subscription accepted by the response is closed.
+	///
+	/// Without it the subscription is only accounted as closed once the connection is closed.
+	fn subscription_close_hook(&self) -> Option<SubscriptionCloseHook> {
+		None
+	}
+}
+
+type CloseCallbacks = Option<Vec<Box<dyn FnOnce() + Send>>>;
+
+/// Runs callbacks once a subscription has been closed, either by the client or by the server.
+///
+/// The server inserts it into the extensions of the response to a subscription call.
+#[derive(Clone)]
+pub struct SubscriptionCloseHook(Arc<Mutex<CloseCallbacks>>);
+
+impl std::fmt::Debug for SubscriptionCloseHook {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("SubscriptionCloseHook").finish_non_exhaustive()
+	}
+}
+
+impl SubscriptionCloseHook {
+	/// Create a new hook and the guard which runs the callbacks once dropped.
+	#[cfg(any(feature = "server", feature = "client"))]
+	pub(crate) fn new() -> (Self, SubscriptionCloseGuard) {
+		let hook = Self(Arc::new(Mutex::new(Some(Vec::new()))));
+		(hook.clone(), SubscriptionCloseGuard(hook))
+	}
+
+	/// Run `f` once the subscription is closed, or right away if it's already closed.
+	pub fn on_close(&self, f: impl FnOnce() + Send + 'static) {
+		let mut callbacks = self.0.lock().expect("lock poisoned; qed");
+		match callbacks.as_mut() {
+			Some(callbacks) => callbacks.push(Box::new(f)),
+			None => {
+				drop(callbacks);
+				f();
+			}
+		}
+	}
+}
+
+/// Runs the callbacks of the [`SubscriptionCloseHook`] once dropped.
+#[cfg(any(feature = "server", feature = "client"))]
+#[derive(Debug)]
+pub(crate) struct SubscriptionCloseGuard(SubscriptionCloseHook);
+
+#[cfg(feature = "client")]
+impl SubscriptionCloseGuard {
+	/// Get the hook which runs the callbacks once the guard is dropped.
+	pub(crate) fn hook(&self) -> SubscriptionCloseHook {
+		self.0.clone()
+	}
+}
+
+#[cfg(any(feature = "server", feature = "client"))]
+impl Drop for SubscriptionCloseGuard {
+	fn drop(&mut self) {
+		let callbacks = self.0.0.lock().unwrap_or_else(|e| e.into_inner()).take();
+
+		for f in callbacks.into_iter().flatten() {
+			f();
+		}
+	}
+}
+
+/// RPC metrics layer.
+#[derive(Debug)]
+pub struct RpcMetricsLayer<R> {
+	recorder: Arc<R>,
+}
+
+impl<R> Clone for RpcMetricsLayer<R> {
+	fn clone(&self) -> Self {
+		Self { recorder: self.recorder.clone() }
+	}
+}
+
+impl<R: MetricsRecorder> RpcMetricsLayer<R> {
+	/// Create a new metrics layer which records the metrics to `recorder`.
+	pub fn new(recorder: Arc<R>) -> Self {
+		Self { recorder }
+	}
+}
+
+impl<S, R: MetricsRecorder> tower::Layer<S> for RpcMetricsLayer<R> {
+	type Service = RpcMetrics<S, R>;
+
+	fn layer(&self, service: S) -> Self::Service {
+		RpcMetrics {
+			service,
+			recorder: self.recorder.clone(),
+			conn: Arc::new(ConnectionMetrics {
+				recorder: self.recorder.clone(),
+				connected: AtomicBool::new(false),
+				subscriptions: Mutex::default(),
+			}),
+		}
+	}
+}
+
+/// A middleware that records metrics for each RPC call.
+///
+/// The middleware is created for each connection, which is accounted as opened once it makes
+/// its first call and as closed once all clones of the middleware have been dropped. The server
+/// creates the middleware for each HTTP request as well, thus the calls with [`IsHttpRequest`]
+/// in their extensions aren't accounted as connections.
+///
+/// Subscriptions are accounted as closed once they are closed by the server, unsubscribed
+/// or the connection is closed.
+#[derive(Debug)]
+pub struct RpcMetrics<S, R: MetricsRecorder> {
+	service: S,
+	recorder: Arc<R>,
+	conn: Arc<ConnectionMetrics<R>>,
+}
+
+impl<S: Clone, R: MetricsRecorder> Clone for RpcMetrics<S, R> {
+	fn clone(&self) -> Self {
+		Self { service: self.service.clone(), recorder: self.recorder.clone(), conn: self.conn.clone() }
+	}
+}
+
+impl<S, R> RpcServiceT for RpcMetrics<S, R>
+where
+	S: RpcServiceT + Send + Sync + Clone + 'static,
+	S::MethodResponse: MetricsResponse,
+	S::BatchResponse: MetricsResponse,
+	R: MetricsRecorder,
+{
+	type MethodResponse = S::MethodResponse;
+	type NotificationResponse = S::NotificationResponse;
+	type BatchResponse = S::BatchResponse;
+
+	fn call<'a>(&self, request: Request<'a>) -> impl Future<Output = Self::MethodResponse> + Send + 'a {
+		let service = self.service.clone();
+		let recorder = self.recorder.clone();
+		let conn = self.conn.clone();
+
+		let method = request.method_name().to_owned();
+		let request_size = serialized_size(&request);
+		conn.connect(request.extensions());
+
+		async move {
+			let started = Instant::now();
+			let rp = service.call(request).await;
+			let outcome = rp.outcome();
+
+			recorder.on_call(&CallMetrics {
+				method: &method,
+				kind: CallKind::MethodCall,
+				outcome,
+				latency: started.elapsed(),
+				request_size,
+				response_size: rp.size(),
+			});
+
+			if let Some(sub_id) = rp.subscription_id().filter(|_| outcome == Outcome::Success) {
+				conn.subscribe(sub_id.clone(), method);
+
+				// The hook runs once the subscription is unsubscribed or closed by the server.
+				if let Some(hook) = rp.subscription_close_hook() {
+					let conn = Arc::downgrade(&conn);
+					hook.on_close(move || {
+						if let Some(conn) = conn.upgrade() {
+							conn.unsubscribe(&sub_id);
+						}
+					});
+				}
+			}
+
+			rp
+		}
+	}
+
+	fn batch<'a>(&self, mut batch: Batch<'a>) -> impl Future<Output = Self::BatchResponse> + Send + 'a {
+		let service = self.service.clone();
+		let recorder = self.recorder.clone();
+		let request_size = serialized_size(&batch);
+		self.conn.connect(batch.extensions());
+
+		async move {
+			let started = Instant::now();
+			let rp = service.batch(batch).await;
+
+			recorder.on_call(&CallMetrics {
+				method: CallKind::Batch.as_str(),
+				kind: CallKind::Batch,
+				outcome: rp.outcome(),
+				latency: started.elapsed(),
+				request_size,
+				response_size: rp.size(),
+			});
+
+			rp
+		}
+	}
+
+	fn notification<'a>(&self, n: Notification<'a>) -> impl Future<Output = Self::NotificationResponse> + Send + 'a {
+		let service = self.service.clone();
+		let recorder = self.recorder.clone();
+		let method = n.method_name().to_owned();
+		let request_size = serialized_size(&n);
+		self.conn.connect(n.extensions());
+
+		async move {
+			let started = Instant::now();
+			let rp = service.notification(n).await;
+
+			recorder.on_call(&CallMetrics {
+				method: &method,
+				kind: CallKind::Notification,
+				outcome: Outcome::Success,
+				latency: started.elapsed(),
+				request_size,
+				response_size: 0,
+			});
+
+			rp
+		}
+	}
+}
+
+/// Metrics state of a connection.
+#[derive(Debug)]
+struct ConnectionMetrics<R: MetricsRecorder> {
+	recorder: Arc<R>,
+	/// Whether the connection has been accounted as opened.
+	connected: AtomicBool,
+	/// Active subscriptions, mapping serialized subscription IDs to the subscription method.
+	subscriptions: Mutex<HashMap<String, String>>,
+}
+
+impl<R: MetricsRecorder> ConnectionMetrics<R> {
+	/// Account the connection as opened on its first call, unless the call was received in an HTTP request.
+	fn connect(&self, extensions: &Extensions) {
+		if extensions.get::<IsHttpRequest>().is_none() && !self.connected.swap(true, Ordering::Relaxed) {
+			self.recorder.on_connect();
+		}
+	}
+
+	fn subscribe(&self, sub_id: String, method: String) {
+		self.recorder.on_subscribe(&method);
+		self.subscriptions.lock().expect("lock poisoned; qed").insert(sub_id, method);
+	}
+
+	fn unsubscribe(&self, sub_id: &str) {
+		if let Some(method) = self.subscriptions.lock().expect("lock poisoned; qed").remove(sub_id) {
+			self.recorder.on_unsubscribe(&method);
+		}
+	}
+}
+
+impl<R: MetricsRecorder> Drop for ConnectionMetrics<R> {
+	fn drop(&mut self) {
+		let subscriptions = std::mem::take(self.subscriptions.get_mut().unwrap_or_else(|e| e.into_inner()));
+
+		for method in subscriptions.values() {
+			self.recorder.on_unsubscribe(method);
+		}
+
+		if *self.connected.get_mut() {
+			self.recorder.on_disconnect();
+		}
+	}
+}
+
+/// Get the size of `value` serialized as JSON without allocating it.
+pub(crate) fn serialized_size<T: Serialize + ?Sized>(value: &T) -> usize {
+	struct Counter(usize);
+
+	impl io::Write for Counter {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.0 += buf.len();
+			Ok(buf.len())
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	let mut counter = Counter(0);
+	match serde_json::to_writer(&mut counter, value) {
+		Ok(()) => counter.0,
+		Err(_) => 0,
+	}
+}
+
+/// Label of calls to methods that don't exist, such that clients can't create arbitrarily many series.
+const UNKNOWN_METHOD: &str = "<unknown>";
+
+/// Default latency buckets in seconds.
+const DEFAULT_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Metrics recorder which keeps the metrics in memory and renders them in
+/// the [Prometheus text exposition format](https://prometheus.io/docs/instrumenting/exposition_formats/).
+///
+/// The following metrics are recorded:
+///
+/// - `jsonrpsee_calls_total{method, kind}`: number of completed calls.
+/// - `jsonrpsee_call_errors_total{method, kind, code}`: number of failed calls by error code.
+///   Calls that failed without a JSON-RPC error code use the code `none`.
+/// - `jsonrpsee_call_duration_seconds{method, kind}`: histogram of the call latencies.
+/// - `jsonrpsee_request_size_bytes_total{method, kind}`: total size of the requests.
+/// - `jsonrpsee_response_size_bytes_total{method, kind}`: total size of the responses.
+/// - `jsonrpsee_active_connections`: number of active connections.
+/// - `jsonrpsee_active_subscriptions{method}`: number of active subscriptions.
+///
+/// Calls which failed because the method doesn't exist are recorded with the method `<unknown>`.
+#[derive(Debug)]
+pub struct PrometheusRecorder {
+	buckets: Vec<f64>,
+	active_connections: AtomicI64,
+	inner: Mutex<PrometheusInner>,
+}
+
+#[derive(Debug, Default)]
+struct PrometheusInner {
+	calls: BTreeMap<(String, CallKind), CallStats>,
+	errors: BTreeMap<(String, CallKind, Option<i32>), u64>,
+	subscriptions: BTreeMap<String, i64>,
+}
+
+#[derive(Debug)]
+struct CallStats {
+	count: u64,
+	request_size: u64,
+	response_size: u64,
+	latency_sum: f64,
+	/// Number of observations per bucket, not cumulative.
+	latency_buckets: Vec<u64>,
+}
+
+impl Default for PrometheusRecorder {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl PrometheusRecorder {
+	/// Content type of the text exposition format.
+	pub const CONTENT_TYPE: &'static str = "text/plain; version=0.0.4; charset=utf-8";
+
+	/// Create a new recorder with the default latency buckets.
+	pub fn new() -> Self {
+		Self::with_buckets(DEFAULT_BUCKETS.to_vec())
+	}
+
+	/// Create a new recorder with custom latency buckets in seconds.
+	///
+	/// # Panics
+	///
+	/// Panics if the buckets are not sorted in increasing order.
+	pub fn with_buckets(buckets: Vec<f64>) -> Self {
+		assert!(buckets.windows(2).all(|w| w[0] < w[1]), "Buckets must be sorted in increasing order");
+		Self { buckets, active_connections: AtomicI64::new(0), inner: Mutex::default() }
+	}
+
+	/// Render the metrics in the Prometheus text exposition format.
+	pub fn render(&self) -> String {
+		let inner = self.inner.lock().expect("lock poisoned; qed");
+		let mut out = String::new();
+
+		out.push_str("# HELP jsonrpsee_calls_total Number of completed JSON-RPC calls.\n");
+		out.push_str("# TYPE jsonrpsee_calls_total counter\n");
+		for ((method, kind), stats) in &inner.calls {
+			let _ = writeln!(out, "jsonrpsee_calls_total{{{}}} {}", labels(method, *kind), stats.count);
+		}
+
+		out.push_str("# HELP jsonrpsee_call_errors_total Number of failed JSON-RPC calls.\n");
+		out.push_str("# TYPE jsonrpsee_call_errors_total counter\n");
+		for ((method, kind, code), count) in &inner.errors {
+			let code = code.map_or_else(|| "none".to_string(), |c| c.to_string());
+			let _ = writeln!(out, "jsonrpsee_call_errors_total{{{},code=\"{}\"}} {}", labels(method, *kind), code, count);
+		}
+
+		out.push_str("# HELP jsonrpsee_call_duration_seconds Latency of JSON-RPC calls.\n");
+		out.push_str("# TYPE jsonrpsee_call_duration_seconds histogram\n");
+		for ((method, kind), stats) in &inner.calls {
+			let labels = labels(method, *kind);
+			let mut cumulative = 0;
+			for (le, count) in self.buckets.iter().zip(&stats.latency_buckets) {
+				cumulative += count;
+				let _ = writeln!(out, "jsonrpsee_call_duration_seconds_bucket{{{labels},le=\"{le}\"}} {cumulative}");
+			}
+			let _ = writeln!(out, "jsonrpsee_call_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}", stats.count);
+			let _ = writeln!(out, "jsonrpsee_call_duration_seconds_sum{{{labels}}} {}", stats.latency_sum);
+			let _ = writeln!(out, "jsonrpsee_call_duration_seconds_count{{{labels}}} {}", stats.count);
+		}
+
+		out.push_str("# HELP jsonrpsee_request_size_bytes_total Total size of JSON-RPC requests.\n");
+		out.push_str("# TYPE jsonrpsee_request_size_bytes_total counter\n");
+		for ((method, kind), stats) in &inner.calls {
+			let _ = writeln!(out, "jsonrpsee_request_size_bytes_total{{{}}} {}", labels(method, *kind), stats.request_size);
+		}
+
+		out.push_str("# HELP jsonrpsee_response_size_bytes_total Total size of JSON-RPC responses.\n");
+		out.push_str("# TYPE jsonrpsee_response_size_bytes_total counter\n");
+		for ((method, kind), stats) in &inner.calls {
+			let _ =
+				writeln!(out, "jsonrpsee_response_size_bytes_total{{{}}} {}", labels(method, *kind), stats.response_size);
+		}
+
+		out.push_str("# HELP jsonrpsee_active_connections Number of active connections.\n");
+		out.push_str("# TYPE jsonrpsee_active_connections gauge\n");
+		let _ = writeln!(out, "jsonrpsee_active_connections {}", self.active_connections.load(Ordering::Relaxed));
+
+		out.push_str("# HELP jsonrpsee_active_subscriptions Number of active subscriptions.\n");
+		out.push_str("# TYPE jsonrpsee_active_subscriptions gauge\n");
+		for (method, count) in &inner.subscriptions {
+			let _ = writeln!(out, "jsonrpsee_active_subscriptions{{method=\"{}\"}} {}", escape(method), count);
+		}
+
+		out
+	}
+}
+
+impl MetricsRecorder for PrometheusRecorder {
+	fn on_call(&self, call: &CallMetrics<'_>) {
+		let mut inner = self.inner.lock().expect("lock poisoned; qed");
+
+		let method = match call.outcome {
+			Outcome::Error(METHOD_NOT_FOUND_CODE) => UNKNOWN_METHOD,
+			_ => call.method,
+		};
+
+		let stats = inner.calls.entry((method.to_owned(), call.kind)).or_insert_with(|| CallStats {
+			count: 0,
+			request_size: 0,
+			response_size: 0,
+			latency_sum: 0.0,
+			latency_buckets: vec![0; self.buckets.len()],
+		});
+
+		let latency = call.latency.as_secs_f64();
+		stats.count += 1;
+		stats.request_size += call.request_size as u64;
+		stats.response_size += call.response_size as u64;
+		stats.latency_sum += latency;
+		if let Some(bucket) = self.buckets.iter().position(|le| latency <= *le) {
+			stats.latency_buckets[bucket] += 1;
+		}
+
+		let code = match call.outcome {
+			Outcome::Success => return,
+			Outcome::Error(code) => Some(code),
+			Outcome::Failed => None,
+		};
+		*inner.errors.entry((method.to_owned(), call.kind, code)).or_default() += 1;
+	}
+
+	fn on_connect(&self) {
+		self.active_connections.fetch_add(1, Ordering::Relaxed);
+	}
+
+	fn on_disconnect(&self) {
+		self.active_connections.fetch_sub(1, Ordering::Relaxed);
+	}
+
+	fn on_subscribe(&self, method: &str) {
+		*self.inner.lock().expect("lock poisoned; qed").subscriptions.entry(method.to_owned()).or_default() += 1;
+	}
+
+	fn on_unsubscribe(&self, method: &str) {
+		if let Some(count) = self.inner.lock().expect("lock poisoned; qed").subscriptions.get_mut(method) {
+			*count -= 1;
+		}
+	}
+}
+
+fn labels(method: &str, kind: CallKind) -> String {
+	format!("method=\"{}\",kind=\"{}\"", escape(method), kind.as_str())
+}
+
+/// Escape a label value according to the text exposition format.
+fn escape(value: &str) -> String {
+	value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn prometheus_recorder_renders_metrics() {
+		let recorder = PrometheusRecorder::with_buckets(vec![0.1, 1.0]);
+
+		recorder.on_connect();
+		recorder.on_subscribe("sub");
+		for (outcome, latency) in [(Outcome::Success, 50), (Outcome::Error(-32602), 500), (Outcome::Failed, 5000)] {
+			recorder.on_call(&CallMetrics {
+				method: "say_\"hello\"",
+				kind: CallKind::MethodCall,
+				outcome,
+				latency: Duration::from_millis(latency),
+				request_size: 10,
+				response_size: 20,
+			});
+		}
+
+		let out = recorder.render();
+		let labels = r#"method="say_\"hello\"",kind="method_call""#;
+
+		assert!(out.contains(&format!("jsonrpsee_calls_total{{{labels}}} 3\n")));
+		assert!(out.contains(&format!("jsonrpsee_call_errors_total{{{labels},code=\"-32602\"}} 1\n")));
+		assert!(out.contains(&format!("jsonrpsee_call_errors_total{{{labels},code=\"none\"}} 1\n")));
+		assert!(out.contains(&format!("jsonrpsee_call_duration_seconds_bucket{{{labels},le=\"0.1\"}} 1\n")));
+		assert!(out.contains(&format!("jsonrpsee_call_duration_seconds_bucket{{{labels},le=\"1\"}} 2\n")));
+		assert!(out.contains(&format!("jsonrpsee_call_duration_seconds_bucket{{{labels},le=\"+Inf\"}} 3\n")));
+		assert!(out.contains(&format!("jsonrpsee_request_size_bytes_total{{{labels}}} 30\n")));
+		assert!(out.contains(&format!("jsonrpsee_response_size_bytes_total{{{labels}}} 60\n")));
+		assert!(out.contains("jsonrpsee_active_connections 1\n"));
+		assert!(out.contains("jsonrpsee_active_subscriptions{method=\"sub\"} 1\n"));
+
+		recorder.on_unsubscribe("sub");
+		recorder.on_disconnect();
+
+		let out = recorder.render();
+		assert!(out.contains("jsonrpsee_active_connections 0\n"));
+		assert!(out.contains("jsonrpsee_active_subscriptions{method=\"sub\"} 0\n"));
+	}
+
+	#[test]
+	fn unknown_methods_share_a_label() {
+		let recorder = PrometheusRecorder::new();
+
+		for method in ["foo", "bar"] {
+			recorder.on_call(&CallMetrics {
+				method,
+				kind: CallKind::MethodCall,
+				outcome: Outcome::Error(METHOD_NOT_FOUND_CODE),
+				latency: Duration::from_millis(1),
+				request_size: 10,
+				response_size: 20,
+			});
+		}
+
+		let out = recorder.render();
+		assert!(out.contains("jsonrpsee_calls_total{method=\"<unknown>\",kind=\"method_call\"} 2\n"));
+		assert!(!out.contains("foo"));
+	}
+}
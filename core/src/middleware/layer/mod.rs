@@ -28,6 +28,17 @@
 
 mod either;
 mod logger;
+mod metrics;
+mod response;
+mod trace_context;
 
 pub use either::*;
 pub use logger::*;
+pub use metrics::*;
+pub use response::*;
+pub use trace_context::*;
+
+cfg_client! {
+	mod retry;
+	pub use retry::*;
+}
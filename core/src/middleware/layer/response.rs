@@ -0,0 +1,48 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Outcome of the responses, which is shared by the middleware that inspects them.
+
+/// Outcome of a call.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+	/// The call succeeded.
+	Success,
+	/// The call failed with a JSON-RPC error code.
+	Error(i32),
+	/// The call failed without a JSON-RPC response, such as a transport error in the client.
+	Failed,
+}
+
+/// Response whose outcome can be inspected by the middleware, such as
+/// the [`RpcMetrics`](super::RpcMetrics) and [`RpcTracing`](super::RpcTracing) middleware.
+pub trait ResponseOutcome {
+	/// Get the outcome of the call.
+	fn outcome(&self) -> Outcome;
+
+	/// Get the size of the serialized response in bytes.
+	fn size(&self) -> usize;
+}
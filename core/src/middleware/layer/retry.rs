@@ -0,0 +1,383 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! RPC retry layer.
+
+use std::collections::BTreeSet;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::middleware::{Batch, Notification, RpcServiceT};
+
+use futures_util::Future;
+use jsonrpsee_types::Request;
+
+/// Response which can be inspected by the [`RpcRetry`] middleware.
+pub trait RetryResponse {
+	/// Get the JSON-RPC error code if the call failed with an error response.
+	fn error_code(&self) -> Option<i32>;
+
+	/// Returns whether the call failed without a response, such as a network error or a timeout.
+	fn is_transport_error(&self) -> bool;
+}
+
+/// Policy of the [`RpcRetry`] middleware.
+///
+/// Only method calls to methods marked as idempotent are retried, because a failed call may
+/// have been executed by the server anyway. The client traits generated by the `rpc` macro
+/// provide the methods marked with `#[method(idempotent)]` as the `IDEMPOTENT_METHODS` constant.
+///
+/// The delay before the next attempt starts at `initial_delay` and is multiplied by `factor`
+/// after each failed attempt, but never exceeds `max_delay`. With jitter, a random delay
+/// between zero and the computed delay is used instead.
+///
+/// Default: 3 attempts, initial delay 100 ms, factor 2, max delay 10 seconds, jitter enabled,
+/// retry on transport errors but not on error responses and no idempotent methods.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+	max_attempts: usize,
+	initial_delay: Duration,
+	max_delay: Duration,
+	factor: u32,
+	jitter: bool,
+	retry_on_transport_errors: bool,
+	retry_on_codes: BTreeSet<i32>,
+	idempotent_methods: BTreeSet<String>,
+	all_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self {
+			max_attempts: 3,
+			initial_delay: Duration::from_millis(100),
+			max_delay: Duration::from_secs(10),
+			factor: 2,
+			jitter: true,
+			retry_on_transport_errors: true,
+			retry_on_codes: BTreeSet::new(),
+			idempotent_methods: BTreeSet::new(),
+			all_idempotent: false,
+		}
+	}
+}
+
+impl RetryPolicy {
+	/// Create a new retry policy with the default exponential backoff.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Configure the maximum number of attempts, including the first call (default is 3).
+	///
+	/// # Panics
+	///
+	/// This method panics if `max` is 0.
+	pub fn max_attempts(mut self, max: usize) -> Self {
+		assert!(max > 0);
+		self.max_attempts = max;
+		self
+	}
+
+	/// Configure the delay before the first retry (default is 100 ms).
+	pub fn initial_delay(mut self, delay: Duration) -> Self {
+		self.initial_delay = delay;
+		self
+	}
+
+	/// Configure the maximum delay between two attempts (default is 10 seconds).
+	pub fn max_delay(mut self, max: Duration) -> Self {
+		self.max_delay = max;
+		self
+	}
+
+	/// Configure the factor the delay is multiplied with after each failed attempt (default is 2).
+	///
+	/// # Panics
+	///
+	/// This method panics if `factor` is 0.
+	pub fn factor(mut self, factor: u32) -> Self {
+		assert!(factor > 0);
+		self.factor = factor;
+		self
+	}
+
+	/// Configure whether a random jitter is applied to the delays (default is `true`).
+	pub fn jitter(mut self, jitter: bool) -> Self {
+		self.jitter = jitter;
+		self
+	}
+
+	/// Configure whether calls that failed without a response are retried (default is `true`).
+	pub fn retry_on_transport_errors(mut self, retry: bool) -> Self {
+		self.retry_on_transport_errors = retry;
+		self
+	}
+
+	/// Retry calls that failed with one of the JSON-RPC error `codes`.
+	pub fn retry_on_codes(mut self, codes: impl IntoIterator<Item = i32>) -> Self {
+		self.retry_on_codes.extend(codes);
+		self
+	}
+
+	/// Mark the `methods` as idempotent such that failed calls to them are retried.
+	pub fn idempotent_methods<T: Into<String>>(mut self, methods: impl IntoIterator<Item = T>) -> Self {
+		self.idempotent_methods.extend(methods.into_iter().map(Into::into));
+		self
+	}
+
+	/// Regard all methods as idempotent.
+	pub fn all_idempotent(mut self) -> Self {
+		self.all_idempotent = true;
+		self
+	}
+
+	fn is_idempotent(&self, method: &str) -> bool {
+		self.all_idempotent || self.idempotent_methods.contains(method)
+	}
+
+	fn should_retry<R: RetryResponse>(&self, rp: &R) -> bool {
+		match rp.error_code() {
+			Some(code) => self.retry_on_codes.contains(&code),
+			None => self.retry_on_transport_errors && rp.is_transport_error(),
+		}
+	}
+
+	/// Delay before the attempt following the failed attempt `attempt`, starting at 1.
+	fn delay(&self, attempt: usize) -> Duration {
+		let exp = u32::try_from(attempt - 1).unwrap_or(u32::MAX);
+		let delay = self.factor.checked_pow(exp).and_then(|f| self.initial_delay.checked_mul(f)).unwrap_or(self.max_delay);
+		let delay = delay.min(self.max_delay);
+
+		if self.jitter { delay.mul_f64(random_fraction()) } else { delay }
+	}
+}
+
+/// RPC retry layer.
+#[derive(Debug, Clone)]
+pub struct RpcRetryLayer(Arc<RetryPolicy>);
+
+impl RpcRetryLayer {
+	/// Create a new retry layer with the given policy.
+	pub fn new(policy: RetryPolicy) -> Self {
+		Self(Arc::new(policy))
+	}
+}
+
+impl<S> tower::Layer<S> for RpcRetryLayer {
+	type Service = RpcRetry<S>;
+
+	fn layer(&self, service: S) -> Self::Service {
+		RpcRetry { service, policy: self.0.clone() }
+	}
+}
+
+/// A middleware that retries failed method calls according to a [`RetryPolicy`].
+///
+/// Batch requests and notifications are not retried.
+///
+/// The middleware is meant for the HTTP client, which sends each attempt as a new HTTP request
+/// with the same request ID. It must not be used with the WebSocket client: once its connection
+/// is closed every call fails with [`Error::RestartNeeded`](crate::client::Error::RestartNeeded),
+/// which isn't retried since the client has to be rebuilt, and the response to a call that
+/// timed out may still arrive with the request ID of the retry. Use the `ReconnectingWsClient`
+/// of `jsonrpsee-ws-client` to retry calls over WebSocket instead.
+#[derive(Debug, Clone)]
+pub struct RpcRetry<S> {
+	service: S,
+	policy: Arc<RetryPolicy>,
+}
+
+impl<S> RpcServiceT for RpcRetry<S>
+where
+	S: RpcServiceT + Send + Sync + Clone + 'static,
+	S::MethodResponse: RetryResponse + Send,
+{
+	type MethodResponse = S::MethodResponse;
+	type NotificationResponse = S::NotificationResponse;
+	type BatchResponse = S::BatchResponse;
+
+	fn call<'a>(&self, request: Request<'a>) -> impl Future<Output = Self::MethodResponse> + Send + 'a {
+		let service = self.service.clone();
+		let policy = self.policy.clone();
+
+		async move {
+			if !policy.is_idempotent(request.method_name()) {
+				return service.call(request).await;
+			}
+
+			let mut attempt = 1;
+			loop {
+				let rp = service.call(request.clone()).await;
+
+				if attempt >= policy.max_attempts || !policy.should_retry(&rp) {
+					break rp;
+				}
+
+				let delay = policy.delay(attempt);
+				tracing::debug!(
+					target: "jsonrpsee",
+					"Retrying call to `{}` in {:?} (attempt {}/{})",
+					request.method_name(),
+					delay,
+					attempt + 1,
+					policy.max_attempts
+				);
+				futures_timer::Delay::new(delay).await;
+				attempt += 1;
+			}
+		}
+	}
+
+	fn batch<'a>(&self, batch: Batch<'a>) -> impl Future<Output = Self::BatchResponse> + Send + 'a {
+		self.service.batch(batch)
+	}
+
+	fn notification<'a>(&self, n: Notification<'a>) -> impl Future<Output = Self::NotificationResponse> + Send + 'a {
+		self.service.notification(n)
+	}
+}
+
+/// Random number in `[0, 1)`, which is good enough for jitter.
+fn random_fraction() -> f64 {
+	let random = RandomState::new().build_hasher().finish();
+	(random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use tower::Layer;
+
+	/// The response of the [`Flaky`] service.
+	#[derive(Debug, PartialEq)]
+	enum MockResponse {
+		Success,
+		Error(i32),
+		Transport,
+	}
+
+	impl RetryResponse for MockResponse {
+		fn error_code(&self) -> Option<i32> {
+			match self {
+				Self::Error(code) => Some(*code),
+				_ => None,
+			}
+		}
+
+		fn is_transport_error(&self) -> bool {
+			*self == Self::Transport
+		}
+	}
+
+	/// Service which fails the first `fail_first` calls with the `failure` response.
+	#[derive(Clone)]
+	struct Flaky {
+		calls: Arc<AtomicUsize>,
+		fail_first: usize,
+		failure: fn() -> MockResponse,
+	}
+
+	impl RpcServiceT for Flaky {
+		type MethodResponse = MockResponse;
+		type NotificationResponse = ();
+		type BatchResponse = ();
+
+		fn call<'a>(&self, _: Request<'a>) -> impl Future<Output = Self::MethodResponse> + Send + 'a {
+			let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+			let rp = if n <= self.fail_first { (self.failure)() } else { MockResponse::Success };
+			async move { rp }
+		}
+
+		fn batch<'a>(&self, _: Batch<'a>) -> impl Future<Output = Self::BatchResponse> + Send + 'a {
+			async {}
+		}
+
+		fn notification<'a>(&self, _: Notification<'a>) -> impl Future<Output = Self::NotificationResponse> + Send + 'a {
+			async {}
+		}
+	}
+
+	/// Call `method` on a flaky service wrapped by the retry middleware and return the response
+	/// and the number of attempts.
+	async fn call(
+		policy: RetryPolicy,
+		method: &'static str,
+		fail_first: usize,
+		failure: fn() -> MockResponse,
+	) -> (MockResponse, usize) {
+		let calls = Arc::new(AtomicUsize::new(0));
+		let service = RpcRetryLayer::new(policy.jitter(false).initial_delay(Duration::ZERO))
+			.layer(Flaky { calls: calls.clone(), fail_first, failure });
+		let rp = service.call(Request::borrowed(method, None, jsonrpsee_types::Id::Number(1))).await;
+		(rp, calls.load(Ordering::SeqCst))
+	}
+
+	#[tokio::test]
+	async fn retryable_failures_are_retried() {
+		let policy = RetryPolicy::new().retry_on_codes([-32099]).idempotent_methods(["get"]);
+
+		assert_eq!(call(policy.clone(), "get", 2, || MockResponse::Transport).await, (MockResponse::Success, 3));
+		assert_eq!(call(policy, "get", 1, || MockResponse::Error(-32099)).await, (MockResponse::Success, 2));
+	}
+
+	#[tokio::test]
+	async fn non_retryable_failures_are_not_retried() {
+		let policy = RetryPolicy::new().retry_on_codes([-32099]).idempotent_methods(["get"]);
+
+		// Error code that isn't configured to be retried.
+		let rp = call(policy.clone(), "get", 1, || MockResponse::Error(-32000)).await;
+		assert_eq!(rp, (MockResponse::Error(-32000), 1));
+		// Method that isn't idempotent.
+		assert_eq!(call(policy.clone(), "put", 1, || MockResponse::Transport).await, (MockResponse::Transport, 1));
+		// Transport errors that aren't configured to be retried.
+		let policy = policy.retry_on_transport_errors(false);
+		assert_eq!(call(policy, "get", 1, || MockResponse::Transport).await, (MockResponse::Transport, 1));
+	}
+
+	#[tokio::test]
+	async fn attempts_are_limited() {
+		let policy = RetryPolicy::new().all_idempotent();
+		assert_eq!(call(policy.clone(), "get", 10, || MockResponse::Transport).await, (MockResponse::Transport, 3));
+
+		let policy = policy.max_attempts(5);
+		assert_eq!(call(policy.clone(), "get", 10, || MockResponse::Transport).await, (MockResponse::Transport, 5));
+		assert_eq!(call(policy, "get", 4, || MockResponse::Transport).await, (MockResponse::Success, 5));
+	}
+
+	#[test]
+	fn delays_are_capped() {
+		let policy = RetryPolicy::new().jitter(false).max_delay(Duration::from_millis(500));
+		let delays: Vec<_> = (1..=5).map(|attempt| policy.delay(attempt).as_millis()).collect();
+		assert_eq!(delays, [100, 200, 400, 500, 500]);
+
+		let policy = RetryPolicy::new().max_delay(Duration::from_millis(500));
+		assert!((1..=100).all(|attempt| policy.delay(attempt) <= Duration::from_millis(500)));
+	}
+}
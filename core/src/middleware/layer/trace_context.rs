@@ -0,0 +1,460 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! RPC tracing layer which propagates the W3C trace context.
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use std::borrow::Cow;
+
+use crate::middleware::layer::{Outcome, ResponseOutcome};
+use crate::middleware::{Batch, Notification, RpcServiceT};
+
+use futures_util::Future;
+use jsonrpsee_types::Request;
+use serde_json::value::RawValue;
+use tracing::Instrument;
+
+/// Name of the HTTP header and of the reserved member of the JSON-RPC messages
+/// which carry the [`TraceContext`].
+pub const TRACEPARENT: &str = "traceparent";
+
+/// Trace context as defined by the [W3C Trace Context](https://www.w3.org/TR/trace-context/) specification.
+///
+/// The [`RpcTracing`] middleware inserts the context of the span of each call in the extensions
+/// of the call. The clients send it in the `traceparent` HTTP header or, over WebSocket, in the
+/// reserved `traceparent` member of the JSON-RPC message, and the server inserts the received
+/// context as [`RemoteTraceContext`] in the extensions of the call.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TraceContext {
+	trace_id: [u8; 16],
+	span_id: [u8; 8],
+	flags: u8,
+}
+
+impl TraceContext {
+	const SAMPLED: u8 = 0x01;
+
+	/// Create the context of a new trace with a random trace ID and span ID.
+	pub fn new_root(sampled: bool) -> Self {
+		let mut trace_id = [0; 16];
+		trace_id[..8].copy_from_slice(&random_id());
+		trace_id[8..].copy_from_slice(&random_id());
+
+		Self { trace_id, span_id: random_id(), flags: if sampled { Self::SAMPLED } else { 0 } }
+	}
+
+	/// Create the context of a child span, which belongs to the same trace.
+	pub fn child(&self) -> Self {
+		Self { trace_id: self.trace_id, span_id: random_id(), flags: self.flags }
+	}
+
+	/// Parse the value of a `traceparent` header.
+	///
+	/// Returns `None` if the value is malformed or the trace ID or span ID are all zeros.
+	pub fn from_traceparent(value: &str) -> Option<Self> {
+		let mut parts = value.trim().split('-');
+		let version = parts.next()?;
+		let trace_id = parts.next()?;
+		let span_id = parts.next()?;
+		let flags = parts.next()?;
+
+		// Future versions may append more fields, but version `00` has exactly four.
+		let version = decode_hex::<1>(version)?[0];
+		if version == 0xff || (version == 0 && parts.next().is_some()) {
+			return None;
+		}
+
+		let trace_id = decode_hex::<16>(trace_id)?;
+		let span_id = decode_hex::<8>(span_id)?;
+		let flags = decode_hex::<1>(flags)?[0];
+
+		if trace_id == [0; 16] || span_id == [0; 8] {
+			return None;
+		}
+
+		Some(Self { trace_id, span_id, flags })
+	}
+
+	/// Format the context as the value of a `traceparent` header.
+	pub fn to_traceparent(&self) -> String {
+		self.to_string()
+	}
+
+	/// Get the trace ID as a lowercase hex string.
+	pub fn trace_id(&self) -> String {
+		encode_hex(&self.trace_id)
+	}
+
+	/// Get the span ID as a lowercase hex string.
+	pub fn span_id(&self) -> String {
+		encode_hex(&self.span_id)
+	}
+
+	/// Whether the caller may have recorded the trace.
+	pub fn is_sampled(&self) -> bool {
+		self.flags & Self::SAMPLED != 0
+	}
+}
+
+impl fmt::Display for TraceContext {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "00-{}-{}-{:02x}", self.trace_id(), self.span_id(), self.flags)
+	}
+}
+
+/// Trace context received by the server from the client, which the server [`RpcTracing`]
+/// middleware continues.
+///
+/// It's inserted in the extensions of the calls whose JSON-RPC message or HTTP request have
+/// a valid `traceparent`, whether or not the server has a tracing middleware.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RemoteTraceContext(pub TraceContext);
+
+/// JSON-RPC call or notification with the reserved `traceparent` member.
+#[cfg(feature = "client")]
+#[derive(serde::Serialize)]
+pub(crate) struct WithTraceParent<'a, T> {
+	#[serde(flatten)]
+	pub(crate) message: &'a T,
+	pub(crate) traceparent: String,
+}
+
+fn random_id() -> [u8; 8] {
+	static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+	// `RandomState` is randomly seeded, which is sufficient to make the IDs unique.
+	let mut hasher = RandomState::new().build_hasher();
+	hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+	hasher.finish().to_be_bytes()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn decode_hex<const N: usize>(s: &str) -> Option<[u8; N]> {
+	if s.len() != N * 2 || !s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+		return None;
+	}
+
+	let mut out = [0; N];
+	for (i, byte) in out.iter_mut().enumerate() {
+		*byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok()?;
+	}
+	Some(out)
+}
+
+/// Whether the [`RpcTracing`] middleware traces the calls of a client or of a server.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SpanKind {
+	/// The calls are made by the client and the trace context is propagated to the server.
+	Client,
+	/// The calls are handled by the server and continue the trace context of the client.
+	Server,
+}
+
+impl SpanKind {
+	/// Get the kind as used by OpenTelemetry.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::Client => "client",
+			Self::Server => "server",
+		}
+	}
+}
+
+/// RPC tracing layer.
+#[derive(Debug, Copy, Clone)]
+pub struct RpcTracingLayer {
+	kind: SpanKind,
+}
+
+impl RpcTracingLayer {
+	/// Create a tracing layer for a client.
+	pub fn client() -> Self {
+		Self { kind: SpanKind::Client }
+	}
+
+	/// Create a tracing layer for a server.
+	///
+	/// The spans continue the [`RemoteTraceContext`] of the calls.
+	pub fn server() -> Self {
+		Self { kind: SpanKind::Server }
+	}
+
+	/// Get the kind of the traced calls.
+	pub fn kind(&self) -> SpanKind {
+		self.kind
+	}
+}
+
+impl<S> tower::Layer<S> for RpcTracingLayer {
+	type Service = RpcTracing<S>;
+
+	fn layer(&self, service: S) -> Self::Service {
+		RpcTracing { service, kind: self.kind }
+	}
+}
+
+/// A middleware that creates a span for each RPC call and propagates its [`TraceContext`].
+///
+/// The spans are created at the `INFO` level with the fields of the OpenTelemetry semantic
+/// conventions for JSON-RPC (`rpc.system`, `rpc.method`, `rpc.jsonrpc.request_id` and
+/// `rpc.jsonrpc.error_code`), the sizes of the params and of the response and the `otel.kind`,
+/// `otel.name` and `otel.status_code` fields, such that they can be exported with an
+/// OpenTelemetry subscriber.
+///
+/// The client continues the trace of the [`TraceContext`] in the extensions of the call if
+/// an inner middleware inserted one, or else the trace of the current span, and starts a new
+/// trace otherwise. The server continues the [`RemoteTraceContext`] received from the client
+/// and inserts the context of its own span as [`TraceContext`], such that handlers which read
+/// the extensions can propagate it further.
+///
+/// With the `opentelemetry` feature and a `tracing-opentelemetry` layer, the propagated
+/// context is the one of the OpenTelemetry span: the received context is set as the parent
+/// of the span and, without one, the client continues the trace of the current span. Without
+/// an OpenTelemetry layer, the trace of the current span isn't known and the span IDs are
+/// generated by the middleware.
+#[derive(Debug, Clone)]
+pub struct RpcTracing<S> {
+	service: S,
+	kind: SpanKind,
+}
+
+impl<S> RpcTracing<S> {
+	fn span(&self, name: &str, params_size: usize) -> tracing::Span {
+		tracing::info_span!(
+			target: "jsonrpsee",
+			"rpc",
+			otel.name = name,
+			otel.kind = self.kind.as_str(),
+			otel.status_code = tracing::field::Empty,
+			rpc.system = "jsonrpc",
+			rpc.method = name,
+			rpc.jsonrpc.version = "2.0",
+			rpc.jsonrpc.request_id = tracing::field::Empty,
+			rpc.jsonrpc.error_code = tracing::field::Empty,
+			rpc.request.params_size = params_size,
+			rpc.response.size = tracing::field::Empty,
+			trace_id = tracing::field::Empty,
+			span_id = tracing::field::Empty,
+			parent_span_id = tracing::field::Empty,
+		)
+	}
+
+	/// Continue the trace of the parent of the call in `span` and insert the context of `span` in `extensions`.
+	fn enter(&self, span: &tracing::Span, extensions: &mut http::Extensions) -> TraceContext {
+		let parent = match self.kind {
+			SpanKind::Client => extensions.get::<TraceContext>().copied(),
+			SpanKind::Server => extensions.get::<RemoteTraceContext>().map(|cx| cx.0),
+		};
+
+		let parent = match parent {
+			Some(parent) => {
+				set_otel_parent(span, &parent);
+				Some(parent)
+			}
+			// The span of a client call is a child of the current span, so it continues its trace.
+			None if self.kind == SpanKind::Client => otel_context(&tracing::Span::current()),
+			None => None,
+		};
+
+		let cx = otel_context(span).unwrap_or_else(|| match parent {
+			Some(parent) => parent.child(),
+			None => TraceContext::new_root(true),
+		});
+
+		span.record("trace_id", tracing::field::display(cx.trace_id()));
+		span.record("span_id", tracing::field::display(cx.span_id()));
+		if let Some(parent) = parent {
+			span.record("parent_span_id", tracing::field::display(parent.span_id()));
+		}
+		extensions.insert(cx);
+
+		cx
+	}
+}
+
+/// Get the context of the OpenTelemetry span of `span`, if it's exported by an OpenTelemetry layer.
+#[cfg(feature = "opentelemetry")]
+fn otel_context(span: &tracing::Span) -> Option<TraceContext> {
+	use opentelemetry::trace::TraceContextExt;
+	use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+	let cx = span.context();
+	let span_cx = cx.span().span_context().clone();
+
+	span_cx.is_valid().then(|| TraceContext {
+		trace_id: span_cx.trace_id().to_bytes(),
+		span_id: span_cx.span_id().to_bytes(),
+		flags: span_cx.trace_flags().to_u8(),
+	})
+}
+
+#[cfg(not(feature = "opentelemetry"))]
+fn otel_context(_span: &tracing::Span) -> Option<TraceContext> {
+	None
+}
+
+/// Set `parent` as the remote parent of the OpenTelemetry span of `span`.
+#[cfg(feature = "opentelemetry")]
+fn set_otel_parent(span: &tracing::Span, parent: &TraceContext) {
+	use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
+	use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+	let span_cx = SpanContext::new(
+		TraceId::from_bytes(parent.trace_id),
+		SpanId::from_bytes(parent.span_id),
+		TraceFlags::new(parent.flags),
+		true,
+		TraceState::default(),
+	);
+
+	// This only fails if the span isn't exported by an OpenTelemetry layer.
+	let _ = span.set_parent(opentelemetry::Context::new().with_remote_span_context(span_cx));
+}
+
+#[cfg(not(feature = "opentelemetry"))]
+fn set_otel_parent(_span: &tracing::Span, _parent: &TraceContext) {}
+
+/// Get the size of `params`, which is cheaper than serializing the whole message.
+fn params_size(params: Option<&Cow<RawValue>>) -> usize {
+	params.map_or(0, |p| p.get().len())
+}
+
+fn record_response<R: ResponseOutcome>(span: &tracing::Span, rp: &R) {
+	span.record("rpc.response.size", rp.size());
+	match rp.outcome() {
+		Outcome::Success => {
+			span.record("otel.status_code", "OK");
+		}
+		Outcome::Error(code) => {
+			span.record("otel.status_code", "ERROR");
+			span.record("rpc.jsonrpc.error_code", code);
+		}
+		Outcome::Failed => {
+			span.record("otel.status_code", "ERROR");
+		}
+	}
+}
+
+impl<S> RpcServiceT for RpcTracing<S>
+where
+	S: RpcServiceT + Send + Sync + Clone + 'static,
+	S::MethodResponse: ResponseOutcome,
+	S::BatchResponse: ResponseOutcome,
+{
+	type MethodResponse = S::MethodResponse;
+	type NotificationResponse = S::NotificationResponse;
+	type BatchResponse = S::BatchResponse;
+
+	fn call<'a>(&self, mut request: Request<'a>) -> impl Future<Output = Self::MethodResponse> + Send + 'a {
+		let span = self.span(request.method_name(), params_size(request.params.as_ref()));
+		self.enter(&span, request.extensions_mut());
+		span.record("rpc.jsonrpc.request_id", tracing::field::display(&request.id));
+		let service = self.service.clone();
+
+		async move {
+			let rp = service.call(request).await;
+			record_response(&tracing::Span::current(), &rp);
+			rp
+		}
+		.instrument(span)
+	}
+
+	fn batch<'a>(&self, mut batch: Batch<'a>) -> impl Future<Output = Self::BatchResponse> + Send + 'a {
+		let params_size = batch.iter().flatten().map(|entry| params_size(entry.params())).sum();
+		let span = self.span("batch", params_size);
+		let cx = self.enter(&span, batch.extensions_mut());
+		// Each call of the batch is part of the span of the batch.
+		for entry in batch.iter_mut().flatten() {
+			entry.extensions_mut().insert(cx);
+		}
+		let service = self.service.clone();
+
+		async move {
+			let rp = service.batch(batch).await;
+			record_response(&tracing::Span::current(), &rp);
+			rp
+		}
+		.instrument(span)
+	}
+
+	fn notification<'a>(
+		&self,
+		mut n: Notification<'a>,
+	) -> impl Future<Output = Self::NotificationResponse> + Send + 'a {
+		let span = self.span(n.method_name(), params_size(n.params.as_ref()));
+		self.enter(&span, n.extensions_mut());
+
+		self.service.notification(n).instrument(span)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::TraceContext;
+
+	#[test]
+	fn traceparent_roundtrip_works() {
+		let value = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+		let cx = TraceContext::from_traceparent(value).unwrap();
+
+		assert_eq!(cx.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+		assert_eq!(cx.span_id(), "00f067aa0ba902b7");
+		assert!(cx.is_sampled());
+		assert_eq!(cx.to_traceparent(), value);
+
+		let child = cx.child();
+		assert_eq!(child.trace_id(), cx.trace_id());
+		assert_ne!(child.span_id(), cx.span_id());
+	}
+
+	#[test]
+	fn invalid_traceparent_is_rejected() {
+		for value in [
+			"",
+			"00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+			"00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+			"ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+			"00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+			"00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+			"00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+			"00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+		] {
+			assert!(TraceContext::from_traceparent(value).is_none(), "{value}");
+		}
+
+		// Unknown future versions may have more fields.
+		assert!(
+			TraceContext::from_traceparent("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra").is_some()
+		);
+	}
+}
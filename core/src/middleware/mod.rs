@@ -198,6 +198,11 @@ impl IsSubscription {
 	}
 }
 
+/// A marker type in the extensions of the calls the server received in an HTTP request
+/// rather than on a WebSocket or IPC connection.
+#[derive(Debug, Copy, Clone)]
+pub struct IsHttpRequest;
+
 /// An extension type for the [`RpcServiceT::batch`] for the expected id range of the batch entries.
 #[derive(Debug, Clone)]
 pub struct IsBatch {
@@ -371,6 +376,37 @@ impl<L> RpcServiceBuilder<L> {
 		RpcServiceBuilder(self.0.layer(layer::RpcLoggerLayer::new(max_log_len)))
 	}
 
+	/// Add a metrics layer to [`RpcServiceBuilder`]
+	///
+	/// This records metrics such as call counts, error codes and latencies for every call
+	/// to `recorder`, see [`layer::PrometheusRecorder`] for a recorder that can be served
+	/// on a `/metrics` route.
+	pub fn rpc_metrics<R: layer::MetricsRecorder>(
+		self,
+		recorder: std::sync::Arc<R>,
+	) -> RpcServiceBuilder<Stack<layer::RpcMetricsLayer<R>, L>> {
+		RpcServiceBuilder(self.0.layer(layer::RpcMetricsLayer::new(recorder)))
+	}
+
+	/// Add a tracing layer to [`RpcServiceBuilder`]
+	///
+	/// This creates a span for every call and propagates its [`layer::TraceContext`] between
+	/// the client and the server, use [`layer::RpcTracingLayer::client`] in clients and
+	/// [`layer::RpcTracingLayer::server`] in servers.
+	pub fn rpc_tracing(self, layer: layer::RpcTracingLayer) -> RpcServiceBuilder<Stack<layer::RpcTracingLayer, L>> {
+		RpcServiceBuilder(self.0.layer(layer))
+	}
+
+	/// Add a retry layer to [`RpcServiceBuilder`]
+	///
+	/// This retries the failed method calls to idempotent methods according to `policy`,
+	/// which is only supported by the HTTP client, see [`layer::RpcRetry`].
+	#[cfg(feature = "client")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "client")))]
+	pub fn rpc_retry(self, policy: layer::RetryPolicy) -> RpcServiceBuilder<Stack<layer::RpcRetryLayer, L>> {
+		RpcServiceBuilder(self.0.layer(layer::RpcRetryLayer::new(policy)))
+	}
+
 	/// Wrap the service `S` with the middleware.
 	pub fn service<S>(&self, service: S) -> L::Service
 	where
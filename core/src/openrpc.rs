@@ -0,0 +1,388 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! [OpenRPC](https://spec.open-rpc.org/) service discovery.
+//!
+//! The document is generated by the `#[rpc(server, openrpc)]` proc macro and
+//! served by the `rpc.discover` method of the generated `RpcModule`.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value as JsonValue, json};
+
+/// Version of the OpenRPC specification the generated documents conform to.
+pub const OPENRPC_VERSION: &str = "1.3.2";
+
+/// Name of the method which returns the OpenRPC document.
+pub const DISCOVER_METHOD: &str = "rpc.discover";
+
+/// OpenRPC document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenRpc {
+	/// Version of the OpenRPC specification.
+	pub openrpc: String,
+	/// Metadata about the API.
+	pub info: Info,
+	/// Methods provided by the API.
+	pub methods: Vec<Method>,
+}
+
+impl OpenRpc {
+	/// Create a new OpenRPC document without any methods.
+	pub fn new(info: Info) -> Self {
+		Self { openrpc: OPENRPC_VERSION.to_owned(), info, methods: Vec::new() }
+	}
+
+	/// Add a method to the document.
+	pub fn with_method(mut self, method: Method) -> Self {
+		self.methods.push(method);
+		self
+	}
+
+	/// Merge the methods of another document into this document.
+	///
+	/// This is useful when several RPC traits are merged into the same `RpcModule`.
+	pub fn merge(&mut self, other: OpenRpc) {
+		self.methods.extend(other.methods);
+	}
+}
+
+/// Metadata about the API.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Info {
+	/// Title of the API.
+	pub title: String,
+	/// Version of the API.
+	pub version: String,
+	/// Description of the API.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+}
+
+/// Whether the params of a method are passed by name, by position or either.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParamStructure {
+	/// Params are passed as a JSON object.
+	ByName,
+	/// Params are passed as a JSON array.
+	ByPosition,
+	/// Params may be passed as either a JSON object or a JSON array.
+	Either,
+}
+
+/// Description of a method or a subscription.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Method {
+	/// Name of the method.
+	pub name: String,
+	/// Short summary of the method.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub summary: Option<String>,
+	/// Verbose description of the method.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+	/// Params of the method.
+	pub params: Vec<ContentDescriptor>,
+	/// Result of the method, `None` for notifications.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub result: Option<ContentDescriptor>,
+	/// How the params are passed.
+	pub param_structure: ParamStructure,
+	/// Whether the method is deprecated.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub deprecated: bool,
+	/// Other names the method can be called with.
+	#[serde(default, rename = "x-aliases", skip_serializing_if = "Vec::is_empty")]
+	pub aliases: Vec<String>,
+	/// Set if the method is a subscription, the result then describes the notifications.
+	#[serde(default, rename = "x-subscription", skip_serializing_if = "Option::is_none")]
+	pub subscription: Option<SubscriptionInfo>,
+}
+
+/// Extra information about a subscription.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionInfo {
+	/// Method name of the notifications.
+	pub notification: String,
+	/// Method to unsubscribe with.
+	pub unsubscribe: String,
+	/// Other names the unsubscribe method can be called with.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub unsubscribe_aliases: Vec<String>,
+}
+
+/// Describes a param or a result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentDescriptor {
+	/// Name of the content.
+	pub name: String,
+	/// Description of the content.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+	/// Whether the content is required.
+	#[serde(default)]
+	pub required: bool,
+	/// JSON Schema of the content.
+	pub schema: JsonValue,
+}
+
+impl ContentDescriptor {
+	/// Create a content descriptor where the schema is derived from `T`.
+	pub fn new<T: JsonSchema + ?Sized>(name: impl Into<String>, required: bool) -> Self {
+		Self { name: name.into(), description: None, required, schema: T::json_schema() }
+	}
+}
+
+/// Something that can describe its JSON representation as a [JSON Schema](https://json-schema.org/).
+///
+/// OpenRPC 1.x documents use JSON Schema draft 7, so the schemas must not use keywords of later drafts.
+///
+/// The `#[rpc(server, openrpc)]` proc macro requires all params, return types and
+/// subscription items to implement this trait.
+///
+/// # Examples
+///
+/// ```rust
+/// use jsonrpsee_core::openrpc::JsonSchema;
+/// use serde_json::{json, Value};
+///
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Block {
+///     number: u64,
+///     hash: String,
+/// }
+///
+/// impl JsonSchema for Block {
+///     fn json_schema() -> Value {
+///         json!({
+///             "type": "object",
+///             "properties": {
+///                 "number": u64::json_schema(),
+///                 "hash": String::json_schema(),
+///             },
+///             "required": ["number", "hash"],
+///         })
+///     }
+/// }
+/// ```
+pub trait JsonSchema {
+	/// Returns the JSON Schema of the type.
+	fn json_schema() -> JsonValue;
+}
+
+macro_rules! impl_json_schema {
+	($schema:tt => $($ty:ty),*) => {
+		$(
+			impl JsonSchema for $ty {
+				fn json_schema() -> JsonValue {
+					json!($schema)
+				}
+			}
+		)*
+	};
+}
+
+impl_json_schema!({ "type": "integer", "minimum": 0 } => u8, u16, u32, u64, u128, usize);
+impl_json_schema!({ "type": "integer" } => i8, i16, i32, i64, i128, isize);
+impl_json_schema!({ "type": "number" } => f32, f64);
+impl_json_schema!({ "type": "boolean" } => bool);
+impl_json_schema!({ "type": "string" } => str, String);
+impl_json_schema!({ "type": "string", "minLength": 1, "maxLength": 1 } => char);
+impl_json_schema!({ "type": "null" } => ());
+
+// Any JSON value.
+impl_json_schema!({} => JsonValue, serde_json::value::RawValue);
+
+impl<T: JsonSchema> JsonSchema for Option<T> {
+	fn json_schema() -> JsonValue {
+		json!({ "anyOf": [T::json_schema(), { "type": "null" }] })
+	}
+}
+
+impl<T: JsonSchema> JsonSchema for [T] {
+	fn json_schema() -> JsonValue {
+		json!({ "type": "array", "items": T::json_schema() })
+	}
+}
+
+impl<T: JsonSchema, const N: usize> JsonSchema for [T; N] {
+	fn json_schema() -> JsonValue {
+		json!({ "type": "array", "items": T::json_schema(), "minItems": N, "maxItems": N })
+	}
+}
+
+impl<T: JsonSchema> JsonSchema for Vec<T> {
+	fn json_schema() -> JsonValue {
+		<[T]>::json_schema()
+	}
+}
+
+impl<T: JsonSchema> JsonSchema for HashSet<T> {
+	fn json_schema() -> JsonValue {
+		json!({ "type": "array", "items": T::json_schema(), "uniqueItems": true })
+	}
+}
+
+impl<T: JsonSchema> JsonSchema for BTreeSet<T> {
+	fn json_schema() -> JsonValue {
+		HashSet::<T>::json_schema()
+	}
+}
+
+impl<K, V: JsonSchema> JsonSchema for HashMap<K, V> {
+	fn json_schema() -> JsonValue {
+		json!({ "type": "object", "additionalProperties": V::json_schema() })
+	}
+}
+
+impl<K, V: JsonSchema> JsonSchema for BTreeMap<K, V> {
+	fn json_schema() -> JsonValue {
+		HashMap::<K, V>::json_schema()
+	}
+}
+
+impl<T: JsonSchema + ?Sized> JsonSchema for &T {
+	fn json_schema() -> JsonValue {
+		T::json_schema()
+	}
+}
+
+impl<T: JsonSchema + ?Sized> JsonSchema for Box<T> {
+	fn json_schema() -> JsonValue {
+		T::json_schema()
+	}
+}
+
+impl<T: JsonSchema + ?Sized> JsonSchema for Arc<T> {
+	fn json_schema() -> JsonValue {
+		T::json_schema()
+	}
+}
+
+impl<T: JsonSchema + ToOwned + ?Sized> JsonSchema for Cow<'_, T> {
+	fn json_schema() -> JsonValue {
+		T::json_schema()
+	}
+}
+
+// Only the `Ok` value is described because errors are returned as JSON-RPC error objects.
+impl<T: JsonSchema, E> JsonSchema for Result<T, E> {
+	fn json_schema() -> JsonValue {
+		T::json_schema()
+	}
+}
+
+macro_rules! impl_json_schema_tuple {
+	($($len:expr => ($($name:ident)+))+) => {
+		$(
+			impl<$($name: JsonSchema),+> JsonSchema for ($($name,)+) {
+				fn json_schema() -> JsonValue {
+					json!({
+						"type": "array",
+						"items": [$($name::json_schema()),+],
+						"additionalItems": false,
+						"minItems": $len,
+						"maxItems": $len,
+					})
+				}
+			}
+		)+
+	}
+}
+
+impl_json_schema_tuple! {
+	1 => (T0)
+	2 => (T0 T1)
+	3 => (T0 T1 T2)
+	4 => (T0 T1 T2 T3)
+	5 => (T0 T1 T2 T3 T4)
+	6 => (T0 T1 T2 T3 T4 T5)
+	7 => (T0 T1 T2 T3 T4 T5 T6)
+	8 => (T0 T1 T2 T3 T4 T5 T6 T7)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn json_schema_works() {
+		assert_eq!(u8::json_schema(), json!({ "type": "integer", "minimum": 0 }));
+		assert_eq!(i64::json_schema(), json!({ "type": "integer" }));
+		assert_eq!(
+			Option::<Vec<String>>::json_schema(),
+			json!({ "anyOf": [{ "type": "array", "items": { "type": "string" } }, { "type": "null" }] })
+		);
+		assert_eq!(Result::<bool, ()>::json_schema(), json!({ "type": "boolean" }));
+		assert_eq!(
+			<(u8, String)>::json_schema(),
+			json!({
+				"type": "array",
+				"items": [{ "type": "integer", "minimum": 0 }, { "type": "string" }],
+				"additionalItems": false,
+				"minItems": 2,
+				"maxItems": 2,
+			})
+		);
+	}
+
+	#[test]
+	fn serialize_document_works() {
+		let doc = OpenRpc::new(Info { title: "Api".into(), version: "0.1.0".into(), description: None }).with_method(
+			Method {
+				name: "foo".into(),
+				summary: None,
+				description: None,
+				params: vec![ContentDescriptor::new::<u8>("a", true)],
+				result: Some(ContentDescriptor::new::<String>("result", true)),
+				param_structure: ParamStructure::Either,
+				deprecated: false,
+				aliases: Vec::new(),
+				subscription: None,
+			},
+		);
+
+		let exp = json!({
+			"openrpc": OPENRPC_VERSION,
+			"info": { "title": "Api", "version": "0.1.0" },
+			"methods": [{
+				"name": "foo",
+				"params": [{ "name": "a", "required": true, "schema": { "type": "integer", "minimum": 0 } }],
+				"result": { "name": "result", "required": true, "schema": { "type": "string" } },
+				"paramStructure": "either",
+			}],
+		});
+
+		assert_eq!(serde_json::to_value(&doc).unwrap(), exp);
+		assert_eq!(serde_json::from_value::<OpenRpc>(exp).unwrap(), doc);
+	}
+}
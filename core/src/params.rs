@@ -237,6 +237,16 @@ impl<'a> BatchRequestBuilder<'a> {
 		Ok(())
 	}
 
+	/// Get the number of calls in the batch request.
+	pub fn len(&self) -> usize {
+		self.0.len()
+	}
+
+	/// Is empty.
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
 	/// Finish the building process and return a valid batch parameter.
 	#[allow(clippy::type_complexity)]
 	pub fn build(self) -> Result<Vec<(&'a str, Option<Box<RawValue>>)>, EmptyBatchRequest> {
@@ -31,6 +31,7 @@ const LOG_TARGET: &str = "jsonrpsee-core";
 use std::io;
 use std::task::Poll;
 
+use crate::middleware::layer::{MetricsResponse, Outcome, ResponseOutcome, SubscriptionCloseHook};
 use crate::traits::ToJson;
 
 use futures_util::{Future, FutureExt};
@@ -83,6 +84,37 @@ impl ToJson for MethodResponse {
 	}
 }
 
+impl ResponseOutcome for MethodResponse {
+	fn outcome(&self) -> Outcome {
+		match self.as_error_code() {
+			Some(code) => Outcome::Error(code),
+			None => Outcome::Success,
+		}
+	}
+
+	fn size(&self) -> usize {
+		self.json.get().len()
+	}
+}
+
+impl MetricsResponse for MethodResponse {
+	fn subscription_id(&self) -> Option<String> {
+		if !self.is_subscription() || !self.is_success() {
+			return None;
+		}
+
+		let rp: Response<&RawValue> = serde_json::from_str(self.json.get()).ok()?;
+		match rp.payload {
+			InnerResponsePayload::Success(sub_id) => Some(sub_id.get().to_owned()),
+			InnerResponsePayload::Error(_) => None,
+		}
+	}
+
+	fn subscription_close_hook(&self) -> Option<SubscriptionCloseHook> {
+		self.extensions.get::<SubscriptionCloseHook>().cloned()
+	}
+}
+
 impl std::fmt::Display for MethodResponse {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		write!(f, "{}", self.json)
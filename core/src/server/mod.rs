@@ -32,17 +32,23 @@ mod error;
 pub mod helpers;
 /// Method response.
 mod method_response;
+/// Identity of the callers.
+mod principal;
 /// JSON-RPC "modules" group sets of methods that belong together and handles method/subscription registration.
 mod rpc_module;
 /// Subscription related types.
 mod subscription;
+/// Broadcast of subscription notifications.
+mod topic;
 
 pub use error::*;
 pub use helpers::*;
 pub use http::Extensions;
 pub use method_response::*;
+pub use principal::*;
 pub use rpc_module::*;
 pub use subscription::*;
+pub use topic::*;
 
 use jsonrpsee_types::ErrorObjectOwned;
 
@@ -168,5 +174,6 @@ impl_into_response!(
 	&'static str,
 	bool,
 	serde_json::Value,
+	crate::openrpc::OpenRpc,
 	()
 );
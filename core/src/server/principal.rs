@@ -0,0 +1,70 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Identity of the callers.
+
+use std::collections::BTreeSet;
+
+/// Identity of the caller and the roles it has been granted.
+///
+/// The principal is inserted in the extensions of the calls by the authenticator of the server
+/// and methods restricted with [`RpcModule::set_required_roles`](crate::server::RpcModule::set_required_roles)
+/// are only executed if the principal has all of the required roles.
+///
+/// A principal inserted by a HTTP middleware into the extensions of the request works as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+	name: String,
+	roles: BTreeSet<String>,
+}
+
+impl Principal {
+	/// Create a new [`Principal`] without any roles.
+	pub fn new(name: impl Into<String>) -> Self {
+		Self { name: name.into(), roles: BTreeSet::new() }
+	}
+
+	/// Grant the role to the principal.
+	pub fn with_role(mut self, role: impl Into<String>) -> Self {
+		self.roles.insert(role.into());
+		self
+	}
+
+	/// Get the name of the principal.
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Get the roles of the principal.
+	pub fn roles(&self) -> impl Iterator<Item = &str> {
+		self.roles.iter().map(String::as_str)
+	}
+
+	/// Returns whether the principal has the role.
+	pub fn has_role(&self, role: &str) -> bool {
+		self.roles.contains(role)
+	}
+}
@@ -29,19 +29,22 @@ use std::fmt::{self, Debug};
 use std::future::Future;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::error::RegisterMethodError;
 use crate::id_providers::RandomIntegerIdProvider;
+use crate::middleware::layer::SubscriptionCloseHook;
 use crate::server::helpers::MethodSink;
 use crate::server::subscription::{
-	BoundedSubscriptions, IntoSubscriptionCloseResponse, PendingSubscriptionSink, Subscribers, Subscription,
-	SubscriptionCloseResponse, SubscriptionKey, SubscriptionPermit, SubscriptionState, sub_message_to_json,
+	BoundedSubscriptions, IntoSubscriptionCloseResponse, PendingSubscriptionSink, ReplayConfig, ResumableSubscriptions,
+	Subscribers, Subscription, SubscriptionCloseResponse, SubscriptionKey, SubscriptionPermit, SubscriptionState,
+	sub_message_to_json,
 };
-use crate::server::{LOG_TARGET, MethodResponse, ResponsePayload};
+use crate::server::{LOG_TARGET, MethodResponse, Principal, ResponsePayload, TopicHub};
 use crate::traits::ToRpcParams;
 use futures_util::{FutureExt, future::BoxFuture};
 use http::Extensions;
-use jsonrpsee_types::error::{ErrorCode, ErrorObject};
+use jsonrpsee_types::error::{ErrorCode, ErrorObject, reject_subscription_not_resumable};
 use jsonrpsee_types::{
 	ErrorObjectOwned, Id, Params, Request, Response, ResponseSuccess, SubscriptionId as RpcSubscriptionId,
 };
@@ -212,6 +215,10 @@ impl Debug for MethodCallback {
 #[derive(Default, Debug, Clone)]
 pub struct Methods {
 	callbacks: Arc<FxHashMap<&'static str, MethodCallback>>,
+	timeouts: Arc<FxHashMap<&'static str, Duration>>,
+	required_roles: Arc<FxHashMap<&'static str, Arc<[&'static str]>>>,
+	/// Name of the method each alias was registered for.
+	aliases: Arc<FxHashMap<&'static str, &'static str>>,
 	extensions: Extensions,
 }
 
@@ -263,6 +270,24 @@ impl Methods {
 			callbacks.insert(name, callback);
 		}
 
+		let timeouts = Arc::make_mut(&mut self.timeouts);
+
+		for (name, timeout) in other.timeouts.iter() {
+			timeouts.insert(name, *timeout);
+		}
+
+		let required_roles = Arc::make_mut(&mut self.required_roles);
+
+		for (name, roles) in other.required_roles.iter() {
+			required_roles.insert(name, roles.clone());
+		}
+
+		let aliases = Arc::make_mut(&mut self.aliases);
+
+		for (alias, method) in other.aliases.iter() {
+			aliases.insert(alias, method);
+		}
+
 		Ok(())
 	}
 
@@ -277,6 +302,23 @@ impl Methods {
 		self.callbacks.get_key_value(method_name).map(|(k, v)| (*k, v))
 	}
 
+	/// Returns the execution timeout of the method if one has been configured
+	/// with [`RpcModule::set_method_timeout`].
+	pub fn method_timeout(&self, method_name: &str) -> Option<Duration> {
+		self.timeouts.get(self.canonical_name(method_name)).copied()
+	}
+
+	/// Returns the roles required to call the method if they have been configured
+	/// with [`RpcModule::set_required_roles`].
+	pub fn required_roles(&self, method_name: &str) -> Option<&[&'static str]> {
+		self.required_roles.get(self.canonical_name(method_name)).map(|roles| &**roles)
+	}
+
+	/// Returns the name of the method that `method_name` is an alias of, or `method_name` itself.
+	fn canonical_name<'a>(&self, method_name: &'a str) -> &'a str {
+		self.aliases.get(method_name).copied().unwrap_or(method_name)
+	}
+
 	/// Helper to call a method on the `RPC module` without having to spin up a server.
 	///
 	/// The params must be serializable as JSON array, see [`ToRpcParams`] for further documentation.
@@ -597,6 +639,12 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	/// Be aware that a subscription consist of two methods, `subscribe` and `unsubscribe` and
 	/// it's the caller responsibility to remove both `subscribe` and `unsubscribe` methods for subscriptions.
 	pub fn remove_method(&mut self, method_name: &'static str) -> Option<MethodCallback> {
+		let is_alias = Arc::make_mut(&mut self.methods.aliases).remove(method_name).is_some();
+		// The aliases of the method are still protected by its settings.
+		if !is_alias && !self.methods.aliases.values().any(|method| *method == method_name) {
+			Arc::make_mut(&mut self.methods.timeouts).remove(method_name);
+			Arc::make_mut(&mut self.methods.required_roles).remove(method_name);
+		}
 		self.methods.mut_callbacks().remove(method_name)
 	}
 
@@ -784,6 +832,178 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 		unsubscribe_method_name: &'static str,
 		callback: F,
 	) -> Result<&mut MethodCallback, RegisterMethodError>
+	where
+		Context: Send + Sync + 'static,
+		F: (Fn(Params<'static>, PendingSubscriptionSink, Arc<Context>, Extensions) -> Fut)
+			+ Send
+			+ Sync
+			+ Clone
+			+ 'static,
+		Fut: Future<Output = R> + Send + 'static,
+		R: IntoSubscriptionCloseResponse + Send,
+	{
+		self.register_subscription_inner(subscribe_method_name, notif_method_name, unsubscribe_method_name, None, callback)
+	}
+
+	/// Register a subscription which can be resumed by the client after the connection was closed.
+	///
+	/// This works like [`RpcModule::register_subscription`] but every notification is assigned a
+	/// sequence number, starting at 1, and the `result` of the notification is wrapped in
+	/// [`jsonrpsee_types::Sequenced`]:
+	///
+	/// ```json
+	/// {
+	///  "jsonrpc": "2.0",
+	///  "method": "<method>",
+	///  "params": {
+	///    "subscription": "<subscriptionID>",
+	///    "result": { "seq": 1, "value": <your msg> }
+	///  }
+	/// }
+	/// ```
+	///
+	/// The last messages are kept in a replay buffer and the subscription is kept alive for the grace period
+	/// after the connection was closed, see [`ReplayConfig`]. In the meantime [`SubscriptionSink::send`](crate::server::SubscriptionSink::send)
+	/// keeps buffering the messages and [`SubscriptionSink::is_closed`](crate::server::SubscriptionSink::is_closed)
+	/// returns `false`.
+	///
+	/// The client resumes the subscription, on any connection, by calling `resume_method_name` with the subscription ID
+	/// and the sequence number of the last message it received, i.e. `[subscriptionID, seq]`.
+	/// The server responds with the subscription ID, replays the buffered messages after `seq` and continues the
+	/// subscription on the new connection. The call fails if the subscription expired or if messages after `seq`
+	/// were dropped from the replay buffer, in which case the client has to subscribe again.
+	///
+	/// The resume method requires the same roles as the subscribe method, see [`RpcModule::set_required_roles`],
+	/// and only the [`Principal`] which subscribed, if any, may resume the subscription.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jsonrpsee_core::server::{RpcModule, ReplayConfig, SubscriptionMessage};
+	/// use std::time::Duration;
+	///
+	/// let mut module = RpcModule::new(());
+	/// let replay = ReplayConfig::new().max_buffered(128).grace_period(Duration::from_secs(10));
+	///
+	/// module.register_resumable_subscription("sub", "notif", "unsub", "resume", replay, |_, pending, _, _| async move {
+	///     let sink = pending.accept().await?;
+	///
+	///     for i in 0..u64::MAX {
+	///         let msg = serde_json::value::to_raw_value(&i).unwrap();
+	///         // Only fails if the subscription is closed and wasn't resumed.
+	///         sink.send(msg).await?;
+	///         tokio::time::sleep(Duration::from_secs(1)).await;
+	///     }
+	///
+	///     Ok(())
+	/// });
+	/// ```
+	pub fn register_resumable_subscription<R, F, Fut>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		resume_method_name: &'static str,
+		config: ReplayConfig,
+		callback: F,
+	) -> Result<&mut MethodCallback, RegisterMethodError>
+	where
+		Context: Send + Sync + 'static,
+		F: (Fn(Params<'static>, PendingSubscriptionSink, Arc<Context>, Extensions) -> Fut)
+			+ Send
+			+ Sync
+			+ Clone
+			+ 'static,
+		Fut: Future<Output = R> + Send + 'static,
+		R: IntoSubscriptionCloseResponse + Send,
+	{
+		if resume_method_name == subscribe_method_name || resume_method_name == unsubscribe_method_name {
+			return Err(RegisterMethodError::SubscriptionNameConflict(resume_method_name.into()));
+		}
+
+		self.methods.verify_method_name(resume_method_name)?;
+		self.methods.verify_method_name(subscribe_method_name)?;
+		self.methods.verify_method_name(unsubscribe_method_name)?;
+
+		let store = ResumableSubscriptions::default();
+
+		// Resume
+		{
+			let store = store.clone();
+			self.methods.mut_callbacks().insert(
+				resume_method_name,
+				MethodCallback::Subscription(Arc::new(move |id, params, method_sink, conn, extensions| {
+					let id = id.into_owned();
+					let resume = params.parse::<(RpcSubscriptionId, u64)>().map(|(sub_id, last_seq)| {
+						let replay = store.subscriptions.lock().get(&sub_id).cloned();
+						(replay, last_seq)
+					});
+
+					Box::pin(async move {
+						let rp = match resume {
+							Ok((Some(replay), last_seq)) => {
+								let principal = extensions.get::<Principal>();
+								replay
+									.resume(id, last_seq, method_sink, conn.conn_id, conn.subscription_permit, principal)
+									.await
+							}
+							Ok((None, _)) => MethodResponse::error(
+								id,
+								reject_subscription_not_resumable("Unknown or expired subscription"),
+							),
+							Err(e) => MethodResponse::error(id, e),
+						};
+
+						rp.with_extensions(extensions)
+					})
+				})),
+			);
+		}
+
+		// The resume method is protected by the settings of the subscribe method.
+		Arc::make_mut(&mut self.methods.aliases).insert(resume_method_name, subscribe_method_name);
+
+		self.register_subscription_inner(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			Some((config, store)),
+			callback,
+		)
+	}
+
+	/// Register a subscription method whose notifications are produced by a [`TopicHub`].
+	///
+	/// Every subscriber receives the messages sent to the hub after it subscribed. Messages are
+	/// serialized once by [`TopicHub::send`] and shared by all subscribers, which only copy them into
+	/// their notifications. Subscribers that fall behind are handled according to the
+	/// [`LagPolicy`](crate::server::LagPolicy) of the hub.
+	///
+	/// The subscription doesn't take any parameters.
+	pub fn register_topic(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		hub: TopicHub,
+	) -> Result<&mut MethodCallback, RegisterMethodError>
+	where
+		Context: Send + Sync + 'static,
+	{
+		self.register_subscription(subscribe_method_name, notif_method_name, unsubscribe_method_name, move |_, pending, _, _| {
+			let hub = hub.clone();
+			async move { hub.pipe(pending).await }
+		})
+	}
+
+	fn register_subscription_inner<R, F, Fut>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		replay: Option<(ReplayConfig, ResumableSubscriptions)>,
+		callback: F,
+	) -> Result<&mut MethodCallback, RegisterMethodError>
 	where
 		Context: Send + Sync + 'static,
 		F: (Fn(Params<'static>, PendingSubscriptionSink, Arc<Context>, Extensions) -> Fut)
@@ -810,6 +1030,7 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 
 					let sub_id = uniq_sub.sub_id.clone();
 					let method = notif_method_name;
+					let (close_hook, on_close) = SubscriptionCloseHook::new();
 
 					let sink = PendingSubscriptionSink {
 						inner: method_sink.clone(),
@@ -819,6 +1040,10 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 						id: id.clone().into_owned(),
 						subscribe: tx,
 						permit: conn.subscription_permit,
+						replay: replay
+							.clone()
+							.map(|(config, store)| (config, store, extensions.get::<Principal>().cloned())),
+						on_close,
 					};
 
 					// The subscription callback is a future from the subscription
@@ -866,7 +1091,9 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 							Err(_) => MethodResponse::error(id, ErrorCode::InternalError),
 						};
 
-						rp.with_extensions(extensions)
+						let mut rp = rp.with_extensions(extensions);
+						rp.extensions_mut().insert(close_hook);
+						rp
 					})
 				})),
 			)?
@@ -944,6 +1171,7 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 
 					// response to the subscription call.
 					let (tx, rx) = oneshot::channel();
+					let (close_hook, on_close) = SubscriptionCloseHook::new();
 
 					let sink = PendingSubscriptionSink {
 						inner: method_sink.clone(),
@@ -953,6 +1181,8 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 						id: id.clone().into_owned(),
 						subscribe: tx,
 						permit: conn.subscription_permit,
+						replay: None,
+						on_close,
 					};
 
 					callback(params, sink, ctx.clone(), &extensions);
@@ -965,7 +1195,9 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 							Err(_) => MethodResponse::error(id, ErrorCode::InternalError),
 						};
 
-						rp.with_extensions(extensions)
+						let mut rp = rp.with_extensions(extensions);
+						rp.extensions_mut().insert(close_hook);
+						rp
 					})
 				})),
 			)?
@@ -1033,6 +1265,8 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	}
 
 	/// Register an alias for an existing_method. Alias uniqueness is enforced.
+	///
+	/// The alias shares the timeout and the required roles of the method.
 	pub fn register_alias(
 		&mut self,
 		alias: &'static str,
@@ -1047,6 +1281,73 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 
 		self.methods.mut_callbacks().insert(alias, callback);
 
+		// The alias shares the timeout and the roles of the method, even if they are set later.
+		let method = self.methods.canonical_name(existing_method);
+		Arc::make_mut(&mut self.methods.aliases).insert(alias, method);
+
+		Ok(())
+	}
+
+	/// Set the maximum duration a call to an already registered method may run.
+	///
+	/// When the timeout elapses the server drops the call and replies with
+	/// [`METHOD_TIMEOUT_CODE`](jsonrpsee_types::error::METHOD_TIMEOUT_CODE). It overrides the default timeout
+	/// of the server and only applies to async and blocking methods; a blocking method keeps running on
+	/// its thread but its result is discarded.
+	///
+	/// The timeout has no effect on methods registered with [`RpcModule::register_method`], which run
+	/// to completion on the connection task, nor on subscriptions.
+	///
+	/// ## Examples
+	///
+	/// ```
+	/// use std::time::Duration;
+	/// use jsonrpsee_core::server::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_async_method("slow", |_params, _ctx, _| async { "done" }).unwrap();
+	/// module.set_method_timeout("slow", Duration::from_secs(5)).unwrap();
+	/// ```
+	pub fn set_method_timeout(&mut self, method_name: &'static str, timeout: Duration) -> Result<(), RegisterMethodError> {
+		if !self.methods.callbacks.contains_key(method_name) {
+			return Err(RegisterMethodError::MethodNotFound(method_name.into()));
+		}
+
+		let method_name = self.methods.canonical_name(method_name);
+		Arc::make_mut(&mut self.methods.timeouts).insert(method_name, timeout);
+
+		Ok(())
+	}
+
+	/// Restrict an already registered method to callers that have all of the `roles`.
+	///
+	/// The server looks up the `Principal` of the caller in the extensions of the call and rejects the call
+	/// with [`UNAUTHORIZED_CODE`](jsonrpsee_types::error::UNAUTHORIZED_CODE) before the method runs if it's
+	/// missing or lacks one of the roles. Calls made directly on the module, such as [`Methods::call`], aren't checked.
+	///
+	/// The roles apply to the method and all of its aliases.
+	///
+	/// ## Examples
+	///
+	/// ```
+	/// use jsonrpsee_core::server::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_method("admin_shutdown", |_params, _ctx, _| "bye").unwrap();
+	/// module.set_required_roles("admin_shutdown", ["admin"]).unwrap();
+	/// ```
+	pub fn set_required_roles(
+		&mut self,
+		method_name: &'static str,
+		roles: impl IntoIterator<Item = &'static str>,
+	) -> Result<(), RegisterMethodError> {
+		if !self.methods.callbacks.contains_key(method_name) {
+			return Err(RegisterMethodError::MethodNotFound(method_name.into()));
+		}
+
+		let method_name = self.methods.canonical_name(method_name);
+		Arc::make_mut(&mut self.methods.required_roles).insert(method_name, roles.into_iter().collect());
+
 		Ok(())
 	}
 }
@@ -28,22 +28,27 @@
 
 use super::helpers::MethodSink;
 use super::{MethodResponse, MethodsError, ResponsePayload};
-use crate::server::LOG_TARGET;
+use crate::middleware::layer::SubscriptionCloseGuard;
+use crate::server::{LOG_TARGET, Principal};
 use crate::server::error::{DisconnectError, PendingSubscriptionAcceptError, SendTimeoutError, TrySendError};
 use crate::server::rpc_module::ConnectionId;
 use crate::{error::SubscriptionError, traits::IdProvider};
-use jsonrpsee_types::SubscriptionPayload;
+use jsonrpsee_types::error::reject_subscription_not_resumable;
 use jsonrpsee_types::response::SubscriptionPayloadError;
-use jsonrpsee_types::{ErrorObjectOwned, Id, SubscriptionId, SubscriptionResponse};
+use jsonrpsee_types::{ErrorObjectOwned, Id, Sequenced, SubscriptionId, SubscriptionPayload, SubscriptionResponse};
 use parking_lot::Mutex;
 use rustc_hash::FxHashMap;
 use serde::{Serialize, de::DeserializeOwned};
 use serde_json::value::RawValue;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::{sync::Arc, time::Duration};
 use tokio::sync::{OwnedSemaphorePermit, Semaphore, mpsc, oneshot};
 
 /// Type-alias for subscribers.
 pub type Subscribers = Arc<Mutex<FxHashMap<SubscriptionKey, (MethodSink, mpsc::Receiver<()>)>>>;
+/// Type-alias for the replay buffers of the resumable subscriptions.
+pub(crate) type ResumableSubscriptions = Arc<ResumableStore>;
 /// Subscription permit.
 pub type SubscriptionPermit = OwnedSemaphorePermit;
 
@@ -55,7 +60,7 @@ pub trait IntoSubscriptionCloseResponse {
 }
 
 /// Represents what action that will sent when a subscription callback returns.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SubscriptionCloseResponse {
 	/// No further message will be sent.
 	None,
@@ -209,6 +214,10 @@ pub struct PendingSubscriptionSink {
 	pub(crate) subscribe: oneshot::Sender<MethodResponse>,
 	/// Subscription permit.
 	pub(crate) permit: OwnedSemaphorePermit,
+	/// Replay buffer settings and the principal of the caller if the subscription is resumable.
+	pub(crate) replay: Option<(ReplayConfig, ResumableSubscriptions, Option<Principal>)>,
+	/// Runs the close callbacks once the subscription is closed.
+	pub(crate) on_close: SubscriptionCloseGuard,
 }
 
 impl PendingSubscriptionSink {
@@ -251,6 +260,16 @@ impl PendingSubscriptionSink {
 		if success {
 			let (tx, rx) = mpsc::channel(1);
 			self.subscribers.lock().insert(self.uniq_sub.clone(), (self.inner.clone(), rx));
+			let replay = self.replay.map(|(config, store, owner)| {
+				ReplayBuffer::new(
+					config,
+					self.inner.clone(),
+					self.uniq_sub.clone(),
+					self.subscribers.clone(),
+					store,
+					owner,
+				)
+			});
 			Ok(SubscriptionSink {
 				inner: self.inner,
 				method: self.method,
@@ -258,6 +277,8 @@ impl PendingSubscriptionSink {
 				uniq_sub: self.uniq_sub,
 				unsubscribe: IsUnsubscribed(tx),
 				_permit: Arc::new(self.permit),
+				_on_close: Arc::new(self.on_close),
+				replay,
 			})
 		} else {
 			panic!(
@@ -302,6 +323,10 @@ pub struct SubscriptionSink {
 	unsubscribe: IsUnsubscribed,
 	/// Subscription permit
 	_permit: Arc<SubscriptionPermit>,
+	/// Runs the close callbacks once all clones of the sink have been dropped.
+	_on_close: Arc<SubscriptionCloseGuard>,
+	/// Replay buffer if the subscription is resumable.
+	replay: Option<Arc<ReplayBuffer>>,
 }
 
 impl SubscriptionSink {
@@ -316,8 +341,18 @@ impl SubscriptionSink {
 	}
 
 	/// Get the connection ID.
+	///
+	/// For resumable subscriptions this is the connection the subscription was last resumed on.
 	pub fn connection_id(&self) -> ConnectionId {
-		self.uniq_sub.conn_id
+		match &self.replay {
+			Some(replay) => replay.attached.lock().key.conn_id,
+			None => self.uniq_sub.conn_id,
+		}
+	}
+
+	/// Returns whether the subscription is resumable.
+	pub fn is_resumable(&self) -> bool {
+		self.replay.is_some()
 	}
 
 	/// Send out a response on the subscription and wait until there is capacity.
@@ -338,6 +373,11 @@ impl SubscriptionSink {
 			return Err(DisconnectError(msg));
 		}
 
+		if let Some(replay) = &self.replay {
+			replay.send(msg, self.method).await;
+			return Ok(());
+		}
+
 		let json = sub_message_to_json(msg, &self.uniq_sub.sub_id, self.method);
 		self.inner.send(json).await
 	}
@@ -355,6 +395,10 @@ impl SubscriptionSink {
 			return Err(SendTimeoutError::Closed(msg));
 		}
 
+		if let Some(replay) = &self.replay {
+			return replay.send_timeout(msg, self.method, timeout).await;
+		}
+
 		let json = sub_message_to_json(msg, &self.uniq_sub.sub_id, self.method);
 		self.inner.send_timeout(json, timeout).await
 	}
@@ -373,17 +417,32 @@ impl SubscriptionSink {
 			return Err(TrySendError::Closed(msg));
 		}
 
+		if let Some(replay) = &self.replay {
+			return replay.try_send(msg, self.method);
+		}
+
 		let json = sub_message_to_json(msg, &self.uniq_sub.sub_id, self.method);
 		self.inner.try_send(json)
 	}
 
 	/// Returns whether the subscription is closed.
+	///
+	/// Resumable subscriptions are only closed once they are unsubscribed or
+	/// the client didn't resume them within the grace period after the connection was closed.
 	pub fn is_closed(&self) -> bool {
+		if self.replay.is_some() {
+			return !self.is_active_subscription();
+		}
+
 		self.inner.is_closed() || !self.is_active_subscription()
 	}
 
 	/// Completes when the subscription has been closed.
 	pub async fn closed(&self) {
+		if self.replay.is_some() {
+			return self.unsubscribe.unsubscribed().await;
+		}
+
 		// Both are cancel-safe thus ok to use select here.
 		tokio::select! {
 			_ = self.inner.closed() => (),
@@ -393,12 +452,18 @@ impl SubscriptionSink {
 
 	/// Get the capacity of the subscription.
 	pub fn capacity(&self) -> usize {
-		self.inner.capacity()
+		match &self.replay {
+			Some(replay) => replay.attached.lock().sink.capacity(),
+			None => self.inner.capacity(),
+		}
 	}
 
 	/// Get the max capacity of the subscription.
 	pub fn max_capacity(&self) -> usize {
-		self.inner.max_capacity()
+		match &self.replay {
+			Some(replay) => replay.attached.lock().sink.max_capacity(),
+			None => self.inner.max_capacity(),
+		}
 	}
 
 	fn is_active_subscription(&self) -> bool {
@@ -408,12 +473,340 @@ impl SubscriptionSink {
 
 impl Drop for SubscriptionSink {
 	fn drop(&mut self) {
-		if self.is_active_subscription() {
+		if let Some(replay) = &self.replay {
+			replay.close();
+		} else if self.is_active_subscription() {
 			self.subscribers.lock().remove(&self.uniq_sub);
 		}
 	}
 }
 
+/// Configuration of the replay buffer of resumable subscriptions.
+///
+/// See [`crate::server::RpcModule::register_resumable_subscription`] for further information.
+#[derive(Debug, Copy, Clone)]
+pub struct ReplayConfig {
+	max_buffered: usize,
+	grace_period: Duration,
+	max_detached: usize,
+}
+
+impl Default for ReplayConfig {
+	fn default() -> Self {
+		Self { max_buffered: 1024, grace_period: Duration::from_secs(30), max_detached: 1024 }
+	}
+}
+
+impl ReplayConfig {
+	/// Create a new [`ReplayConfig`] with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the maximum number of messages kept for replay per subscription,
+	/// the oldest messages are dropped first.
+	///
+	/// At least one message is kept.
+	///
+	/// Default: 1024.
+	pub fn max_buffered(mut self, max: usize) -> Self {
+		self.max_buffered = max.max(1);
+		self
+	}
+
+	/// Set how long a subscription is kept alive after the connection was closed.
+	///
+	/// Default: 30 seconds.
+	pub fn grace_period(mut self, grace_period: Duration) -> Self {
+		self.grace_period = grace_period;
+		self
+	}
+
+	/// Set the maximum number of subscriptions, of all connections, that are kept alive
+	/// after their connection was closed.
+	///
+	/// Subscriptions whose connection is closed when the limit is reached are closed immediately.
+	///
+	/// Default: 1024.
+	pub fn max_detached(mut self, max: usize) -> Self {
+		self.max_detached = max;
+		self
+	}
+}
+
+/// Replay buffers of the resumable subscriptions of a method.
+#[derive(Debug, Default)]
+pub(crate) struct ResumableStore {
+	pub(crate) subscriptions: Mutex<FxHashMap<SubscriptionId<'static>, Arc<ReplayBuffer>>>,
+	/// Number of subscriptions that are waiting to be resumed.
+	detached: AtomicUsize,
+}
+
+/// Replay buffer of a resumable subscription which outlives the connection
+/// the subscription was made on for the grace period.
+#[derive(Debug)]
+pub(crate) struct ReplayBuffer {
+	config: ReplayConfig,
+	/// Messages that can be replayed.
+	///
+	/// The lock is held while replaying to serialize the messages with a resume.
+	state: tokio::sync::Mutex<ReplayState>,
+	/// Connection the subscription is currently attached to.
+	attached: Mutex<Attached>,
+	/// Whether the subscription is counted as detached in the store.
+	detached: AtomicBool,
+	subscribers: Subscribers,
+	store: ResumableSubscriptions,
+	/// Principal of the caller which subscribed, only the same principal may resume the subscription.
+	owner: Option<Principal>,
+}
+
+#[derive(Debug)]
+struct ReplayState {
+	next_seq: u64,
+	messages: VecDeque<(u64, Box<RawValue>)>,
+	/// Incremented on every resume to ignore when a previous connection is closed.
+	generation: u64,
+}
+
+#[derive(Debug)]
+struct Attached {
+	sink: MethodSink,
+	key: SubscriptionKey,
+	_permit: Option<SubscriptionPermit>,
+}
+
+impl ReplayBuffer {
+	fn new(
+		config: ReplayConfig,
+		sink: MethodSink,
+		key: SubscriptionKey,
+		subscribers: Subscribers,
+		store: ResumableSubscriptions,
+		owner: Option<Principal>,
+	) -> Arc<Self> {
+		let sub_id = key.sub_id.clone();
+		let this = Arc::new(Self {
+			config,
+			state: tokio::sync::Mutex::new(ReplayState { next_seq: 1, messages: VecDeque::new(), generation: 0 }),
+			attached: Mutex::new(Attached { sink: sink.clone(), key, _permit: None }),
+			detached: AtomicBool::new(false),
+			subscribers,
+			store,
+			owner,
+		});
+
+		this.store.subscriptions.lock().insert(sub_id, this.clone());
+		this.expire_when_detached(0, sink);
+		this
+	}
+
+	async fn send(&self, msg: SubscriptionMessage, method: &str) {
+		let (json, sink) = {
+			let mut state = self.state.lock().await;
+			self.push(&mut state, msg, method)
+		};
+		// The message is replayed if the client resumes the subscription.
+		let _ = sink.send(json).await;
+	}
+
+	async fn send_timeout(
+		&self,
+		msg: SubscriptionMessage,
+		method: &str,
+		timeout: Duration,
+	) -> Result<(), SendTimeoutError> {
+		let deadline = tokio::time::Instant::now() + timeout;
+		let (json, sink, seq, generation) = {
+			let Ok(mut state) = tokio::time::timeout_at(deadline, self.state.lock()).await else {
+				return Err(SendTimeoutError::Timeout(msg));
+			};
+			let (json, sink) = self.push(&mut state, msg.clone(), method);
+			(json, sink, state.next_seq - 1, state.generation)
+		};
+
+		match sink.send_timeout(json, deadline.saturating_duration_since(tokio::time::Instant::now())).await {
+			Err(SendTimeoutError::Timeout(_)) => {
+				let mut state = self.state.lock().await;
+				// The message was replayed if the subscription was resumed in the meantime.
+				if state.generation != generation {
+					return Ok(());
+				}
+				Self::remove(&mut state, seq);
+				Err(SendTimeoutError::Timeout(msg))
+			}
+			_ => Ok(()),
+		}
+	}
+
+	fn try_send(&self, msg: SubscriptionMessage, method: &str) -> Result<(), TrySendError> {
+		let Ok(mut state) = self.state.try_lock() else {
+			return Err(TrySendError::Full(msg));
+		};
+
+		let (json, mut sink) = self.push(&mut state, msg.clone(), method);
+		match sink.try_send(json) {
+			Err(TrySendError::Full(_)) => {
+				let seq = state.next_seq - 1;
+				Self::remove(&mut state, seq);
+				Err(TrySendError::Full(msg))
+			}
+			_ => Ok(()),
+		}
+	}
+
+	/// Assign the next sequence number to the message and buffer it.
+	fn push(&self, state: &mut ReplayState, msg: SubscriptionMessage, method: &str) -> (Box<RawValue>, MethodSink) {
+		let attached = self.attached.lock();
+		let seq = state.next_seq;
+		let json = sequenced_message_to_json(msg, &attached.key.sub_id, method, seq);
+
+		state.next_seq += 1;
+		if state.messages.len() >= self.config.max_buffered {
+			state.messages.pop_front();
+		}
+		state.messages.push_back((seq, json.clone()));
+
+		(json, attached.sink.clone())
+	}
+
+	/// Remove a buffered message because it wasn't sent.
+	///
+	/// The sequence number is only reused if no message was buffered after it.
+	fn remove(state: &mut ReplayState, seq: u64) {
+		if state.next_seq == seq + 1 {
+			state.messages.pop_back();
+			state.next_seq -= 1;
+		} else {
+			state.messages.retain(|(s, _)| *s != seq);
+		}
+	}
+
+	/// Attach the subscription to a new connection and replay the messages after `last_seq`.
+	pub(crate) async fn resume(
+		self: Arc<Self>,
+		id: Id<'static>,
+		last_seq: u64,
+		sink: MethodSink,
+		conn_id: ConnectionId,
+		permit: SubscriptionPermit,
+		principal: Option<&Principal>,
+	) -> MethodResponse {
+		// Respond as if the subscription didn't exist to not reveal it to other callers.
+		if self.owner.as_ref().map(Principal::name) != principal.map(Principal::name) {
+			return MethodResponse::error(id, reject_subscription_not_resumable("Unknown or expired subscription"));
+		}
+
+		let mut state = self.state.lock().await;
+
+		let first_seq = state.messages.front().map_or(state.next_seq, |(seq, _)| *seq);
+		if last_seq >= state.next_seq {
+			let reason = format!("Sequence number {last_seq} was never sent");
+			return MethodResponse::error(id, reject_subscription_not_resumable(reason));
+		}
+		if last_seq.saturating_add(1) < first_seq {
+			let reason = format!("Messages after {last_seq} are no longer buffered");
+			return MethodResponse::error(id, reject_subscription_not_resumable(reason));
+		}
+
+		let sub_id = {
+			let mut attached = self.attached.lock();
+			let mut subscribers = self.subscribers.lock();
+
+			let Some((_, unsubscribe)) = subscribers.remove(&attached.key) else {
+				return MethodResponse::error(id, reject_subscription_not_resumable("Subscription was closed"));
+			};
+
+			let key = SubscriptionKey { conn_id, sub_id: attached.key.sub_id.clone() };
+			subscribers.insert(key.clone(), (sink.clone(), unsubscribe));
+			*attached = Attached { sink: sink.clone(), key, _permit: Some(permit) };
+			attached.key.sub_id.clone()
+		};
+
+		state.generation += 1;
+		self.attach();
+		self.expire_when_detached(state.generation, sink.clone());
+
+		let response = MethodResponse::subscription_response(
+			id,
+			ResponsePayload::success_borrowed(&sub_id),
+			sink.max_response_size() as usize,
+		);
+
+		if sink.send(response.to_json()).await.is_ok() {
+			for (_, json) in state.messages.iter().filter(|(seq, _)| *seq > last_seq) {
+				if sink.send(json.clone()).await.is_err() {
+					break;
+				}
+			}
+		}
+
+		response
+	}
+
+	/// Close the subscription if it's not resumed within the grace period after the connection was closed.
+	fn expire_when_detached(self: &Arc<Self>, generation: u64, sink: MethodSink) {
+		let this = Arc::downgrade(self);
+		let grace_period = self.config.grace_period;
+
+		tokio::spawn(async move {
+			sink.closed().await;
+
+			{
+				let Some(this) = this.upgrade() else { return };
+				let state = this.state.lock().await;
+				if state.generation != generation {
+					return;
+				}
+				if !this.detach() {
+					let key = this.attached.lock().key.clone();
+					tracing::debug!(target: LOG_TARGET, "Too many detached subscriptions, closing {:?}", key);
+					this.close();
+					return;
+				}
+			}
+
+			tokio::time::sleep(grace_period).await;
+
+			let Some(this) = this.upgrade() else { return };
+			let state = this.state.lock().await;
+			if state.generation == generation {
+				let key = this.attached.lock().key.clone();
+				tracing::debug!(target: LOG_TARGET, "Resumable subscription {:?} expired", key);
+				this.close();
+			}
+		});
+	}
+
+	/// Count the subscription as detached, fails if the limit of detached subscriptions is reached.
+	fn detach(&self) -> bool {
+		let reserved = self
+			.store
+			.detached
+			.fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| (n < self.config.max_detached).then_some(n + 1))
+			.is_ok();
+		if reserved {
+			self.detached.store(true, Ordering::Release);
+		}
+		reserved
+	}
+
+	/// Stop counting the subscription as detached.
+	fn attach(&self) {
+		if self.detached.swap(false, Ordering::AcqRel) {
+			self.store.detached.fetch_sub(1, Ordering::AcqRel);
+		}
+	}
+
+	/// Close the subscription and drop the buffered messages.
+	fn close(&self) {
+		self.attach();
+		let key = self.attached.lock().key.clone();
+		self.subscribers.lock().remove(&key);
+		self.store.subscriptions.lock().remove(&key.sub_id);
+	}
+}
+
 /// Wrapper struct that maintains a subscription "mainly" for testing.
 #[derive(Debug)]
 pub struct Subscription {
@@ -508,6 +901,25 @@ pub(crate) fn sub_message_to_json(msg: SubscriptionMessage, sub_id: &Subscriptio
 	}
 }
 
+/// Serialize a message of a resumable subscription, the result is wrapped in [`Sequenced`].
+fn sequenced_message_to_json(msg: SubscriptionMessage, sub_id: &SubscriptionId, method: &str, seq: u64) -> Box<RawValue> {
+	let value = match msg.0 {
+		SubscriptionMessageInner::NeedsData(result) => result,
+		SubscriptionMessageInner::Complete(msg) => {
+			serde_json::from_str::<SubscriptionResponse<Box<RawValue>>>(msg.get())
+				.expect("Complete messages are subscription notifications; qed")
+				.params
+				.result
+		}
+	};
+
+	serde_json::value::to_raw_value(&SubscriptionResponse::new(
+		method.into(),
+		SubscriptionPayload { subscription: sub_id.clone(), result: Sequenced { seq, value } },
+	))
+	.expect("Serialize infallible; qed")
+}
+
 pub(crate) fn sub_err_to_json(error: SubscriptionError, sub_id: SubscriptionId, method: &str) -> Box<RawValue> {
 	serde_json::value::to_raw_value(&jsonrpsee_types::response::SubscriptionError::new(
 		method.into(),
@@ -0,0 +1,153 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Fan-out of subscription notifications from a single producer to many subscribers.
+
+use std::sync::Arc;
+
+use crate::server::LOG_TARGET;
+use crate::server::subscription::{PendingSubscriptionSink, SubscriptionCloseResponse, SubscriptionMessage};
+use serde::Serialize;
+use serde_json::value::RawValue;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// What to do with a subscriber which didn't keep up with the producer of a [`TopicHub`].
+///
+/// A subscriber falls behind when more than `capacity` messages were sent to the hub
+/// since the oldest message that the subscriber hasn't sent to the client yet.
+#[derive(Debug, Clone, Default)]
+pub enum LagPolicy {
+	/// Skip the oldest messages and continue with the oldest message that is still buffered.
+	#[default]
+	DropOldest,
+	/// Close the subscription without sending a close notification to the client.
+	DropSubscriber,
+	/// Close the subscription and send the given notification to the client.
+	Close(SubscriptionCloseResponse),
+}
+
+/// Topic hub which sends the messages of a single producer to all subscribers of a subscription method,
+/// see [`RpcModule::register_topic`](crate::server::RpcModule::register_topic).
+///
+/// Every message is serialized once and shared by all subscribers, which only copy it
+/// into the notification sent to their client. Clones of the hub refer to the same topic.
+///
+/// # Examples
+///
+/// ```no_run
+/// use jsonrpsee_core::server::{LagPolicy, RpcModule, TopicHub};
+///
+/// let hub = TopicHub::new(16).lag_policy(LagPolicy::DropSubscriber);
+/// let mut module = RpcModule::new(());
+/// module.register_topic("subscribe_blocks", "block", "unsubscribe_blocks", hub.clone()).unwrap();
+///
+/// std::thread::spawn(move || {
+///     for block in 0..u64::MAX {
+///         // Fails only if the item can't be serialized.
+///         let _subscribers = hub.send(&block).unwrap();
+///         std::thread::sleep(std::time::Duration::from_secs(1));
+///     }
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct TopicHub {
+	tx: broadcast::Sender<Arc<RawValue>>,
+	lag_policy: LagPolicy,
+}
+
+impl TopicHub {
+	/// Create a new hub which buffers up to `capacity` messages for the subscribers.
+	///
+	/// The buffer is shared by all subscribers and a message is removed from it once
+	/// all subscribers have sent it or when the buffer is full.
+	///
+	/// # Panics
+	///
+	/// Panics if `capacity` is zero.
+	pub fn new(capacity: usize) -> Self {
+		let (tx, _) = broadcast::channel(capacity);
+		Self { tx, lag_policy: LagPolicy::default() }
+	}
+
+	/// Configure what happens to subscribers that fall behind.
+	///
+	/// Default: [`LagPolicy::DropOldest`].
+	pub fn lag_policy(mut self, policy: LagPolicy) -> Self {
+		self.lag_policy = policy;
+		self
+	}
+
+	/// Serialize `item` and send it to all subscribers.
+	///
+	/// Returns the number of subscribers the message was sent to,
+	/// messages sent while there are no subscribers are dropped.
+	pub fn send<T: Serialize + ?Sized>(&self, item: &T) -> Result<usize, serde_json::Error> {
+		let msg = Arc::from(serde_json::value::to_raw_value(item)?);
+		Ok(self.tx.send(msg).unwrap_or(0))
+	}
+
+	/// Get the number of active subscribers.
+	pub fn subscriber_count(&self) -> usize {
+		self.tx.receiver_count()
+	}
+
+	/// Accept the subscription and send it the messages of the hub until the subscription is closed.
+	pub(crate) async fn pipe(&self, pending: PendingSubscriptionSink) -> SubscriptionCloseResponse {
+		// Subscribe before the subscription is accepted to not miss messages sent in the meantime.
+		let mut rx = self.tx.subscribe();
+
+		let Ok(sink) = pending.accept().await else {
+			return SubscriptionCloseResponse::None;
+		};
+
+		loop {
+			let msg = tokio::select! {
+				_ = sink.closed() => return SubscriptionCloseResponse::None,
+				msg = rx.recv() => msg,
+			};
+
+			match msg {
+				Ok(msg) => {
+					let msg = SubscriptionMessage::new(sink.method_name(), sink.subscription_id(), &&*msg)
+						.expect("Serialize infallible; qed");
+					if sink.send(msg).await.is_err() {
+						return SubscriptionCloseResponse::None;
+					}
+				}
+				Err(RecvError::Lagged(skipped)) => {
+					tracing::debug!(target: LOG_TARGET, "Subscription {:?} lagged behind by {skipped} messages", sink.subscription_id());
+
+					match &self.lag_policy {
+						LagPolicy::DropOldest => (),
+						LagPolicy::DropSubscriber => return SubscriptionCloseResponse::None,
+						LagPolicy::Close(close) => return close.clone(),
+					}
+				}
+				Err(RecvError::Closed) => return SubscriptionCloseResponse::None,
+			}
+		}
+	}
+}
@@ -24,13 +24,12 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-//! This example adds upstream CORS layers to the RPC service,
+//! This example configures the origin policy of the server,
 //! with access control allowing requests from all hosts.
 
 use hyper::Method;
-use jsonrpsee::server::{RpcModule, Server};
+use jsonrpsee::server::{OriginPolicy, RpcModule, Server, ServerConfig};
 use std::net::SocketAddr;
-use tower_http::cors::{Any, CorsLayer};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -69,23 +68,19 @@ async fn main() -> anyhow::Result<()> {
 }
 
 async fn run_server() -> anyhow::Result<SocketAddr> {
-	// Add a CORS middleware for handling HTTP requests.
-	// This middleware does affect the response, including appropriate
-	// headers to satisfy CORS. Because any origins are allowed, the
+	// The origin policy answers the CORS preflight requests and adds the
+	// appropriate headers to the responses. Because any origins are allowed, the
 	// "Access-Control-Allow-Origin: *" header is appended to the response.
-	let cors = CorsLayer::new()
+	//
+	// Use `OriginPolicy::allow_only` to restrict the origins, which also rejects
+	// WebSocket connections opened from other origins.
+	let policy = OriginPolicy::allow_any()
 		// Allow `POST` when accessing the resource
 		.allow_methods([Method::POST])
-		// Allow requests from any origin
-		.allow_origin(Any)
 		.allow_headers([hyper::header::CONTENT_TYPE]);
-	let middleware = tower::ServiceBuilder::new().layer(cors);
+	let config = ServerConfig::builder().set_origin_policy(policy).build();
 
-	// The RPC exposes the access control for filtering and the middleware for
-	// modifying requests / responses. These features are independent of one another
-	// and can also be used separately.
-	// In this example, we use both features.
-	let server = Server::builder().set_http_middleware(middleware).build("127.0.0.1:0".parse::<SocketAddr>()?).await?;
+	let server = Server::builder().set_config(config).build("127.0.0.1:0".parse::<SocketAddr>()?).await?;
 
 	let mut module = RpcModule::new(());
 	module.register_method("say_hello", |_, _, _| {
@@ -0,0 +1,126 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Example showing how to record metrics with the `rpc_metrics` middleware
+//! and serve them in the Prometheus text exposition format on `GET /metrics`.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::future::{Either, Ready, ready};
+use http_body_util::BodyExt;
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::core::middleware::RpcServiceBuilder;
+use jsonrpsee::core::middleware::layer::PrometheusRecorder;
+use jsonrpsee::http_client::HttpClient;
+use jsonrpsee::rpc_params;
+use jsonrpsee::server::{HttpBody, HttpRequest, HttpResponse, RpcModule, Server};
+use jsonrpsee::ws_client::WsClientBuilder;
+
+/// HTTP middleware that serves the metrics on `GET /metrics`.
+#[derive(Clone)]
+struct MetricsRoute<S> {
+	service: S,
+	recorder: Arc<PrometheusRecorder>,
+}
+
+impl<S, B> tower::Service<HttpRequest<B>> for MetricsRoute<S>
+where
+	S: tower::Service<HttpRequest<B>, Response = HttpResponse>,
+{
+	type Response = HttpResponse;
+	type Error = S::Error;
+	type Future = Either<Ready<Result<HttpResponse, S::Error>>, S::Future>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.service.poll_ready(cx)
+	}
+
+	fn call(&mut self, request: HttpRequest<B>) -> Self::Future {
+		if request.method() == hyper::Method::GET && request.uri().path() == "/metrics" {
+			let response = HttpResponse::builder()
+				.header(hyper::header::CONTENT_TYPE, PrometheusRecorder::CONTENT_TYPE)
+				.body(HttpBody::from(self.recorder.render()))
+				.expect("valid response; qed");
+			Either::Left(ready(Ok(response)))
+		} else {
+			Either::Right(self.service.call(request))
+		}
+	}
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+	tracing_subscriber::FmtSubscriber::builder()
+		.with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+		.try_init()
+		.expect("setting default subscriber failed");
+
+	let addr = run_server().await?;
+
+	let client = WsClientBuilder::default().build(format!("ws://{}", addr)).await?;
+	let _response: String = client.request("say_hello", rpc_params![]).await?;
+	let _response: Result<String, _> = client.request("unknown_method", rpc_params![]).await;
+
+	let client = HttpClient::builder().build(format!("http://{}", addr))?;
+	let _response: String = client.request("say_hello", rpc_params![]).await?;
+
+	let http_client = hyper_util::client::legacy::Client::builder(hyper_util::rt::TokioExecutor::new()).build_http();
+	let uri = format!("http://{}/metrics", addr);
+	let request = hyper::Request::get(uri).body(http_body_util::Empty::<hyper::body::Bytes>::new())?;
+	let response = http_client.request(request).await?;
+	let metrics = response.into_body().collect().await?.to_bytes();
+
+	println!("{}", String::from_utf8_lossy(&metrics));
+
+	Ok(())
+}
+
+async fn run_server() -> anyhow::Result<SocketAddr> {
+	let recorder = Arc::new(PrometheusRecorder::new());
+
+	let rpc_middleware = RpcServiceBuilder::new().rpc_metrics(recorder.clone());
+	let http_middleware =
+		tower::ServiceBuilder::new().layer_fn(move |service| MetricsRoute { service, recorder: recorder.clone() });
+
+	let server = Server::builder()
+		.set_rpc_middleware(rpc_middleware)
+		.set_http_middleware(http_middleware)
+		.build("127.0.0.1:0")
+		.await?;
+
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _, _| "lo")?;
+	let addr = server.local_addr()?;
+	let handle = server.start(module);
+
+	// In this example we don't care about doing shutdown so let's it run forever.
+	// You may use the `ServerHandle` to shut it down or manage it yourself.
+	tokio::spawn(handle.stopped());
+
+	Ok(addr)
+}
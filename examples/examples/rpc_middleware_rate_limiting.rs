@@ -30,6 +30,10 @@
 //! As demonstrated in this example any state must be
 //! stored in something to provide interior mutability
 //! such as `Arc<Mutex>`
+//!
+//! jsonrpsee also provides a token bucket rate limiter
+//! `jsonrpsee::server::middleware::rpc::RateLimitLayer` which
+//! supports limits per connection, per IP address and per method.
 
 use jsonrpsee::core::client::ClientT;
 use jsonrpsee::core::middleware::{
@@ -24,20 +24,17 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-//! Example that shows how to broadcast to all active subscriptions using `tokio::sync::broadcast`.
+//! Example that shows how to broadcast to all active subscriptions using a `TopicHub`.
 
 use std::net::SocketAddr;
 
 use futures::StreamExt;
-use futures::future::{self, Either};
-use jsonrpsee::PendingSubscriptionSink;
+use futures::future;
 use jsonrpsee::core::client::{Subscription, SubscriptionClientT};
 use jsonrpsee::core::middleware::RpcServiceBuilder;
 use jsonrpsee::rpc_params;
-use jsonrpsee::server::{RpcModule, Server, ServerConfig};
+use jsonrpsee::server::{LagPolicy, RpcModule, Server, ServerConfig, TopicHub};
 use jsonrpsee::ws_client::WsClientBuilder;
-use tokio::sync::broadcast;
-use tokio_stream::wrappers::BroadcastStream;
 
 const NUM_SUBSCRIPTION_RESPONSES: usize = 5;
 
@@ -74,20 +71,16 @@ async fn run_server() -> anyhow::Result<SocketAddr> {
 		.set_rpc_middleware(RpcServiceBuilder::new().rpc_logger(1024))
 		.build("127.0.0.1:0")
 		.await?;
-	let (tx, _rx) = broadcast::channel::<usize>(16);
 
-	let mut module = RpcModule::new(tx.clone());
+	// The hub buffers up to 16 items for the subscribers, subscribers which fall further
+	// behind skip the oldest items.
+	let hub = TopicHub::new(16).lag_policy(LagPolicy::DropOldest);
 
-	std::thread::spawn(move || produce_items(tx));
+	let mut module = RpcModule::new(());
+	module.register_topic("subscribe_hello", "s_hello", "unsubscribe_hello", hub.clone())?;
+
+	std::thread::spawn(move || produce_items(hub));
 
-	module
-		.register_subscription("subscribe_hello", "s_hello", "unsubscribe_hello", |_, pending, tx, _| async move {
-			let rx = tx.subscribe();
-			let stream = BroadcastStream::new(rx);
-			pipe_from_stream_with_bounded_buffer(pending, stream).await?;
-			Ok(())
-		})
-		.unwrap();
 	let addr = server.local_addr()?;
 	let handle = server.start(module);
 
@@ -98,52 +91,14 @@ async fn run_server() -> anyhow::Result<SocketAddr> {
 	Ok(addr)
 }
 
-async fn pipe_from_stream_with_bounded_buffer(
-	pending: PendingSubscriptionSink,
-	stream: BroadcastStream<usize>,
-) -> Result<(), anyhow::Error> {
-	let sink = pending.accept().await?;
-	let closed = sink.closed();
-
-	futures::pin_mut!(closed, stream);
-
-	loop {
-		match future::select(closed, stream.next()).await {
-			// subscription closed.
-			Either::Left((_, _)) => break Ok(()),
-
-			// received new item from the stream.
-			Either::Right((Some(Ok(item)), c)) => {
-				let msg = serde_json::value::to_raw_value(&item)?;
-
-				// NOTE: this will block until there a spot in the queue
-				// and you might want to do something smarter if it's
-				// critical that "the most recent item" must be sent when it is produced.
-				if sink.send(msg).await.is_err() {
-					break Ok(());
-				}
-
-				closed = c;
-			}
-
-			// Send back back the error.
-			Either::Right((Some(Err(e)), _)) => break Err(e.into()),
-
-			// Stream is closed.
-			Either::Right((None, _)) => break Ok(()),
-		}
-	}
-}
-
 // Naive example that broadcasts the produced values to all active subscribers.
-fn produce_items(tx: broadcast::Sender<usize>) {
+fn produce_items(hub: TopicHub) {
 	for c in 1..=100 {
 		std::thread::sleep(std::time::Duration::from_millis(1));
 
-		// This might fail if no receivers are alive, could occur if no subscriptions are active...
-		// Also be aware that this will succeed when at least one receiver is alive
-		// Thus, clients connecting at different point in time will not receive
+		// The item is serialized once and sent to the subscribers that are active right now,
+		// thus clients connecting at different point in time will not receive
 		// the items sent before the subscription got established.
-		let _ = tx.send(c);
+		hub.send(&c).expect("usize is serializable; qed");
 	}
 }
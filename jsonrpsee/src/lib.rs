@@ -48,6 +48,11 @@
 //! - **`client-ws-transport`** - Enables `ws` transport with TLS.
 //! - **`client-ws-transport-no-tls`** - Enables `ws` transport without TLS.
 //! - **`client-web-transport`** - Enables `websys` transport.
+//! - **`client-ipc-transport`** - Enables `ipc` transport over Unix domain sockets.
+//! - **`ws-deflate`** - Enables WebSocket `permessage-deflate` compression for the server and `ws-client`.
+//! - **`ws-bidirectional`** - Enables the `ws-client` to answer requests of the server with local methods.
+//! - **`server-tls`** - Enables TLS termination in the server.
+//! - **`server-jwt`** - Enables the JWT authentication middleware in the server.
 
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 #![cfg_attr(docsrs, feature(doc_cfg))]
@@ -58,6 +58,9 @@ pub struct Bracketed<T> {
 
 pub type Aliases = Bracketed<LitStr>;
 
+/// Either a single string or a list of strings.
+pub struct OneOrMany(pub Vec<LitStr>);
+
 impl Parse for Argument {
 	fn parse(input: ParseStream) -> syn::Result<Self> {
 		let label = input.parse()?;
@@ -102,6 +105,16 @@ impl Parse for NameMapping {
 	}
 }
 
+impl Parse for OneOrMany {
+	fn parse(input: ParseStream) -> syn::Result<Self> {
+		if input.peek(syn::token::Bracket) {
+			Ok(OneOrMany(input.parse::<Bracketed<LitStr>>()?.list.into_iter().collect()))
+		} else {
+			Ok(OneOrMany(vec![input.parse()?]))
+		}
+	}
+}
+
 impl<T: Parse> Parse for Bracketed<T> {
 	fn parse(input: ParseStream) -> syn::Result<Self> {
 		let content;
@@ -230,3 +243,44 @@ pub(crate) fn parse_param_kind(arg: Result<Argument, MissingArgument>) -> syn::R
 		ident => Err(Error::new(ident.span(), "param_kind must be either `map` or `array`")),
 	}
 }
+
+/// Parses a timeout such as `timeout = "5s"` into milliseconds.
+///
+/// The supported units are `ms`, `s`, `m` and `h`.
+pub(crate) fn parse_timeout(arg: Result<Argument, MissingArgument>) -> syn::Result<Option<u64>> {
+	let Some(lit) = optional(arg, Argument::value::<LitStr>)? else {
+		return Ok(None);
+	};
+
+	let value = lit.value();
+	let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+	let (amount, unit) = value.split_at(split);
+
+	let multiplier = match unit.trim() {
+		"ms" => 1,
+		"s" => 1_000,
+		"m" => 60 * 1_000,
+		"h" => 60 * 60 * 1_000,
+		_ => return Err(Error::new(lit.span(), "timeout must be an integer followed by `ms`, `s`, `m` or `h`")),
+	};
+
+	match amount.parse::<u64>().ok().and_then(|amount| amount.checked_mul(multiplier)) {
+		Some(millis) if millis > 0 => Ok(Some(millis)),
+		_ => Err(Error::new(lit.span(), "timeout must be a positive integer followed by `ms`, `s`, `m` or `h`")),
+	}
+}
+
+/// Parses the roles required to call a method such as `requires = "admin"` or `requires = ["admin", "ops"]`.
+pub(crate) fn parse_requires(arg: Result<Argument, MissingArgument>) -> syn::Result<Vec<String>> {
+	let Some(OneOrMany(roles)) = optional(arg, Argument::value::<OneOrMany>)? else {
+		return Ok(Vec::new());
+	};
+
+	roles
+		.into_iter()
+		.map(|lit| match lit.value() {
+			role if role.is_empty() => Err(Error::new(lit.span(), "required roles must not be empty")),
+			role => Ok(role),
+		})
+		.collect()
+}
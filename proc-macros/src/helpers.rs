@@ -208,9 +208,31 @@ pub(crate) fn extract_doc_comments(attrs: &[syn::Attribute]) -> TokenStream2 {
 	quote! ( #(#docs)* )
 }
 
+/// Extracts the text of the doc comments with the leading space of each line removed.
+///
+/// Returns `None` if there are no doc comments.
+pub(crate) fn doc_comment_text(attrs: &[syn::Attribute]) -> Option<String> {
+	let lines: Vec<String> = attrs
+		.iter()
+		.filter_map(|attr| match &attr.meta {
+			syn::Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+				syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) => {
+					let line = s.value();
+					Some(line.strip_prefix(' ').map(ToOwned::to_owned).unwrap_or(line))
+				}
+				_ => None,
+			},
+			_ => None,
+		})
+		.collect();
+
+	let text = lines.join("\n").trim().to_owned();
+	if text.is_empty() { None } else { Some(text) }
+}
+
 #[cfg(test)]
 mod tests {
-	use super::is_option;
+	use super::{doc_comment_text, is_option};
 	use syn::parse_quote;
 
 	#[test]
@@ -221,4 +243,21 @@ mod tests {
 		assert!(is_option(&parse_quote!(std::option::Option<R>)));
 		assert!(!is_option(&parse_quote!(foo::bar::Option::Booyah)));
 	}
+
+	#[test]
+	fn doc_comment_text_works() {
+		let item: syn::ItemFn = parse_quote! {
+			/// Foo
+			///
+			///   bar
+			#[inline]
+			fn foo() {}
+		};
+		assert_eq!(doc_comment_text(&item.attrs).as_deref(), Some("Foo\n\n  bar"));
+
+		let item: syn::ItemFn = parse_quote!(
+			fn foo() {}
+		);
+		assert_eq!(doc_comment_text(&item.attrs), None);
+	}
 }
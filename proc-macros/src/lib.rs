@@ -132,6 +132,24 @@ pub(crate) mod visitor;
 /// }
 ///
 /// impl<T> RpcClient for T where T: SubscriptionClient {}
+///
+/// // Typed batch request builder, only generated with the `batch` argument. Every method
+/// // inserts a call and returns a handle to its response.
+/// pub struct RpcBatch { /* ... */ }
+///
+/// impl RpcBatch {
+///     pub fn async_method(&mut self, param_a: u8, param_b: String) -> BatchCall<u16> {
+///         // ...
+///     }
+///     pub fn sync_method(&mut self) -> BatchCall<String> {
+///         // ...
+///     }
+///
+///     // Sends the batch, the responses are read with `BatchCall::response`.
+///     pub async fn execute<C: ClientT>(self, client: &C) -> Result<TypedBatchResponse, Error> {
+///         // ...
+///     }
+/// }
 /// ```
 ///
 /// ## Attributes
@@ -145,6 +163,8 @@ pub(crate) mod visitor;
 /// - `server`: generate `<Trait>Server` trait for the server implementation.
 /// - `client`: generate `<Trait>Client` extension trait that builds RPC clients to invoke a concrete RPC
 ///   implementation's methods conveniently.
+/// - `batch`: generate the `<Trait>Batch` builder for typed batch requests, requires `client`. The handles
+///   returned by the builder only read the responses of the batch request they were inserted into.
 /// - `namespace`: add a prefix to all the methods and subscriptions in this RPC. For example, with namespace `foo` and
 ///   method `spam`, the resulting method name will be `foo_spam`.
 /// - `namespace_separator`: customize the separator used between namespace and method name. Defaults to `_`.
@@ -153,6 +173,11 @@ pub(crate) mod visitor;
 ///   implementation.
 /// - `client_bounds`: replace *all* auto-generated trait bounds with the user-defined ones for the client
 ///   implementation.
+/// - `openrpc`: generate an [OpenRPC](https://spec.open-rpc.org) document from the trait definition and its doc comments.
+///   The document is returned by the generated `<Trait>Server::openrpc` method and served by `rpc.discover`.
+///   All parameter, return and subscription item types must implement `jsonrpsee::core::openrpc::JsonSchema`.
+///   Requires `server`. When merging several such modules, remove the duplicate `rpc.discover` method with
+///   `RpcModule::remove_method` and combine the documents with `OpenRpc::merge`.
 ///
 /// **Trait requirements:**
 ///
@@ -174,7 +199,13 @@ pub(crate) mod visitor;
 /// - `aliases`: list of name aliases for the RPC method as a comma separated string.
 ///   Aliases are processed ignoring the namespace, so add the complete name, including the namespace.
 /// - `blocking`: when set method execution will always spawn on a dedicated thread. Only usable with non-`async` methods.
+/// - `idempotent`: marks the method as safe to call more than once. The names of these methods are provided
+///   by the `IDEMPOTENT_METHODS` constant of the client trait, for use with `RetryPolicy::idempotent_methods`.
 /// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to "array".
+/// - `requires`: role or list of roles such as "admin" or ["admin", "ops"] that the caller must all have,
+///   see `RpcModule::set_required_roles`.
+/// - `timeout`: maximum execution time of the method on the server such as "500ms", "5s", "1m" or "1h".
+///   Only usable with `async` or `blocking` methods and overrides the default timeout of the server.
 ///
 /// **Method requirements:**
 ///
@@ -198,6 +229,7 @@ pub(crate) mod visitor;
 /// - `unsubscribe_aliases` (optional): Similar to `aliases` but for `unsubscribe`.
 /// - `item` (mandatory): type of items yielded by the subscription. Note that it must be the type, not string.
 /// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to "array".
+/// - `requires`: role or list of roles that the caller must all have to subscribe, see the `method` attribute.
 ///
 /// **Method requirements:**
 ///
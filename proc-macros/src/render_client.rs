@@ -51,11 +51,20 @@ impl RpcDescription {
 			self.methods.iter().map(|method| self.render_method(method)).collect::<Result<Vec<_>, _>>()?;
 		let sub_impls = self.subscriptions.iter().map(|sub| self.render_sub(sub)).collect::<Result<Vec<_>, _>>()?;
 
+		let idempotent_methods = self
+			.methods
+			.iter()
+			.filter(|method| method.idempotent)
+			.map(|method| self.rpc_identifier(&method.name).into_owned());
+
 		// Doc-comment to be associated with the client.
 		let doc_comment = format!("Client implementation for the `{}` RPC API.", &self.trait_def.ident);
 		let trait_impl = quote! {
 			#[doc = #doc_comment]
 			pub trait #trait_name #impl_generics: #super_trait where #(#where_clause,)* {
+				/// Names of the methods marked as idempotent, which are safe to retry.
+				const IDEMPOTENT_METHODS: &'static [&'static str] = &[#(#idempotent_methods),*];
+
 				#(#method_impls)*
 				#(#sub_impls)*
 			}
@@ -63,7 +72,108 @@ impl RpcDescription {
 			impl<TypeJsonRpseeInternal #(,#type_idents)*> #trait_name #type_generics for TypeJsonRpseeInternal where TypeJsonRpseeInternal: #super_trait #(,#where_clause)* {}
 		};
 
-		Ok(trait_impl)
+		let batch_impl = if self.needs_batch { self.render_batch(&where_clause)? } else { TokenStream2::new() };
+
+		Ok(quote! {
+			#trait_impl
+			#batch_impl
+		})
+	}
+
+	/// Render the typed batch request builder, which has a method for every RPC method that returns a value.
+	fn render_batch(&self, where_clause: &[syn::WherePredicate]) -> Result<TokenStream2, syn::Error> {
+		let jrps_error = self.jrps_client_item(quote! { core::client::Error });
+		let batch_builder = self.jrps_client_item(quote! { core::params::BatchRequestBuilder });
+		let batch_id = self.jrps_client_item(quote! { core::client::BatchId });
+		let batch_response = self.jrps_client_item(quote! { core::client::TypedBatchResponse });
+		let client_t = self.jrps_client_item(quote! { core::client::ClientT });
+
+		let batch_name = quote::format_ident!("{}Batch", &self.trait_def.ident);
+		let type_params = self.trait_def.generics.type_params().map(|ty| &ty.ident);
+		let (impl_generics, type_generics, _) = self.trait_def.generics.split_for_impl();
+
+		let method_impls =
+			self.methods.iter().map(|method| self.render_batch_method(method)).collect::<Result<Vec<_>, _>>()?;
+
+		// Doc-comment to be associated with the batch builder.
+		let doc_comment = format!(
+			"Typed batch request builder for the `{}` RPC API.\n\nEvery call inserted into the batch returns a handle to deserialize its response once the batch has been executed.",
+			&self.trait_def.ident
+		);
+
+		Ok(quote! {
+			#[doc = #doc_comment]
+			#[derive(Debug)]
+			pub struct #batch_name #impl_generics {
+				id: #batch_id,
+				batch: #batch_builder<'static>,
+				_marker: core::marker::PhantomData<fn() -> (#(#type_params,)*)>,
+			}
+
+			impl #impl_generics Default for #batch_name #type_generics {
+				fn default() -> Self {
+					Self { id: #batch_id::unique(), batch: #batch_builder::new(), _marker: core::marker::PhantomData }
+				}
+			}
+
+			impl #impl_generics #batch_name #type_generics where #(#where_clause,)* {
+				/// Create a new empty batch.
+				pub fn new() -> Self {
+					Self::default()
+				}
+
+				/// Get the number of calls in the batch.
+				pub fn len(&self) -> usize {
+					self.batch.len()
+				}
+
+				/// Is empty.
+				pub fn is_empty(&self) -> bool {
+					self.batch.is_empty()
+				}
+
+				/// Send the batch request with `client`.
+				///
+				/// The responses of the calls are read with the handles returned when they were inserted.
+				pub async fn execute<TypeJsonRpseeClient: #client_t>(self, client: &TypeJsonRpseeClient) -> Result<#batch_response, #jrps_error> {
+					let response = client.batch_request(self.batch).await?;
+					Ok(#batch_response::new(self.id, response))
+				}
+
+				#(#method_impls)*
+			}
+		})
+	}
+
+	/// Render the method of the batch builder which inserts a call to `method`.
+	fn render_batch_method(&self, method: &RpcMethod) -> Result<TokenStream2, syn::Error> {
+		// Notifications don't get a response and can't be part of the batch.
+		let Some(returns) = method.returns.as_ref().and_then(batch_result_type) else {
+			return Ok(TokenStream2::new());
+		};
+
+		let batch_call = self.jrps_client_item(quote! { core::client::BatchCall });
+		let rust_method_name = &method.signature.sig.ident;
+		// The inputs without the `&self` receiver.
+		let rust_method_params = method.signature.sig.inputs.iter().filter(|arg| matches!(arg, FnArg::Typed(_)));
+		let rpc_method_name = self.rpc_identifier(&method.name);
+
+		let parameter_builder = self.encode_params(&method.params, &method.param_kind, &method.signature);
+		let docs = &method.docs;
+		let deprecated = &method.deprecated;
+
+		Ok(quote! {
+			#docs
+			#deprecated
+			#[allow(non_snake_case)]
+			#[allow(clippy::used_underscore_binding)]
+			pub fn #rust_method_name(&mut self, #(#rust_method_params),*) -> #batch_call<#returns> {
+				let params = { #parameter_builder };
+				let index = self.batch.len();
+				self.batch.insert(#rpc_method_name, params).expect("Parameters are serialized above; qed");
+				#batch_call::new(self.id, index)
+			}
+		})
 	}
 
 	/// Verify and rewrite the return type (for methods).
@@ -274,6 +384,31 @@ impl RpcDescription {
 	}
 }
 
+/// Extract `T` from the return type `Result<T, E>`, `RpcResult<T>` or `ResponsePayload<'a, T>` of a method.
+///
+/// Other return types are rejected when rendering the client method.
+fn batch_result_type(ty: &syn::Type) -> Option<syn::Type> {
+	let syn::Type::Path(type_path) = ty else {
+		return None;
+	};
+	let type_name = type_path.path.segments.last()?;
+	let PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. }) = &type_name.arguments else {
+		return None;
+	};
+	let mut types = args.iter().filter_map(|arg| match arg {
+		syn::GenericArgument::Type(ty) => Some(ty.clone()),
+		_ => None,
+	});
+
+	if type_name.ident == "Result" || type_name.ident == "RpcResult" {
+		types.next()
+	} else if type_name.ident == "ResponsePayload" {
+		types.last()
+	} else {
+		None
+	}
+}
+
 fn extract_param_names(sig: &syn::Signature) -> Vec<String> {
 	sig.inputs
 		.iter()
@@ -43,6 +43,7 @@ impl RpcDescription {
 		let (impl_generics, _, where_clause) = generics.split_for_impl();
 
 		let method_impls = self.render_methods()?;
+		let openrpc_impl = if self.openrpc { self.render_openrpc() } else { TokenStream2::new() };
 		let into_rpc_impl = self.render_into_rpc()?;
 		let async_trait = self.jrps_server_item(quote! { core::__reexports::async_trait });
 
@@ -54,6 +55,7 @@ impl RpcDescription {
 			#[doc = #doc_comment]
 			pub trait #trait_name #impl_generics: Sized + Send + Sync + 'static #where_clause {
 				#method_impls
+				#openrpc_impl
 				#into_rpc_impl
 			}
 		};
@@ -137,6 +139,17 @@ impl RpcDescription {
 			}
 		};
 
+		let discover = if self.openrpc {
+			let openrpc = self.jrps_server_item(quote! { core::openrpc });
+			check_name("rpc.discover", self.trait_def.ident.span());
+			self.handle_register_result(quote! {{
+				let openrpc = Self::openrpc();
+				rpc.register_method(#openrpc::DISCOVER_METHOD, move |_, _, _| openrpc.clone())
+			}})
+		} else {
+			TokenStream2::new()
+		};
+
 		let methods = self
 			.methods
 			.iter()
@@ -155,7 +168,7 @@ impl RpcDescription {
 
 				check_name(&rpc_method_name, rust_method_name.span());
 
-				if method.signature.sig.asyncness.is_some() {
+				let register = if method.signature.sig.asyncness.is_some() {
 					if method.with_extensions {
 						self.handle_register_result(quote! {
 							rpc.register_async_method(#rpc_method_name, |params, context, ext| async move {
@@ -190,6 +203,25 @@ impl RpcDescription {
 							})
 						})
 					}
+				};
+
+				let timeout = method.timeout.map(|millis| {
+					self.handle_register_result(quote! {
+						rpc.set_method_timeout(#rpc_method_name, ::std::time::Duration::from_millis(#millis))
+					})
+				});
+
+				let requires = (!method.requires.is_empty()).then(|| {
+					let roles = &method.requires;
+					self.handle_register_result(quote! {
+						rpc.set_required_roles(#rpc_method_name, [#(#roles),*])
+					})
+				});
+
+				quote! {
+					#register
+					#timeout
+					#requires
 				}
 			})
 			.collect::<Vec<_>>();
@@ -225,7 +257,7 @@ impl RpcDescription {
 					None => rpc_sub_name.clone(),
 				};
 
-				if sub.signature.sig.asyncness.is_some() {
+				let register = if sub.signature.sig.asyncness.is_some() {
 					if sub.with_extensions {
 						self.handle_register_result(quote! {
 							rpc.register_subscription(#rpc_sub_name, #rpc_notif_name, #rpc_unsub_name, |params, mut pending, context, ext| async move {
@@ -257,6 +289,18 @@ impl RpcDescription {
 							#sub_err::None
 						})
 					})
+				};
+
+				let requires = (!sub.requires.is_empty()).then(|| {
+					let roles = &sub.requires;
+					self.handle_register_result(quote! {
+						rpc.set_required_roles(#rpc_sub_name, [#(#roles),*])
+					})
+				});
+
+				quote! {
+					#register
+					#requires
 				}
 			})
 			.collect::<Vec<_>>();
@@ -323,8 +367,7 @@ impl RpcDescription {
 		let doc_comment = "Collects all the methods and subscriptions defined in the trait \
 								and adds them into a single `RpcModule`.";
 
-		let sub_tys: Vec<syn::Type> = self.subscriptions.clone().into_iter().map(|s| s.item).collect();
-		let where_clause = generate_where_clause(&self.trait_def, &sub_tys, false, self.server_bounds.as_ref());
+		let where_clause = self.server_where_clause();
 
 		// NOTE(niklasad1): empty where clause is valid rust syntax.
 		Ok(quote! {
@@ -333,6 +376,7 @@ impl RpcDescription {
 				let mut rpc = #rpc_module::new(self);
 
 				#(#errors)*
+				#discover
 				#(#methods)*
 				#(#subscriptions)*
 				#(#method_aliases)*
@@ -343,6 +387,122 @@ impl RpcDescription {
 		})
 	}
 
+	/// Trait bounds of the generated `into_rpc` method.
+	///
+	/// If an OpenRPC document is generated, all type params must implement `JsonSchema` as well.
+	fn server_where_clause(&self) -> Vec<syn::WherePredicate> {
+		let sub_tys: Vec<syn::Type> = self.subscriptions.clone().into_iter().map(|s| s.item).collect();
+		let mut where_clause = generate_where_clause(&self.trait_def, &sub_tys, false, self.server_bounds.as_ref());
+
+		if self.openrpc {
+			let json_schema = self.jrps_server_item(quote! { core::openrpc::JsonSchema });
+			where_clause.extend(self.trait_def.generics.type_params().map(|ty| {
+				let ident = &ty.ident;
+				syn::parse_quote!(#ident: #json_schema)
+			}));
+		}
+
+		where_clause
+	}
+
+	fn render_openrpc(&self) -> TokenStream2 {
+		let openrpc = self.jrps_server_item(quote! { core::openrpc });
+		let into_response = self.jrps_server_item(quote! { IntoResponse });
+		let title = self.trait_def.ident.to_string();
+		let description = render_option_string(&self.description);
+
+		let methods = self.methods.iter().map(|method| {
+			let name = self.rpc_identifier(&method.name);
+			let description = render_option_string(&method.description);
+			let params = self.render_openrpc_params(&method.params);
+			let result = match &method.returns {
+				Some(ty) => quote! {
+					Some(#openrpc::ContentDescriptor::new::<<#ty as #into_response>::Output>("result", true))
+				},
+				None => quote!(None),
+			};
+			let deprecated = !method.deprecated.is_empty();
+			let aliases = &method.aliases;
+
+			quote! {
+				#openrpc::Method {
+					name: #name.into(),
+					summary: None,
+					description: #description,
+					params: vec![#(#params),*],
+					result: #result,
+					// `param_kind` only affects the client, the server decodes both objects and arrays.
+					param_structure: #openrpc::ParamStructure::Either,
+					deprecated: #deprecated,
+					aliases: vec![#(#aliases.into()),*],
+					subscription: None,
+				}
+			}
+		});
+
+		let subscriptions = self.subscriptions.iter().map(|sub| {
+			let name = self.rpc_identifier(&sub.name);
+			let notif_name = sub.notif_name_override.as_ref().map_or_else(|| name.clone(), |m| self.rpc_identifier(m));
+			let unsubscribe = self.rpc_identifier(&sub.unsubscribe);
+			let description = render_option_string(&sub.description);
+			let params = self.render_openrpc_params(&sub.params);
+			let item = &sub.item;
+			let aliases = &sub.aliases;
+			let unsubscribe_aliases = &sub.unsubscribe_aliases;
+
+			quote! {
+				#openrpc::Method {
+					name: #name.into(),
+					summary: None,
+					description: #description,
+					params: vec![#(#params),*],
+					result: Some(#openrpc::ContentDescriptor::new::<#item>("notification", true)),
+					param_structure: #openrpc::ParamStructure::Either,
+					deprecated: false,
+					aliases: vec![#(#aliases.into()),*],
+					subscription: Some(#openrpc::SubscriptionInfo {
+						notification: #notif_name.into(),
+						unsubscribe: #unsubscribe.into(),
+						unsubscribe_aliases: vec![#(#unsubscribe_aliases.into()),*],
+					}),
+				}
+			}
+		});
+
+		let where_clause = self.server_where_clause();
+
+		quote! {
+			/// Returns the OpenRPC document which describes the methods and subscriptions of this API.
+			fn openrpc() -> #openrpc::OpenRpc where #(#where_clause,)* {
+				let info = #openrpc::Info {
+					title: #title.into(),
+					version: env!("CARGO_PKG_VERSION").into(),
+					description: #description,
+				};
+
+				#openrpc::OpenRpc {
+					openrpc: #openrpc::OPENRPC_VERSION.into(),
+					info,
+					methods: vec![#(#methods,)* #(#subscriptions,)*],
+				}
+			}
+		}
+	}
+
+	fn render_openrpc_params(&self, params: &[RpcFnArg]) -> Vec<TokenStream2> {
+		let openrpc = self.jrps_server_item(quote! { core::openrpc });
+
+		params
+			.iter()
+			.map(|param| {
+				let name = param.name();
+				let ty = param.ty();
+				let required = !is_option(ty);
+				quote! { #openrpc::ContentDescriptor::new::<#ty>(#name, #required) }
+			})
+			.collect()
+	}
+
 	fn render_params_decoding(
 		&self,
 		params: &[RpcFnArg],
@@ -458,3 +618,10 @@ impl RpcDescription {
 		(parsing, params_fields)
 	}
 }
+
+fn render_option_string(s: &Option<String>) -> TokenStream2 {
+	match s {
+		Some(s) => quote!(Some(#s.into())),
+		None => quote!(None),
+	}
+}
@@ -30,8 +30,9 @@ use std::borrow::Cow;
 
 use crate::attributes::{
 	Aliases, Argument, AttributeMeta, MissingArgument, NameMapping, ParamKind, optional, parse_param_kind,
+	parse_requires, parse_timeout,
 };
-use crate::helpers::extract_doc_comments;
+use crate::helpers::{doc_comment_text, extract_doc_comments};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::spanned::Spanned;
@@ -86,6 +87,8 @@ pub struct RpcMethod {
 	pub name: String,
 	pub blocking: bool,
 	pub docs: TokenStream2,
+	/// Text of the doc comments, used in the OpenRPC document.
+	pub description: Option<String>,
 	pub deprecated: TokenStream2,
 	pub params: Vec<RpcFnArg>,
 	pub param_kind: ParamKind,
@@ -93,20 +96,39 @@ pub struct RpcMethod {
 	pub signature: syn::TraitItemFn,
 	pub aliases: Vec<String>,
 	pub with_extensions: bool,
+	/// Execution timeout of the method in milliseconds.
+	pub timeout: Option<u64>,
+	/// Roles required to call the method.
+	pub requires: Vec<String>,
+	/// Whether the method is safe to call more than once, such that the client may retry failed calls.
+	pub idempotent: bool,
 }
 
 impl RpcMethod {
 	pub fn from_item(attr: Attribute, mut method: syn::TraitItemFn) -> syn::Result<Self> {
-		let [aliases, blocking, name, param_kind, with_extensions] =
-			AttributeMeta::parse(attr)?.retain(["aliases", "blocking", "name", "param_kind", "with_extensions"])?;
+		let [aliases, blocking, idempotent, name, param_kind, requires, timeout, with_extensions] =
+			AttributeMeta::parse(attr)?.retain([
+				"aliases",
+				"blocking",
+				"idempotent",
+				"name",
+				"param_kind",
+				"requires",
+				"timeout",
+				"with_extensions",
+			])?;
 
 		let aliases = parse_aliases(aliases)?;
 		let blocking = optional(blocking, Argument::flag)?.is_some();
+		let idempotent = optional(idempotent, Argument::flag)?.is_some();
 		let name = name?.string()?;
 		let param_kind = parse_param_kind(param_kind)?;
+		let requires = parse_requires(requires)?;
+		let timeout = parse_timeout(timeout)?;
 		let with_extensions = optional(with_extensions, Argument::flag)?.is_some();
 
 		let docs = extract_doc_comments(&method.attrs);
+		let description = doc_comment_text(&method.attrs);
 		let deprecated = match find_attr(&method.attrs, "deprecated") {
 			Some(attr) => quote!(#attr),
 			None => quote!(),
@@ -116,6 +138,10 @@ impl RpcMethod {
 			return Err(syn::Error::new(method.sig.span(), "Blocking method must be synchronous"));
 		}
 
+		if timeout.is_some() && !blocking && method.sig.asyncness.is_none() {
+			return Err(syn::Error::new(method.sig.span(), "Timeout can only be set on async or blocking methods"));
+		}
+
 		let params: Vec<_> = method
 			.sig
 			.inputs
@@ -155,8 +181,12 @@ impl RpcMethod {
 			returns,
 			signature: method,
 			docs,
+			description,
 			deprecated,
 			with_extensions,
+			timeout,
+			requires,
+			idempotent,
 		})
 	}
 }
@@ -172,6 +202,8 @@ pub struct RpcSubscription {
 	/// If no override is provided, the subscription method name is used.
 	pub notif_name_override: Option<String>,
 	pub docs: TokenStream2,
+	/// Text of the doc comments, used in the OpenRPC document.
+	pub description: Option<String>,
 	pub unsubscribe: String,
 	pub params: Vec<RpcFnArg>,
 	pub param_kind: ParamKind,
@@ -179,17 +211,20 @@ pub struct RpcSubscription {
 	pub signature: syn::TraitItemFn,
 	pub aliases: Vec<String>,
 	pub unsubscribe_aliases: Vec<String>,
+	/// Roles required to subscribe.
+	pub requires: Vec<String>,
 	pub with_extensions: bool,
 }
 
 impl RpcSubscription {
 	pub fn from_item(attr: syn::Attribute, mut sub: syn::TraitItemFn) -> syn::Result<Self> {
-		let [aliases, item, name, param_kind, unsubscribe, unsubscribe_aliases, with_extensions] =
+		let [aliases, item, name, param_kind, requires, unsubscribe, unsubscribe_aliases, with_extensions] =
 			AttributeMeta::parse(attr)?.retain([
 				"aliases",
 				"item",
 				"name",
 				"param_kind",
+				"requires",
 				"unsubscribe",
 				"unsubscribe_aliases",
 				"with_extensions",
@@ -201,10 +236,12 @@ impl RpcSubscription {
 		let notif_name_override = map.mapped;
 		let item = item?.value()?;
 		let param_kind = parse_param_kind(param_kind)?;
+		let requires = parse_requires(requires)?;
 		let unsubscribe_aliases = parse_aliases(unsubscribe_aliases)?;
 		let with_extensions = optional(with_extensions, Argument::flag)?.is_some();
 
 		let docs = extract_doc_comments(&sub.attrs);
+		let description = doc_comment_text(&sub.attrs);
 		let unsubscribe = match parse_subscribe(unsubscribe)? {
 			Some(unsub) => unsub,
 			None => build_unsubscribe_method(&name).unwrap_or_else(||
@@ -240,7 +277,9 @@ impl RpcSubscription {
 			item,
 			signature: sub,
 			aliases,
+			requires,
 			docs,
+			description,
 			with_extensions,
 		})
 	}
@@ -260,10 +299,18 @@ pub struct RpcDescription {
 	/// Assuming that trait to which attribute is applied is named `Foo`, the generated
 	/// client trait will have `FooClient` name.
 	pub(crate) needs_client: bool,
+	/// Switch denoting that the typed batch request builder must be generated.
+	/// Assuming that trait to which attribute is applied is named `Foo`, the generated
+	/// builder will have `FooBatch` name.
+	pub(crate) needs_batch: bool,
 	/// Optional prefix for RPC namespace.
 	pub(crate) namespace: Option<String>,
 	/// Optional separator between namespace and method name. Defaults to `_`.
 	pub(crate) namespace_separator: Option<String>,
+	/// Switch denoting that an OpenRPC document must be generated and served by `rpc.discover`.
+	pub(crate) openrpc: bool,
+	/// Text of the doc comments of the trait, used in the OpenRPC document.
+	pub(crate) description: Option<String>,
 	/// Trait definition in which all the attributes were stripped.
 	pub(crate) trait_def: syn::ItemTrait,
 	/// List of RPC methods defined in the trait.
@@ -278,22 +325,26 @@ pub struct RpcDescription {
 
 impl RpcDescription {
 	pub fn from_item(attr: Attribute, mut item: syn::ItemTrait) -> syn::Result<Self> {
-		let [client, server, namespace, namespace_separator, client_bounds, server_bounds] =
+		let [client, server, batch, namespace, namespace_separator, client_bounds, server_bounds, openrpc] =
 			AttributeMeta::parse(attr)?.retain([
 				"client",
 				"server",
+				"batch",
 				"namespace",
 				"namespace_separator",
 				"client_bounds",
 				"server_bounds",
+				"openrpc",
 			])?;
 
 		let needs_server = optional(server, Argument::flag)?.is_some();
 		let needs_client = optional(client, Argument::flag)?.is_some();
+		let needs_batch = optional(batch, Argument::flag)?.is_some();
 		let namespace = optional(namespace, Argument::string)?;
 		let namespace_separator = optional(namespace_separator, Argument::string)?;
 		let client_bounds = optional(client_bounds, Argument::group)?;
 		let server_bounds = optional(server_bounds, Argument::group)?;
+		let openrpc = optional(openrpc, Argument::flag)?.is_some();
 		if !needs_server && !needs_client {
 			return Err(syn::Error::new_spanned(&item.ident, "Either 'server' or 'client' attribute must be applied"));
 		}
@@ -305,6 +356,10 @@ impl RpcDescription {
 			));
 		}
 
+		if needs_batch && !needs_client {
+			return Err(syn::Error::new_spanned(&item.ident, "Attribute 'client' must be specified with 'batch'"));
+		}
+
 		if server_bounds.is_some() && !needs_server {
 			return Err(syn::Error::new_spanned(
 				&item.ident,
@@ -312,6 +367,10 @@ impl RpcDescription {
 			));
 		}
 
+		if openrpc && !needs_server {
+			return Err(syn::Error::new_spanned(&item.ident, "Attribute 'server' must be specified with 'openrpc'"));
+		}
+
 		let jsonrpsee_client_path = crate::helpers::find_jsonrpsee_client_crate().ok();
 		let jsonrpsee_server_path = crate::helpers::find_jsonrpsee_server_crate().ok();
 
@@ -322,6 +381,7 @@ impl RpcDescription {
 			return Err(syn::Error::new_spanned(&item.ident, "Unable to locate 'jsonrpsee' server dependency"));
 		}
 
+		let description = doc_comment_text(&item.attrs);
 		item.attrs.clear(); // Remove RPC attributes.
 
 		let mut methods = Vec::new();
@@ -377,8 +437,11 @@ impl RpcDescription {
 			jsonrpsee_server_path,
 			needs_server,
 			needs_client,
+			needs_batch,
 			namespace,
 			namespace_separator,
+			openrpc,
+			description,
 			trait_def: item,
 			methods,
 			subscriptions,
@@ -0,0 +1,10 @@
+use jsonrpsee::proc_macros::rpc;
+
+// Empty required role.
+#[rpc(server)]
+pub trait EmptyRole {
+	#[method(name = "foo", requires = "")]
+	async fn foo(&self) -> jsonrpsee::core::RpcResult<u8>;
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use jsonrpsee::proc_macros::rpc;
+
+// Timeout without a unit.
+#[rpc(server)]
+pub trait UnknownUnit {
+	#[method(name = "foo", timeout = "5x")]
+	async fn foo(&self) -> jsonrpsee::core::RpcResult<u8>;
+}
+
+// Timeout which would fail every call.
+#[rpc(server)]
+pub trait ZeroTimeout {
+	#[method(name = "foo", timeout = "0s")]
+	async fn foo(&self) -> jsonrpsee::core::RpcResult<u8>;
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use jsonrpsee::proc_macros::rpc;
+
+// Synchronous methods can't be timed out.
+#[rpc(server)]
+pub trait SyncTimeout {
+	#[method(name = "foo", timeout = "5s")]
+	fn foo(&self) -> jsonrpsee::core::RpcResult<u8>;
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use jsonrpsee::proc_macros::rpc;
+
+// Batch calls are only generated for the client.
+#[rpc(server, batch)]
+pub trait BatchWithoutClient {
+	#[method(name = "foo")]
+	async fn foo(&self) -> jsonrpsee::core::RpcResult<u8>;
+}
+
+fn main() {}
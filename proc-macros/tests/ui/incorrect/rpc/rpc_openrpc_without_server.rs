@@ -0,0 +1,10 @@
+use jsonrpsee::proc_macros::rpc;
+
+// The OpenRPC document is only generated for the server.
+#[rpc(client, openrpc)]
+pub trait OpenRpcWithoutServer {
+	#[method(name = "foo")]
+	async fn foo(&self) -> jsonrpsee::core::RpcResult<u8>;
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use jsonrpsee::proc_macros::rpc;
+
+// Empty required role among others.
+#[rpc(server)]
+pub trait EmptyRole {
+	#[subscription(name = "sub", unsubscribe = "unsub", item = u8, requires = ["admin", ""])]
+	async fn sub(&self) -> jsonrpsee::core::SubscriptionResult;
+}
+
+fn main() {}
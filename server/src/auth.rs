@@ -0,0 +1,72 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Authentication of the callers for the authorization of methods.
+
+use http::HeaderMap;
+
+use crate::Extensions;
+
+pub use jsonrpsee_core::server::Principal;
+
+/// Something that identifies the caller from a HTTP request or the HTTP request which
+/// opened a WebSocket connection.
+///
+/// The authenticator sees the headers of the request and its extensions, which contain for example the
+/// [`ConnectionId`](crate::ConnectionId), the remote address and the certificates of the client
+/// if TLS client authentication is enabled.
+/// Anything inserted by the HTTP middleware is available as well.
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpsee_server::{Authenticator, Extensions, Principal, ServerConfig};
+///
+/// #[derive(Debug)]
+/// struct ApiKey(String);
+///
+/// impl Authenticator for ApiKey {
+///     fn authenticate(&self, headers: &http::HeaderMap, _: &Extensions) -> Option<Principal> {
+///         let key = headers.get("x-api-key")?;
+///         (key == self.0.as_str()).then(|| Principal::new("operator").with_role("admin"))
+///     }
+/// }
+///
+/// let config = ServerConfig::builder().set_authenticator(ApiKey("secret".to_string())).build();
+/// ```
+pub trait Authenticator: Send + Sync + std::fmt::Debug + 'static {
+	/// Authenticate the caller.
+	///
+	/// Returns `None` if the caller is anonymous, calls of anonymous callers are only rejected
+	/// if the method requires roles.
+	fn authenticate(&self, headers: &HeaderMap, extensions: &Extensions) -> Option<Principal>;
+}
+
+impl<T: Authenticator + ?Sized> Authenticator for Box<T> {
+	fn authenticate(&self, headers: &HeaderMap, extensions: &Extensions) -> Option<Principal> {
+		(**self).authenticate(headers, extensions)
+	}
+}
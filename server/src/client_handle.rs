@@ -0,0 +1,199 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Requests from the server to the WebSocket clients.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use jsonrpsee_core::server::MethodSink;
+use jsonrpsee_core::traits::ToRpcParams;
+use jsonrpsee_types::{ErrorObjectOwned, Id, Notification, Request, Response, ResponseSuccess};
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+use tokio::sync::oneshot;
+
+/// Error that can occur when sending a request to the client with [`ClientHandle`].
+#[derive(Debug, thiserror::Error)]
+pub enum ClientRequestError {
+	/// The connection was closed before the response was received.
+	#[error("The connection was closed")]
+	Closed,
+	/// Failed to serialize the params or to parse the response.
+	#[error(transparent)]
+	Parse(#[from] serde_json::Error),
+	/// The client answered with an error.
+	#[error(transparent)]
+	JsonRpc(#[from] ErrorObjectOwned),
+}
+
+/// Handle to send JSON-RPC requests to the client of a WebSocket connection.
+///
+/// It's inserted in the extensions of the calls made over WebSocket when the server is configured with
+/// [`ServerConfigBuilder::enable_client_requests`](crate::ServerConfigBuilder::enable_client_requests),
+/// such that methods can call back the client and await its response, for example to ask the user to
+/// confirm an operation. The client must answer these requests, for instance with the local methods of
+/// the `WsClient`.
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpsee_server::{ClientHandle, RpcModule};
+/// use jsonrpsee_server::types::ErrorObjectOwned;
+///
+/// let mut module = RpcModule::new(());
+/// module
+///     .register_async_method("transfer", |_, _, ext| async move {
+///         let client = ext.get::<ClientHandle>().cloned().ok_or_else(|| ErrorObjectOwned::owned(1, "WebSocket only", None::<()>))?;
+///         let confirmed: bool = client
+///             .request("confirm", ["Transfer 10 tokens?"])
+///             .await
+///             .map_err(|e| ErrorObjectOwned::owned(2, e.to_string(), None::<()>))?;
+///         Ok::<_, ErrorObjectOwned>(confirmed)
+///     })
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClientHandle {
+	sink: MethodSink,
+	pending: Arc<Mutex<PendingRequests>>,
+}
+
+#[derive(Debug, Default)]
+struct PendingRequests {
+	next_id: u64,
+	closed: bool,
+	calls: HashMap<u64, oneshot::Sender<Vec<u8>>>,
+}
+
+impl ClientHandle {
+	pub(crate) fn new(sink: MethodSink) -> Self {
+		Self { sink, pending: Arc::default() }
+	}
+
+	/// Send a request to the client and wait for its response.
+	///
+	/// The request is not cancelled when the returned future is dropped, the response is just ignored.
+	/// Use for example [`tokio::time::timeout`] to not wait forever on unresponsive clients.
+	pub async fn request<R, Params>(&self, method: &str, params: Params) -> Result<R, ClientRequestError>
+	where
+		R: DeserializeOwned,
+		Params: ToRpcParams,
+	{
+		let params = params.to_rpc_params()?;
+		let (id, rx) = self.register()?;
+		let _guard = RemoveOnDrop { id, pending: &self.pending };
+
+		let request = Request::borrowed(method, params.as_deref(), Id::Number(id));
+		self.send(&request).await?;
+
+		let response = rx.await.map_err(|_| ClientRequestError::Closed)?;
+		let response = serde_json::from_slice::<Response<&RawValue>>(&response)?;
+		let result = ResponseSuccess::try_from(response).map_err(|e| e.into_owned())?.result;
+
+		Ok(serde_json::from_str(result.get())?)
+	}
+
+	/// Send a notification to the client.
+	pub async fn notification<Params: ToRpcParams>(
+		&self,
+		method: &str,
+		params: Params,
+	) -> Result<(), ClientRequestError> {
+		let params = params.to_rpc_params()?;
+		let notif = Notification::new(method.into(), params);
+		self.send(&notif).await
+	}
+
+	/// Returns whether the connection to the client is closed.
+	pub fn is_closed(&self) -> bool {
+		self.sink.is_closed()
+	}
+
+	/// Deliver the message to the pending request if it's a response.
+	///
+	/// Returns whether the message was a response, even if nobody waits for it anymore.
+	pub(crate) fn on_response(&self, data: &[u8]) -> bool {
+		let Ok(response) = serde_json::from_slice::<Response<&RawValue>>(data) else {
+			return false;
+		};
+
+		let tx = match response.id {
+			Id::Number(id) => self.pending.lock().expect("lock poisoned; qed").calls.remove(&id),
+			_ => None,
+		};
+
+		match tx {
+			Some(tx) => {
+				let _ = tx.send(data.to_vec());
+			}
+			None => {
+				tracing::debug!(target: crate::LOG_TARGET, "Dropped response to unknown request: {:?}", response.id)
+			}
+		}
+
+		true
+	}
+
+	/// Fail all pending requests, used when the connection has been closed.
+	pub(crate) fn close(&self) {
+		let mut pending = self.pending.lock().expect("lock poisoned; qed");
+		pending.closed = true;
+		pending.calls.clear();
+	}
+
+	fn register(&self) -> Result<(u64, oneshot::Receiver<Vec<u8>>), ClientRequestError> {
+		let mut pending = self.pending.lock().expect("lock poisoned; qed");
+		if pending.closed {
+			return Err(ClientRequestError::Closed);
+		}
+
+		let id = pending.next_id;
+		pending.next_id += 1;
+
+		let (tx, rx) = oneshot::channel();
+		pending.calls.insert(id, tx);
+
+		Ok((id, rx))
+	}
+
+	async fn send<T: serde::Serialize>(&self, msg: &T) -> Result<(), ClientRequestError> {
+		let json = serde_json::value::to_raw_value(msg)?;
+		self.sink.send(json).await.map_err(|_| ClientRequestError::Closed)
+	}
+}
+
+/// Removes the pending request when the caller stops waiting for the response.
+struct RemoveOnDrop<'a> {
+	id: u64,
+	pending: &'a Mutex<PendingRequests>,
+}
+
+impl Drop for RemoveOnDrop<'_> {
+	fn drop(&mut self) {
+		self.pending.lock().unwrap_or_else(|e| e.into_inner()).calls.remove(&self.id);
+	}
+}
@@ -27,29 +27,60 @@
 //! # jsonrpsee-server
 //!
 //! `jsonrpsee-server` is a [JSON RPC](https://www.jsonrpc.org/specification) server that supports both HTTP and WebSocket transport.
+//!
+//! Subscriptions are also served over HTTP as [Server-Sent Events](https://html.spec.whatwg.org/multipage/server-sent-events.html)
+//! when the subscription call is made with the `Accept: text/event-stream` header.
+//!
+//! Pending async method calls on WebSocket and IPC connections are cancelled when the connection is closed
+//! or when the client sends a [`$/cancelRequest`](middleware::rpc::CANCEL_REQUEST_METHOD) notification with the ID
+//! of the call. Methods registered with extensions can observe the cancellation through the [`CancellationToken`]
+//! in the extensions, for example to stop blocking work.
+//!
+//! With the `tls` feature the server terminates TLS itself, see `ServerBuilder::tls`.
 
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+mod auth;
+mod client_handle;
 mod future;
+mod origin;
 mod server;
 mod transport;
 mod utils;
 
+/// TLS termination.
+#[cfg(feature = "tls")]
+mod tls;
+
 pub mod middleware;
 
 #[cfg(test)]
 mod tests;
 
+pub use auth::{Authenticator, Principal};
+pub use client_handle::{ClientHandle, ClientRequestError};
 pub use future::{AlreadyStoppedError, ConnectionGuard, ConnectionPermit, ServerHandle, StopHandle, stop_channel};
 pub use jsonrpsee_core::error::RegisterMethodError;
 pub use jsonrpsee_core::server::*;
 pub use jsonrpsee_core::{id_providers::*, traits::IdProvider};
 pub use jsonrpsee_types as types;
+pub use origin::OriginPolicy;
 pub use server::{
-	BatchRequestConfig, Builder as ServerBuilder, ConnectionState, PingConfig, Server, ServerConfig,
-	ServerConfigBuilder, TowerService, TowerServiceBuilder,
+	BatchExecutionConfig, BatchRequestConfig, Builder as ServerBuilder, ConnectionState, PingConfig, Server,
+	ServerConfig, ServerConfigBuilder, TowerService, TowerServiceBuilder,
 };
+
+#[cfg(feature = "deflate")]
+#[cfg_attr(docsrs, doc(cfg(feature = "deflate")))]
+pub use server::DeflateConfig;
+#[cfg(feature = "tls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+pub use tls::{PeerCertificates, TlsConfig, TlsError};
+#[cfg(feature = "tls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+pub use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+pub use tokio_util::sync::CancellationToken;
 pub use tracing;
 
 pub use jsonrpsee_core::http_helpers::{Body as HttpBody, Request as HttpRequest, Response as HttpResponse};
@@ -0,0 +1,549 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Structured access log middleware.
+
+use crate::{HttpBody, HttpRequest, HttpResponse, LOG_TARGET};
+use futures_util::{FutureExt, TryFutureExt};
+use http_body::{Body, Frame, SizeHint};
+use hyper::StatusCode;
+use hyper::body::Bytes;
+use hyper::header::USER_AGENT;
+use jsonrpsee_core::BoxError;
+use jsonrpsee_core::server::ConnectionId;
+use serde_json::Value;
+use serde_json::value::RawValue;
+use std::collections::HashSet;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, ready};
+use std::time::{Duration, Instant};
+use tower::{Layer, Service};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Transport over which the logged request was served.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AccessLogTransport {
+	/// A plain HTTP request.
+	Http,
+	/// A WebSocket session, logged once the session is closed.
+	///
+	/// The record contains the calls of the session, unless its messages are
+	/// logged individually, see [`AccessLogLayer::with_websocket_messages`].
+	WebSocket,
+	/// A message of a WebSocket session, logged once it has been answered.
+	///
+	/// Only logged if enabled by [`AccessLogLayer::with_websocket_messages`].
+	WebSocketMessage,
+}
+
+/// JSON-RPC call or notification found in the body of a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AccessLogCall {
+	/// Name of the method.
+	pub method: String,
+	/// Redacted params of the call, only captured if enabled by [`AccessLogLayer::with_params`].
+	pub params: Option<String>,
+}
+
+/// A single access log record which is written when the response to an HTTP request
+/// has been sent, or a WebSocket session has been closed.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct AccessLogRecord {
+	/// Transport of the request.
+	pub transport: AccessLogTransport,
+	/// HTTP method of the request.
+	pub http_method: http::Method,
+	/// Path of the request.
+	pub path: String,
+	/// Address of the peer if connected over TCP.
+	pub remote_addr: Option<SocketAddr>,
+	/// Connection ID assigned by the server.
+	pub connection_id: Option<ConnectionId>,
+	/// The `User-Agent` header of the request.
+	pub user_agent: Option<String>,
+	/// JSON-RPC calls and notifications in the order they were received.
+	pub calls: Vec<AccessLogCall>,
+	/// Size of each batch that was received.
+	pub batch_sizes: Vec<usize>,
+	/// HTTP status of the response, `101 Switching Protocols` for WebSocket sessions.
+	pub status: StatusCode,
+	/// Number of bytes in the JSON-RPC responses, or the HTTP body if it doesn't contain any.
+	pub response_bytes: usize,
+	/// Time until the response was sent or, for streaming responses and WebSocket sessions, until they ended.
+	pub latency: Duration,
+}
+
+/// Destination of the access log records.
+pub trait AccessLogSink: Send + Sync + 'static {
+	/// Write the record.
+	fn log(&self, record: AccessLogRecord);
+}
+
+impl<F> AccessLogSink for F
+where
+	F: Fn(AccessLogRecord) + Send + Sync + 'static,
+{
+	fn log(&self, record: AccessLogRecord) {
+		self(record)
+	}
+}
+
+/// Sink which emits each record as an `INFO` event with the target `jsonrpsee-server::access`.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TracingAccessLog;
+
+impl AccessLogSink for TracingAccessLog {
+	fn log(&self, record: AccessLogRecord) {
+		let methods: Vec<_> = record.calls.iter().map(|c| c.method.as_str()).collect();
+		let params: Vec<_> = record.calls.iter().filter_map(|c| c.params.as_deref()).collect();
+
+		tracing::info!(
+			target: "jsonrpsee-server::access",
+			transport = ?record.transport,
+			http.method = %record.http_method,
+			http.path = %record.path,
+			http.status = record.status.as_u16(),
+			remote_addr = ?record.remote_addr,
+			connection_id = record.connection_id.map(|id| id.0),
+			user_agent = record.user_agent,
+			rpc.methods = ?methods,
+			rpc.params = ?params,
+			rpc.batch_sizes = ?record.batch_sizes,
+			response_bytes = record.response_bytes,
+			latency_us = record.latency.as_micros() as u64,
+		);
+	}
+}
+
+#[derive(Clone)]
+struct AccessLogConfig {
+	sink: Arc<dyn AccessLogSink>,
+	log_params: bool,
+	log_messages: bool,
+	redact_fields: HashSet<String>,
+	redact_methods: HashSet<String>,
+}
+
+impl AccessLogConfig {
+	fn params(&self, method: &str, params: Option<&RawValue>) -> Option<String> {
+		if !self.log_params {
+			return None;
+		}
+
+		let params = params?;
+
+		if self.redact_methods.contains(method) {
+			return Some(format!("\"{REDACTED}\""));
+		}
+
+		if self.redact_fields.is_empty() {
+			return Some(params.get().to_owned());
+		}
+
+		match serde_json::from_str::<Value>(params.get()) {
+			Ok(mut value) => {
+				redact(&mut value, &self.redact_fields);
+				Some(value.to_string())
+			}
+			Err(_) => Some(format!("\"{REDACTED}\"")),
+		}
+	}
+}
+
+/// Replace the values of all object members named in `fields`, at any depth.
+fn redact(value: &mut Value, fields: &HashSet<String>) {
+	match value {
+		Value::Object(map) => {
+			for (key, v) in map.iter_mut() {
+				if fields.contains(key) {
+					*v = Value::String(REDACTED.to_owned());
+				} else {
+					redact(v, fields);
+				}
+			}
+		}
+		Value::Array(values) => values.iter_mut().for_each(|v| redact(v, fields)),
+		_ => (),
+	}
+}
+
+/// Layer that applies [`AccessLog`] which writes one [`AccessLogRecord`] per HTTP request
+/// and WebSocket session to an [`AccessLogSink`].
+///
+/// The record is completed by the server with the connection ID, the remote address and the
+/// JSON-RPC calls of the request. Records for the individual WebSocket messages can be enabled
+/// with [`AccessLogLayer::with_websocket_messages`]. Params are not logged by default because they may contain
+/// sensitive data; enable them with [`AccessLogLayer::with_params`] and hide individual fields
+/// with [`AccessLogLayer::redact_field`] or all params of a method with [`AccessLogLayer::redact_method`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use jsonrpsee_server::Server;
+/// use jsonrpsee_server::middleware::http::{AccessLogLayer, AccessLogRecord};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let access_log = AccessLogLayer::new(|record: AccessLogRecord| println!("{record:?}"))
+///         .with_params()
+///         .redact_field("password")
+///         .redact_method("personal_sign");
+///
+///     let server = Server::builder()
+///         .set_http_middleware(tower::ServiceBuilder::new().layer(access_log))
+///         .build("127.0.0.1:0")
+///         .await
+///         .unwrap();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct AccessLogLayer(AccessLogConfig);
+
+impl std::fmt::Debug for AccessLogLayer {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("AccessLogLayer")
+			.field("log_params", &self.0.log_params)
+			.field("log_messages", &self.0.log_messages)
+			.field("redact_fields", &self.0.redact_fields)
+			.field("redact_methods", &self.0.redact_methods)
+			.finish_non_exhaustive()
+	}
+}
+
+impl AccessLogLayer {
+	/// Write the access log records to `sink`.
+	pub fn new(sink: impl AccessLogSink) -> Self {
+		Self(AccessLogConfig {
+			sink: Arc::new(sink),
+			log_params: false,
+			log_messages: false,
+			redact_fields: HashSet::new(),
+			redact_methods: HashSet::new(),
+		})
+	}
+
+	/// Write the access log records as `tracing` events, see [`TracingAccessLog`].
+	pub fn tracing() -> Self {
+		Self::new(TracingAccessLog)
+	}
+
+	/// Include the params of each call in the record.
+	pub fn with_params(mut self) -> Self {
+		self.0.log_params = true;
+		self
+	}
+
+	/// Write a record for every message of a WebSocket session, see [`AccessLogTransport::WebSocketMessage`].
+	///
+	/// The calls are then in the records of the messages instead of the record of the session.
+	pub fn with_websocket_messages(mut self) -> Self {
+		self.0.log_messages = true;
+		self
+	}
+
+	/// Replace the value of every object member named `field` in the params with `"[REDACTED]"`.
+	pub fn redact_field(mut self, field: impl Into<String>) -> Self {
+		self.0.redact_fields.insert(field.into());
+		self
+	}
+
+	/// Replace all params of `method` with `"[REDACTED]"`.
+	pub fn redact_method(mut self, method: impl Into<String>) -> Self {
+		self.0.redact_methods.insert(method.into());
+		self
+	}
+}
+
+impl<S> Layer<S> for AccessLogLayer {
+	type Service = AccessLog<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		AccessLog { inner, config: self.0.clone() }
+	}
+}
+
+/// Middleware that writes structured access log records.
+#[derive(Clone)]
+pub struct AccessLog<S> {
+	inner: S,
+	config: AccessLogConfig,
+}
+
+impl<S: std::fmt::Debug> std::fmt::Debug for AccessLog<S> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("AccessLog").field("inner", &self.inner).finish_non_exhaustive()
+	}
+}
+
+impl<S, B, ResBody> Service<HttpRequest<B>> for AccessLog<S>
+where
+	S: Service<HttpRequest<B>, Response = HttpResponse<ResBody>>,
+	S::Error: Into<BoxError> + 'static,
+	S::Future: Send + 'static,
+	ResBody: Body<Data = Bytes> + Send + 'static,
+	ResBody::Error: Into<BoxError>,
+{
+	type Response = HttpResponse;
+	type Error = BoxError;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx).map_err(Into::into)
+	}
+
+	fn call(&mut self, mut request: HttpRequest<B>) -> Self::Future {
+		let record = AccessLogRecord {
+			transport: AccessLogTransport::Http,
+			http_method: request.method().clone(),
+			path: request.uri().path().to_owned(),
+			remote_addr: request.extensions().get::<SocketAddr>().copied(),
+			connection_id: request.extensions().get::<ConnectionId>().copied(),
+			user_agent: request.headers().get(USER_AGENT).and_then(|v| v.to_str().ok()).map(ToOwned::to_owned),
+			calls: Vec::new(),
+			batch_sizes: Vec::new(),
+			status: StatusCode::OK,
+			response_bytes: 0,
+			latency: Duration::ZERO,
+		};
+
+		let entry = AccessLogEntry::new(self.config.clone(), record);
+		request.extensions_mut().insert(entry.clone());
+
+		self.inner
+			.call(request)
+			.map_err(Into::into)
+			.map(move |res| match res {
+				Ok(rp) if rp.status() == StatusCode::SWITCHING_PROTOCOLS => {
+					// The record is written once the WebSocket session drops its extensions.
+					entry.update(|r| {
+						r.transport = AccessLogTransport::WebSocket;
+						r.status = rp.status();
+					});
+					Ok(rp.map(HttpBody::new))
+				}
+				Ok(rp) => {
+					entry.update(|r| {
+						r.status = rp.status();
+						if r.calls.is_empty() {
+							r.response_bytes = rp.body().size_hint().exact().unwrap_or_default() as usize;
+						}
+					});
+					// Streaming responses are written once the stream has ended.
+					let entry = if rp.body().size_hint().exact().is_some() {
+						entry.finish();
+						None
+					} else {
+						Some(entry)
+					};
+					Ok(rp.map(|inner| HttpBody::new(AccessLogBody { inner, entry })))
+				}
+				Err(e) => {
+					entry.update(|r| r.status = StatusCode::INTERNAL_SERVER_ERROR);
+					entry.finish();
+					Err(e)
+				}
+			})
+			.boxed()
+	}
+}
+
+/// Response body which writes the record of a streaming response once the stream has ended.
+#[pin_project::pin_project]
+struct AccessLogBody<B> {
+	#[pin]
+	inner: B,
+	entry: Option<AccessLogEntry>,
+}
+
+impl<B: Body> Body for AccessLogBody<B> {
+	type Data = B::Data;
+	type Error = B::Error;
+
+	fn poll_frame(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+		let this = self.project();
+		let frame = ready!(this.inner.poll_frame(cx));
+
+		if !matches!(frame, Some(Ok(_))) {
+			if let Some(entry) = this.entry.take() {
+				entry.finish();
+			}
+		}
+
+		Poll::Ready(frame)
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.inner.is_end_stream()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		self.inner.size_hint()
+	}
+}
+
+/// Access log record under construction which is shared via the extensions of the request.
+///
+/// The record is written when [`AccessLogEntry::finish`] is called or the last clone is dropped.
+#[derive(Clone)]
+pub(crate) struct AccessLogEntry(Arc<AccessLogEntryInner>);
+
+struct AccessLogEntryInner {
+	config: AccessLogConfig,
+	started: Instant,
+	record: Mutex<Option<AccessLogRecord>>,
+}
+
+impl AccessLogEntry {
+	fn new(config: AccessLogConfig, record: AccessLogRecord) -> Self {
+		Self(Arc::new(AccessLogEntryInner { config, started: Instant::now(), record: Mutex::new(Some(record)) }))
+	}
+
+	fn update(&self, f: impl FnOnce(&mut AccessLogRecord)) {
+		if let Some(record) = self.0.record.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+			f(record);
+		}
+	}
+
+	/// Write the record.
+	pub(crate) fn finish(&self) {
+		self.0.finish();
+	}
+
+	/// Start the record of a message of a WebSocket session, which is written when
+	/// [`AccessLogEntry::finish`] is called.
+	///
+	/// Returns `None` if this isn't the entry of a WebSocket session or its messages
+	/// aren't logged individually.
+	pub(crate) fn message(&self) -> Option<Self> {
+		if !self.0.config.log_messages {
+			return None;
+		}
+
+		let record = self.0.record.lock().unwrap_or_else(|e| e.into_inner());
+		let session = record.as_ref().filter(|r| r.transport == AccessLogTransport::WebSocket)?;
+
+		let record = AccessLogRecord {
+			transport: AccessLogTransport::WebSocketMessage,
+			calls: Vec::new(),
+			batch_sizes: Vec::new(),
+			response_bytes: 0,
+			latency: Duration::ZERO,
+			..session.clone()
+		};
+		Some(Self::new(self.0.config.clone(), record))
+	}
+
+	/// Set the connection details known by the server.
+	pub(crate) fn set_connection(&self, conn_id: ConnectionId, remote_addr: Option<SocketAddr>) {
+		self.update(|r| {
+			r.connection_id = Some(conn_id);
+			r.remote_addr = r.remote_addr.or(remote_addr);
+		});
+	}
+
+	/// Record a JSON-RPC call or notification.
+	pub(crate) fn record_call(&self, method: &str, params: Option<&RawValue>) {
+		let params = self.0.config.params(method, params);
+		self.update(|r| r.calls.push(AccessLogCall { method: method.to_owned(), params }));
+	}
+
+	/// Record the size of a batch.
+	pub(crate) fn record_batch(&self, len: usize) {
+		self.update(|r| r.batch_sizes.push(len));
+	}
+
+	/// Record the size of a JSON-RPC response.
+	pub(crate) fn record_response(&self, bytes: usize) {
+		self.update(|r| r.response_bytes += bytes);
+	}
+}
+
+impl AccessLogEntryInner {
+	fn finish(&self) {
+		let record = self.record.lock().unwrap_or_else(|e| e.into_inner()).take();
+
+		if let Some(mut record) = record {
+			record.latency = self.started.elapsed();
+			tracing::trace!(target: LOG_TARGET, "access log: {:?}", record);
+			self.config.sink.log(record);
+		}
+	}
+}
+
+impl Drop for AccessLogEntryInner {
+	fn drop(&mut self) {
+		self.finish();
+	}
+}
+
+impl std::fmt::Debug for AccessLogEntry {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_tuple("AccessLogEntry").field(&self.0.record).finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{AccessLogLayer, AccessLogRecord};
+	use serde_json::value::RawValue;
+
+	fn params(layer: &AccessLogLayer, method: &str, params: &str) -> Option<String> {
+		let params = RawValue::from_string(params.to_owned()).unwrap();
+		layer.0.params(method, Some(&params))
+	}
+
+	#[test]
+	fn params_are_not_logged_by_default() {
+		let layer = AccessLogLayer::new(|_: AccessLogRecord| {});
+		assert_eq!(params(&layer, "say_hello", r#"["hello"]"#), None);
+	}
+
+	#[test]
+	fn redacts_nested_fields() {
+		let layer = AccessLogLayer::new(|_: AccessLogRecord| {}).with_params().redact_field("password");
+
+		let redacted = params(&layer, "login", r#"[{"user":"alice","password":"hunter2"},{"nested":{"password":1}}]"#);
+		assert_eq!(
+			serde_json::from_str::<serde_json::Value>(&redacted.unwrap()).unwrap(),
+			serde_json::json!([{ "user": "alice", "password": "[REDACTED]" }, { "nested": { "password": "[REDACTED]" } }])
+		);
+		assert_eq!(params(&layer, "login", r#"["password"]"#).unwrap(), r#"["password"]"#);
+	}
+
+	#[test]
+	fn redacts_methods() {
+		let layer = AccessLogLayer::new(|_: AccessLogRecord| {}).with_params().redact_method("login");
+
+		assert_eq!(params(&layer, "login", r#"["alice","hunter2"]"#).unwrap(), r#""[REDACTED]""#);
+		assert_eq!(params(&layer, "logout", r#"["alice"]"#).unwrap(), r#"["alice"]"#);
+	}
+}
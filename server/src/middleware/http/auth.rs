@@ -0,0 +1,383 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! JWT authentication middleware.
+
+use crate::transport::{http, ws};
+use crate::{HttpBody, HttpRequest, HttpResponse, LOG_TARGET};
+use futures_util::{FutureExt, TryFutureExt};
+use hyper::header::AUTHORIZATION;
+use jsonrpsee_core::BoxError;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower::{Layer, Service};
+
+/// Error that can occur when configuring the [`AuthLayer`].
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid JWT key: {0}")]
+pub struct AuthError(#[from] jsonwebtoken::errors::Error);
+
+/// Claims of a verified JSON Web Token.
+///
+/// They are inserted in the extensions of the request by [`AuthLayer`] and are thus
+/// available to the methods registered with extensions and to the [`Authenticator`](crate::Authenticator).
+#[derive(Debug, Clone, PartialEq)]
+pub struct JwtClaims(Arc<Map<String, Value>>);
+
+impl JwtClaims {
+	/// Get the value of a claim.
+	pub fn get(&self, claim: &str) -> Option<&Value> {
+		self.0.get(claim)
+	}
+
+	/// Get the subject (`sub`) of the token.
+	pub fn subject(&self) -> Option<&str> {
+		self.get("sub").and_then(Value::as_str)
+	}
+
+	/// Get all claims of the token.
+	pub fn as_map(&self) -> &Map<String, Value> {
+		&self.0
+	}
+
+	/// Deserialize the claims into a custom type.
+	pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+		serde_json::from_value(Value::Object((*self.0).clone()))
+	}
+}
+
+/// Layer that applies [`Auth`] which validates JSON Web Tokens signed with HS256 or EdDSA (Ed25519).
+///
+/// The token is read from the `Authorization: Bearer <token>` header or, for WebSocket
+/// upgrade requests only, from the query parameter configured with [`AuthLayer::query_param`]
+/// because browsers can't set headers on the WebSocket handshake.
+///
+/// The `exp` claim is required and validated with the tolerance configured with [`AuthLayer::leeway`].
+/// Requests without a valid token are rejected with `401 Unauthorized` unless [`AuthLayer::allow_anonymous`]
+/// is set, otherwise the verified claims are inserted in the extensions of the request as [`JwtClaims`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use jsonrpsee_server::middleware::http::{AuthLayer, JwtClaims};
+/// use jsonrpsee_server::{Authenticator, Principal, Server};
+///
+/// /// Map the `roles` claim of the token to the roles of the principal.
+/// #[derive(Debug)]
+/// struct JwtRoles;
+///
+/// impl Authenticator for JwtRoles {
+///     fn authenticate(&self, _: &http::HeaderMap, extensions: &http::Extensions) -> Option<Principal> {
+///         let claims = extensions.get::<JwtClaims>()?;
+///         let mut principal = Principal::new(claims.subject()?);
+///         for role in claims.get("roles")?.as_array()?.iter().filter_map(|r| r.as_str()) {
+///             principal = principal.with_role(role);
+///         }
+///         Some(principal)
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let auth = AuthLayer::hs256(b"secret").query_param("token");
+///
+///     let server = Server::builder()
+///         .set_config(jsonrpsee_server::ServerConfig::builder().set_authenticator(JwtRoles).build())
+///         .set_http_middleware(tower::ServiceBuilder::new().layer(auth))
+///         .build("127.0.0.1:0")
+///         .await
+///         .unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AuthLayer(Arc<JwtValidator>);
+
+impl AuthLayer {
+	/// Validate tokens signed with HS256 and the shared `secret`.
+	pub fn hs256(secret: &[u8]) -> Self {
+		Self::new(DecodingKey::from_secret(secret), Algorithm::HS256)
+	}
+
+	/// Validate tokens signed with EdDSA and the PEM encoded Ed25519 public key.
+	pub fn ed25519_pem(public_key: &[u8]) -> Result<Self, AuthError> {
+		Ok(Self::new(DecodingKey::from_ed_pem(public_key)?, Algorithm::EdDSA))
+	}
+
+	/// Validate tokens signed with EdDSA and the raw Ed25519 public key.
+	pub fn ed25519_der(public_key: &[u8]) -> Self {
+		Self::new(DecodingKey::from_ed_der(public_key), Algorithm::EdDSA)
+	}
+
+	/// Set the tolerated clock skew when validating the `exp` and `nbf` claims.
+	///
+	/// Default: 60 seconds.
+	pub fn leeway(mut self, leeway: Duration) -> Self {
+		self.validator_mut().validation.leeway = leeway.as_secs();
+		self
+	}
+
+	/// Read the token from the query parameter `name` of WebSocket upgrade requests
+	/// when the `Authorization` header is missing.
+	pub fn query_param(mut self, name: impl Into<String>) -> Self {
+		self.validator_mut().query_param = Some(name.into());
+		self
+	}
+
+	/// Let requests without a token through, without [`JwtClaims`] in their extensions.
+	///
+	/// Requests with an invalid token are still rejected. This is useful to protect only some
+	/// methods with [`RpcModule::set_required_roles`](jsonrpsee_core::server::RpcModule::set_required_roles).
+	pub fn allow_anonymous(mut self) -> Self {
+		self.validator_mut().allow_anonymous = true;
+		self
+	}
+
+	fn new(key: DecodingKey, algorithm: Algorithm) -> Self {
+		let mut validation = Validation::new(algorithm);
+		validation.validate_nbf = true;
+
+		Self(Arc::new(JwtValidator { key, validation, query_param: None, allow_anonymous: false }))
+	}
+
+	fn validator_mut(&mut self) -> &mut JwtValidator {
+		Arc::make_mut(&mut self.0)
+	}
+}
+
+impl<S> Layer<S> for AuthLayer {
+	type Service = Auth<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		Auth { inner, validator: self.0.clone() }
+	}
+}
+
+/// Middleware that validates JSON Web Tokens, see [`AuthLayer`] for more details.
+#[derive(Debug, Clone)]
+pub struct Auth<S> {
+	inner: S,
+	validator: Arc<JwtValidator>,
+}
+
+impl<S, B> Service<HttpRequest<B>> for Auth<S>
+where
+	S: Service<HttpRequest<B>, Response = HttpResponse>,
+	S::Error: Into<BoxError> + 'static,
+	S::Future: Send + 'static,
+{
+	type Response = HttpResponse<HttpBody>;
+	type Error = BoxError;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx).map_err(Into::into)
+	}
+
+	fn call(&mut self, mut request: HttpRequest<B>) -> Self::Future {
+		match self.validator.token(&request).map(|token| self.validator.validate(token)) {
+			Some(Ok(claims)) => {
+				request.extensions_mut().insert(claims);
+			}
+			Some(Err(e)) => {
+				tracing::debug!(target: LOG_TARGET, "Rejected request with invalid token: {e}");
+				return async { Ok(http::response::unauthorized()) }.boxed();
+			}
+			None if !self.validator.allow_anonymous => {
+				return async { Ok(http::response::unauthorized()) }.boxed();
+			}
+			None => (),
+		}
+
+		Box::pin(self.inner.call(request).map_err(Into::into))
+	}
+}
+
+#[derive(Clone)]
+struct JwtValidator {
+	key: DecodingKey,
+	validation: Validation,
+	query_param: Option<String>,
+	allow_anonymous: bool,
+}
+
+impl std::fmt::Debug for JwtValidator {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("JwtValidator")
+			.field("validation", &self.validation)
+			.field("query_param", &self.query_param)
+			.field("allow_anonymous", &self.allow_anonymous)
+			.finish_non_exhaustive()
+	}
+}
+
+impl JwtValidator {
+	fn token<'a, B>(&self, request: &'a HttpRequest<B>) -> Option<&'a str> {
+		if let Some(header) = request.headers().get(AUTHORIZATION) {
+			// The auth scheme is case-insensitive, see RFC 7235.
+			let (scheme, token) = header.to_str().ok()?.split_once(|c: char| c.is_ascii_whitespace())?;
+			return scheme.eq_ignore_ascii_case("bearer").then(|| token.trim());
+		}
+
+		let name = self.query_param.as_deref()?;
+		if !ws::is_upgrade_request(request) {
+			return None;
+		}
+
+		request.uri().query()?.split('&').find_map(|pair| match pair.split_once('=') {
+			Some((key, value)) if key == name => Some(value),
+			_ => None,
+		})
+	}
+
+	fn validate(&self, token: &str) -> Result<JwtClaims, jsonwebtoken::errors::Error> {
+		let data = jsonwebtoken::decode::<Map<String, Value>>(token, &self.key, &self.validation)?;
+		Ok(JwtClaims(Arc::new(data.claims)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{AuthLayer, JwtClaims};
+	use crate::{Authenticator, HttpBody, HttpRequest, Principal, RpcModule, Server, ServerConfig, stop_channel};
+	use http_body_util::BodyExt;
+	use hyper::StatusCode;
+	use jsonrpsee_types::error::UNAUTHORIZED_CODE;
+	use jsonwebtoken::{EncodingKey, Header, encode};
+	use std::time::{SystemTime, UNIX_EPOCH};
+	use tower::Service;
+
+	const SECRET: &[u8] = b"secret";
+
+	/// Map the `roles` claim of the token to the roles of the principal.
+	#[derive(Debug)]
+	struct JwtRoles;
+
+	impl Authenticator for JwtRoles {
+		fn authenticate(&self, _: &http::HeaderMap, extensions: &http::Extensions) -> Option<Principal> {
+			let claims = extensions.get::<JwtClaims>()?;
+			let mut principal = Principal::new(claims.subject()?);
+			for role in claims.get("roles").and_then(|r| r.as_array()).into_iter().flatten() {
+				principal = principal.with_role(role.as_str()?);
+			}
+			Some(principal)
+		}
+	}
+
+	fn token(key: &[u8], expires_in: i64, roles: &[&str]) -> String {
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+		let claims = serde_json::json!({ "sub": "alice", "exp": now + expires_in, "roles": roles });
+		encode(&Header::default(), &claims, &EncodingKey::from_secret(key)).unwrap()
+	}
+
+	/// Call the `admin` method, which requires the `admin` role, with `token`.
+	async fn call_admin(token: &str) -> (StatusCode, String) {
+		let mut module = RpcModule::new(());
+		module.register_method("admin", |_, _, _| "ok").unwrap();
+		module.set_required_roles("admin", ["admin"]).unwrap();
+
+		let (stop_handle, _server_handle) = stop_channel();
+		let mut service = Server::builder()
+			.set_config(ServerConfig::builder().set_authenticator(JwtRoles).build())
+			.set_http_middleware(tower::ServiceBuilder::new().layer(AuthLayer::hs256(SECRET)))
+			.to_service_builder()
+			.build(module, stop_handle);
+
+		let request = HttpRequest::builder()
+			.method("POST")
+			.header("content-type", "application/json")
+			.header("authorization", format!("Bearer {token}"))
+			.body(HttpBody::from(r#"{"jsonrpc":"2.0","method":"admin","id":1}"#))
+			.unwrap();
+		let response = service.call(request).await.unwrap();
+		let status = response.status();
+		let body = response.into_body().collect().await.unwrap().to_bytes();
+
+		(status, String::from_utf8(body.to_vec()).unwrap())
+	}
+
+	fn request(uri: &str, headers: &[(&str, &str)]) -> HttpRequest<()> {
+		let mut builder = HttpRequest::builder().uri(uri);
+		for (name, value) in headers {
+			builder = builder.header(*name, *value);
+		}
+		builder.body(()).unwrap()
+	}
+
+	#[test]
+	fn token_from_header_or_ws_query() {
+		let layer = AuthLayer::hs256(b"secret").query_param("token");
+		let upgrade = [("connection", "upgrade"), ("upgrade", "websocket")];
+
+		assert_eq!(layer.0.token(&request("/", &[("authorization", "Bearer abc")])), Some("abc"));
+		assert_eq!(layer.0.token(&request("/", &[("authorization", "Basic abc")])), None);
+		assert_eq!(layer.0.token(&request("/?token=abc", &[])), None);
+		assert_eq!(layer.0.token(&request("/?foo=1&token=abc", &upgrade)), Some("abc"));
+		assert_eq!(layer.0.token(&request("/?foo=1", &upgrade)), None);
+	}
+
+	#[test]
+	fn bearer_scheme_is_case_insensitive() {
+		let layer = AuthLayer::hs256(b"secret");
+
+		assert_eq!(layer.0.token(&request("/", &[("authorization", "BEARER abc")])), Some("abc"));
+		assert_eq!(layer.0.token(&request("/", &[("authorization", "bEaReR abc")])), Some("abc"));
+		assert_eq!(layer.0.token(&request("/", &[("authorization", "Bearer  abc")])), Some("abc"));
+		assert_eq!(layer.0.token(&request("/", &[("authorization", "Bearerabc")])), None);
+	}
+
+	#[tokio::test]
+	async fn valid_token_with_role_is_accepted() {
+		let (status, body) = call_admin(&token(SECRET, 3600, &["admin"])).await;
+		assert_eq!(status, StatusCode::OK);
+		assert!(body.contains(r#""result":"ok""#), "{body}");
+	}
+
+	#[tokio::test]
+	async fn expired_token_is_rejected() {
+		// Beyond the default leeway of 60 seconds.
+		let (status, _) = call_admin(&token(SECRET, -120, &["admin"])).await;
+		assert_eq!(status, StatusCode::UNAUTHORIZED);
+	}
+
+	#[tokio::test]
+	async fn token_with_invalid_signature_is_rejected() {
+		let (status, _) = call_admin(&token(b"other", 3600, &["admin"])).await;
+		assert_eq!(status, StatusCode::UNAUTHORIZED);
+	}
+
+	#[tokio::test]
+	async fn token_without_role_is_rejected() {
+		let (status, body) = call_admin(&token(SECRET, 3600, &["user"])).await;
+		assert_eq!(status, StatusCode::OK);
+		assert!(body.contains(&format!(r#""code":{UNAUTHORIZED_CODE}"#)), "{body}");
+	}
+}
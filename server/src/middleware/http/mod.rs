@@ -26,11 +26,29 @@
 
 //! Various middleware implementations for HTTP specific purposes.
 
+/// Structured access log middleware.
+mod access_log;
+/// JWT authentication middleware.
+#[cfg(feature = "jwt")]
+mod auth;
 /// Utility and types related to the authority of an URI.
 mod authority;
 /// HTTP Host filtering middleware.
 mod host_filter;
-/// Proxy `GET /path` to internal RPC methods.
+/// Proxy HTTP requests such as `GET /block/{number}` to internal RPC methods.
 mod proxy_get_request;
 
-pub use {authority::*, host_filter::*, proxy_get_request::*};
+pub use {
+	access_log::{
+		AccessLog, AccessLogCall, AccessLogLayer, AccessLogRecord, AccessLogSink, AccessLogTransport, TracingAccessLog,
+	},
+	authority::*,
+	host_filter::*,
+	proxy_get_request::*,
+};
+
+pub(crate) use access_log::AccessLogEntry;
+
+#[cfg(feature = "jwt")]
+#[cfg_attr(docsrs, doc(cfg(feature = "jwt")))]
+pub use auth::{Auth, AuthError, AuthLayer, JwtClaims};
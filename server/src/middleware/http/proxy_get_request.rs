@@ -24,19 +24,26 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-//! Middleware that proxies HTTP GET requests at a specified URI to an internal RPC method call.
+//! Middleware that proxies HTTP requests at a specified URI to an internal RPC method call.
 
 use crate::transport::http;
 use crate::{HttpBody, HttpRequest, HttpResponse};
 use futures_util::{FutureExt, TryFutureExt};
-use http_body_util::BodyExt;
+use http_body_util::{BodyExt, LengthLimitError, Limited};
 use hyper::body::Bytes;
-use hyper::header::{ACCEPT, CONTENT_TYPE};
+use hyper::header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE};
 use hyper::http::HeaderValue;
 use hyper::{Method, StatusCode, Uri};
-use jsonrpsee_core::BoxError;
+use jsonrpsee_core::{BoxError, TEN_MB_SIZE_BYTES};
+use jsonrpsee_types::error::{
+	INVALID_PARAMS_CODE, INVALID_REQUEST_CODE, METHOD_NOT_FOUND_CODE, METHOD_TIMEOUT_CODE, OVERSIZED_REQUEST_CODE,
+	PARSE_ERROR_CODE, RATE_LIMITED_CODE, SERVER_IS_BUSY_CODE, UNAUTHORIZED_CODE,
+};
 use jsonrpsee_types::{ErrorCode, ErrorObject, Id, Request};
-use std::collections::HashMap;
+use route_recognizer::Router;
+use serde_json::value::RawValue;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::pin::Pin;
 use std::str::FromStr;
@@ -44,7 +51,7 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 use tower::{Layer, Service};
 
-/// Error that occur if the specified path doesn't start with `/<path>`
+/// Error that can occur when configuring the [`ProxyGetRequestLayer`].
 #[derive(Debug, thiserror::Error)]
 pub enum ProxyGetRequestError {
 	/// Duplicated path.
@@ -53,17 +60,264 @@ pub enum ProxyGetRequestError {
 	/// Invalid path.
 	#[error("ProxyGetRequestLayer path must start with `/`, got `{0}`")]
 	InvalidPath(String),
+	/// Invalid path template.
+	#[error("ProxyGetRequestLayer path placeholders must be whole segments such as `/{{name}}`, got `{0}`")]
+	InvalidTemplate(String),
 }
 
-/// Layer that applies [`ProxyGetRequest`] which proxies the `GET /path` requests to
+/// Body of the HTTP response to a proxied call.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum ProxyResponseFormat {
+	/// Only the `result` of a successful call or the `error` object of a failed call.
+	#[default]
+	Result,
+	/// The complete JSON-RPC response.
+	Envelope,
+}
+
+/// Maps an HTTP request to an RPC method call, see [`ProxyGetRequestLayer::from_routes`].
+#[derive(Debug, Clone)]
+pub struct ProxyRoute {
+	verb: Method,
+	path: String,
+	method: String,
+	positional: Option<Vec<String>>,
+	query: bool,
+	format: ProxyResponseFormat,
+}
+
+impl ProxyRoute {
+	/// Proxy the `verb` requests to `path` to the provided method.
+	///
+	/// The path may contain placeholders such as `/block/{number}` which match a whole segment.
+	pub fn new(verb: Method, path: impl Into<String>, method: impl Into<String>) -> Self {
+		Self {
+			verb,
+			path: path.into(),
+			method: method.into(),
+			positional: None,
+			query: false,
+			format: ProxyResponseFormat::Result,
+		}
+	}
+
+	/// Proxy the `GET` requests to `path` to the provided method.
+	pub fn get(path: impl Into<String>, method: impl Into<String>) -> Self {
+		Self::new(Method::GET, path, method)
+	}
+
+	/// Pass the params as an array ordered by `names` instead of an object of named params.
+	///
+	/// Params which are missing from the request are passed as `null`.
+	pub fn positional<I, N>(mut self, names: I) -> Self
+	where
+		I: IntoIterator<Item = N>,
+		N: Into<String>,
+	{
+		self.positional = Some(names.into_iter().map(Into::into).collect());
+		self
+	}
+
+	/// Collect params from the query of the request as well, the query is ignored by default.
+	pub fn query_params(mut self) -> Self {
+		self.query = true;
+		self
+	}
+
+	/// Configure the body of the HTTP response, [`ProxyResponseFormat::Result`] by default.
+	pub fn response_format(mut self, format: ProxyResponseFormat) -> Self {
+		self.format = format;
+		self
+	}
+}
+
+#[derive(Debug)]
+struct Route {
+	method: String,
+	positional: Option<Vec<String>>,
+	query: bool,
+	format: ProxyResponseFormat,
+}
+
+impl Route {
+	fn params(&self, mut params: Map<String, Value>) -> Option<Box<RawValue>> {
+		let params = match &self.positional {
+			Some(names) => Value::Array(names.iter().map(|n| params.remove(n).unwrap_or(Value::Null)).collect()),
+			None if params.is_empty() => return None,
+			None => Value::Object(params),
+		};
+
+		Some(serde_json::value::to_raw_value(&params).expect("JSON serialization infallible; qed"))
+	}
+}
+
+#[derive(Debug, Clone)]
+struct ProxyConfig {
+	// verb => path => route mapping
+	routes: HashMap<Method, Router<Arc<Route>>>,
+	error_status: HashMap<i32, StatusCode>,
+	max_body_size: u32,
+}
+
+impl ProxyConfig {
+	/// Find the route of the request and collect the params from the path and, if enabled, the query.
+	fn recognize(&self, verb: &Method, uri: &Uri) -> Option<(Arc<Route>, Map<String, Value>)> {
+		let matched = self.routes.get(verb)?.recognize(uri.path()).ok()?;
+		let route = (*matched.handler()).clone();
+		let mut params = Map::new();
+
+		for (name, value) in matched.params().iter() {
+			params.insert(name.to_owned(), param_value(percent_decode(value, false)));
+		}
+
+		let query = if route.query { uri.query().unwrap_or_default() } else { "" };
+		for pair in query.split('&').filter(|p| !p.is_empty()) {
+			let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+			params.entry(percent_decode(name, true)).or_insert_with(|| param_value(percent_decode(value, true)));
+		}
+
+		Some((route, params))
+	}
+
+	/// HTTP status of a failed call with the JSON-RPC error `code`.
+	fn status(&self, code: i32) -> StatusCode {
+		if let Some(status) = self.error_status.get(&code) {
+			return *status;
+		}
+
+		match code {
+			PARSE_ERROR_CODE | INVALID_REQUEST_CODE | INVALID_PARAMS_CODE => StatusCode::BAD_REQUEST,
+			METHOD_NOT_FOUND_CODE => StatusCode::NOT_FOUND,
+			UNAUTHORIZED_CODE => StatusCode::FORBIDDEN,
+			OVERSIZED_REQUEST_CODE => StatusCode::PAYLOAD_TOO_LARGE,
+			RATE_LIMITED_CODE => StatusCode::TOO_MANY_REQUESTS,
+			SERVER_IS_BUSY_CODE => StatusCode::SERVICE_UNAVAILABLE,
+			METHOD_TIMEOUT_CODE => StatusCode::GATEWAY_TIMEOUT,
+			_ => StatusCode::INTERNAL_SERVER_ERROR,
+		}
+	}
+
+	/// Shape the JSON-RPC response of the inner service according to `format`.
+	async fn response(&self, format: ProxyResponseFormat, res: HttpResponse) -> Result<HttpResponse, BoxError> {
+		let (mut parts, body) = res.into_parts();
+		let bytes = body.collect().await?.to_bytes();
+
+		#[derive(serde::Deserialize)]
+		struct SuccessResponse<'a> {
+			#[serde(borrow)]
+			result: &'a RawValue,
+		}
+
+		#[derive(serde::Deserialize)]
+		struct ErrorResponse<'a> {
+			#[serde(borrow)]
+			error: ErrorObject<'a>,
+		}
+
+		let (status, payload) = if let Ok(payload) = serde_json::from_slice::<SuccessResponse>(&bytes) {
+			(parts.status, payload.result.to_string())
+		} else if let Ok(payload) = serde_json::from_slice::<ErrorResponse>(&bytes) {
+			// Keep the status set by the server, such as `429 Too Many Requests` for rate limited calls.
+			let status = if parts.status == StatusCode::OK { self.status(payload.error.code()) } else { parts.status };
+			(status, serde_json::to_string(&payload.error).expect("JSON serialization infallible; qed"))
+		} else if parts.status != StatusCode::OK {
+			// The request was rejected before the method was called.
+			return Ok(HttpResponse::from_parts(parts, HttpBody::from(bytes.to_vec())));
+		} else {
+			let mut response = http::response::error_response(ErrorObject::from(ErrorCode::InternalError));
+			response.extensions_mut().extend(parts.extensions);
+			return Ok(response);
+		};
+
+		let body = match format {
+			ProxyResponseFormat::Result => HttpBody::from(payload),
+			ProxyResponseFormat::Envelope => HttpBody::from(bytes.to_vec()),
+		};
+
+		parts.status = status;
+		parts.headers.remove(CONTENT_LENGTH);
+		parts.headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json; charset=utf-8"));
+
+		Ok(HttpResponse::from_parts(parts, body))
+	}
+}
+
+/// Numbers and booleans are passed as such, any other value as a string.
+///
+/// Numbers which can't be represented exactly, such as integers beyond `u64`, are passed as strings as well.
+fn param_value(value: String) -> Value {
+	match serde_json::from_str::<Value>(&value) {
+		Ok(v @ Value::Bool(_)) => v,
+		Ok(Value::Number(n)) if n.to_string() == value => Value::Number(n),
+		_ => Value::String(value),
+	}
+}
+
+fn percent_decode(s: &str, plus_as_space: bool) -> String {
+	let bytes = s.as_bytes();
+	let mut decoded = Vec::with_capacity(bytes.len());
+	let mut i = 0;
+
+	while i < bytes.len() {
+		match bytes[i] {
+			b'%' => {
+				let hex = s.get(i + 1..i + 3).filter(|h| h.bytes().all(|b| b.is_ascii_hexdigit()));
+				match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+					Some(b) => {
+						decoded.push(b);
+						i += 3;
+						continue;
+					}
+					None => decoded.push(b'%'),
+				}
+			}
+			b'+' if plus_as_space => decoded.push(b' '),
+			b => decoded.push(b),
+		}
+		i += 1;
+	}
+
+	String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Convert the `{name}` placeholders of `path` to the syntax of the router and
+/// return the path with anonymous placeholders to detect duplicated routes.
+fn parse_template(path: &str) -> Result<(String, String), ProxyGetRequestError> {
+	if !path.starts_with('/') {
+		return Err(ProxyGetRequestError::InvalidPath(path.to_owned()));
+	}
+
+	let mut route = String::with_capacity(path.len());
+	let mut anonymous = String::with_capacity(path.len());
+
+	for segment in path.split('/').skip(1) {
+		route.push('/');
+		anonymous.push('/');
+
+		if let Some(name) = segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+			if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+				return Err(ProxyGetRequestError::InvalidTemplate(path.to_owned()));
+			}
+			route.push(':');
+			route.push_str(name);
+			anonymous.push_str("{}");
+		} else if segment.contains(['{', '}', ':', '*']) {
+			return Err(ProxyGetRequestError::InvalidTemplate(path.to_owned()));
+		} else {
+			route.push_str(segment);
+			anonymous.push_str(segment);
+		}
+	}
+
+	Ok((route, anonymous))
+}
+
+/// Layer that applies [`ProxyGetRequest`] which proxies HTTP requests to
 /// specific RPC method calls and that strips the response.
 ///
 /// See [`ProxyGetRequest`] for more details.
 #[derive(Debug, Clone)]
-pub struct ProxyGetRequestLayer {
-	// path => method mapping
-	methods: Arc<HashMap<String, String>>,
-}
+pub struct ProxyGetRequestLayer(Arc<ProxyConfig>);
 
 impl ProxyGetRequestLayer {
 	/// Creates a new [`ProxyGetRequestLayer`].
@@ -75,22 +329,63 @@ impl ProxyGetRequestLayer {
 		P: Into<String>,
 		M: Into<String>,
 	{
-		let mut methods = HashMap::new();
+		Self::from_routes(pairs.into_iter().map(|(path, method)| ProxyRoute::get(path, method)))
+	}
 
-		for (path, method) in pairs {
-			let path = path.into();
-			let method = method.into();
+	/// Creates a new [`ProxyGetRequestLayer`] from routes with path templates and arbitrary HTTP verbs.
+	///
+	/// Fails if a path does not start with `/`, contains invalid placeholders or
+	/// is configured twice for the same verb.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use jsonrpsee_server::middleware::http::{ProxyGetRequestLayer, ProxyResponseFormat, ProxyRoute};
+	///
+	/// let proxy = ProxyGetRequestLayer::from_routes([
+	///     // `GET /block/42?full=true` calls `chain_getBlock` with the params `[42, true]`.
+	///     ProxyRoute::get("/block/{number}", "chain_getBlock").positional(["number", "full"]).query_params(),
+	///     // `POST /accounts/alice` with the body `{"balance":1}` calls `set_account`
+	///     // with the params `{"name":"alice","balance":1}`.
+	///     ProxyRoute::new(http::Method::POST, "/accounts/{name}", "set_account")
+	///         .response_format(ProxyResponseFormat::Envelope),
+	/// ])
+	/// .unwrap();
+	/// ```
+	pub fn from_routes(routes: impl IntoIterator<Item = ProxyRoute>) -> Result<Self, ProxyGetRequestError> {
+		let mut routers: HashMap<Method, Router<Arc<Route>>> = HashMap::new();
+		let mut unique = HashSet::new();
 
-			if !path.starts_with('/') {
-				return Err(ProxyGetRequestError::InvalidPath(path));
-			}
+		for ProxyRoute { verb, path, method, positional, query, format } in routes {
+			let (template, anonymous) = parse_template(&path)?;
 
-			if let Some(path) = methods.insert(path, method) {
+			if !unique.insert((verb.clone(), anonymous)) {
 				return Err(ProxyGetRequestError::DuplicatedPath(path));
 			}
+
+			routers.entry(verb).or_default().add(&template, Arc::new(Route { method, positional, query, format }));
 		}
 
-		Ok(Self { methods: Arc::new(methods) })
+		Ok(Self(Arc::new(ProxyConfig {
+			routes: routers,
+			error_status: HashMap::new(),
+			max_body_size: TEN_MB_SIZE_BYTES,
+		})))
+	}
+
+	/// Respond with `status` to calls that failed with the JSON-RPC error `code`.
+	///
+	/// By default, the standard JSON-RPC errors and the errors of the server
+	/// are mapped to the closest HTTP status and any other error to `500 Internal Server Error`.
+	pub fn error_status(mut self, code: i32, status: StatusCode) -> Self {
+		Arc::make_mut(&mut self.0).error_status.insert(code, status);
+		self
+	}
+
+	/// Set the maximum size of the body of requests that are not `GET`, 10 MB by default.
+	pub fn max_body_size(mut self, size: u32) -> Self {
+		Arc::make_mut(&mut self.0).max_body_size = size;
+		self
 	}
 }
 
@@ -98,32 +393,37 @@ impl<S> Layer<S> for ProxyGetRequestLayer {
 	type Service = ProxyGetRequest<S>;
 
 	fn layer(&self, inner: S) -> Self::Service {
-		ProxyGetRequest { inner, methods: self.methods.clone() }
+		ProxyGetRequest { inner, config: self.0.clone() }
 	}
 }
 
-/// Proxy `GET /path` requests to the specified RPC method calls.
+/// Proxy HTTP requests to the specified RPC method calls.
 ///
 /// # Request
 ///
-/// The `GET /path` requests are modified into valid `POST` requests for
+/// The requests matching a route are modified into valid `POST` requests for
 /// calling the RPC method. This middleware adds appropriate headers to the
 /// request, and completely modifies the request `BODY`.
 ///
+/// The params of the call are collected from the placeholders of the path, the query if enabled
+/// with [`ProxyRoute::query_params`] and, for verbs other than `GET`, the members of the JSON object
+/// in the body. A body without a JSON `Content-Type` is rejected with `415 Unsupported Media Type`.
+/// Numbers and booleans in the path and query are passed as such, anything else as strings.
+///
 /// # Response
 ///
-/// The response of the RPC method is stripped down to contain only the method's
+/// By default, the response of the RPC method is stripped down to contain only the method's
 /// response, removing any RPC 2.0 spec logic regarding the response' body.
+/// Failed calls are responded with the error object and an HTTP status matching the error code.
 #[derive(Debug, Clone)]
 pub struct ProxyGetRequest<S> {
 	inner: S,
-	// path => method mapping
-	methods: Arc<HashMap<String, String>>,
+	config: Arc<ProxyConfig>,
 }
 
 impl<S, B> Service<HttpRequest<B>> for ProxyGetRequest<S>
 where
-	S: Service<HttpRequest, Response = HttpResponse>,
+	S: Service<HttpRequest, Response = HttpResponse> + Clone + Send + 'static,
 	S::Response: 'static,
 	S::Error: Into<BoxError> + 'static,
 	S::Future: Send + 'static,
@@ -141,85 +441,152 @@ where
 	}
 
 	fn call(&mut self, mut req: HttpRequest<B>) -> Self::Future {
-		let path = req.uri().path();
-		let method = self.methods.get(path);
-
-		match (method, req.method()) {
-			// Proxy the `GET /path` request to the appropriate method call.
-			(Some(method), &Method::GET) => {
-				// RPC methods are accessed with `POST`.
-				*req.method_mut() = Method::POST;
-				// Precautionary remove the URI path.
-				*req.uri_mut() = if let Some(query) = req.uri().query() {
-					Uri::from_str(&format!("/?{}", query)).expect("The query comes from a valid URI; qed")
-				} else {
-					Uri::from_static("/")
-				};
-				// Requests must have the following headers:
-				req.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-				req.headers_mut().insert(ACCEPT, HeaderValue::from_static("application/json"));
+		let Some((route, mut params)) = self.config.recognize(req.method(), req.uri()) else {
+			// Call the inner service and get a future that resolves to the response.
+			let req = req.map(HttpBody::new);
+			return self.inner.call(req).map_err(Into::into).boxed();
+		};
 
-				// Adjust the body to reflect the method call.
-				let bytes =
-					serde_json::to_vec(&Request::borrowed(method, None, Id::Number(0))).expect("Valid request; qed");
-				let req = req.map(|_| HttpBody::from(bytes));
+		let read_body = req.method() != Method::GET;
+		// The body is only parsed if it's declared as JSON, which browsers don't send cross-origin without a preflight.
+		let body_is_json = http::content_type_is_json(&req);
 
-				// Call the inner service and get a future that resolves to the response.
-				let fut = self.inner.call(req);
+		// RPC methods are accessed with `POST`.
+		*req.method_mut() = Method::POST;
+		// Precautionary remove the URI path.
+		*req.uri_mut() = if let Some(query) = req.uri().query() {
+			Uri::from_str(&format!("/?{}", query)).expect("The query comes from a valid URI; qed")
+		} else {
+			Uri::from_static("/")
+		};
+		// Requests must have the following headers:
+		req.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+		req.headers_mut().insert(ACCEPT, HeaderValue::from_static("application/json"));
+		req.headers_mut().remove(CONTENT_LENGTH);
 
-				async move {
-					let res = fut.await.map_err(Into::into)?;
+		let config = self.config.clone();
+		// The service that was driven to readiness is used for the call.
+		let clone = self.inner.clone();
+		let mut inner = std::mem::replace(&mut self.inner, clone);
 
-					let (parts, body) = res.into_parts();
-					let mut body = http_body_util::BodyStream::new(body);
-					let mut bytes = Vec::new();
+		async move {
+			let (parts, body) = req.into_parts();
 
-					while let Some(frame) = body.frame().await {
-						let data = frame?.into_data().map_err(|e| format!("{e:?}"))?;
-						bytes.extend(data);
-					}
+			if read_body {
+				let bytes = match Limited::new(body, config.max_body_size as usize).collect().await {
+					Ok(body) => body.to_bytes(),
+					Err(e) if e.is::<LengthLimitError>() => return Ok(http::response::too_large(config.max_body_size)),
+					Err(e) => return Err(e),
+				};
 
-					#[derive(serde::Deserialize)]
-					struct SuccessResponse<'a> {
-						#[serde(borrow)]
-						result: &'a serde_json::value::RawValue,
+				if !bytes.is_empty() {
+					if !body_is_json {
+						return Ok(http::response::unsupported_content_type());
 					}
 
-					let mut response = if let Ok(payload) = serde_json::from_slice::<SuccessResponse>(&bytes) {
-						http::response::ok_response(payload.result.to_string())
-					} else {
-						internal_proxy_error(&bytes)
+					let Ok(members) = serde_json::from_slice::<Map<String, Value>>(&bytes) else {
+						return Ok(http::response::malformed());
 					};
 
-					response.extensions_mut().extend(parts.extensions);
-
-					Ok(response)
+					// The params of the path and the query take precedence.
+					for (name, value) in members {
+						params.entry(name).or_insert(value);
+					}
 				}
-				.boxed()
-			}
-			// Call the inner service and get a future that resolves to the response.
-			_ => {
-				let req = req.map(HttpBody::new);
-				self.inner.call(req).map_err(Into::into).boxed()
 			}
+
+			// Adjust the body to reflect the method call.
+			let params = route.params(params);
+			let bytes = serde_json::to_vec(&Request::borrowed(&route.method, params.as_deref(), Id::Number(0)))
+				.expect("Valid request; qed");
+			let req = HttpRequest::from_parts(parts, HttpBody::from(bytes));
+
+			let res = inner.call(req).await.map_err(Into::into)?;
+			config.response(route.format, res).await
 		}
+		.boxed()
 	}
 }
 
-fn internal_proxy_error(bytes: &[u8]) -> HttpResponse {
-	#[derive(serde::Deserialize)]
-	struct ErrorResponse<'a> {
-		#[serde(borrow)]
-		error: ErrorObject<'a>,
+#[cfg(test)]
+mod tests {
+	use super::{ProxyGetRequestError, ProxyGetRequestLayer, ProxyRoute, parse_template, percent_decode};
+	use hyper::{Method, StatusCode, Uri};
+	use serde_json::json;
+
+	fn recognize(layer: &ProxyGetRequestLayer, verb: Method, uri: &'static str) -> Option<(String, serde_json::Value)> {
+		let (route, params) = layer.0.recognize(&verb, &Uri::from_static(uri))?;
+		let params = route.params(params).map_or(serde_json::Value::Null, |p| serde_json::from_str(p.get()).unwrap());
+		Some((route.method.clone(), params))
+	}
+
+	#[test]
+	fn templates_are_validated() {
+		assert_eq!(parse_template("/block/{number}").unwrap(), ("/block/:number".into(), "/block/{}".into()));
+		assert!(matches!(parse_template("block"), Err(ProxyGetRequestError::InvalidPath(_))));
+		assert!(matches!(parse_template("/block/{number"), Err(ProxyGetRequestError::InvalidTemplate(_))));
+		assert!(matches!(parse_template("/block/{}"), Err(ProxyGetRequestError::InvalidTemplate(_))));
+		assert!(matches!(parse_template("/block/n{number}"), Err(ProxyGetRequestError::InvalidTemplate(_))));
+		assert!(matches!(parse_template("/block/:number"), Err(ProxyGetRequestError::InvalidTemplate(_))));
+	}
+
+	#[test]
+	fn duplicated_routes_are_rejected() {
+		let routes = [ProxyRoute::get("/block/{number}", "a"), ProxyRoute::get("/block/{hash}", "b")];
+		assert!(matches!(ProxyGetRequestLayer::from_routes(routes), Err(ProxyGetRequestError::DuplicatedPath(_))));
+
+		let routes = [ProxyRoute::get("/block/{number}", "a"), ProxyRoute::new(Method::PUT, "/block/{number}", "b")];
+		assert!(ProxyGetRequestLayer::from_routes(routes).is_ok());
 	}
 
-	let error = serde_json::from_slice::<ErrorResponse>(bytes)
-		.map(|payload| payload.error)
-		.unwrap_or_else(|_| ErrorObject::from(ErrorCode::InternalError));
+	#[test]
+	fn params_are_collected_from_path_and_query() {
+		let layer = ProxyGetRequestLayer::from_routes([
+			ProxyRoute::get("/health", "system_health"),
+			ProxyRoute::get("/block/{number}", "chain_getBlock").positional(["number", "full"]).query_params(),
+			ProxyRoute::new(Method::DELETE, "/accounts/{name}", "remove_account").query_params(),
+		])
+		.unwrap();
+
+		assert_eq!(recognize(&layer, Method::GET, "/health"), Some(("system_health".into(), json!(null))));
+		assert_eq!(recognize(&layer, Method::GET, "/health?verbose=true"), Some(("system_health".into(), json!(null))));
+		assert_eq!(recognize(&layer, Method::POST, "/health"), None);
+		assert_eq!(recognize(&layer, Method::GET, "/block"), None);
+		assert_eq!(recognize(&layer, Method::GET, "/block/42"), Some(("chain_getBlock".into(), json!([42, null]))));
+		assert_eq!(
+			recognize(&layer, Method::GET, "/block/0x2a?full=true&number=1"),
+			Some(("chain_getBlock".into(), json!(["0x2a", true])))
+		);
+		assert_eq!(
+			recognize(&layer, Method::GET, "/block/18446744073709551617?full=1e3"),
+			Some(("chain_getBlock".into(), json!(["18446744073709551617", "1e3"])))
+		);
+		assert_eq!(
+			recognize(&layer, Method::GET, "/block/-7?full=1.5"),
+			Some(("chain_getBlock".into(), json!([-7, 1.5])))
+		);
+		assert_eq!(
+			recognize(&layer, Method::DELETE, "/accounts/j%C3%B6rg?reason=no+longer+used&force"),
+			Some(("remove_account".into(), json!({ "name": "jörg", "reason": "no longer used", "force": "" })))
+		);
+	}
 
-	http::response::from_template(
-		StatusCode::INTERNAL_SERVER_ERROR,
-		serde_json::to_string(&error).expect("JSON serialization infallible; qed"),
-		"application/json; charset=utf-8",
-	)
+	#[test]
+	fn percent_decoding_works() {
+		assert_eq!(percent_decode("a%20b+c", false), "a b+c");
+		assert_eq!(percent_decode("a%20b+c", true), "a b c");
+		assert_eq!(percent_decode("100%", false), "100%");
+		assert_eq!(percent_decode("%+1", false), "%+1");
+	}
+
+	#[test]
+	fn error_codes_are_mapped_to_status() {
+		let layer =
+			ProxyGetRequestLayer::new([("/health", "system_health")]).unwrap().error_status(-1, StatusCode::CONFLICT);
+
+		assert_eq!(layer.0.status(-32601), StatusCode::NOT_FOUND);
+		assert_eq!(layer.0.status(-32602), StatusCode::BAD_REQUEST);
+		assert_eq!(layer.0.status(-32000), StatusCode::INTERNAL_SERVER_ERROR);
+		assert_eq!(layer.0.status(-1), StatusCode::CONFLICT);
+	}
 }
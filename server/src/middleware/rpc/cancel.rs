@@ -0,0 +1,137 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Cancellation of pending method calls.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use jsonrpsee_core::server::MethodResponse;
+use jsonrpsee_types::error::{REQUEST_CANCELLED_CODE, REQUEST_CANCELLED_MSG};
+use jsonrpsee_types::{ErrorObject, Id, Params};
+use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
+
+/// Name of the notification which cancels a pending method call on the same connection.
+///
+/// The ID of the call to cancel is passed either as `{"id": <id>}` or `[<id>]`
+/// and the cancelled call is answered with [`REQUEST_CANCELLED_CODE`].
+pub const CANCEL_REQUEST_METHOD: &str = "$/cancelRequest";
+
+/// Pending async method calls of a connection which can be cancelled.
+///
+/// It's inserted in the extensions of each call by transports that keep the
+/// connection open such as WebSocket.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PendingCalls {
+	connection: CancellationToken,
+	calls: Arc<Mutex<HashMap<Id<'static>, (u64, CancellationToken)>>>,
+	next_call: Arc<AtomicU64>,
+}
+
+impl PendingCalls {
+	/// Cancel all pending calls, used when the connection has been closed.
+	pub(crate) fn cancel_all(&self) {
+		self.connection.cancel();
+	}
+
+	/// Cancel the pending call with `id`.
+	pub(crate) fn cancel(&self, id: &Id) {
+		if let Some((_, token)) = self.calls.lock().expect("lock poisoned; qed").get(id) {
+			token.cancel();
+		}
+	}
+
+	/// Cancel the pending call in the params of a [`CANCEL_REQUEST_METHOD`] notification.
+	pub(crate) fn cancel_from_params(&self, params: Params) {
+		#[derive(Deserialize)]
+		struct CancelParams<'a> {
+			#[serde(borrow)]
+			id: Id<'a>,
+		}
+
+		let id = match params.parse::<CancelParams>() {
+			Ok(p) => p.id,
+			Err(_) => match params.one::<Id>() {
+				Ok(id) => id,
+				Err(e) => {
+					tracing::debug!(target: crate::LOG_TARGET, "Invalid params for {}: {}", CANCEL_REQUEST_METHOD, e);
+					return;
+				}
+			},
+		};
+
+		self.cancel(&id);
+	}
+
+	/// Register a new call that is cancelled with the connection or by its `id`.
+	pub(crate) fn register(&self, id: Id<'static>) -> PendingCall {
+		let seq = self.next_call.fetch_add(1, Ordering::Relaxed);
+		let token = self.connection.child_token();
+		self.calls.lock().expect("lock poisoned; qed").insert(id.clone(), (seq, token.clone()));
+
+		PendingCall { id, seq, token, calls: self.calls.clone() }
+	}
+}
+
+/// A registered call which is removed from [`PendingCalls`] when dropped.
+#[derive(Debug)]
+pub(crate) struct PendingCall {
+	id: Id<'static>,
+	seq: u64,
+	token: CancellationToken,
+	calls: Arc<Mutex<HashMap<Id<'static>, (u64, CancellationToken)>>>,
+}
+
+impl PendingCall {
+	/// Get the cancellation token of the call.
+	pub(crate) fn token(&self) -> CancellationToken {
+		self.token.clone()
+	}
+
+	/// Run the call until it completes or is cancelled.
+	pub(crate) async fn run(self, call: impl Future<Output = MethodResponse>) -> MethodResponse {
+		tokio::select! {
+			rp = call => rp,
+			_ = self.token.cancelled() => {
+				let err = ErrorObject::borrowed(REQUEST_CANCELLED_CODE, REQUEST_CANCELLED_MSG, None);
+				MethodResponse::error(self.id.clone(), err)
+			}
+		}
+	}
+}
+
+impl Drop for PendingCall {
+	fn drop(&mut self) {
+		let mut calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+		// Another call with the same ID may have been registered after this one.
+		if calls.get(&self.id).is_some_and(|(seq, _)| *seq == self.seq) {
+			calls.remove(&self.id);
+		}
+	}
+}
@@ -0,0 +1,378 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! JSON-RPC service middleware.
+
+pub use jsonrpsee_core::middleware::*;
+pub use jsonrpsee_core::server::MethodResponse;
+
+/// Cancellation of pending method calls.
+mod cancel;
+/// Token bucket rate limiting middleware.
+mod rate_limit;
+
+pub use cancel::CANCEL_REQUEST_METHOD;
+pub(crate) use cancel::PendingCalls;
+pub use rate_limit::*;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{BatchExecutionConfig, ConnectionId, Principal};
+use futures_util::FutureExt;
+use futures_util::future::Either;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use jsonrpsee_core::server::{
+	BatchResponseBuilder, BoundedSubscriptions, MethodCallback, MethodSink, Methods, SubscriptionState,
+};
+use jsonrpsee_core::traits::IdProvider;
+use jsonrpsee_types::error::{
+	ErrorCode, reject_batch_budget_exceeded, reject_method_timeout, reject_too_many_subscriptions, reject_unauthorized,
+};
+use jsonrpsee_types::{ErrorObject, ErrorObjectOwned, Id};
+
+/// JSON-RPC service middleware.
+#[derive(Clone, Debug)]
+pub struct RpcService {
+	conn_id: ConnectionId,
+	methods: Methods,
+	max_response_body_size: usize,
+	method_timeout: Option<Duration>,
+	batch_config: BatchExecutionConfig,
+	cfg: RpcServiceCfg,
+}
+
+/// Configuration of the RpcService.
+#[derive(Clone, Debug)]
+pub(crate) enum RpcServiceCfg {
+	/// The server supports only calls.
+	OnlyCalls,
+	/// The server supports both method calls and subscriptions.
+	CallsAndSubscriptions {
+		bounded_subscriptions: BoundedSubscriptions,
+		sink: MethodSink,
+		id_provider: Arc<dyn IdProvider>,
+		_pending_calls: tokio::sync::mpsc::Sender<()>,
+	},
+}
+
+impl RpcService {
+	/// Create a new service.
+	pub(crate) fn new(
+		methods: Methods,
+		max_response_body_size: usize,
+		method_timeout: Option<Duration>,
+		batch_config: BatchExecutionConfig,
+		conn_id: ConnectionId,
+		cfg: RpcServiceCfg,
+	) -> Self {
+		Self { methods, max_response_body_size, method_timeout, batch_config, conn_id, cfg }
+	}
+}
+
+impl RpcServiceT for RpcService {
+	type BatchResponse = MethodResponse;
+	type MethodResponse = MethodResponse;
+	type NotificationResponse = MethodResponse;
+
+	fn call<'a>(&self, req: Request<'a>) -> impl Future<Output = Self::MethodResponse> + Send + 'a {
+		let conn_id = self.conn_id;
+		let max_response_body_size = self.max_response_body_size;
+
+		let Request { id, method, params, extensions, .. } = req;
+		let params = jsonrpsee_types::Params::new(params.as_ref().map(|p| serde_json::value::RawValue::get(p)));
+
+		if let Some(roles) = self.methods.required_roles(&method) {
+			if let Err(err) = authorize(roles, extensions.get::<Principal>()) {
+				return ResponseFuture::ready(MethodResponse::error(id, err).with_extensions(extensions));
+			}
+		}
+
+		match self.methods.method_with_name(&method) {
+			None => {
+				let rp =
+					MethodResponse::error(id, ErrorObject::from(ErrorCode::MethodNotFound)).with_extensions(extensions);
+				ResponseFuture::ready(rp)
+			}
+			Some((name, method)) => match method {
+				MethodCallback::Async(callback) => {
+					let params = params.into_owned();
+					let id = id.into_owned();
+					let timeout = self.methods.method_timeout(name).or(self.method_timeout);
+
+					// The call may be cancelled if the transport supports it.
+					let pending_call = extensions.get::<PendingCalls>().map(|calls| calls.register(id.clone()));
+					let mut extensions = extensions;
+					if let Some(call) = &pending_call {
+						extensions.insert(call.token());
+					}
+
+					// The response of a call which times out has the extensions of the call as well.
+					let timeout = timeout.map(|timeout| (timeout, extensions.clone()));
+
+					let fut = (callback)(id.clone(), params, conn_id, max_response_body_size, extensions);
+					let fut = match pending_call {
+						Some(call) => call.run(fut).boxed(),
+						None => fut,
+					};
+
+					match timeout {
+						Some((timeout, extensions)) => {
+							ResponseFuture::future(run_with_timeout(id, timeout, extensions, fut).boxed())
+						}
+						None => ResponseFuture::future(fut),
+					}
+				}
+				MethodCallback::Sync(callback) => {
+					let rp = (callback)(id, params, max_response_body_size, extensions);
+					ResponseFuture::ready(rp)
+				}
+				MethodCallback::Subscription(callback) => {
+					let RpcServiceCfg::CallsAndSubscriptions {
+						bounded_subscriptions,
+						sink,
+						id_provider,
+						_pending_calls,
+					} = self.cfg.clone()
+					else {
+						tracing::warn!("Subscriptions not supported");
+						let rp = MethodResponse::error(id, ErrorObject::from(ErrorCode::InternalError))
+							.with_extensions(extensions);
+						return ResponseFuture::ready(rp);
+					};
+
+					if let Some(p) = bounded_subscriptions.acquire() {
+						let conn_state =
+							SubscriptionState { conn_id, id_provider: &*id_provider.clone(), subscription_permit: p };
+
+						let fut = (callback)(id.clone(), params, sink, conn_state, extensions);
+						ResponseFuture::future(fut)
+					} else {
+						let max = bounded_subscriptions.max();
+						let rp =
+							MethodResponse::error(id, reject_too_many_subscriptions(max)).with_extensions(extensions);
+						ResponseFuture::ready(rp)
+					}
+				}
+				MethodCallback::Unsubscription(callback) => {
+					// Don't adhere to any resource or subscription limits; always let unsubscribing happen!
+
+					let RpcServiceCfg::CallsAndSubscriptions { .. } = self.cfg else {
+						tracing::warn!("Subscriptions not supported");
+						let rp = MethodResponse::error(id, ErrorObject::from(ErrorCode::InternalError))
+							.with_extensions(extensions);
+						return ResponseFuture::ready(rp);
+					};
+
+					let rp = callback(id, params, conn_id, max_response_body_size, extensions);
+					ResponseFuture::ready(rp)
+				}
+			},
+		}
+	}
+
+	fn batch<'a>(&self, batch: Batch<'a>) -> impl Future<Output = Self::BatchResponse> + Send + 'a {
+		let mut batch_rp = BatchResponseBuilder::new_with_limit(self.max_response_body_size);
+		let service = self.clone();
+		async move {
+			let cfg = &service.batch_config;
+			let mut got_notification = false;
+			let mut spent_cost: u64 = 0;
+			let mut within_budget = true;
+			// The response of each call, or its ID until the call has been executed.
+			let mut responses = Vec::with_capacity(batch.len());
+			// The entries to execute, in the order of the batch, with the index of the response of calls.
+			let mut entries = Vec::new();
+
+			for batch_entry in batch.into_iter() {
+				let entry = match batch_entry {
+					Ok(entry) => entry,
+					Err(err) => {
+						let (err, id) = err.into_parts();
+						responses.push(Ok(MethodResponse::error(id, err)));
+						continue;
+					}
+				};
+
+				// Once the budget is exceeded, none of the following entries are executed.
+				within_budget = within_budget
+					&& match &cfg.cost {
+						Some((max_cost, cost)) => {
+							match spent_cost.checked_add(cost(&entry)).filter(|total| total <= max_cost) {
+								Some(total) => {
+									spent_cost = total;
+									true
+								}
+								None => false,
+							}
+						}
+						None => true,
+					};
+
+				match entry {
+					BatchEntry::Call(req) if !within_budget => {
+						let rp = MethodResponse::error(req.id, reject_batch_budget_exceeded("Cost limit reached"));
+						responses.push(Ok(rp));
+					}
+					BatchEntry::Call(req) => {
+						responses.push(Err(req.id.clone().into_owned()));
+						entries.push((Some(responses.len() - 1), BatchEntry::Call(req)));
+					}
+					BatchEntry::Notification(n) => {
+						got_notification = true;
+						if within_budget {
+							entries.push((None, BatchEntry::Notification(n)));
+						}
+					}
+				}
+			}
+
+			let exhausted = service.run_batch_entries(entries, &mut responses).await;
+
+			for rp in responses {
+				let rp = rp.unwrap_or_else(|id| {
+					let reason = exhausted.unwrap_or("Batch aborted");
+					MethodResponse::error(id, reject_batch_budget_exceeded(reason))
+				});
+
+				if let Err(err) = batch_rp.append(rp) {
+					return err;
+				}
+			}
+
+			// If the batch is empty and we got a notification, we return an empty response.
+			if batch_rp.is_empty() && got_notification {
+				MethodResponse::notification()
+			}
+			// An empty batch is regarded as an invalid request here.
+			else {
+				MethodResponse::from_batch(batch_rp.finish())
+			}
+		}
+	}
+
+	fn notification<'a>(&self, n: Notification<'a>) -> impl Future<Output = Self::BatchResponse> + Send + 'a {
+		if n.method == CANCEL_REQUEST_METHOD {
+			if let Some(pending_calls) = n.extensions.get::<PendingCalls>() {
+				let params = n.params.as_ref().map(|p| serde_json::value::RawValue::get(p));
+				pending_calls.cancel_from_params(jsonrpsee_types::Params::new(params));
+			}
+		}
+
+		// The notification should not be replied to with a response
+		// but we propogate the extensions to the response which can be useful
+		// for example HTTP transport to set the headers.
+		async move { MethodResponse::notification().with_extensions(n.extensions) }
+	}
+}
+
+impl RpcService {
+	/// Execute the calls and notifications of a batch according to the [`BatchExecutionConfig`]
+	/// and store the responses at the index of the call.
+	///
+	/// Returns the reason why the budget of the batch was exhausted, if it was.
+	async fn run_batch_entries<'a>(
+		&self,
+		entries: Vec<(Option<usize>, BatchEntry<'a>)>,
+		responses: &mut [Result<MethodResponse, Id<'static>>],
+	) -> Option<&'static str> {
+		let cfg = &self.batch_config;
+		let mut entries = entries.into_iter();
+		let mut running = FuturesUnordered::new();
+		let mut response_bytes = 0;
+
+		let deadline = match cfg.timeout {
+			Some(timeout) => Either::Left(tokio::time::sleep(timeout)),
+			None => Either::Right(std::future::pending()),
+		};
+		tokio::pin!(deadline);
+
+		loop {
+			while running.len() < cfg.max_parallel {
+				let Some((idx, entry)) = entries.next() else { break };
+				let fut = match entry {
+					BatchEntry::Call(req) => Either::Left(self.call(req)),
+					BatchEntry::Notification(n) => Either::Right(self.notification(n)),
+				};
+				running.push(fut.map(move |rp| (idx, rp)));
+			}
+
+			let (idx, rp) = tokio::select! {
+				next = running.next() => match next {
+					Some(next) => next,
+					None => return None,
+				},
+				_ = &mut deadline => return Some("Timeout reached"),
+			};
+
+			// Notifications are not replied to.
+			let Some(idx) = idx else { continue };
+			response_bytes += rp.as_json().get().len();
+			responses[idx] = Ok(rp);
+
+			// The batch response is rejected anyway once it's bigger than the maximum response size.
+			let too_big = response_bytes > self.max_response_body_size;
+			if too_big || cfg.max_response_bytes.is_some_and(|max| response_bytes > max) {
+				// Complete the calls that are already running but don't start new ones.
+				while let Some((idx, rp)) = tokio::select! {
+					next = running.next() => next,
+					_ = &mut deadline => return Some("Timeout reached"),
+				} {
+					if let Some(idx) = idx {
+						responses[idx] = Ok(rp);
+					}
+				}
+
+				return Some("Response size limit reached");
+			}
+		}
+	}
+}
+
+/// Check that the principal has all of the required roles.
+fn authorize(roles: &[&str], principal: Option<&Principal>) -> Result<(), ErrorObjectOwned> {
+	let Some(principal) = principal else {
+		return Err(reject_unauthorized("Authentication required"));
+	};
+
+	match roles.iter().find(|role| !principal.has_role(role)) {
+		Some(role) => Err(reject_unauthorized(format!("Missing role `{role}`"))),
+		None => Ok(()),
+	}
+}
+
+/// Run the method call until it completes or the `timeout` elapses.
+async fn run_with_timeout(
+	id: Id<'static>,
+	timeout: Duration,
+	extensions: Extensions,
+	call: impl Future<Output = MethodResponse>,
+) -> MethodResponse {
+	match tokio::time::timeout(timeout, call).await {
+		Ok(rp) => rp,
+		Err(_) => MethodResponse::error(id, reject_method_timeout(timeout)).with_extensions(extensions),
+	}
+}
@@ -0,0 +1,426 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Token bucket rate limiting middleware.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::ConnectionId;
+use jsonrpsee_core::middleware::{Batch, BatchEntry, BatchEntryErr, Notification, ResponseFuture, RpcServiceT};
+use jsonrpsee_core::server::MethodResponse;
+use jsonrpsee_types::Request;
+use jsonrpsee_types::error::reject_rate_limited;
+
+/// Buckets which are full are removed after this many rate limit checks.
+const PRUNE_INTERVAL: u64 = 1024;
+
+/// Marker inserted in the extensions of a [`MethodResponse`] that was rejected
+/// by the [`RateLimit`] middleware.
+///
+/// For HTTP such responses are sent with the status code `429 Too Many Requests`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RateLimited {
+	/// Time until the call would be allowed.
+	pub retry_after: Duration,
+}
+
+/// Quota of a token bucket.
+///
+/// The bucket holds up to `burst` tokens and is refilled at a rate of
+/// `burst` tokens per `period`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quota {
+	burst: u32,
+	period: Duration,
+}
+
+impl Quota {
+	/// Create a new quota of `burst` tokens per `period`.
+	///
+	/// # Panics
+	///
+	/// Panics if `burst` or `period` is zero.
+	pub fn new(burst: u32, period: Duration) -> Self {
+		assert!(burst > 0, "burst must be greater than zero");
+		assert!(!period.is_zero(), "period must be greater than zero");
+		Self { burst, period }
+	}
+
+	/// Create a new quota of `burst` tokens per second.
+	pub fn per_second(burst: u32) -> Self {
+		Self::new(burst, Duration::from_secs(1))
+	}
+
+	/// Create a new quota of `burst` tokens per minute.
+	pub fn per_minute(burst: u32) -> Self {
+		Self::new(burst, Duration::from_secs(60))
+	}
+
+	fn tokens_per_sec(&self) -> f64 {
+		self.burst as f64 / self.period.as_secs_f64()
+	}
+}
+
+#[derive(Debug, Clone, Default)]
+struct RateLimitConfig {
+	per_connection: Option<Quota>,
+	per_ip: Option<Quota>,
+	per_method: HashMap<String, Quota>,
+	weights: HashMap<String, u32>,
+}
+
+/// Rate limiting middleware layer based on token buckets.
+///
+/// Each call consumes the weight of the method, by default `1`, from every bucket
+/// that applies to the call and the call is rejected with [`RATE_LIMITED_CODE`](jsonrpsee_types::error::RATE_LIMITED_CODE)
+/// if any of the buckets doesn't have enough tokens left. The following buckets are supported:
+///
+/// - [`RateLimitLayer::per_connection`] which is keyed by the [`ConnectionId`] of the call.
+/// - [`RateLimitLayer::per_ip`] which is keyed by the IP address of the peer. The server inserts the
+///   [`SocketAddr`] of the peer in the extensions of each call for TCP connections, if the
+///   low-level API is used the [`SocketAddr`] must be inserted by a HTTP middleware.
+/// - [`RateLimitLayer::per_method`] which is keyed by the [`ConnectionId`] and the method name.
+///
+/// The state of the buckets is shared by all clones of the layer and all connections.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use jsonrpsee_server::middleware::rpc::{Quota, RateLimitLayer, RpcServiceBuilder};
+/// use jsonrpsee_server::Server;
+///
+/// # async fn run() {
+/// let rate_limit = RateLimitLayer::new()
+///     .per_connection(Quota::per_second(10))
+///     .per_ip(Quota::per_second(100))
+///     .per_method("expensive_call", Quota::per_minute(10))
+///     .weight("expensive_call", 5);
+///
+/// let server = Server::builder()
+///     .set_rpc_middleware(RpcServiceBuilder::new().layer(rate_limit))
+///     .build("127.0.0.1:0")
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitLayer {
+	config: Arc<RateLimitConfig>,
+	buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimitLayer {
+	/// Create a new rate limiting layer without any limits.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Limit the calls per connection.
+	pub fn per_connection(mut self, quota: Quota) -> Self {
+		Arc::make_mut(&mut self.config).per_connection = Some(quota);
+		self
+	}
+
+	/// Limit the calls per IP address of the peer.
+	pub fn per_ip(mut self, quota: Quota) -> Self {
+		Arc::make_mut(&mut self.config).per_ip = Some(quota);
+		self
+	}
+
+	/// Limit the calls to `method` per connection.
+	pub fn per_method(mut self, method: impl Into<String>, quota: Quota) -> Self {
+		Arc::make_mut(&mut self.config).per_method.insert(method.into(), quota);
+		self
+	}
+
+	/// Set the number of tokens a call to `method` consumes, defaults to `1`.
+	pub fn weight(mut self, method: impl Into<String>, weight: u32) -> Self {
+		Arc::make_mut(&mut self.config).weights.insert(method.into(), weight);
+		self
+	}
+}
+
+impl<S> tower::Layer<S> for RateLimitLayer {
+	type Service = RateLimit<S>;
+
+	fn layer(&self, service: S) -> Self::Service {
+		RateLimit { service, config: self.config.clone(), buckets: self.buckets.clone() }
+	}
+}
+
+/// Rate limiting middleware, see [`RateLimitLayer`] for further information.
+#[derive(Debug, Clone)]
+pub struct RateLimit<S> {
+	service: S,
+	config: Arc<RateLimitConfig>,
+	buckets: Arc<Mutex<Buckets>>,
+}
+
+impl<S> RateLimit<S> {
+	/// Consume the tokens for a call to `method`.
+	///
+	/// Returns the time until the call would be allowed if it was rejected.
+	fn check(&self, method: &str, extensions: &http::Extensions) -> Result<(), Duration> {
+		let conn_id = extensions.get::<ConnectionId>().copied();
+		let ip = extensions.get::<SocketAddr>().map(SocketAddr::ip);
+		let weight = self.config.weights.get(method).copied().unwrap_or(1);
+
+		let mut buckets = self.buckets.lock().expect("lock poisoned; qed");
+		buckets.check(&self.config, method, conn_id, ip, weight, Instant::now())
+	}
+}
+
+impl<S> RpcServiceT for RateLimit<S>
+where
+	S: RpcServiceT<
+			MethodResponse = MethodResponse,
+			BatchResponse = MethodResponse,
+			NotificationResponse = MethodResponse,
+		> + Send
+		+ Sync
+		+ Clone
+		+ 'static,
+{
+	type MethodResponse = S::MethodResponse;
+	type NotificationResponse = S::NotificationResponse;
+	type BatchResponse = S::BatchResponse;
+
+	fn call<'a>(&self, req: Request<'a>) -> impl Future<Output = Self::MethodResponse> + Send + 'a {
+		match self.check(&req.method, &req.extensions) {
+			Ok(()) => ResponseFuture::future(self.service.call(req)),
+			Err(retry_after) => {
+				let mut extensions = req.extensions;
+				extensions.insert(RateLimited { retry_after });
+				let rp = MethodResponse::error(req.id, reject_rate_limited(retry_after)).with_extensions(extensions);
+				ResponseFuture::ready(rp)
+			}
+		}
+	}
+
+	fn batch<'a>(&self, batch: Batch<'a>) -> impl Future<Output = Self::BatchResponse> + Send + 'a {
+		let mut allowed_calls = 0;
+		let mut max_retry_after = None;
+		let mut entries = Vec::with_capacity(batch.len());
+
+		// Rejected calls are replaced with an error entry and rejected notifications are dropped.
+		for entry in batch {
+			let retry_after = match &entry {
+				Ok(BatchEntry::Call(req)) => {
+					allowed_calls += 1;
+					self.check(&req.method, &req.extensions).err()
+				}
+				Ok(BatchEntry::Notification(n)) => match self.check(&n.method, &n.extensions) {
+					Ok(()) => None,
+					Err(_) => continue,
+				},
+				Err(_) => None,
+			};
+
+			match (entry, retry_after) {
+				(Ok(BatchEntry::Call(req)), Some(retry_after)) => {
+					max_retry_after = max_retry_after.max(Some(retry_after));
+					allowed_calls -= 1;
+					entries.push(Err(BatchEntryErr::new(req.id, reject_rate_limited(retry_after))));
+				}
+				(entry, _) => entries.push(entry),
+			}
+		}
+
+		let service = self.service.clone();
+
+		async move {
+			let mut rp = service.batch(Batch::from(entries)).await;
+
+			// Only regard the batch as rate limited if all calls in the batch were rejected.
+			if let (0, Some(retry_after)) = (allowed_calls, max_retry_after) {
+				rp.extensions_mut().insert(RateLimited { retry_after });
+			}
+
+			rp
+		}
+	}
+
+	fn notification<'a>(&self, n: Notification<'a>) -> impl Future<Output = Self::NotificationResponse> + Send + 'a {
+		match self.check(&n.method, &n.extensions) {
+			Ok(()) => ResponseFuture::future(self.service.notification(n)),
+			// Notifications are not expected to return a response so just ignore them.
+			Err(_) => ResponseFuture::ready(MethodResponse::notification()),
+		}
+	}
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+	connections: HashMap<ConnectionId, Bucket>,
+	ips: HashMap<IpAddr, Bucket>,
+	methods: HashMap<(ConnectionId, String), Bucket>,
+	checks: u64,
+}
+
+impl Buckets {
+	fn check(
+		&mut self,
+		config: &RateLimitConfig,
+		method: &str,
+		conn_id: Option<ConnectionId>,
+		ip: Option<IpAddr>,
+		weight: u32,
+		now: Instant,
+	) -> Result<(), Duration> {
+		self.checks += 1;
+		if self.checks % PRUNE_INTERVAL == 0 {
+			self.prune(config, now);
+		}
+
+		let mut limited: Vec<(&mut Bucket, Quota)> = Vec::with_capacity(3);
+
+		if let (Some(quota), Some(conn_id)) = (config.per_connection, conn_id) {
+			limited.push((self.connections.entry(conn_id).or_insert_with(|| Bucket::full(quota, now)), quota));
+		}
+		if let (Some(quota), Some(ip)) = (config.per_ip, ip) {
+			limited.push((self.ips.entry(ip).or_insert_with(|| Bucket::full(quota, now)), quota));
+		}
+		if let (Some(quota), Some(conn_id)) = (config.per_method.get(method).copied(), conn_id) {
+			let bucket = self.methods.entry((conn_id, method.to_owned())).or_insert_with(|| Bucket::full(quota, now));
+			limited.push((bucket, quota));
+		}
+
+		let mut retry_after = Duration::ZERO;
+		for (bucket, quota) in limited.iter_mut() {
+			bucket.refill(*quota, now);
+			retry_after = retry_after.max(bucket.retry_after(*quota, weight));
+		}
+
+		if !retry_after.is_zero() {
+			return Err(retry_after);
+		}
+
+		for (bucket, _) in limited {
+			bucket.tokens -= weight as f64;
+		}
+
+		Ok(())
+	}
+
+	/// Remove the buckets which are full because they are equivalent to new buckets.
+	fn prune(&mut self, config: &RateLimitConfig, now: Instant) {
+		if let Some(quota) = config.per_connection {
+			self.connections.retain(|_, bucket| !bucket.is_full(quota, now));
+		}
+		if let Some(quota) = config.per_ip {
+			self.ips.retain(|_, bucket| !bucket.is_full(quota, now));
+		}
+		self.methods.retain(|(_, method), bucket| match config.per_method.get(method) {
+			Some(quota) => !bucket.is_full(*quota, now),
+			None => false,
+		});
+	}
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Bucket {
+	tokens: f64,
+	updated: Instant,
+}
+
+impl Bucket {
+	fn full(quota: Quota, now: Instant) -> Self {
+		Self { tokens: quota.burst as f64, updated: now }
+	}
+
+	fn refill(&mut self, quota: Quota, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * quota.tokens_per_sec()).min(quota.burst as f64);
+		self.updated = now;
+	}
+
+	fn is_full(&mut self, quota: Quota, now: Instant) -> bool {
+		self.refill(quota, now);
+		self.tokens >= quota.burst as f64
+	}
+
+	/// Get the time until `weight` tokens are available or zero if they are available now.
+	fn retry_after(&self, quota: Quota, weight: u32) -> Duration {
+		// The call can never succeed but it's not known
+		// whether the weights are going to be changed.
+		if weight > quota.burst {
+			return quota.period;
+		}
+
+		let missing = weight as f64 - self.tokens;
+		if missing <= 0.0 { Duration::ZERO } else { Duration::from_secs_f64(missing / quota.tokens_per_sec()) }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn token_bucket_works() {
+		let config = RateLimitConfig {
+			per_connection: Some(Quota::new(2, Duration::from_secs(1))),
+			per_ip: Some(Quota::new(3, Duration::from_secs(1))),
+			per_method: [("expensive".to_string(), Quota::new(5, Duration::from_secs(10)))].into(),
+			weights: HashMap::new(),
+		};
+		let mut buckets = Buckets::default();
+		let ip = Some(IpAddr::from([127, 0, 0, 1]));
+		let now = Instant::now();
+
+		// The connection bucket is empty after two calls.
+		assert!(buckets.check(&config, "a", Some(ConnectionId(1)), ip, 1, now).is_ok());
+		assert!(buckets.check(&config, "a", Some(ConnectionId(1)), ip, 1, now).is_ok());
+		assert_eq!(buckets.check(&config, "a", Some(ConnectionId(1)), ip, 1, now), Err(Duration::from_millis(500)));
+
+		// The IP bucket is shared by the connections.
+		assert!(buckets.check(&config, "a", Some(ConnectionId(2)), ip, 1, now).is_ok());
+		assert_eq!(
+			buckets.check(&config, "a", Some(ConnectionId(2)), ip, 1, now),
+			Err(Duration::from_secs_f64(1.0 / 3.0))
+		);
+
+		// Refilled after the period.
+		let now = now + Duration::from_secs(1);
+		assert!(buckets.check(&config, "a", Some(ConnectionId(1)), ip, 1, now).is_ok());
+
+		// Weights are consumed from all buckets, the method bucket has tokens left but not the connection.
+		assert!(buckets.check(&config, "expensive", Some(ConnectionId(3)), None, 2, now).is_ok());
+		assert!(buckets.check(&config, "expensive", Some(ConnectionId(3)), None, 2, now).is_err());
+		assert_eq!(buckets.methods[&(ConnectionId(3), "expensive".to_string())].tokens, 3.0);
+
+		// Weights larger than the burst are never allowed.
+		assert_eq!(buckets.check(&config, "a", Some(ConnectionId(4)), None, 3, now), Err(Duration::from_secs(1)));
+
+		// Full buckets are pruned.
+		buckets.prune(&config, now + Duration::from_secs(10));
+		assert!(buckets.connections.is_empty() && buckets.ips.is_empty() && buckets.methods.is_empty());
+	}
+}
@@ -0,0 +1,405 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Origin policy for cross-origin HTTP requests and WebSocket handshakes.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::{FutureExt, TryFutureExt};
+use http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use http::{Method, StatusCode};
+use jsonrpsee_core::BoxError;
+use tower::Service;
+
+use crate::middleware::http::{Authority, AuthorityError, Port};
+use crate::{HttpBody, HttpRequest, HttpResponse, LOG_TARGET};
+
+/// Policy which decides from which origins browsers may call the server.
+///
+/// The `Origin` header of HTTP requests and of WebSocket handshakes is checked against the allowed
+/// origins and the requests from other origins are rejected with `403 Forbidden`. Allowed HTTP
+/// requests get the CORS headers in their response and preflight requests are answered by the server.
+/// Requests without an `Origin` header, which are not made by browsers, are always allowed.
+///
+/// The patterns of the allowed origins are parsed like the [`Authority`] of the
+/// [`HostFilterLayer`](crate::middleware::http::HostFilterLayer) and may contain:
+/// - a scheme such as `https://example.com`, otherwise only `http` and `https` are allowed.
+/// - a wildcard subdomain such as `https://*.example.com` which matches `https://api.example.com`
+///   but not `https://example.com`.
+/// - a wildcard port such as `http://localhost:*`, otherwise only the specified or the default port is allowed.
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpsee_server::{OriginPolicy, ServerConfig};
+///
+/// let policy = OriginPolicy::allow_only(["https://example.com", "https://*.example.com", "http://localhost:*"])
+///     .unwrap()
+///     .allow_headers([http::header::CONTENT_TYPE, http::header::AUTHORIZATION])
+///     .allow_credentials();
+///
+/// let config = ServerConfig::builder().set_origin_policy(policy).build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct OriginPolicy {
+	/// Allowed origins, `None` if any origin is allowed.
+	allowed: Option<Vec<OriginPattern>>,
+	allow_credentials: bool,
+	allowed_methods: Vec<Method>,
+	allowed_headers: Vec<HeaderName>,
+	exposed_headers: Vec<HeaderName>,
+	max_age: Option<Duration>,
+}
+
+impl OriginPolicy {
+	fn new(allowed: Option<Vec<OriginPattern>>) -> Self {
+		Self {
+			allowed,
+			allow_credentials: false,
+			allowed_methods: vec![Method::GET, Method::POST],
+			allowed_headers: vec![header::CONTENT_TYPE],
+			exposed_headers: Vec::new(),
+			max_age: None,
+		}
+	}
+
+	/// Allow requests from any origin.
+	pub fn allow_any() -> Self {
+		Self::new(None)
+	}
+
+	/// Allow only requests from the origins which match one of the `patterns`.
+	///
+	/// Fails if a pattern is not a valid authority.
+	pub fn allow_only<T, U>(patterns: T) -> Result<Self, AuthorityError>
+	where
+		T: IntoIterator<Item = U>,
+		U: AsRef<str>,
+	{
+		let allowed: Result<Vec<_>, _> = patterns.into_iter().map(|p| OriginPattern::parse(p.as_ref())).collect();
+		Ok(Self::new(Some(allowed?)))
+	}
+
+	/// Allow requests with credentials such as cookies.
+	///
+	/// The origin of the request is then sent back in `Access-Control-Allow-Origin` instead of `*`
+	/// because browsers reject the wildcard for requests with credentials.
+	///
+	/// # Panics
+	///
+	/// Panics if any origin is allowed, because any website could then make requests with the
+	/// credentials of its visitors.
+	pub fn allow_credentials(mut self) -> Self {
+		assert!(self.allowed.is_some(), "Credentials can't be allowed for any origin, use `OriginPolicy::allow_only`");
+		self.allow_credentials = true;
+		self
+	}
+
+	/// Set the methods allowed in cross-origin requests.
+	///
+	/// Default: `GET` and `POST`.
+	pub fn allow_methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+		self.allowed_methods = methods.into_iter().collect();
+		self
+	}
+
+	/// Set the headers which may be sent in cross-origin requests.
+	///
+	/// Default: `Content-Type`.
+	pub fn allow_headers(mut self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+		self.allowed_headers = headers.into_iter().collect();
+		self
+	}
+
+	/// Set the headers of the responses which may be read by the browser.
+	///
+	/// Default: none besides the CORS-safelisted response headers.
+	pub fn expose_headers(mut self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+		self.exposed_headers = headers.into_iter().collect();
+		self
+	}
+
+	/// Set how long browsers may cache the response to a preflight request.
+	///
+	/// Default: not set, browsers use their own default.
+	pub fn max_age(mut self, max_age: Duration) -> Self {
+		self.max_age = Some(max_age);
+		self
+	}
+
+	/// Check the `Origin` header of the request.
+	pub(crate) fn check(&self, headers: &HeaderMap) -> OriginCheck {
+		let Some(origin) = headers.get(header::ORIGIN) else {
+			return OriginCheck::Missing;
+		};
+
+		let Some(allowed) = &self.allowed else {
+			return OriginCheck::Allowed(origin.clone());
+		};
+
+		let parsed = origin.to_str().ok().and_then(|o| {
+			let (scheme, _) = o.split_once("://")?;
+			let authority = Authority::try_from(o).ok()?;
+			Some((scheme.to_ascii_lowercase(), authority))
+		});
+
+		match parsed {
+			Some((scheme, authority)) if allowed.iter().any(|p| p.matches(&scheme, &authority)) => {
+				OriginCheck::Allowed(origin.clone())
+			}
+			_ => OriginCheck::Denied,
+		}
+	}
+
+	/// Create the response to a preflight request from an allowed `origin`.
+	pub(crate) fn preflight(&self, origin: &HeaderValue) -> HttpResponse {
+		let mut response = HttpResponse::new(HttpBody::empty());
+		*response.status_mut() = StatusCode::NO_CONTENT;
+
+		let headers = response.headers_mut();
+		self.insert_allow_origin(origin, headers);
+		headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, join(&self.allowed_methods));
+		if !self.allowed_headers.is_empty() {
+			headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, join(&self.allowed_headers));
+		}
+		if let Some(max_age) = self.max_age {
+			headers.insert(header::ACCESS_CONTROL_MAX_AGE, max_age.as_secs().into());
+		}
+		headers.append(header::VARY, HeaderValue::from_static("Access-Control-Request-Method"));
+		headers.append(header::VARY, HeaderValue::from_static("Access-Control-Request-Headers"));
+
+		response
+	}
+
+	/// Add the CORS headers to the response of a request from an allowed `origin`.
+	pub(crate) fn apply(&self, origin: &HeaderValue, headers: &mut HeaderMap) {
+		self.insert_allow_origin(origin, headers);
+		if !self.exposed_headers.is_empty() {
+			headers.insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, join(&self.exposed_headers));
+		}
+	}
+
+	fn insert_allow_origin(&self, origin: &HeaderValue, headers: &mut HeaderMap) {
+		if self.allowed.is_none() && !self.allow_credentials {
+			headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+		} else {
+			headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+			headers.append(header::VARY, HeaderValue::from_static("Origin"));
+		}
+
+		if self.allow_credentials {
+			headers.insert(header::ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static("true"));
+		}
+	}
+}
+
+/// Result of checking the `Origin` header of a request against the [`OriginPolicy`].
+#[derive(Debug)]
+pub(crate) enum OriginCheck {
+	/// The request has no `Origin` header.
+	Missing,
+	/// The origin is allowed.
+	Allowed(HeaderValue),
+	/// The origin is not allowed.
+	Denied,
+}
+
+/// Service which applies the [`OriginPolicy`] before the HTTP middleware.
+///
+/// The requests from denied origins and the preflight requests are answered without calling
+/// the HTTP middleware, such that middleware which requires credentials doesn't reject the
+/// preflight requests, and all responses of the HTTP middleware get the CORS headers.
+///
+/// The responses of the HTTP middleware are passed through as such and the
+/// responses of the filter are converted into the response body of the HTTP middleware.
+#[derive(Debug, Clone)]
+pub(crate) struct OriginFilter<S> {
+	service: S,
+	policy: Option<Arc<OriginPolicy>>,
+}
+
+impl<S> OriginFilter<S> {
+	pub(crate) fn new(service: S, policy: Option<Arc<OriginPolicy>>) -> Self {
+		Self { service, policy }
+	}
+}
+
+impl<S, B, ResBody> Service<HttpRequest<B>> for OriginFilter<S>
+where
+	S: Service<HttpRequest<B>, Response = HttpResponse<ResBody>>,
+	S::Error: Into<BoxError> + 'static,
+	S::Future: Send + 'static,
+	ResBody: From<HttpBody> + Send + 'static,
+{
+	type Response = HttpResponse<ResBody>;
+	type Error = BoxError;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.service.poll_ready(cx).map_err(Into::into)
+	}
+
+	fn call(&mut self, request: HttpRequest<B>) -> Self::Future {
+		let Some(policy) = self.policy.clone() else {
+			return self.service.call(request).map_err(Into::into).boxed();
+		};
+
+		match policy.check(request.headers()) {
+			OriginCheck::Missing => self.service.call(request).map_err(Into::into).boxed(),
+			OriginCheck::Denied => {
+				let origin = request.headers().get(header::ORIGIN);
+				tracing::debug!(target: LOG_TARGET, "Denied request from origin: {:?}", origin);
+				let response = crate::transport::http::response::origin_not_allowed().map(ResBody::from);
+				async { Ok(response) }.boxed()
+			}
+			OriginCheck::Allowed(origin) if is_preflight(&request) => {
+				let response = policy.preflight(&origin).map(ResBody::from);
+				async { Ok(response) }.boxed()
+			}
+			OriginCheck::Allowed(origin) => self
+				.service
+				.call(request)
+				.map_ok(move |mut rp| {
+					policy.apply(&origin, rp.headers_mut());
+					rp
+				})
+				.map_err(Into::into)
+				.boxed(),
+		}
+	}
+}
+
+/// Returns whether the request is a CORS preflight request.
+pub(crate) fn is_preflight<T>(request: &HttpRequest<T>) -> bool {
+	request.method() == Method::OPTIONS && request.headers().contains_key(header::ACCESS_CONTROL_REQUEST_METHOD)
+}
+
+fn join<T: AsRef<str>>(values: &[T]) -> HeaderValue {
+	let joined = values.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(", ");
+	HeaderValue::from_str(&joined).expect("Methods and header names are valid header values; qed")
+}
+
+/// Pattern of an allowed origin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct OriginPattern {
+	/// Scheme of the origin, `None` if the pattern has no scheme and `http` or `https` are allowed.
+	scheme: Option<String>,
+	authority: Authority,
+}
+
+impl OriginPattern {
+	fn parse(pattern: &str) -> Result<Self, AuthorityError> {
+		let scheme = pattern.split_once("://").map(|(scheme, _)| scheme.to_ascii_lowercase());
+		let mut authority = Authority::try_from(pattern)?;
+		authority.host.make_ascii_lowercase();
+		Ok(Self { scheme, authority })
+	}
+
+	fn matches(&self, scheme: &str, origin: &Authority) -> bool {
+		let scheme_matches = match &self.scheme {
+			Some(s) => s == scheme,
+			None => scheme == "http" || scheme == "https",
+		};
+		if !scheme_matches {
+			return false;
+		}
+
+		let host = origin.host.to_ascii_lowercase();
+		let host_matches = match self.authority.host.strip_prefix("*.") {
+			Some(domain) => {
+				host.strip_suffix(domain).and_then(|sub| sub.strip_suffix('.')).is_some_and(|sub| !sub.is_empty())
+			}
+			None => self.authority.host == host,
+		};
+
+		host_matches
+			&& match (&self.authority.port, &origin.port) {
+				(Port::Any, _) => true,
+				(Port::Default, Port::Default) => true,
+				(Port::Fixed(p1), Port::Fixed(p2)) => p1 == p2,
+				_ => false,
+			}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{OriginCheck, OriginPolicy};
+	use http::header::{self, HeaderMap, HeaderValue};
+
+	fn check(policy: &OriginPolicy, origin: &'static str) -> bool {
+		let mut headers = HeaderMap::new();
+		headers.insert(header::ORIGIN, HeaderValue::from_static(origin));
+		matches!(policy.check(&headers), OriginCheck::Allowed(_))
+	}
+
+	#[test]
+	fn exact_origins_work() {
+		let policy = OriginPolicy::allow_only(["https://example.com", "http://localhost:8080"]).unwrap();
+
+		assert!(check(&policy, "https://example.com"));
+		assert!(check(&policy, "https://EXAMPLE.com:443"));
+		assert!(check(&policy, "http://localhost:8080"));
+		assert!(!check(&policy, "http://example.com"));
+		assert!(!check(&policy, "https://example.com:8443"));
+		assert!(!check(&policy, "https://api.example.com"));
+		assert!(!check(&policy, "https://example.com.evil.io"));
+		assert!(!check(&policy, "http://localhost"));
+		assert!(!check(&policy, "null"));
+	}
+
+	#[test]
+	fn wildcards_work() {
+		let policy = OriginPolicy::allow_only(["https://*.example.com", "localhost:*"]).unwrap();
+
+		assert!(check(&policy, "https://api.example.com"));
+		assert!(check(&policy, "https://a.b.example.com"));
+		assert!(!check(&policy, "https://example.com"));
+		assert!(!check(&policy, "https://evilexample.com"));
+		assert!(check(&policy, "http://localhost"));
+		assert!(check(&policy, "https://localhost:3000"));
+		// Patterns without a scheme only match `http` and `https`.
+		assert!(!check(&policy, "chrome-extension://localhost"));
+		assert!(!check(&policy, "ws://localhost:3000"));
+	}
+
+	#[test]
+	#[should_panic(expected = "Credentials can't be allowed for any origin")]
+	fn credentials_for_any_origin_are_rejected() {
+		let _ = OriginPolicy::allow_any().allow_credentials();
+	}
+
+	#[test]
+	fn missing_origin_is_allowed() {
+		let policy = OriginPolicy::allow_only(["https://example.com"]).unwrap();
+		assert!(matches!(policy.check(&HeaderMap::new()), OriginCheck::Missing));
+		assert!(check(&OriginPolicy::allow_any(), "null"));
+	}
+}
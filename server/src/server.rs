@@ -27,25 +27,35 @@
 use std::error::Error as StdError;
 use std::future::Future;
 use std::net::{SocketAddr, TcpListener as StdTcpListener};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener as StdUnixListener;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU32;
 use std::task::Poll;
 use std::time::Duration;
 
+#[cfg(feature = "tls")]
+use crate::TlsConfig;
 use crate::future::{ConnectionGuard, ServerHandle, SessionClose, SessionClosedFuture, StopHandle, session_close};
+use crate::middleware::http::AccessLogEntry;
 use crate::middleware::rpc::{RpcService, RpcServiceCfg};
+use crate::origin::OriginFilter;
+use crate::transport::stream::Stream;
 use crate::transport::ws::BackgroundTaskParams;
 use crate::transport::{http, ws};
 use crate::utils::deserialize_with_ext;
-use crate::{Extensions, HttpBody, HttpRequest, HttpResponse, LOG_TARGET};
+use crate::{Authenticator, Extensions, HttpBody, HttpRequest, HttpResponse, LOG_TARGET, OriginPolicy};
 
 use futures_util::future::{self, Either, FutureExt};
 use futures_util::io::{BufReader, BufWriter};
 use hyper::body::Bytes;
 use hyper_util::rt::{TokioExecutor, TokioIo};
 use jsonrpsee_core::id_providers::RandomIntegerIdProvider;
-use jsonrpsee_core::middleware::{Batch, BatchEntry, BatchEntryErr, RpcServiceBuilder, RpcServiceT};
+use jsonrpsee_core::middleware::layer::{RemoteTraceContext, TRACEPARENT, TraceContext};
+use jsonrpsee_core::middleware::{Batch, BatchEntry, BatchEntryErr, IsHttpRequest, RpcServiceBuilder, RpcServiceT};
 use jsonrpsee_core::server::helpers::prepare_error;
 use jsonrpsee_core::server::{BoundedSubscriptions, ConnectionId, MethodResponse, MethodSink, Methods};
 use jsonrpsee_core::traits::IdProvider;
@@ -55,11 +65,13 @@ use jsonrpsee_types::error::{
 };
 use jsonrpsee_types::{ErrorObject, Id};
 use soketto::handshake::http::is_upgrade_request;
-use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::net::{TcpListener, ToSocketAddrs};
 use tokio::sync::{OwnedSemaphorePermit, mpsc, watch};
 use tokio_util::compat::TokioAsyncReadCompatExt;
 use tower::layer::util::Identity;
-use tower::{Layer, Service};
+use tower::{Layer, Service, ServiceExt};
 use tracing::{Instrument, instrument};
 
 /// Default maximum connections allowed.
@@ -69,10 +81,12 @@ type Notif<'a> = Option<std::borrow::Cow<'a, JsonRawValue>>;
 
 /// JSON RPC server.
 pub struct Server<HttpMiddleware = Identity, RpcMiddleware = Identity> {
-	listener: TcpListener,
+	listener: Listener,
 	server_cfg: ServerConfig,
 	rpc_middleware: RpcServiceBuilder<RpcMiddleware>,
 	http_middleware: tower::ServiceBuilder<HttpMiddleware>,
+	#[cfg(feature = "tls")]
+	tls: Option<TlsConfig>,
 }
 
 impl Server<Identity, Identity> {
@@ -90,15 +104,96 @@ impl<RpcMiddleware, HttpMiddleware> std::fmt::Debug for Server<RpcMiddleware, Ht
 
 impl<RpcMiddleware, HttpMiddleware> Server<RpcMiddleware, HttpMiddleware> {
 	/// Returns socket address to which the server is bound.
+	///
+	/// Fails if the server is bound to a Unix domain socket, use [`Server::local_path`] instead.
 	pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
-		self.listener.local_addr()
+		match &self.listener {
+			Listener::Tcp(listener) => listener.local_addr(),
+			#[cfg(unix)]
+			Listener::Unix { .. } => {
+				Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "The server is bound to a Unix domain socket"))
+			}
+		}
+	}
+
+	/// Returns the path of the Unix domain socket to which the server is bound.
+	///
+	/// Returns `None` if the server is bound to a TCP socket or to an unnamed Unix socket.
+	#[cfg(unix)]
+	pub fn local_path(&self) -> Option<PathBuf> {
+		match &self.listener {
+			Listener::Tcp(_) => None,
+			Listener::Unix { listener, .. } => listener.local_addr().ok()?.as_pathname().map(Path::to_path_buf),
+		}
+	}
+}
+
+/// Listener that the server accepts connections on.
+#[derive(Debug)]
+enum Listener {
+	Tcp(TcpListener),
+	#[cfg(unix)]
+	Unix {
+		listener: UnixListener,
+		/// Serve newline-delimited JSON-RPC instead of HTTP and WebSocket.
+		raw: bool,
+		/// Socket file created by the server which is removed when the server stops.
+		cleanup: Option<PathBuf>,
+	},
+}
+
+impl Listener {
+	async fn accept(&self) -> std::io::Result<(Stream, RemoteAddr)> {
+		match self {
+			Self::Tcp(listener) => {
+				let (socket, remote_addr) = listener.accept().await?;
+				Ok((Stream::Tcp(socket), RemoteAddr::Tcp(remote_addr)))
+			}
+			#[cfg(unix)]
+			Self::Unix { listener, .. } => {
+				let (socket, _) = listener.accept().await?;
+				Ok((Stream::Unix(socket), RemoteAddr::Unix))
+			}
+		}
+	}
+
+	fn is_raw(&self) -> bool {
+		match self {
+			Self::Tcp(_) => false,
+			#[cfg(unix)]
+			Self::Unix { raw, .. } => *raw,
+		}
+	}
+}
+
+/// Address of the remote peer.
+#[derive(Debug, Copy, Clone)]
+enum RemoteAddr {
+	Tcp(SocketAddr),
+	#[cfg(unix)]
+	Unix,
+}
+
+impl std::fmt::Display for RemoteAddr {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Tcp(addr) => addr.fmt(f),
+			#[cfg(unix)]
+			Self::Unix => f.write_str("unix"),
+		}
 	}
 }
 
 impl<HttpMiddleware, RpcMiddleware, Body> Server<HttpMiddleware, RpcMiddleware>
 where
 	RpcMiddleware: tower::Layer<RpcService> + Clone + Send + 'static,
-	<RpcMiddleware as Layer<RpcService>>::Service: RpcServiceT,
+	<RpcMiddleware as Layer<RpcService>>::Service: RpcServiceT<
+			MethodResponse = MethodResponse,
+			BatchResponse = MethodResponse,
+			NotificationResponse = MethodResponse,
+		> + Send
+		+ Sync
+		+ 'static,
 	HttpMiddleware: Layer<TowerServiceNoHttp<RpcMiddleware>> + Send + 'static,
 	<HttpMiddleware as Layer<TowerServiceNoHttp<RpcMiddleware>>>::Service:
 		Send + Clone + Service<HttpRequest, Response = HttpResponse<Body>, Error = BoxError>,
@@ -134,11 +229,26 @@ where
 
 		let (drop_on_completion, mut process_connection_awaiter) = mpsc::channel::<()>(1);
 
+		// The acceptor is shared by the connections, it reads the reloaded certificates.
+		#[cfg(feature = "tls")]
+		let tls = self.tls.as_ref().map(|tls| (tls.acceptor(), tls.handshake_timeout));
+
 		loop {
 			match try_accept_conn(&listener, stopped).await {
 				AcceptConnection::Established { socket, remote_addr, stop } => {
-					process_connection(ProcessConnection {
+					stopped = stop;
+
+					if let Stream::Tcp(socket) = &socket {
+						if let Err(e) = socket.set_nodelay(self.server_cfg.tcp_no_delay) {
+							tracing::warn!(target: LOG_TARGET, "Could not set NODELAY on socket: {:?}", e);
+							continue;
+						}
+					}
+
+					let params = ProcessConnection {
 						http_middleware: &self.http_middleware,
+						#[cfg(feature = "tls")]
+						tls: tls.clone(),
 						rpc_middleware: self.rpc_middleware.clone(),
 						remote_addr,
 						methods: methods.clone(),
@@ -148,9 +258,14 @@ where
 						conn_guard: &connection_guard,
 						socket,
 						drop_on_completion: drop_on_completion.clone(),
-					});
+					};
+
+					if listener.is_raw() {
+						process_raw_connection(params);
+					} else {
+						process_connection(params);
+					}
 					id = id.wrapping_add(1);
-					stopped = stop;
 				}
 				AcceptConnection::Err((e, stop)) => {
 					tracing::debug!(target: LOG_TARGET, "Error while awaiting a new connection: {:?}", e);
@@ -168,6 +283,13 @@ where
 			// Generally, messages should not be sent across this channel,
 			// but we'll loop here to wait for `None` just to be on the safe side
 		}
+
+		#[cfg(unix)]
+		if let Listener::Unix { cleanup: Some(path), .. } = listener {
+			if let Err(e) = std::fs::remove_file(&path) {
+				tracing::debug!(target: LOG_TARGET, "Could not remove socket file {}: {:?}", path.display(), e);
+			}
+		}
 	}
 }
 
@@ -194,10 +316,23 @@ pub struct ServerConfig {
 	pub(crate) message_buffer_capacity: u32,
 	/// Ping settings.
 	pub(crate) ping_config: Option<PingConfig>,
+	/// WebSocket compression settings.
+	#[cfg(feature = "deflate")]
+	pub(crate) deflate_config: Option<DeflateConfig>,
 	/// ID provider.
 	pub(crate) id_provider: Arc<dyn IdProvider>,
 	/// `TCP_NODELAY` settings.
 	pub(crate) tcp_no_delay: bool,
+	/// Default execution timeout of method calls.
+	pub(crate) method_timeout: Option<Duration>,
+	/// Execution of batch requests.
+	pub(crate) batch_execution_config: BatchExecutionConfig,
+	/// Authentication of the callers.
+	pub(crate) authenticator: Option<Arc<dyn Authenticator>>,
+	/// Origins allowed to call the server from browsers.
+	pub(crate) origin_policy: Option<Arc<OriginPolicy>>,
+	/// Whether methods may send requests to the WebSocket clients.
+	pub(crate) client_requests: bool,
 }
 
 /// The builder to configure and create a JSON-RPC server configuration.
@@ -223,10 +358,23 @@ pub struct ServerConfigBuilder {
 	message_buffer_capacity: u32,
 	/// Ping settings.
 	ping_config: Option<PingConfig>,
+	/// WebSocket compression settings.
+	#[cfg(feature = "deflate")]
+	deflate_config: Option<DeflateConfig>,
 	/// ID provider.
 	id_provider: Arc<dyn IdProvider>,
 	/// `TCP_NODELAY` settings.
 	tcp_no_delay: bool,
+	/// Default execution timeout of method calls.
+	method_timeout: Option<Duration>,
+	/// Execution of batch requests.
+	batch_execution_config: BatchExecutionConfig,
+	/// Authentication of the callers.
+	authenticator: Option<Arc<dyn Authenticator>>,
+	/// Origins allowed to call the server from browsers.
+	origin_policy: Option<Arc<OriginPolicy>>,
+	/// Whether methods may send requests to the WebSocket clients.
+	client_requests: bool,
 }
 
 /// Builder for [`TowerService`].
@@ -255,6 +403,112 @@ pub enum BatchRequestConfig {
 	Unlimited,
 }
 
+/// Cost of a call in a batch request, see [`BatchExecutionConfig::cost`].
+type BatchCostFn = Arc<dyn Fn(&jsonrpsee_core::middleware::BatchEntry) -> u64 + Send + Sync>;
+
+/// Configuration of how the calls of a batch request are executed.
+///
+/// By default the calls and notifications are executed one after the other without any budget.
+/// Calls that aren't executed because the budget of the batch was exhausted are replied to with
+/// [`BATCH_BUDGET_EXCEEDED_CODE`](jsonrpsee_types::error::BATCH_BUDGET_EXCEEDED_CODE).
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use jsonrpsee_server::{BatchExecutionConfig, ServerConfig};
+///
+/// let batch = BatchExecutionConfig::new()
+///     .concurrent(4)
+///     .timeout(Duration::from_secs(10))
+///     .cost(100, |req| if req.method_name() == "expensive" { 10 } else { 1 });
+///
+/// let config = ServerConfig::builder().set_batch_execution_config(batch).build();
+/// ```
+#[derive(Clone)]
+pub struct BatchExecutionConfig {
+	pub(crate) max_parallel: usize,
+	pub(crate) max_response_bytes: Option<usize>,
+	pub(crate) timeout: Option<Duration>,
+	pub(crate) cost: Option<(u64, BatchCostFn)>,
+}
+
+impl Default for BatchExecutionConfig {
+	fn default() -> Self {
+		Self { max_parallel: 1, max_response_bytes: None, timeout: None, cost: None }
+	}
+}
+
+impl std::fmt::Debug for BatchExecutionConfig {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("BatchExecutionConfig")
+			.field("max_parallel", &self.max_parallel)
+			.field("max_response_bytes", &self.max_response_bytes)
+			.field("timeout", &self.timeout)
+			.field("max_cost", &self.cost.as_ref().map(|(max, _)| max))
+			.finish()
+	}
+}
+
+impl BatchExecutionConfig {
+	/// Create a new [`BatchExecutionConfig`] with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Execute the calls and notifications of a batch one after the other, in the order of the batch.
+	///
+	/// This is the default.
+	pub fn sequential(mut self) -> Self {
+		self.max_parallel = 1;
+		self
+	}
+
+	/// Execute up to `max_parallel` calls of a batch concurrently.
+	///
+	/// The responses are still in the order of the batch. A limit of zero is treated as one.
+	pub fn concurrent(mut self, max_parallel: usize) -> Self {
+		self.max_parallel = max_parallel.max(1);
+		self
+	}
+
+	/// Stop executing calls once the responses of the batch add up to more than `bytes`.
+	///
+	/// The calls that completed are replied to as usual. This is different from
+	/// [`ServerConfigBuilder::max_response_body_size`] which fails the whole batch.
+	///
+	/// Default: no limit.
+	pub fn max_response_bytes(mut self, bytes: usize) -> Self {
+		self.max_response_bytes = Some(bytes);
+		self
+	}
+
+	/// Set the maximum duration of the whole batch.
+	///
+	/// The calls and notifications that haven't completed once `timeout` elapsed are aborted.
+	///
+	/// Default: no timeout.
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = Some(timeout);
+		self
+	}
+
+	/// Assign a cost to every call and notification of a batch and limit the total cost of a batch to `max_cost`.
+	///
+	/// The entries are charged in the order of the batch before they are executed. The first call
+	/// or notification whose cost exceeds the remaining budget and all the entries after it are not
+	/// executed, even if they are cheaper.
+	///
+	/// Default: no limit.
+	pub fn cost<F>(mut self, max_cost: u64, cost: F) -> Self
+	where
+		F: Fn(&jsonrpsee_core::middleware::BatchEntry) -> u64 + Send + Sync + 'static,
+	{
+		self.cost = Some((max_cost, Arc::new(cost)));
+		self
+	}
+}
+
 /// Connection related state that is needed
 /// to execute JSON-RPC calls.
 #[derive(Debug, Clone)]
@@ -337,6 +591,55 @@ impl PingConfig {
 	}
 }
 
+/// Configuration of the WebSocket `permessage-deflate` extension.
+#[cfg(feature = "deflate")]
+#[derive(Debug, Copy, Clone)]
+pub struct DeflateConfig {
+	/// Base-2 logarithm of the LZ77 sliding window size used to compress messages.
+	pub(crate) max_window_bits: u8,
+	/// Messages smaller than this are sent uncompressed.
+	pub(crate) threshold: usize,
+}
+
+#[cfg(feature = "deflate")]
+impl Default for DeflateConfig {
+	fn default() -> Self {
+		Self { max_window_bits: 15, threshold: 1024 }
+	}
+}
+
+#[cfg(feature = "deflate")]
+impl DeflateConfig {
+	/// Create a new DeflateConfig.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Maximum size of the LZ77 sliding window, as base-2 logarithm, used to compress messages
+	/// sent by the server. A smaller window uses less memory per connection at the cost of
+	/// a worse compression ratio.
+	///
+	/// Default: 15 (32 KiB).
+	///
+	/// # Panics
+	///
+	/// This method panics if `bits` isn't within `9..=15`.
+	pub fn max_window_bits(mut self, bits: u8) -> Self {
+		assert!((9..=15).contains(&bits), "max window bits must be within 9..=15");
+		self.max_window_bits = bits;
+		self
+	}
+
+	/// Messages smaller than `bytes` are sent uncompressed because
+	/// compressing them isn't worth the overhead.
+	///
+	/// Default: 1024 bytes.
+	pub fn threshold(mut self, bytes: usize) -> Self {
+		self.threshold = bytes;
+		self
+	}
+}
+
 impl Default for ServerConfig {
 	fn default() -> Self {
 		ServerConfig::builder().build()
@@ -363,8 +666,15 @@ impl Default for ServerConfigBuilder {
 			enable_ws: true,
 			message_buffer_capacity: 1024,
 			ping_config: None,
+			#[cfg(feature = "deflate")]
+			deflate_config: None,
 			id_provider: Arc::new(RandomIntegerIdProvider),
 			tcp_no_delay: true,
+			method_timeout: None,
+			batch_execution_config: BatchExecutionConfig::default(),
+			authenticator: None,
+			origin_policy: None,
+			client_requests: false,
 		}
 	}
 }
@@ -408,6 +718,14 @@ impl ServerConfigBuilder {
 		self
 	}
 
+	/// Configure how the calls of batch requests are executed, see [`BatchExecutionConfig`].
+	///
+	/// Default: the calls are executed sequentially.
+	pub fn set_batch_execution_config(mut self, cfg: BatchExecutionConfig) -> Self {
+		self.batch_execution_config = cfg;
+		self
+	}
+
 	/// Configure a custom [`tokio::runtime::Handle`] to run the server on.
 	///
 	/// Default: [`tokio::spawn`]
@@ -487,6 +805,34 @@ impl ServerConfigBuilder {
 		self
 	}
 
+	/// Accept the WebSocket `permessage-deflate` extension if a client offers it.
+	///
+	/// Default: compression is disabled.
+	///
+	/// # Examples
+	///
+	/// ```rust
+	/// use jsonrpsee_server::{ServerConfigBuilder, DeflateConfig};
+	///
+	/// // Use a 32 KiB window and only compress messages which are larger than 512 bytes.
+	/// let deflate_cfg = DeflateConfig::new().max_window_bits(15).threshold(512);
+	/// let builder = ServerConfigBuilder::default().enable_ws_compression(deflate_cfg);
+	/// ```
+	#[cfg(feature = "deflate")]
+	pub fn enable_ws_compression(mut self, config: DeflateConfig) -> Self {
+		self.deflate_config = Some(config);
+		self
+	}
+
+	/// Disable WebSocket compression on the server.
+	///
+	/// Default: compression is disabled.
+	#[cfg(feature = "deflate")]
+	pub fn disable_ws_compression(mut self) -> Self {
+		self.deflate_config = None;
+		self
+	}
+
 	/// Configure custom `subscription ID` provider for the server to use
 	/// to when getting new subscription calls.
 	///
@@ -512,6 +858,45 @@ impl ServerConfigBuilder {
 		self
 	}
 
+	/// Configure the [`Authenticator`] which identifies the callers.
+	///
+	/// The authenticator is invoked for every HTTP request and once for the HTTP request
+	/// which opens a WebSocket connection. Connections of [`Builder::build_ipc`] have no
+	/// HTTP request, so it's invoked once per connection with empty headers.
+	/// The [`Principal`](crate::Principal) it returns is inserted in the
+	/// extensions of the calls and checked against the roles required by the methods,
+	/// see [`RpcModule::set_required_roles`](crate::RpcModule::set_required_roles).
+	///
+	/// Default: no authentication, all callers are anonymous.
+	pub fn set_authenticator<A: Authenticator>(mut self, authenticator: A) -> Self {
+		self.authenticator = Some(Arc::new(authenticator));
+		self
+	}
+
+	/// Configure the [`OriginPolicy`] which decides from which origins browsers may call the server.
+	///
+	/// The policy is applied to the HTTP requests, including the CORS preflight requests,
+	/// and to the HTTP requests which open a WebSocket connection. It's applied before the
+	/// HTTP middleware, which doesn't see the preflight requests and the denied requests.
+	///
+	/// Default: no origin checks and no CORS headers.
+	pub fn set_origin_policy(mut self, policy: OriginPolicy) -> Self {
+		self.origin_policy = Some(Arc::new(policy));
+		self
+	}
+
+	/// Allow the methods to send requests to the WebSocket clients with the [`ClientHandle`](crate::ClientHandle)
+	/// in the extensions of the calls.
+	///
+	/// Messages of the clients are then checked for responses to these requests before
+	/// they are handled as method calls.
+	///
+	/// Default: disabled.
+	pub fn enable_client_requests(mut self) -> Self {
+		self.client_requests = true;
+		self
+	}
+
 	/// Configure `TCP_NODELAY` on the socket to the supplied value `nodelay`.
 	///
 	/// Default is `true`.
@@ -520,6 +905,19 @@ impl ServerConfigBuilder {
 		self
 	}
 
+	/// Configure the maximum duration an async or blocking method call may run before
+	/// the server aborts it and replies with [`METHOD_TIMEOUT_CODE`](jsonrpsee_types::error::METHOD_TIMEOUT_CODE).
+	///
+	/// A timeout set for an individual method with [`RpcModule::set_method_timeout`](crate::RpcModule::set_method_timeout)
+	/// takes precedence over this one. Methods registered with [`RpcModule::register_method`](crate::RpcModule::register_method)
+	/// and subscriptions aren't affected, they run to completion.
+	///
+	/// Default: no timeout.
+	pub fn set_method_timeout(mut self, timeout: Duration) -> Self {
+		self.method_timeout = Some(timeout);
+		self
+	}
+
 	/// Build the [`ServerConfig`].
 	pub fn build(self) -> ServerConfig {
 		ServerConfig {
@@ -533,8 +931,15 @@ impl ServerConfigBuilder {
 			enable_ws: self.enable_ws,
 			message_buffer_capacity: self.message_buffer_capacity,
 			ping_config: self.ping_config,
+			#[cfg(feature = "deflate")]
+			deflate_config: self.deflate_config,
 			id_provider: self.id_provider,
 			tcp_no_delay: self.tcp_no_delay,
+			method_timeout: self.method_timeout,
+			batch_execution_config: self.batch_execution_config,
+			authenticator: self.authenticator,
+			origin_policy: self.origin_policy,
+			client_requests: self.client_requests,
 		}
 	}
 }
@@ -545,6 +950,8 @@ pub struct Builder<HttpMiddleware, RpcMiddleware> {
 	server_cfg: ServerConfig,
 	rpc_middleware: RpcServiceBuilder<RpcMiddleware>,
 	http_middleware: tower::ServiceBuilder<HttpMiddleware>,
+	#[cfg(feature = "tls")]
+	tls: Option<TlsConfig>,
 }
 
 impl Default for Builder<Identity, Identity> {
@@ -553,6 +960,8 @@ impl Default for Builder<Identity, Identity> {
 			server_cfg: ServerConfig::default(),
 			rpc_middleware: RpcServiceBuilder::new(),
 			http_middleware: tower::ServiceBuilder::new(),
+			#[cfg(feature = "tls")]
+			tls: None,
 		}
 	}
 }
@@ -586,6 +995,7 @@ impl<RpcMiddleware, HttpMiddleware> TowerServiceBuilder<RpcMiddleware, HttpMiddl
 				conn_id,
 				conn_guard: self.conn_guard,
 				server_cfg: self.server_cfg,
+				remote_addr: None,
 			},
 			on_session_close: None,
 		};
@@ -707,7 +1117,13 @@ impl<HttpMiddleware, RpcMiddleware> Builder<HttpMiddleware, RpcMiddleware> {
 	/// let builder = ServerBuilder::default().set_rpc_middleware(m);
 	/// ```
 	pub fn set_rpc_middleware<T>(self, rpc_middleware: RpcServiceBuilder<T>) -> Builder<HttpMiddleware, T> {
-		Builder { server_cfg: self.server_cfg, rpc_middleware, http_middleware: self.http_middleware }
+		Builder {
+			server_cfg: self.server_cfg,
+			rpc_middleware,
+			http_middleware: self.http_middleware,
+			#[cfg(feature = "tls")]
+			tls: self.tls,
+		}
 	}
 
 	/// Configure a custom [`tower::ServiceBuilder`] middleware for composing layers to be applied to the RPC service.
@@ -733,7 +1149,26 @@ impl<HttpMiddleware, RpcMiddleware> Builder<HttpMiddleware, RpcMiddleware> {
 	/// }
 	/// ```
 	pub fn set_http_middleware<T>(self, http_middleware: tower::ServiceBuilder<T>) -> Builder<T, RpcMiddleware> {
-		Builder { server_cfg: self.server_cfg, http_middleware, rpc_middleware: self.rpc_middleware }
+		Builder {
+			server_cfg: self.server_cfg,
+			http_middleware,
+			rpc_middleware: self.rpc_middleware,
+			#[cfg(feature = "tls")]
+			tls: self.tls,
+		}
+	}
+
+	/// Terminate TLS on the server to serve `https://` and `wss://` connections.
+	///
+	/// Plaintext connections are rejected once TLS is enabled and the HTTP middleware is applied
+	/// after the TLS handshake. It doesn't apply to the raw connections of [`Builder::build_ipc`].
+	///
+	/// Default: TLS is disabled.
+	#[cfg(feature = "tls")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+	pub fn tls(mut self, config: TlsConfig) -> Self {
+		self.tls = Some(config);
+		self
 	}
 
 	/// Convert the server builder to a [`TowerServiceBuilder`].
@@ -843,10 +1278,12 @@ impl<HttpMiddleware, RpcMiddleware> Builder<HttpMiddleware, RpcMiddleware> {
 		let listener = TcpListener::bind(addrs).await?;
 
 		Ok(Server {
-			listener,
+			listener: Listener::Tcp(listener),
 			server_cfg: self.server_cfg,
 			rpc_middleware: self.rpc_middleware,
 			http_middleware: self.http_middleware,
+			#[cfg(feature = "tls")]
+			tls: self.tls,
 		})
 	}
 
@@ -880,10 +1317,97 @@ impl<HttpMiddleware, RpcMiddleware> Builder<HttpMiddleware, RpcMiddleware> {
 		let listener = TcpListener::from_std(listener.into())?;
 
 		Ok(Server {
-			listener,
+			listener: Listener::Tcp(listener),
 			server_cfg: self.server_cfg,
 			rpc_middleware: self.rpc_middleware,
 			http_middleware: self.http_middleware,
+			#[cfg(feature = "tls")]
+			tls: self.tls,
+		})
+	}
+
+	/// Finalize the configuration of the server and bind it to a Unix domain socket
+	/// which serves HTTP and WebSocket connections.
+	///
+	/// A stale socket file at `path` is removed, but if another server is still listening
+	/// on it this fails with [`std::io::ErrorKind::AddrInUse`].
+	/// The socket file is removed when the server is stopped.
+	///
+	/// ```rust
+	/// #[tokio::main]
+	/// async fn main() {
+	///   let path = std::env::temp_dir().join("jsonrpsee-doc-build-unix.sock");
+	///   let server = jsonrpsee_server::Server::builder().build_unix(&path).await.unwrap();
+	///   assert_eq!(server.local_path(), Some(path));
+	/// }
+	/// ```
+	#[cfg(unix)]
+	pub async fn build_unix(self, path: impl AsRef<Path>) -> std::io::Result<Server<HttpMiddleware, RpcMiddleware>> {
+		self.bind_unix(path.as_ref(), false)
+	}
+
+	/// Finalize the configuration of the server and bind it to a Unix domain socket
+	/// which serves newline-delimited JSON-RPC messages, similar to the IPC endpoint of geth.
+	///
+	/// Every request, batch and response is a single line of JSON terminated by `\n`.
+	/// Subscriptions are supported but the HTTP middleware isn't applied to these connections.
+	///
+	/// The [`Authenticator`] is invoked once per connection with empty headers. Without an authenticator,
+	/// or if it returns no [`Principal`](crate::Principal) for the connection, the callers are anonymous
+	/// and calls of methods which require roles are rejected.
+	///
+	/// A stale socket file at `path` is removed, but if another server is still listening
+	/// on it this fails with [`std::io::ErrorKind::AddrInUse`].
+	/// The socket file is removed when the server is stopped.
+	#[cfg(unix)]
+	pub async fn build_ipc(self, path: impl AsRef<Path>) -> std::io::Result<Server<HttpMiddleware, RpcMiddleware>> {
+		self.bind_unix(path.as_ref(), true)
+	}
+
+	/// Finalizes the configuration of the server with an existing Unix domain socket listener
+	/// which serves HTTP and WebSocket connections.
+	///
+	/// The socket file isn't removed when the server is stopped.
+	#[cfg(unix)]
+	pub fn build_from_unix(
+		self,
+		listener: impl Into<StdUnixListener>,
+	) -> std::io::Result<Server<HttpMiddleware, RpcMiddleware>> {
+		let listener = UnixListener::from_std(listener.into())?;
+
+		Ok(Server {
+			listener: Listener::Unix { listener, raw: false, cleanup: None },
+			server_cfg: self.server_cfg,
+			rpc_middleware: self.rpc_middleware,
+			http_middleware: self.http_middleware,
+			#[cfg(feature = "tls")]
+			tls: self.tls,
+		})
+	}
+
+	#[cfg(unix)]
+	fn bind_unix(self, path: &Path, raw: bool) -> std::io::Result<Server<HttpMiddleware, RpcMiddleware>> {
+		use std::os::unix::fs::FileTypeExt;
+
+		if let Ok(meta) = std::fs::symlink_metadata(path) {
+			// Only remove the socket file if no server is listening on it anymore.
+			let stale = meta.file_type().is_socket()
+				&& std::os::unix::net::UnixStream::connect(path)
+					.is_err_and(|e| e.kind() == std::io::ErrorKind::ConnectionRefused);
+			if stale {
+				std::fs::remove_file(path)?;
+			}
+		}
+
+		let listener = UnixListener::bind(path)?;
+
+		Ok(Server {
+			listener: Listener::Unix { listener, raw, cleanup: Some(path.to_path_buf()) },
+			server_cfg: self.server_cfg,
+			rpc_middleware: self.rpc_middleware,
+			http_middleware: self.http_middleware,
+			#[cfg(feature = "tls")]
+			tls: self.tls,
 		})
 	}
 }
@@ -901,6 +1425,8 @@ struct ServiceData {
 	conn_guard: ConnectionGuard,
 	/// ServerConfig
 	server_cfg: ServerConfig,
+	/// Address of the peer if connected over TCP.
+	remote_addr: Option<SocketAddr>,
 }
 
 /// jsonrpsee tower service
@@ -942,6 +1468,7 @@ where
 		Send + 'static,
 	RequestBody: http_body::Body<Data = Bytes> + Send + 'static,
 	RequestBody::Error: Into<BoxError>,
+	ResponseBody: From<HttpBody> + Send + 'static,
 {
 	type Response = HttpResponse<ResponseBody>;
 	type Error = BoxError;
@@ -952,7 +1479,10 @@ where
 	}
 
 	fn call(&mut self, request: HttpRequest<RequestBody>) -> Self::Future {
-		Box::pin(self.http_middleware.service(self.rpc_middleware.clone()).call(request))
+		let service = self.http_middleware.service(self.rpc_middleware.clone());
+		// The origin policy is applied before the HTTP middleware.
+		let policy = self.rpc_middleware.inner.server_cfg.origin_policy.clone();
+		OriginFilter::new(service, policy).call(request)
 	}
 }
 
@@ -1015,13 +1545,30 @@ where
 		let req_ext = request.extensions_mut();
 		req_ext.insert::<ConnectionGuard>(conn_guard.clone());
 		req_ext.insert::<ConnectionId>(conn.conn_id.into());
+		if let Some(remote_addr) = self.inner.remote_addr {
+			req_ext.insert::<SocketAddr>(remote_addr);
+		}
+		if let Some(entry) = req_ext.get::<AccessLogEntry>() {
+			entry.set_connection(conn.conn_id.into(), self.inner.remote_addr);
+		}
+
+		let trace_context = request.headers().get(TRACEPARENT).and_then(|v| v.to_str().ok());
+		if let Some(cx) = trace_context.and_then(TraceContext::from_traceparent) {
+			request.extensions_mut().insert(RemoteTraceContext(cx));
+		}
+
+		if let Some(authenticator) = &self.inner.server_cfg.authenticator {
+			if let Some(principal) = authenticator.authenticate(request.headers(), request.extensions()) {
+				request.extensions_mut().insert(principal);
+			}
+		}
 
 		let is_upgrade_request = is_upgrade_request(&request);
 
 		if self.inner.server_cfg.enable_ws && is_upgrade_request {
 			let this = self.inner.clone();
 
-			let mut server = soketto::handshake::http::Server::new();
+			let mut server = ws::handshake_server(&this.server_cfg);
 
 			let response = match server.receive_request(&request) {
 				Ok(response) => {
@@ -1045,6 +1592,8 @@ where
 					let rpc_service = RpcService::new(
 						this.methods.clone(),
 						this.server_cfg.max_response_body_size as usize,
+						this.server_cfg.method_timeout,
+						this.server_cfg.batch_execution_config.clone(),
 						this.conn_id.into(),
 						cfg,
 					);
@@ -1103,10 +1652,31 @@ where
 			let max_request_size = this.server_cfg.max_request_body_size;
 			let methods = this.methods.clone();
 			let batch_config = this.server_cfg.batch_requests_config;
+			request.extensions_mut().insert(IsHttpRequest);
+
+			if http::accepts_event_stream(&request) {
+				let (cfg, rx) = http::sse_service_cfg(&this.server_cfg);
+				let rpc_service = self.rpc_middleware.service(RpcService::new(
+					methods,
+					max_response_size as usize,
+					this.server_cfg.method_timeout,
+					this.server_cfg.batch_execution_config.clone(),
+					this.conn_id.into(),
+					cfg,
+				));
+
+				// NOTE: The `conn guard` is held by the response stream until it's closed.
+				return Box::pin(async move {
+					Ok(http::call_with_event_stream(request, batch_config, max_request_size, rpc_service, rx, conn)
+						.await)
+				});
+			}
 
 			let rpc_service = self.rpc_middleware.service(RpcService::new(
 				methods,
 				max_response_size as usize,
+				this.server_cfg.method_timeout,
+				this.server_cfg.batch_execution_config.clone(),
 				this.conn_id.into(),
 				RpcServiceCfg::OnlyCalls,
 			));
@@ -1128,14 +1698,17 @@ where
 
 struct ProcessConnection<'a, HttpMiddleware, RpcMiddleware> {
 	http_middleware: &'a tower::ServiceBuilder<HttpMiddleware>,
+	/// TLS acceptor and handshake timeout.
+	#[cfg(feature = "tls")]
+	tls: Option<(tokio_rustls::TlsAcceptor, Duration)>,
 	rpc_middleware: RpcServiceBuilder<RpcMiddleware>,
 	conn_guard: &'a ConnectionGuard,
 	conn_id: u32,
 	server_cfg: ServerConfig,
 	stop_handle: StopHandle,
-	socket: TcpStream,
+	socket: Stream,
 	drop_on_completion: mpsc::Sender<()>,
-	remote_addr: SocketAddr,
+	remote_addr: RemoteAddr,
 	methods: Methods,
 }
 
@@ -1162,13 +1735,16 @@ where
 		stop_handle,
 		drop_on_completion,
 		methods,
-		..
+		remote_addr,
+		#[cfg(feature = "tls")]
+		tls,
 	} = params;
 
-	if let Err(e) = socket.set_nodelay(server_cfg.tcp_no_delay) {
-		tracing::warn!(target: LOG_TARGET, "Could not set NODELAY on socket: {:?}", e);
-		return;
-	}
+	let remote_addr = match remote_addr {
+		RemoteAddr::Tcp(addr) => Some(addr),
+		#[cfg(unix)]
+		RemoteAddr::Unix => None,
+	};
 
 	let tower_service = TowerServiceNoHttp {
 		inner: ServiceData {
@@ -1177,16 +1753,54 @@ where
 			stop_handle: stop_handle.clone(),
 			conn_id,
 			conn_guard: conn_guard.clone(),
+			remote_addr,
 		},
 		rpc_middleware,
 		on_session_close: None,
 	};
 
-	let service = http_middleware.service(tower_service);
+	let origin_policy = tower_service.inner.server_cfg.origin_policy.clone();
+	// The origin policy is applied before the HTTP middleware.
+	let service = http_middleware.service(tower_service).map_response(|rp: HttpResponse<Body>| rp.map(HttpBody::new));
+	let service = OriginFilter::new(service, origin_policy);
 
 	tokio::spawn(async {
 		// this requires Clone.
 		let service = crate::utils::TowerToHyperService::new(service);
+
+		#[cfg(feature = "tls")]
+		let (socket, service) = match tls {
+			Some((acceptor, handshake_timeout)) => {
+				let handshake = tokio::time::timeout(handshake_timeout, acceptor.accept(socket));
+				let stopped = stop_handle.clone().shutdown();
+				tokio::pin!(handshake, stopped);
+
+				let stream = match future::select(handshake, stopped).await {
+					Either::Left((Ok(Ok(stream)), _)) => stream,
+					Either::Left((Ok(Err(e)), _)) => {
+						tracing::debug!(target: LOG_TARGET, "TLS handshake failed {:?}", e);
+						return;
+					}
+					Either::Left((Err(_), _)) => {
+						tracing::debug!(target: LOG_TARGET, "TLS handshake timed out");
+						return;
+					}
+					Either::Right(_) => {
+						tracing::debug!(target: LOG_TARGET, "TLS handshake aborted, the server was stopped");
+						return;
+					}
+				};
+
+				let mut extensions = Extensions::new();
+				if let Some(certs) = stream.get_ref().1.peer_certificates() {
+					extensions.insert(crate::PeerCertificates::new(certs.to_vec()));
+				}
+
+				(Stream::Tls(Box::new(stream)), service.with_extensions(extensions))
+			}
+			None => (socket, service),
+		};
+
 		let io = TokioIo::new(socket);
 		let builder = hyper_util::server::conn::auto::Builder::new(TokioExecutor::new());
 
@@ -1212,13 +1826,102 @@ where
 	});
 }
 
+/// Serve a connection which speaks newline-delimited JSON-RPC without HTTP.
+#[instrument(name = "connection", skip_all, fields(remote_addr = %params.remote_addr, conn_id = %params.conn_id), level = "INFO")]
+fn process_raw_connection<RpcMiddleware, HttpMiddleware>(params: ProcessConnection<HttpMiddleware, RpcMiddleware>)
+where
+	RpcMiddleware: tower::Layer<RpcService>,
+	<RpcMiddleware as Layer<RpcService>>::Service: RpcServiceT<
+			MethodResponse = MethodResponse,
+			BatchResponse = MethodResponse,
+			NotificationResponse = MethodResponse,
+		> + Send
+		+ Sync
+		+ 'static,
+{
+	#[cfg(unix)]
+	{
+		let ProcessConnection {
+			rpc_middleware,
+			conn_guard,
+			conn_id,
+			server_cfg,
+			socket,
+			stop_handle,
+			drop_on_completion,
+			methods,
+			..
+		} = params;
+
+		let Some(conn_permit) = conn_guard.try_acquire() else {
+			tracing::debug!(target: LOG_TARGET, "Too many connections. Please try again later.");
+			return;
+		};
+
+		let conn = ConnectionState::new(stop_handle, conn_id, conn_permit);
+
+		let (tx, rx) = mpsc::channel(server_cfg.message_buffer_capacity as usize);
+		let sink = MethodSink::new(tx);
+		let (pending_calls, pending_calls_completed) = mpsc::channel::<()>(1);
+
+		let cfg = RpcServiceCfg::CallsAndSubscriptions {
+			bounded_subscriptions: BoundedSubscriptions::new(server_cfg.max_subscriptions_per_connection),
+			id_provider: server_cfg.id_provider.clone(),
+			sink: sink.clone(),
+			_pending_calls: pending_calls,
+		};
+
+		let rpc_service = rpc_middleware.service(RpcService::new(
+			methods,
+			server_cfg.max_response_body_size as usize,
+			server_cfg.method_timeout,
+			server_cfg.batch_execution_config.clone(),
+			conn_id.into(),
+			cfg,
+		));
+
+		let mut extensions = Extensions::new();
+		extensions.insert::<ConnectionId>(conn_id.into());
+
+		// There are no HTTP headers, so the authenticator can only identify the local caller
+		// by the extensions of the connection.
+		if let Some(authenticator) = &server_cfg.authenticator {
+			if let Some(principal) = authenticator.authenticate(&hyper::HeaderMap::new(), &extensions) {
+				extensions.insert(principal);
+			}
+		}
+
+		let params = crate::transport::ipc::BackgroundTaskParams {
+			server_cfg,
+			conn,
+			stream: socket,
+			rpc_service,
+			sink,
+			rx,
+			pending_calls_completed,
+			extensions,
+		};
+
+		tokio::spawn(
+			async move {
+				crate::transport::ipc::background_task(params).await;
+				drop(drop_on_completion);
+			}
+			.in_current_span(),
+		);
+	}
+
+	#[cfg(not(unix))]
+	drop(params);
+}
+
 enum AcceptConnection<S> {
 	Shutdown,
-	Established { socket: TcpStream, remote_addr: SocketAddr, stop: S },
+	Established { socket: Stream, remote_addr: RemoteAddr, stop: S },
 	Err((std::io::Error, S)),
 }
 
-async fn try_accept_conn<S>(listener: &TcpListener, stopped: S) -> AcceptConnection<S>
+async fn try_accept_conn<S>(listener: &Listener, stopped: S) -> AcceptConnection<S>
 where
 	S: Future + Unpin,
 {
@@ -1241,6 +1944,32 @@ pub(crate) async fn handle_rpc_call<S>(
 	rpc_service: &S,
 	extensions: Extensions,
 ) -> MethodResponse
+where
+	S: RpcServiceT<
+			MethodResponse = MethodResponse,
+			BatchResponse = MethodResponse,
+			NotificationResponse = MethodResponse,
+		> + Send,
+{
+	let access_log = extensions.get::<AccessLogEntry>().cloned();
+
+	let rp = handle_rpc_call_inner(body, is_single, batch_config, rpc_service, extensions, access_log.as_ref()).await;
+
+	if let Some(entry) = access_log {
+		entry.record_response(rp.as_json().get().len());
+	}
+
+	rp
+}
+
+async fn handle_rpc_call_inner<S>(
+	body: &[u8],
+	is_single: bool,
+	batch_config: BatchRequestConfig,
+	rpc_service: &S,
+	extensions: Extensions,
+	access_log: Option<&AccessLogEntry>,
+) -> MethodResponse
 where
 	S: RpcServiceT<
 			MethodResponse = MethodResponse,
@@ -1251,8 +1980,14 @@ where
 	// Single request or notification
 	if is_single {
 		if let Ok(req) = deserialize_with_ext::call::from_slice(body, &extensions) {
+			if let Some(entry) = access_log {
+				entry.record_call(req.method_name(), req.params.as_deref());
+			}
 			rpc_service.call(req).await
 		} else if let Ok(notif) = deserialize_with_ext::notif::from_slice::<Notif>(body, &extensions) {
+			if let Some(entry) = access_log {
+				entry.record_call(&notif.method, notif.params.as_deref());
+			}
 			rpc_service.notification(notif).await
 		} else {
 			let (id, code) = prepare_error(body);
@@ -1280,10 +2015,20 @@ where
 
 			let mut batch = Vec::with_capacity(unchecked_batch.len());
 
+			if let Some(entry) = access_log {
+				entry.record_batch(unchecked_batch.len());
+			}
+
 			for call in unchecked_batch {
 				if let Ok(req) = deserialize_with_ext::call::from_str(call.get(), &extensions) {
+					if let Some(entry) = access_log {
+						entry.record_call(req.method_name(), req.params.as_deref());
+					}
 					batch.push(Ok(BatchEntry::Call(req)));
 				} else if let Ok(notif) = deserialize_with_ext::notif::from_str::<Notif>(call.get(), &extensions) {
+					if let Some(entry) = access_log {
+						entry.record_call(&notif.method, notif.params.as_deref());
+					}
 					batch.push(Ok(BatchEntry::Notification(notif)));
 				} else {
 					let id = match serde_json::from_str::<jsonrpsee_types::InvalidRequest>(call.get()) {
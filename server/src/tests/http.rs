@@ -28,8 +28,8 @@ use std::net::SocketAddr;
 
 use crate::types::Request;
 use crate::{
-	BatchRequestConfig, HttpBody, HttpRequest, HttpResponse, RegisterMethodError, RpcModule, ServerBuilder,
-	ServerConfig, ServerHandle,
+	BatchExecutionConfig, BatchRequestConfig, HttpBody, HttpRequest, HttpResponse, RegisterMethodError, RpcModule,
+	ServerBuilder, ServerConfig, ServerHandle,
 };
 use futures_util::future::{Future, FutureExt};
 use hyper::body::Bytes;
@@ -654,6 +654,146 @@ async fn batch_limit_works() {
 	handle.stopped().await;
 }
 
+#[tokio::test]
+async fn batch_concurrent_execution_works() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	init_logger();
+
+	#[derive(Default)]
+	struct Running {
+		now: AtomicUsize,
+		max: AtomicUsize,
+	}
+
+	for (batch_cfg, expected_parallel) in
+		[(BatchExecutionConfig::new(), 1), (BatchExecutionConfig::new().concurrent(2), 2)]
+	{
+		let config = ServerConfig::builder().set_batch_execution_config(batch_cfg).build();
+		let server = ServerBuilder::with_config(config).build("127.0.0.1:0").await.unwrap();
+		let running = std::sync::Arc::new(Running::default());
+		let mut module = RpcModule::new(running.clone());
+		module
+			.register_async_method("wait", |params, running, _| async move {
+				let now = running.now.fetch_add(1, Ordering::SeqCst) + 1;
+				running.max.fetch_max(now, Ordering::SeqCst);
+				tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+				running.now.fetch_sub(1, Ordering::SeqCst);
+				params.one::<usize>().unwrap()
+			})
+			.unwrap();
+		let addr = server.local_addr().unwrap();
+		let uri = to_http_uri(addr);
+		let handle = server.start(module);
+
+		let calls: Vec<_> =
+			(1..=6).map(|id| format!(r#"{{"jsonrpc":"2.0","method":"wait","params":[{id}],"id":{id}}}"#)).collect();
+		let req = format!("[{}]", calls.join(","));
+		let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+
+		// The responses are in the order of the batch.
+		let expected: Vec<_> = (1..=6).map(|id| ok_response(id.into(), Id::Num(id))).collect();
+		assert_eq!(response.body, format!("[{}]", expected.join(",")));
+		assert_eq!(running.max.load(Ordering::SeqCst), expected_parallel);
+
+		handle.stop().unwrap();
+		handle.stopped().await;
+	}
+}
+
+#[tokio::test]
+async fn batch_budget_works() {
+	use jsonrpsee_types::error::BATCH_BUDGET_EXCEEDED_CODE;
+	use std::time::Duration;
+
+	init_logger();
+
+	let configs = [
+		BatchExecutionConfig::new().cost(11, |req| if req.method_name() == "slow" { 10 } else { 1 }),
+		BatchExecutionConfig::new().timeout(Duration::from_millis(200)),
+		BatchExecutionConfig::new().max_response_bytes(1),
+	];
+
+	// The first call succeeds, the remaining calls aren't executed.
+	let req = r#"[
+		{"jsonrpc":"2.0","method":"slow","params":[1],"id":1},
+		{"jsonrpc":"2.0","method":"slow","params":[1000],"id":2},
+		{"jsonrpc":"2.0","method":"slow","params":[1000],"id":3}
+	]"#;
+
+	for batch_cfg in configs {
+		let config = ServerConfig::builder().set_batch_execution_config(batch_cfg).build();
+		let server = ServerBuilder::with_config(config).build("127.0.0.1:0").await.unwrap();
+		let mut module = RpcModule::new(());
+		module
+			.register_async_method("slow", |params, _, _| async move {
+				let ms = params.one::<u64>().unwrap();
+				tokio::time::sleep(Duration::from_millis(ms)).await;
+				ms
+			})
+			.unwrap();
+		let addr = server.local_addr().unwrap();
+		let uri = to_http_uri(addr);
+		let handle = server.start(module);
+
+		let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+		let rp: JsonValue = serde_json::from_str(&response.body).unwrap();
+		assert_eq!(rp[0]["result"], 1);
+		for (idx, id) in [(1, 2), (2, 3)] {
+			assert_eq!(rp[idx]["error"]["code"], BATCH_BUDGET_EXCEEDED_CODE);
+			assert_eq!(rp[idx]["id"], id);
+		}
+
+		handle.stop().unwrap();
+		handle.stopped().await;
+	}
+}
+
+#[tokio::test]
+async fn batch_budget_counts_notifications() {
+	use jsonrpsee_types::error::BATCH_BUDGET_EXCEEDED_CODE;
+
+	init_logger();
+
+	// The total cost doesn't overflow.
+	let batch_cfg = BatchExecutionConfig::new()
+		.cost(u64::MAX, |entry| if entry.method_name() == "expensive" { u64::MAX } else { 1 });
+	let config = ServerConfig::builder().set_batch_execution_config(batch_cfg).build();
+	let server = ServerBuilder::with_config(config).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("cheap", |_, _, _| "cheap").unwrap();
+	let addr = server.local_addr().unwrap();
+	let uri = to_http_uri(addr);
+	let handle = server.start(module);
+
+	// The notification exhausts the budget before the call is executed.
+	let req = r#"[
+		{"jsonrpc":"2.0","method":"expensive"},
+		{"jsonrpc":"2.0","method":"cheap","id":1}
+	]"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	let rp: JsonValue = serde_json::from_str(&response.body).unwrap();
+	assert_eq!(rp[0]["error"]["code"], BATCH_BUDGET_EXCEEDED_CODE);
+	assert_eq!(rp[0]["id"], 1);
+
+	// The entries after the one which exceeds the budget aren't executed either, even if they are cheaper.
+	let req = r#"[
+		{"jsonrpc":"2.0","method":"cheap","id":1},
+		{"jsonrpc":"2.0","method":"expensive","id":2},
+		{"jsonrpc":"2.0","method":"cheap","id":3}
+	]"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	let rp: JsonValue = serde_json::from_str(&response.body).unwrap();
+	assert_eq!(rp[0]["result"], "cheap");
+	for (idx, id) in [(1, 2), (2, 3)] {
+		assert_eq!(rp[idx]["error"]["code"], BATCH_BUDGET_EXCEEDED_CODE);
+		assert_eq!(rp[idx]["id"], id);
+	}
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+}
+
 #[tokio::test]
 async fn http2_method_call_works() {
 	init_logger();
@@ -727,3 +867,201 @@ async fn http_extensions_from_rpc_batch_response_overwrite() {
 	handle.stop().unwrap();
 	handle.stopped().await;
 }
+
+#[tokio::test]
+async fn http_rate_limit_works() {
+	use crate::middleware::rpc::{Quota, RateLimitLayer};
+	use jsonrpsee_types::error::RATE_LIMITED_CODE;
+
+	init_logger();
+
+	let rate_limit = RateLimitLayer::new().per_ip(Quota::per_minute(2)).weight("expensive", 2);
+	let server = ServerBuilder::default()
+		.set_rpc_middleware(RpcServiceBuilder::new().layer(rate_limit))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _ctx, _| "lo").unwrap();
+	module.register_method("expensive", |_, _ctx, _| "lo").unwrap();
+	let addr = server.local_addr().unwrap();
+	let uri = to_http_uri(addr);
+	let handle = server.start(module);
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, ok_response("lo".into(), Id::Num(1)));
+
+	// Each HTTP request is a new connection but the limit applies per IP.
+	let req = r#"{"jsonrpc":"2.0","method":"expensive","id":2}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+	assert_eq!(response.header.get(hyper::header::RETRY_AFTER).unwrap(), "30");
+	let rp: JsonValue = serde_json::from_str(&response.body).unwrap();
+	assert_eq!(rp["error"]["code"], RATE_LIMITED_CODE);
+	assert_eq!(rp["id"], 2);
+
+	let req = r#"[
+		{"jsonrpc":"2.0","method":"say_hello","id":3},
+		{"jsonrpc":"2.0","method":"say_hello","id":4}
+	]"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	let rp: JsonValue = serde_json::from_str(&response.body).unwrap();
+	assert_eq!(rp[0]["result"], "lo");
+	assert_eq!(rp[1]["error"]["code"], RATE_LIMITED_CODE);
+
+	// All calls in the batch are rejected.
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+}
+
+#[tokio::test]
+async fn http_method_timeout_works() {
+	use jsonrpsee_types::error::METHOD_TIMEOUT_CODE;
+	use std::time::Duration;
+
+	init_logger();
+
+	let cfg = ServerConfig::builder().set_method_timeout(Duration::from_millis(100)).build();
+	let server = ServerBuilder::with_config(cfg).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("slow", |_, _ctx, _| async {
+			tokio::time::sleep(Duration::from_secs(60)).await;
+			"never"
+		})
+		.unwrap();
+	module
+		.register_async_method("patient", |_, _ctx, _| async {
+			tokio::time::sleep(Duration::from_millis(300)).await;
+			"done"
+		})
+		.unwrap();
+	module.set_method_timeout("patient", Duration::from_secs(60)).unwrap();
+	module.register_blocking_method("blocking", |_, _ctx, _| std::thread::sleep(Duration::from_secs(1))).unwrap();
+	assert!(matches!(
+		module.set_method_timeout("unknown", Duration::from_secs(1)),
+		Err(RegisterMethodError::MethodNotFound(_))
+	));
+	let addr = server.local_addr().unwrap();
+	let uri = to_http_uri(addr);
+	let handle = server.start(module);
+
+	for (id, method) in [(1, "slow"), (2, "blocking")] {
+		let req = format!(r#"{{"jsonrpc":"2.0","method":"{method}","id":{id}}}"#);
+		let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+		let rp: JsonValue = serde_json::from_str(&response.body).unwrap();
+		assert_eq!(rp["error"]["code"], METHOD_TIMEOUT_CODE);
+		assert_eq!(rp["id"], id);
+	}
+
+	// The timeout of the method takes precedence over the default timeout.
+	let req = r#"{"jsonrpc":"2.0","method":"patient","id":3}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("done".into(), Id::Num(3)));
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+}
+
+#[tokio::test]
+async fn http_method_timeout_keeps_extensions() {
+	use std::time::Duration;
+
+	init_logger();
+
+	let server = ServerBuilder::default()
+		.set_config(ServerConfig::builder().set_method_timeout(Duration::from_millis(100)).build())
+		.set_rpc_middleware(RpcServiceBuilder::new().layer_fn(|service| InjectExt { service }))
+		.set_http_middleware(tower::ServiceBuilder::new().layer_fn(|service| ModifyHttpStatus { service }))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("slow_err", |_, _ctx, _| async {
+			tokio::time::sleep(Duration::from_secs(60)).await;
+			"never"
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let uri = to_http_uri(addr);
+	let handle = server.start(module);
+
+	let req = r#"{"jsonrpc":"2.0","method":"slow_err","id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::IM_A_TEAPOT);
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+}
+
+#[tokio::test]
+async fn http_required_roles_works() {
+	use crate::{Authenticator, Extensions, Principal};
+	use jsonrpsee_types::error::UNAUTHORIZED_CODE;
+
+	init_logger();
+
+	#[derive(Debug)]
+	struct LocalAuthenticator;
+
+	impl Authenticator for LocalAuthenticator {
+		fn authenticate(&self, _: &http::HeaderMap, ext: &Extensions) -> Option<Principal> {
+			let addr = ext.get::<SocketAddr>()?;
+			addr.ip().is_loopback().then(|| Principal::new("local").with_role("local"))
+		}
+	}
+
+	let mut module = RpcModule::new(());
+	module.register_method("needs_local", |_, _, ext| ext.get::<Principal>().unwrap().name().to_string()).unwrap();
+	module.register_method("needs_admin", |_, _, _| "never").unwrap();
+	module.register_method("public", |_, _, _| "ok").unwrap();
+	module.set_required_roles("needs_local", ["local"]).unwrap();
+	module.set_required_roles("needs_admin", ["local", "admin"]).unwrap();
+	module.register_alias("admin_alias", "needs_admin").unwrap();
+	assert!(matches!(module.set_required_roles("unknown", ["admin"]), Err(RegisterMethodError::MethodNotFound(_))));
+
+	let config = ServerConfig::builder().set_authenticator(LocalAuthenticator).build();
+	let server = ServerBuilder::with_config(config).build("127.0.0.1:0").await.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module.clone());
+
+	let call = |method: &str, id: u32| format!(r#"{{"jsonrpc":"2.0","method":"{method}","id":{id}}}"#);
+
+	let response =
+		http_request(call("needs_local", 1).into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("local".into(), Id::Num(1)));
+
+	for (id, method) in [(2, "needs_admin"), (3, "admin_alias")] {
+		let response =
+			http_request(call(method, id).into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+		let rp: JsonValue = serde_json::from_str(&response.body).unwrap();
+		assert_eq!(rp["error"]["code"], UNAUTHORIZED_CODE);
+		assert_eq!(rp["error"]["data"], "Missing role `admin`");
+	}
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+
+	// Without an authenticator all callers are anonymous.
+	let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module);
+
+	let response = http_request(call("public", 4).into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("ok".into(), Id::Num(4)));
+
+	let response = http_request(call("needs_local", 5).into(), uri).with_default_timeout().await.unwrap().unwrap();
+	let rp: JsonValue = serde_json::from_str(&response.body).unwrap();
+	assert_eq!(rp["error"]["code"], UNAUTHORIZED_CODE);
+	assert_eq!(rp["error"]["data"], "Authentication required");
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+}
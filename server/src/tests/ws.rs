@@ -918,3 +918,80 @@ async fn server_with_infinite_call(
 
 	(server.start(module), addr)
 }
+
+/// Server with a method that never completes and reports when it has started and when it was dropped.
+async fn server_with_pending_method()
+-> (std::net::SocketAddr, crate::ServerHandle, tokio::sync::mpsc::UnboundedReceiver<&'static str>) {
+	struct DropGuard(tokio::sync::mpsc::UnboundedSender<&'static str>);
+
+	impl Drop for DropGuard {
+		fn drop(&mut self) {
+			let _ = self.0.send("dropped");
+		}
+	}
+
+	let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+	let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(tx);
+	module
+		.register_async_method("pending", |_, tx, ext| async move {
+			assert!(ext.get::<crate::CancellationToken>().is_some());
+			let _guard = DropGuard((*tx).clone());
+			let _ = tx.send("started");
+			futures_util::future::pending::<()>().await;
+			"never"
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+
+	(addr, server.start(module), rx)
+}
+
+#[tokio::test]
+async fn cancel_request_works() {
+	init_logger();
+
+	let (addr, handle, mut rx) = server_with_pending_method().with_default_timeout().await.unwrap();
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	client.send(r#"{"jsonrpc":"2.0","method":"pending","id":1}"#).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(rx.recv().with_default_timeout().await.unwrap(), Some("started"));
+
+	// Cancelling an unknown call is ignored.
+	client
+		.send(r#"{"jsonrpc":"2.0","method":"$/cancelRequest","params":{"id":2}}"#)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	client
+		.send(r#"{"jsonrpc":"2.0","method":"$/cancelRequest","params":{"id":1}}"#)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	let response = client.receive().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32800,"message":"Request cancelled"}}"#);
+	assert_eq!(rx.recv().with_default_timeout().await.unwrap(), Some("dropped"));
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+}
+
+#[tokio::test]
+async fn pending_calls_are_cancelled_on_disconnect() {
+	init_logger();
+
+	let (addr, handle, mut rx) = server_with_pending_method().with_default_timeout().await.unwrap();
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	client.send(r#"{"jsonrpc":"2.0","method":"pending","id":1}"#).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(rx.recv().with_default_timeout().await.unwrap(), Some("started"));
+
+	client.close().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(rx.recv().with_default_timeout().await.unwrap(), Some("dropped"));
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+}
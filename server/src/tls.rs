@@ -0,0 +1,236 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! TLS termination for the server.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::crypto::{CryptoProvider, ring};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::danger::ClientCertVerifier;
+use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier};
+use tokio_rustls::rustls::sign::CertifiedKey;
+use tokio_rustls::rustls::{self, RootCertStore, ServerConfig};
+
+/// Error that can occur when configuring TLS.
+#[derive(Debug, thiserror::Error)]
+pub enum TlsError {
+	/// Invalid PEM input.
+	#[error("Invalid PEM: {0}")]
+	Pem(String),
+	/// No certificate was provided.
+	#[error("No certificate found")]
+	NoCertificate,
+	/// Invalid certificate or key.
+	#[error(transparent)]
+	Rustls(#[from] rustls::Error),
+	/// Invalid client certificate verification settings.
+	#[error(transparent)]
+	ClientVerifier(#[from] rustls::server::VerifierBuilderError),
+}
+
+/// TLS settings of the server.
+///
+/// Clones of the configuration share the certificate so reloading it with
+/// [`TlsConfig::reload_pem`] or [`TlsConfig::reload_der`] applies to all servers built with it.
+/// Established connections keep using the certificate they were accepted with.
+///
+/// # Examples
+///
+/// ```no_run
+/// use jsonrpsee_server::{Server, TlsConfig};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let cert = std::fs::read("server.pem").unwrap();
+///     let key = std::fs::read("server.key").unwrap();
+///     let tls = TlsConfig::from_pem(&cert, &key).unwrap();
+///
+///     let server = Server::builder().tls(tls.clone()).build("127.0.0.1:443").await.unwrap();
+///
+///     // Later on, for example when the certificate has been renewed.
+///     tls.reload_pem(&std::fs::read("server.pem").unwrap(), &std::fs::read("server.key").unwrap()).unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+	resolver: Arc<ReloadableCert>,
+	client_verifier: Option<Arc<dyn ClientCertVerifier>>,
+	pub(crate) handshake_timeout: Duration,
+}
+
+impl TlsConfig {
+	/// Create a new [`TlsConfig`] from a PEM encoded certificate chain and private key.
+	///
+	/// The first certificate of the chain must be the certificate of the server.
+	pub fn from_pem(cert_chain: &[u8], key: &[u8]) -> Result<Self, TlsError> {
+		let (cert_chain, key) = parse_pem(cert_chain, key)?;
+		Self::from_der(cert_chain, key)
+	}
+
+	/// Create a new [`TlsConfig`] from a DER encoded certificate chain and private key.
+	///
+	/// The first certificate of the chain must be the certificate of the server.
+	pub fn from_der(cert_chain: Vec<CertificateDer<'static>>, key: PrivateKeyDer<'static>) -> Result<Self, TlsError> {
+		let cert = certified_key(cert_chain, key)?;
+
+		Ok(Self {
+			resolver: Arc::new(ReloadableCert(RwLock::new(Arc::new(cert)))),
+			client_verifier: None,
+			handshake_timeout: Duration::from_secs(10),
+		})
+	}
+
+	/// Require clients to authenticate with a certificate issued by one of the `roots` (mutual TLS).
+	///
+	/// The certificates of the client are available to the methods as [`PeerCertificates`] in the extensions.
+	pub fn require_client_auth(self, roots: Vec<CertificateDer<'static>>) -> Result<Self, TlsError> {
+		self.client_auth(roots, true)
+	}
+
+	/// Similar to [`TlsConfig::require_client_auth`] but clients without a certificate are accepted as well.
+	pub fn allow_client_auth(self, roots: Vec<CertificateDer<'static>>) -> Result<Self, TlsError> {
+		self.client_auth(roots, false)
+	}
+
+	/// Set the maximum duration of the TLS handshake.
+	///
+	/// Default: 10 seconds.
+	pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+		self.handshake_timeout = timeout;
+		self
+	}
+
+	/// Replace the certificate chain and private key with PEM encoded ones.
+	pub fn reload_pem(&self, cert_chain: &[u8], key: &[u8]) -> Result<(), TlsError> {
+		let (cert_chain, key) = parse_pem(cert_chain, key)?;
+		self.reload_der(cert_chain, key)
+	}
+
+	/// Replace the certificate chain and private key with DER encoded ones.
+	pub fn reload_der(
+		&self,
+		cert_chain: Vec<CertificateDer<'static>>,
+		key: PrivateKeyDer<'static>,
+	) -> Result<(), TlsError> {
+		let cert = certified_key(cert_chain, key)?;
+		*self.resolver.0.write().expect("lock poisoned; qed") = Arc::new(cert);
+		Ok(())
+	}
+
+	/// Create the acceptor for the TLS connections.
+	pub(crate) fn acceptor(&self) -> TlsAcceptor {
+		let builder = ServerConfig::builder_with_provider(provider())
+			.with_safe_default_protocol_versions()
+			.expect("The default protocol versions are supported by the ring provider; qed");
+
+		let builder = match &self.client_verifier {
+			Some(verifier) => builder.with_client_cert_verifier(verifier.clone()),
+			None => builder.with_no_client_auth(),
+		};
+
+		let mut config = builder.with_cert_resolver(self.resolver.clone());
+		config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+		TlsAcceptor::from(Arc::new(config))
+	}
+
+	fn client_auth(mut self, roots: Vec<CertificateDer<'static>>, required: bool) -> Result<Self, TlsError> {
+		let mut store = RootCertStore::empty();
+		for root in roots {
+			store.add(root)?;
+		}
+
+		let builder = WebPkiClientVerifier::builder_with_provider(Arc::new(store), provider());
+		let verifier = if required { builder.build()? } else { builder.allow_unauthenticated().build()? };
+
+		self.client_verifier = Some(verifier);
+		Ok(self)
+	}
+}
+
+/// Certificate chain presented by the client during the TLS handshake.
+///
+/// It's inserted in the extensions of the requests when the client authenticated
+/// with a certificate, see [`TlsConfig::require_client_auth`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerCertificates(Arc<Vec<CertificateDer<'static>>>);
+
+impl PeerCertificates {
+	pub(crate) fn new(certs: Vec<CertificateDer<'static>>) -> Self {
+		Self(Arc::new(certs))
+	}
+
+	/// Get the certificate of the client.
+	pub fn end_entity(&self) -> &CertificateDer<'static> {
+		&self.0[0]
+	}
+
+	/// Get the whole certificate chain, starting with the certificate of the client.
+	pub fn chain(&self) -> &[CertificateDer<'static>] {
+		&self.0
+	}
+}
+
+/// Certificate resolver which allows to replace the certificate while the server is running.
+#[derive(Debug)]
+struct ReloadableCert(RwLock<Arc<CertifiedKey>>);
+
+impl ResolvesServerCert for ReloadableCert {
+	fn resolve(&self, _: ClientHello) -> Option<Arc<CertifiedKey>> {
+		Some(self.0.read().expect("lock poisoned; qed").clone())
+	}
+}
+
+fn provider() -> Arc<CryptoProvider> {
+	Arc::new(ring::default_provider())
+}
+
+fn certified_key(
+	cert_chain: Vec<CertificateDer<'static>>,
+	key: PrivateKeyDer<'static>,
+) -> Result<CertifiedKey, TlsError> {
+	if cert_chain.is_empty() {
+		return Err(TlsError::NoCertificate);
+	}
+
+	Ok(CertifiedKey::from_der(cert_chain, key, &provider())?)
+}
+
+fn parse_pem(
+	cert_chain: &[u8],
+	key: &[u8],
+) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), TlsError> {
+	let cert_chain = CertificateDer::pem_slice_iter(cert_chain)
+		.collect::<Result<Vec<_>, _>>()
+		.map_err(|e| TlsError::Pem(e.to_string()))?;
+	let key = PrivateKeyDer::from_pem_slice(key).map_err(|e| TlsError::Pem(e.to_string()))?;
+
+	Ok((cert_chain, key))
+}
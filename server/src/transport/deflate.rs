@@ -0,0 +1,134 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::DeflateConfig;
+
+use jsonrpsee_core::deflate::Inflater;
+use soketto::base::Header;
+use soketto::extension::{Extension, Param};
+use soketto::{BoxedError, Mode, Storage};
+
+const SERVER_MAX_WINDOW_BITS: &str = "server_max_window_bits";
+
+/// `permessage-deflate` extension which doesn't compress small messages
+/// and limits the window size used by the server.
+#[derive(Debug)]
+pub(crate) struct Deflate {
+	inner: soketto::extension::deflate::Deflate,
+	cfg: DeflateConfig,
+	inflater: Inflater,
+}
+
+impl Deflate {
+	pub(crate) fn new(cfg: DeflateConfig, max_message_size: usize) -> Self {
+		Self {
+			inner: soketto::extension::deflate::Deflate::new(Mode::Server),
+			cfg,
+			inflater: Inflater::new(max_message_size),
+		}
+	}
+}
+
+impl Extension for Deflate {
+	fn is_enabled(&self) -> bool {
+		self.inner.is_enabled()
+	}
+
+	fn name(&self) -> &str {
+		self.inner.name()
+	}
+
+	fn params(&self) -> &[Param<'_>] {
+		self.inner.params()
+	}
+
+	fn configure(&mut self, params: &[Param]) -> Result<(), BoxedError> {
+		let mut params: Vec<Param> = params.to_vec();
+
+		// The server may always reduce its own window size by answering with
+		// `server_max_window_bits` even if the client didn't offer it (RFC 7692, 7.1.2.1).
+		let max_window_bits = self.cfg.max_window_bits;
+		match params.iter_mut().find(|p| p.name() == SERVER_MAX_WINDOW_BITS) {
+			Some(p) if p.value().and_then(|v| v.parse::<u8>().ok()).is_none_or(|v| v > max_window_bits) => {
+				p.set_value(Some(max_window_bits.to_string()));
+			}
+			Some(_) => (),
+			None if max_window_bits < 15 => {
+				let mut p = Param::new(SERVER_MAX_WINDOW_BITS);
+				p.set_value(Some(max_window_bits.to_string()));
+				params.push(p);
+			}
+			None => (),
+		}
+
+		self.inner.configure(&params)
+	}
+
+	fn encode(&mut self, header: &mut Header, data: &mut Storage) -> Result<(), BoxedError> {
+		// Uncompressed messages are always allowed (RFC 7692, 6).
+		if data.as_ref().len() < self.cfg.threshold {
+			return Ok(());
+		}
+
+		self.inner.encode(header, data)
+	}
+
+	fn decode(&mut self, header: &mut Header, data: &mut Vec<u8>) -> Result<(), BoxedError> {
+		self.inflater.decode(header, data)
+	}
+
+	fn reserved_bits(&self) -> (bool, bool, bool) {
+		self.inner.reserved_bits()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use soketto::base::OpCode;
+
+	fn compress(msg: &[u8]) -> (Header, Vec<u8>) {
+		let mut deflate = Deflate::new(DeflateConfig::new().threshold(64), usize::MAX);
+		deflate.configure(&[]).unwrap();
+
+		let mut header = Header::new(OpCode::Text);
+		let mut data = Storage::Owned(msg.to_vec());
+		deflate.encode(&mut header, &mut data).unwrap();
+		(header, data.as_ref().to_vec())
+	}
+
+	#[test]
+	fn small_messages_are_not_compressed() {
+		let (header, data) = compress(b"small");
+		assert!(!header.is_rsv1());
+		assert_eq!(data, b"small");
+
+		let msg = "large".repeat(1000);
+		let (header, data) = compress(msg.as_bytes());
+		assert!(header.is_rsv1());
+		assert!(data.len() < msg.len() / 10);
+	}
+}
@@ -1,16 +1,27 @@
 use crate::{
-	BatchRequestConfig, ConnectionState, HttpRequest, HttpResponse, LOG_TARGET,
+	BatchRequestConfig, ConnectionState, HttpBody, HttpRequest, HttpResponse, LOG_TARGET,
 	middleware::rpc::{RpcService, RpcServiceCfg},
 	server::{ServerConfig, handle_rpc_call},
 };
+use std::time::Duration;
+
+use futures_util::{Stream, StreamExt, future, stream};
 use http::Method;
+use http_body::Frame;
+use http_body_util::StreamBody;
 use hyper::body::{Body, Bytes};
 use jsonrpsee_core::{
 	BoxError,
 	http_helpers::{HttpError, read_body},
 	middleware::{RpcServiceBuilder, RpcServiceT},
-	server::{MethodResponse, Methods},
+	server::{BoundedSubscriptions, MethodResponse, MethodSink, Methods},
 };
+use serde_json::value::RawValue;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{IntervalStream, ReceiverStream};
+
+/// Interval at which a comment is sent to keep Server-Sent Events streams alive.
+const SSE_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
 
 /// Checks that content type of received request is valid for JSON-RPC.
 pub fn content_type_is_json<T: Body>(request: &HttpRequest<T>) -> bool {
@@ -29,8 +40,30 @@ pub fn is_json(content_type: Option<&hyper::header::HeaderValue>) -> bool {
 	})
 }
 
+/// Checks whether the request accepts a `text/event-stream` response,
+/// in which case subscriptions are served as Server-Sent Events.
+///
+/// Wildcards such as `*/*` and media ranges with `q=0` are not regarded as accepting it.
+pub fn accepts_event_stream<T>(request: &HttpRequest<T>) -> bool {
+	request.headers().get_all(hyper::header::ACCEPT).iter().filter_map(|val| val.to_str().ok()).any(|accept| {
+		accept.split(',').any(|media| {
+			let mut parts = media.split(';').map(str::trim);
+			let is_event_stream = parts.next().is_some_and(|m| m.eq_ignore_ascii_case(response::EVENT_STREAM));
+			let rejected = parts.any(|param| {
+				param.split_once('=').is_some_and(|(name, q)| {
+					name.trim().eq_ignore_ascii_case("q") && q.trim().parse::<f32>().is_ok_and(|q| q == 0.0)
+				})
+			});
+			is_event_stream && !rejected
+		})
+	})
+}
+
 /// Make JSON-RPC HTTP call with a [`RpcServiceBuilder`]
 ///
+/// If the request accepts `text/event-stream` subscriptions are supported
+/// and streamed back as Server-Sent Events.
+///
 /// Fails if the HTTP request was a malformed JSON-RPC request.
 pub async fn call_with_service_builder<L, B>(
 	request: HttpRequest<B>,
@@ -50,11 +83,42 @@ where
 			NotificationResponse = MethodResponse,
 		> + Send,
 {
-	let ServerConfig { max_response_body_size, batch_requests_config, max_request_body_size, .. } = server_cfg;
+	if accepts_event_stream(&request) {
+		let (cfg, rx) = sse_service_cfg(&server_cfg);
+		let rpc_service = rpc_service.service(RpcService::new(
+			methods.into(),
+			server_cfg.max_response_body_size as usize,
+			server_cfg.method_timeout,
+			server_cfg.batch_execution_config.clone(),
+			conn.conn_id.into(),
+			cfg,
+		));
+
+		return call_with_event_stream(
+			request,
+			server_cfg.batch_requests_config,
+			server_cfg.max_request_body_size,
+			rpc_service,
+			rx,
+			conn,
+		)
+		.await;
+	}
+
+	let ServerConfig {
+		max_response_body_size,
+		batch_requests_config,
+		max_request_body_size,
+		method_timeout,
+		batch_execution_config,
+		..
+	} = server_cfg;
 
 	let rpc_service = rpc_service.service(RpcService::new(
 		methods.into(),
 		max_response_body_size as usize,
+		method_timeout,
+		batch_execution_config,
 		conn.conn_id.into(),
 		RpcServiceCfg::OnlyCalls,
 	));
@@ -112,16 +176,151 @@ where
 	}
 }
 
+/// Create the [`RpcServiceCfg`] for a HTTP request served as Server-Sent Events.
+///
+/// Subscription responses and notifications are sent to the returned receiver.
+pub(crate) fn sse_service_cfg(server_cfg: &ServerConfig) -> (RpcServiceCfg, mpsc::Receiver<Box<RawValue>>) {
+	let (tx, rx) = mpsc::channel(server_cfg.message_buffer_capacity as usize);
+	// The HTTP response is only sent once the call has been completed
+	// so there are no pending calls to wait for on shutdown.
+	let (pending_calls, _) = mpsc::channel(1);
+
+	let cfg = RpcServiceCfg::CallsAndSubscriptions {
+		bounded_subscriptions: BoundedSubscriptions::new(server_cfg.max_subscriptions_per_connection),
+		id_provider: server_cfg.id_provider.clone(),
+		sink: MethodSink::new(tx),
+		_pending_calls: pending_calls,
+	};
+
+	(cfg, rx)
+}
+
+/// Make JSON-RPC HTTP call where subscriptions are streamed back as Server-Sent Events.
+///
+/// Plain method calls, notifications and batches without subscriptions are answered like
+/// ordinary HTTP calls whereas subscriptions and batches with subscriptions are answered
+/// with a `text/event-stream` response where each JSON-RPC message is sent as a separate
+/// event. A keep-alive comment is sent periodically such that proxies don't close idle streams.
+///
+/// The stream ends once all subscriptions have been closed or the server is stopped.
+/// If the client closes the stream, the subscriptions are closed the same way as
+/// when a WebSocket connection is closed.
+pub(crate) async fn call_with_event_stream<S, B>(
+	request: HttpRequest<B>,
+	batch_config: BatchRequestConfig,
+	max_request_size: u32,
+	rpc_service: S,
+	rx: mpsc::Receiver<Box<RawValue>>,
+	conn: ConnectionState,
+) -> HttpResponse
+where
+	B: http_body::Body<Data = Bytes> + Send + 'static,
+	B::Data: Send,
+	B::Error: Into<BoxError>,
+	S: RpcServiceT<
+			MethodResponse = MethodResponse,
+			BatchResponse = MethodResponse,
+			NotificationResponse = MethodResponse,
+		> + Send,
+{
+	// Only the `POST` method is allowed.
+	match *request.method() {
+		Method::POST if content_type_is_json(&request) => {
+			let (parts, body) = request.into_parts();
+
+			let (body, is_single) = match read_body(&parts.headers, body, max_request_size).await {
+				Ok(r) => r,
+				Err(HttpError::TooLarge) => return response::too_large(max_request_size),
+				Err(HttpError::Malformed) => return response::malformed(),
+				Err(HttpError::Stream(e)) => {
+					tracing::warn!(target: LOG_TARGET, "Internal error reading request body: {}", e);
+					return response::internal_error();
+				}
+			};
+
+			let rp = handle_rpc_call(&body, is_single, batch_config, &rpc_service, parts.extensions).await;
+
+			// The stream must end once the subscriptions are closed and
+			// the service holds a sink to the stream.
+			drop(rpc_service);
+
+			// All sinks are dropped if the batch didn't start any subscriptions.
+			let has_subscriptions = !rx.is_closed() || !rx.is_empty();
+
+			let is_stream = rp.is_subscription() || (rp.is_batch() && has_subscriptions);
+
+			if !is_stream {
+				return response::from_method_response(rp);
+			}
+
+			// The subscription response has already been sent to the stream by the subscription
+			// itself whereas the batch response is sent before any queued messages.
+			let is_batch = rp.is_batch();
+			let (json, _, extensions) = rp.into_parts();
+			let first = (is_batch && !json.get().is_empty()).then_some(json);
+			let stop = conn.stop_handle.clone().shutdown();
+
+			let events = sse_events(first, rx, SSE_KEEP_ALIVE_INTERVAL).take_until(stop).map(move |event| {
+				// The connection guard must be held until the stream is closed
+				// to respect the `max_connections` limit.
+				let _conn = &conn;
+				Ok::<_, BoxError>(Frame::data(event))
+			});
+
+			let mut rp = response::event_stream(HttpBody::new(StreamBody::new(events)));
+			rp.extensions_mut().extend(extensions);
+			rp
+		}
+		// Error scenarios:
+		Method::POST => response::unsupported_content_type(),
+		_ => response::method_not_allowed(),
+	}
+}
+
+/// Server-Sent Events of `first` and the messages received on `rx` where
+/// a keep-alive comment is interleaved every `keep_alive`.
+///
+/// The stream ends once `rx` is closed.
+fn sse_events(
+	first: Option<Box<RawValue>>,
+	rx: mpsc::Receiver<Box<RawValue>>,
+	keep_alive: Duration,
+) -> impl Stream<Item = Bytes> {
+	let messages = stream::iter(first).chain(ReceiverStream::new(rx)).map(|msg| Some(sse_event(msg.get())));
+	let start = tokio::time::Instant::now() + keep_alive;
+	let keep_alive = IntervalStream::new(tokio::time::interval_at(start, keep_alive))
+		.map(|_| Some(Bytes::from_static(b": keep-alive\n\n")));
+
+	// The keep-alive stream never ends so the end of the messages is marked with `None`.
+	stream::select(messages.chain(stream::once(future::ready(None))), keep_alive)
+		.take_while(|event| future::ready(event.is_some()))
+		.filter_map(future::ready)
+}
+
+/// Encode `msg` as an event where each line is prefixed with `data:`.
+fn sse_event(msg: &str) -> Bytes {
+	let mut event = String::with_capacity(msg.len() + 8);
+	for line in msg.split(['\n', '\r']) {
+		event.push_str("data: ");
+		event.push_str(line);
+		event.push('\n');
+	}
+	event.push('\n');
+	Bytes::from(event)
+}
+
 /// HTTP response helpers.
 pub mod response {
 	use jsonrpsee_core::server::MethodResponse;
 	use jsonrpsee_types::error::{ErrorCode, reject_too_big_request};
 	use jsonrpsee_types::{ErrorObject, ErrorObjectOwned, Id, Response, ResponsePayload};
 
+	use crate::middleware::rpc::RateLimited;
 	use crate::{HttpBody, HttpResponse};
 
 	const JSON: &str = "application/json; charset=utf-8";
 	const TEXT: &str = "text/plain";
+	pub(crate) const EVENT_STREAM: &str = "text/event-stream";
 
 	/// Create a response for json internal error.
 	pub fn internal_error() -> HttpResponse {
@@ -143,6 +342,11 @@ pub mod response {
 		from_template(hyper::StatusCode::FORBIDDEN, "Provided Host header is not whitelisted.\n", TEXT)
 	}
 
+	/// Create a text/plain response for not allowed origins.
+	pub fn origin_not_allowed() -> HttpResponse {
+		from_template(hyper::StatusCode::FORBIDDEN, "Provided Origin header is not allowed.\n", TEXT)
+	}
+
 	/// Create a text/plain response for disallowed method used.
 	pub fn method_not_allowed() -> HttpResponse {
 		from_template(
@@ -189,12 +393,27 @@ pub mod response {
 		from_template(hyper::StatusCode::OK, body, JSON)
 	}
 
+	/// Create a `text/event-stream` response for Server-Sent Events.
+	pub fn event_stream(body: impl Into<HttpBody>) -> HttpResponse {
+		let mut rp = from_template(hyper::StatusCode::OK, body, EVENT_STREAM);
+		rp.headers_mut().insert(hyper::header::CACHE_CONTROL, hyper::header::HeaderValue::from_static("no-cache"));
+		rp
+	}
+
 	/// Create a response from a method response.
 	///
 	/// This will include the body and extensions from the method response.
+	///
+	/// Responses rejected by the [`RateLimit`](crate::middleware::rpc::RateLimit) middleware
+	/// are sent with the status code `429 Too Many Requests`.
 	pub fn from_method_response(rp: MethodResponse) -> HttpResponse {
 		let (body, _, extensions) = rp.into_parts();
-		let mut rp = from_template(hyper::StatusCode::OK, String::from(Box::<str>::from(body)), JSON);
+		let body = String::from(Box::<str>::from(body));
+
+		let mut rp = match extensions.get::<RateLimited>() {
+			Some(rate_limited) => too_many_requests_retry_after(body, rate_limited.retry_after),
+			None => from_template(hyper::StatusCode::OK, body, JSON),
+		};
 		rp.extensions_mut().extend(extensions);
 		rp
 	}
@@ -213,8 +432,71 @@ pub mod response {
 		from_template(hyper::StatusCode::TOO_MANY_REQUESTS, "Too many connections. Please try again later.", TEXT)
 	}
 
+	/// Create a response for when a request was rate limited, where `body` is the JSON-RPC response
+	/// and the client may retry after `retry_after`.
+	pub fn too_many_requests_retry_after(body: impl Into<HttpBody>, retry_after: std::time::Duration) -> HttpResponse {
+		let mut rp = from_template(hyper::StatusCode::TOO_MANY_REQUESTS, body, JSON);
+		// `Retry-After` is in whole seconds so round up.
+		let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+		rp.headers_mut().insert(hyper::header::RETRY_AFTER, secs.into());
+		rp
+	}
+
 	/// Create a response for when the server denied the request.
 	pub fn denied() -> HttpResponse {
 		from_template(hyper::StatusCode::FORBIDDEN, HttpBody::default(), TEXT)
 	}
+
+	/// Create a response for when the request is missing valid credentials.
+	pub fn unauthorized() -> HttpResponse {
+		let mut rp = from_template(hyper::StatusCode::UNAUTHORIZED, "Invalid or missing bearer token.\n", TEXT);
+		rp.headers_mut().insert(hyper::header::WWW_AUTHENTICATE, hyper::header::HeaderValue::from_static("Bearer"));
+		rp
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn request_with_accept(accept: &str) -> HttpRequest<()> {
+		HttpRequest::builder().header(hyper::header::ACCEPT, accept).body(()).unwrap()
+	}
+
+	#[test]
+	fn accepts_event_stream_works() {
+		assert!(accepts_event_stream(&request_with_accept("text/event-stream")));
+		assert!(accepts_event_stream(&request_with_accept("application/json, text/event-stream;q=0.5")));
+		assert!(!accepts_event_stream(&request_with_accept("application/json")));
+		assert!(!accepts_event_stream(&request_with_accept("*/*")));
+		assert!(!accepts_event_stream(&request_with_accept("application/json, text/event-stream; q=0")));
+	}
+
+	#[test]
+	fn multi_line_messages_are_prefixed() {
+		assert_eq!(sse_event(r#"{"a":1}"#), "data: {\"a\":1}\n\n");
+		assert_eq!(sse_event("{\n  \"a\": 1\r\n}"), "data: {\ndata:   \"a\": 1\ndata: \ndata: }\n\n");
+	}
+
+	#[tokio::test]
+	async fn keep_alive_is_sent_until_the_stream_ends() {
+		let (tx, rx) = mpsc::channel(4);
+		let mut events = Box::pin(sse_events(None, rx, Duration::from_millis(10)));
+
+		tx.send(RawValue::from_string("1".into()).unwrap()).await.unwrap();
+		assert_eq!(events.next().await.unwrap(), "data: 1\n\n");
+		assert_eq!(events.next().await.unwrap(), ": keep-alive\n\n");
+		assert_eq!(events.next().await.unwrap(), ": keep-alive\n\n");
+
+		drop(tx);
+		assert!(events.next().await.is_none());
+	}
+
+	#[test]
+	fn retry_after_is_rounded_up_to_seconds() {
+		let rp = response::too_many_requests_retry_after("{}", Duration::from_millis(1500));
+		assert_eq!(rp.status(), hyper::StatusCode::TOO_MANY_REQUESTS);
+		assert_eq!(rp.headers()[hyper::header::RETRY_AFTER], "2");
+		assert_eq!(rp.headers()[hyper::header::CONTENT_TYPE], "application/json; charset=utf-8");
+	}
 }
@@ -0,0 +1,236 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Newline-delimited JSON-RPC over a raw stream, as used by IPC endpoints.
+
+use std::sync::Arc;
+
+use crate::middleware::rpc::PendingCalls;
+use crate::server::{ConnectionState, ServerConfig, handle_rpc_call};
+use crate::transport::ws::Shutdown;
+use crate::{Extensions, LOG_TARGET};
+
+use futures_util::StreamExt;
+use futures_util::future::{self, Either};
+use jsonrpsee_core::middleware::RpcServiceT;
+use jsonrpsee_core::server::{MethodResponse, MethodSink};
+use jsonrpsee_types::Id;
+use jsonrpsee_types::error::{ErrorCode, reject_too_big_request};
+use serde_json::value::RawValue;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::codec::{FramedRead, LinesCodec, LinesCodecError};
+
+pub(crate) async fn send_message<W>(writer: &mut W, response: Box<RawValue>) -> std::io::Result<()>
+where
+	W: AsyncWrite + Unpin,
+{
+	let mut msg = String::from(Box::<str>::from(response));
+	msg.push('\n');
+	writer.write_all(msg.as_bytes()).await?;
+	writer.flush().await
+}
+
+pub(crate) struct BackgroundTaskParams<S, T> {
+	pub(crate) server_cfg: ServerConfig,
+	pub(crate) conn: ConnectionState,
+	pub(crate) stream: T,
+	pub(crate) rpc_service: S,
+	pub(crate) sink: MethodSink,
+	pub(crate) rx: mpsc::Receiver<Box<RawValue>>,
+	pub(crate) pending_calls_completed: mpsc::Receiver<()>,
+	pub(crate) extensions: Extensions,
+}
+
+/// Serve a connection where each JSON-RPC message is terminated by a newline.
+///
+/// Method calls, batches and subscriptions are supported since the stream is full-duplex.
+pub(crate) async fn background_task<S, T>(params: BackgroundTaskParams<S, T>)
+where
+	S: RpcServiceT<
+			MethodResponse = MethodResponse,
+			BatchResponse = MethodResponse,
+			NotificationResponse = MethodResponse,
+		> + Send
+		+ Sync
+		+ 'static,
+	T: AsyncRead + AsyncWrite + Send + 'static,
+{
+	let BackgroundTaskParams {
+		server_cfg,
+		conn,
+		stream,
+		rpc_service,
+		sink,
+		rx,
+		pending_calls_completed,
+		mut extensions,
+	} = params;
+	let ServerConfig { batch_requests_config, max_request_body_size, .. } = server_cfg;
+
+	let (reader, writer) = tokio::io::split(stream);
+	let (conn_tx, conn_rx) = oneshot::channel();
+
+	// Spawn another task that sends out the responses on the socket.
+	let send_task_handle = tokio::spawn(send_task(rx, writer, conn_rx));
+
+	let mut lines = FramedRead::new(reader, LinesCodec::new_with_max_length(max_request_body_size as usize));
+	let stopped = conn.stop_handle.clone().shutdown();
+	let rpc_service = Arc::new(rpc_service);
+	let pending_calls = PendingCalls::default();
+	extensions.insert(pending_calls.clone());
+
+	tokio::pin!(stopped);
+
+	let result = loop {
+		let line = match future::select(lines.next(), stopped).await {
+			Either::Left((Some(Ok(line)), s)) => {
+				stopped = s;
+				line
+			}
+			Either::Left((Some(Err(LinesCodecError::MaxLineLengthExceeded)), s)) => {
+				stopped = s;
+				tracing::debug!(target: LOG_TARGET, "IPC recv error: message too large max={}", max_request_body_size);
+				if sink.send_error(Id::Null, reject_too_big_request(max_request_body_size)).await.is_err() {
+					break Shutdown::ConnectionClosed;
+				}
+				continue;
+			}
+			Either::Left((Some(Err(LinesCodecError::Io(err))), _)) => {
+				tracing::debug!(target: LOG_TARGET, "IPC error: {}; terminate connection: {}", err, conn.conn_id);
+				break Shutdown::ConnectionClosed;
+			}
+			Either::Left((None, _)) => break Shutdown::ConnectionClosed,
+			Either::Right(_) => break Shutdown::Stopped,
+		};
+
+		let data = line.trim();
+
+		// Empty lines are treated as keep-alive and ignored.
+		if data.is_empty() {
+			continue;
+		}
+
+		let is_single = match data.as_bytes()[0] {
+			b'{' => true,
+			b'[' => false,
+			_ => {
+				if sink.send_error(Id::Null, ErrorCode::ParseError.into()).await.is_err() {
+					break Shutdown::ConnectionClosed;
+				}
+				continue;
+			}
+		};
+
+		let rpc_service = rpc_service.clone();
+		let sink = sink.clone();
+		let extensions = extensions.clone();
+
+		tokio::spawn(async move {
+			let rp =
+				handle_rpc_call(line.trim().as_bytes(), is_single, batch_requests_config, &*rpc_service, extensions)
+					.await;
+
+			// Subscriptions are handled by the subscription callback and
+			// "ordinary notifications" should not be sent back to the client.
+			if rp.is_method_call() || rp.is_batch() {
+				let is_success = rp.is_success();
+				let (json, mut on_close, _) = rp.into_parts();
+
+				// The connection is closed, just quit.
+				if sink.send(json).await.is_err() {
+					return;
+				}
+
+				if let Some(n) = on_close.take() {
+					n.notify(is_success);
+				}
+			}
+		});
+	};
+
+	// Drive all running methods to completion.
+	// **NOTE** Do not return early in this function. This `await` needs to run to guarantee
+	// proper drop behaviour.
+	drop(rpc_service);
+	// Nobody will read the responses of the pending calls if the connection was closed.
+	if let Shutdown::ConnectionClosed = result {
+		pending_calls.cancel_all();
+	}
+	graceful_shutdown(result, pending_calls_completed, conn_tx, send_task_handle).await;
+
+	drop(conn);
+}
+
+/// A task that waits for new messages via the `rx channel` and writes them to the socket.
+async fn send_task<W>(mut rx: mpsc::Receiver<Box<RawValue>>, mut writer: W, mut stop: oneshot::Receiver<()>)
+where
+	W: AsyncWrite + Unpin,
+{
+	loop {
+		tokio::select! {
+			response = rx.recv() => {
+				let Some(response) = response else {
+					break;
+				};
+
+				if let Err(err) = send_message(&mut writer, response).await {
+					tracing::debug!(target: LOG_TARGET, "IPC send error: {}", err);
+					break;
+				}
+			}
+			_ = &mut stop => break,
+		}
+	}
+
+	let _ = writer.shutdown().await;
+	rx.close();
+}
+
+/// Enforce a graceful shutdown.
+///
+/// This will return once the connection has been terminated or all pending calls have been executed.
+async fn graceful_shutdown(
+	result: Shutdown,
+	pending_calls: mpsc::Receiver<()>,
+	mut conn_tx: oneshot::Sender<()>,
+	send_task_handle: tokio::task::JoinHandle<()>,
+) {
+	if let Shutdown::Stopped = result {
+		let graceful_shutdown = ReceiverStream::new(pending_calls).for_each(|_| async {});
+
+		tokio::select! {
+			_ = graceful_shutdown => {}
+			_ = conn_tx.closed() => {}
+		}
+	}
+
+	// Send a message to close down the "send task".
+	_ = conn_tx.send(());
+	// Ensure that send task has been closed.
+	_ = send_task_handle.await;
+}
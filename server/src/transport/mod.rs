@@ -1,4 +1,12 @@
+/// WebSocket `permessage-deflate` extension.
+#[cfg(feature = "deflate")]
+pub(crate) mod deflate;
 /// HTTP related server functionality.
 pub mod http;
+/// Newline-delimited JSON-RPC over Unix domain sockets.
+#[cfg(unix)]
+pub(crate) mod ipc;
+/// Stream of an accepted connection.
+pub(crate) mod stream;
 /// WebSocket related server functionality.
 pub mod ws;
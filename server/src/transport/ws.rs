@@ -2,9 +2,10 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use crate::future::{IntervalStream, SessionClose};
-use crate::middleware::rpc::{RpcService, RpcServiceCfg};
+use crate::middleware::http::AccessLogEntry;
+use crate::middleware::rpc::{PendingCalls, RpcService, RpcServiceCfg};
 use crate::server::{ConnectionState, ServerConfig, handle_rpc_call};
-use crate::{HttpBody, HttpRequest, HttpResponse, LOG_TARGET, PingConfig};
+use crate::{ClientHandle, HttpBody, HttpRequest, HttpResponse, LOG_TARGET, PingConfig};
 
 use futures_util::future::{self, Either};
 use futures_util::io::{BufReader, BufWriter};
@@ -33,6 +34,23 @@ enum Incoming {
 	Pong,
 }
 
+/// Create the server side of the WebSocket handshake with the extensions enabled by the config.
+pub(crate) fn handshake_server(server_cfg: &ServerConfig) -> soketto::handshake::http::Server {
+	#[allow(unused_mut)]
+	let mut server = soketto::handshake::http::Server::new();
+
+	#[cfg(feature = "deflate")]
+	if let Some(cfg) = server_cfg.deflate_config {
+		let max_message_size = server_cfg.max_request_body_size as usize;
+		server.add_extension(Box::new(crate::transport::deflate::Deflate::new(cfg, max_message_size)));
+	}
+
+	#[cfg(not(feature = "deflate"))]
+	let _ = server_cfg;
+
+	server
+}
+
 pub(crate) async fn send_message(sender: &mut Sender, response: Box<RawValue>) -> Result<(), SokettoError> {
 	sender.send_text_owned(String::from(Box::<str>::from(response))).await?;
 	sender.flush().await
@@ -81,9 +99,9 @@ where
 		rx,
 		pending_calls_completed,
 		mut on_session_close,
-		extensions,
+		mut extensions,
 	} = params;
-	let ServerConfig { ping_config, batch_requests_config, max_request_body_size, .. } = server_cfg;
+	let ServerConfig { ping_config, batch_requests_config, max_request_body_size, client_requests, .. } = server_cfg;
 
 	let (conn_tx, conn_rx) = oneshot::channel();
 
@@ -93,6 +111,12 @@ where
 	let stopped = conn.stop_handle.clone().shutdown();
 	let rpc_service = Arc::new(rpc_service);
 	let mut missed_pings = 0;
+	let pending_calls = PendingCalls::default();
+	extensions.insert(pending_calls.clone());
+	let client_handle = client_requests.then(|| ClientHandle::new(sink.clone()));
+	if let Some(handle) = &client_handle {
+		extensions.insert(handle.clone());
+	}
 
 	tokio::pin!(stopped);
 
@@ -139,6 +163,15 @@ where
 
 						continue;
 					}
+					#[cfg(feature = "deflate")]
+					SokettoError::Extension(err) if err.is::<jsonrpsee_core::deflate::MessageTooLarge>() => {
+						tracing::debug!(target: LOG_TARGET, "WS recv error: {}", err);
+						if sink.send_error(Id::Null, reject_too_big_request(max_request_body_size)).await.is_err() {
+							break Ok(Shutdown::ConnectionClosed);
+						}
+
+						continue;
+					}
 					err => {
 						tracing::debug!(target: LOG_TARGET, "WS error: {}; terminate connection: {}", err, conn.conn_id);
 						break Err(err);
@@ -147,9 +180,14 @@ where
 			}
 		};
 
+		// Responses of the client to requests sent with the `ClientHandle`.
+		if client_handle.as_ref().is_some_and(|handle| handle.on_response(&data)) {
+			continue;
+		}
+
 		let rpc_service = rpc_service.clone();
 		let sink = sink.clone();
-		let extensions = extensions.clone();
+		let mut extensions = extensions.clone();
 
 		tokio::spawn(async move {
 			let first_non_whitespace = data.iter().enumerate().take(128).find(|(_, byte)| !byte.is_ascii_whitespace());
@@ -163,8 +201,19 @@ where
 				}
 			};
 
+			// If enabled, each message has its own access log record and the record of the session has no calls.
+			let access_log = extensions.get::<AccessLogEntry>().and_then(AccessLogEntry::message);
+			if let Some(entry) = &access_log {
+				extensions.insert(entry.clone());
+			}
+
 			let rp = handle_rpc_call(&data[idx..], is_single, batch_requests_config, &*rpc_service, extensions).await;
 
+			// The extensions may be kept by subscriptions, so the record is written right away.
+			if let Some(entry) = access_log {
+				entry.finish();
+			}
+
 			// Subscriptions are handled by the subscription callback and
 			// "ordinary notifications" should not be sent back to the client.
 			if rp.is_method_call() || rp.is_batch() {
@@ -189,6 +238,13 @@ where
 	// **NOTE** Do not return early in this function. This `await` needs to run to guarantee
 	// proper drop behaviour.
 	drop(rpc_service);
+	// Nobody will read the responses of the pending calls if the connection was closed.
+	if !matches!(result, Ok(Shutdown::Stopped)) {
+		pending_calls.cancel_all();
+	}
+	if let Some(handle) = client_handle {
+		handle.close();
+	}
 	graceful_shutdown(result, pending_calls_completed, ws_stream, conn_tx, send_task_handle).await;
 
 	drop(conn);
@@ -435,7 +491,7 @@ where
 		+ Sync
 		+ 'static,
 {
-	let mut server = soketto::handshake::http::Server::new();
+	let mut server = handshake_server(&server_cfg);
 
 	match server.receive_request(&req) {
 		Ok(response) => {
@@ -457,6 +513,8 @@ where
 			let rpc_service = RpcService::new(
 				methods.into(),
 				server_cfg.max_response_body_size as usize,
+				server_cfg.method_timeout,
+				server_cfg.batch_execution_config.clone(),
 				conn.conn_id.into(),
 				rpc_service_cfg,
 			);
@@ -37,14 +37,23 @@ use pin_project::pin_project;
 use tower::ServiceExt;
 use tower::util::Oneshot;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub(crate) struct TowerToHyperService<S> {
 	service: S,
+	/// Extensions of the connection which are added to every request.
+	extensions: http::Extensions,
 }
 
 impl<S> TowerToHyperService<S> {
 	pub(crate) fn new(service: S) -> Self {
-		Self { service }
+		Self { service, extensions: http::Extensions::new() }
+	}
+
+	/// Add the `extensions` to every request served on the connection.
+	#[cfg(feature = "tls")]
+	pub(crate) fn with_extensions(mut self, extensions: http::Extensions) -> Self {
+		self.extensions = extensions;
+		self
 	}
 }
 
@@ -57,7 +66,8 @@ where
 	type Future = TowerToHyperServiceFuture<S, HttpRequest>;
 
 	fn call(&self, req: HttpRequest<hyper::body::Incoming>) -> Self::Future {
-		let req = req.map(HttpBody::new);
+		let mut req = req.map(HttpBody::new);
+		req.extensions_mut().extend(self.extensions.clone());
 		TowerToHyperServiceFuture { future: self.service.clone().oneshot(req) }
 	}
 }
@@ -142,7 +152,67 @@ where
 }
 
 /// Deserialize calls, notifications and responses with HTTP extensions.
+///
+/// The trace context of the reserved `traceparent` member of the messages is read while deserializing
+/// them and inserted as [`RemoteTraceContext`] in the extensions, it replaces the one of the `traceparent`
+/// HTTP header.
+///
+/// [`RemoteTraceContext`]: jsonrpsee_core::middleware::layer::RemoteTraceContext
 pub mod deserialize_with_ext {
+	use std::borrow::Cow;
+
+	use jsonrpsee_core::middleware::layer::{RemoteTraceContext, TraceContext};
+	use jsonrpsee_types::{Id, Notification, Request, TwoPointZero};
+	use serde::Deserialize;
+	use serde_json::value::RawValue;
+
+	/// Method call with the reserved `traceparent` member.
+	#[derive(Deserialize)]
+	struct Call<'a> {
+		jsonrpc: TwoPointZero,
+		#[serde(borrow)]
+		id: Id<'a>,
+		#[serde(borrow)]
+		method: Cow<'a, str>,
+		#[serde(borrow)]
+		params: Option<Cow<'a, RawValue>>,
+		#[serde(borrow)]
+		traceparent: Option<Cow<'a, str>>,
+	}
+
+	impl<'a> Call<'a> {
+		fn into_request(self, extensions: &http::Extensions) -> Request<'a> {
+			let mut extensions = extensions.clone();
+			insert_trace_context(self.traceparent, &mut extensions);
+			Request { jsonrpc: self.jsonrpc, id: self.id, method: self.method, params: self.params, extensions }
+		}
+	}
+
+	/// Notification with the reserved `traceparent` member.
+	#[derive(Deserialize)]
+	struct Notif<'a, T> {
+		jsonrpc: TwoPointZero,
+		#[serde(borrow)]
+		method: Cow<'a, str>,
+		params: T,
+		#[serde(borrow)]
+		traceparent: Option<Cow<'a, str>>,
+	}
+
+	impl<'a, T> Notif<'a, T> {
+		fn into_notification(self, extensions: &http::Extensions) -> Notification<'a, T> {
+			let mut extensions = extensions.clone();
+			insert_trace_context(self.traceparent, &mut extensions);
+			Notification { jsonrpc: self.jsonrpc, method: self.method, params: self.params, extensions }
+		}
+	}
+
+	fn insert_trace_context(traceparent: Option<Cow<str>>, extensions: &mut http::Extensions) {
+		if let Some(cx) = traceparent.as_deref().and_then(TraceContext::from_traceparent) {
+			extensions.insert(RemoteTraceContext(cx));
+		}
+	}
+
 	/// Method call.
 	pub mod call {
 		use jsonrpsee_types::Request;
@@ -152,16 +222,14 @@ pub mod deserialize_with_ext {
 			data: &'a [u8],
 			extensions: &'a http::Extensions,
 		) -> Result<Request<'a>, serde_json::Error> {
-			let mut req: Request = serde_json::from_slice(data)?;
-			*req.extensions_mut() = extensions.clone();
-			Ok(req)
+			let call: super::Call = serde_json::from_slice(data)?;
+			Ok(call.into_request(extensions))
 		}
 
 		/// Wrapper over `serde_json::from_str` that sets the extensions.
 		pub fn from_str<'a>(data: &'a str, extensions: &'a http::Extensions) -> Result<Request<'a>, serde_json::Error> {
-			let mut req: Request = serde_json::from_str(data)?;
-			*req.extensions_mut() = extensions.clone();
-			Ok(req)
+			let call: super::Call = serde_json::from_str(data)?;
+			Ok(call.into_request(extensions))
 		}
 	}
 
@@ -177,9 +245,8 @@ pub mod deserialize_with_ext {
 		where
 			T: serde::Deserialize<'a>,
 		{
-			let mut notif: Notification<T> = serde_json::from_slice(data)?;
-			*notif.extensions_mut() = extensions.clone();
-			Ok(notif)
+			let notif: super::Notif<T> = serde_json::from_slice(data)?;
+			Ok(notif.into_notification(extensions))
 		}
 
 		/// Wrapper over `serde_json::from_str` that sets the extensions.
@@ -190,9 +257,8 @@ pub mod deserialize_with_ext {
 		where
 			T: serde::Deserialize<'a>,
 		{
-			let mut notif: Notification<T> = serde_json::from_str(data)?;
-			*notif.extensions_mut() = extensions.clone();
-			Ok(notif)
+			let notif: super::Notif<T> = serde_json::from_str(data)?;
+			Ok(notif.into_notification(extensions))
 		}
 	}
 }
@@ -289,6 +289,243 @@ async fn http_method_call_works() {
 	assert_eq!(&response, "hello");
 }
 
+#[tokio::test]
+async fn http2_prior_knowledge_method_call_works() {
+	use jsonrpsee::http_client::{Http2Config, HttpResponse};
+
+	init_logger();
+
+	let server_addr = server().await;
+	let uri = format!("http://{}", server_addr);
+	let versions = Arc::new(std::sync::Mutex::new(Vec::new()));
+	let versions2 = versions.clone();
+	let middleware = tower::ServiceBuilder::new().map_response(move |rp: HttpResponse<hyper::body::Incoming>| {
+		versions2.lock().unwrap().push(rp.version());
+		rp
+	});
+	let client = HttpClientBuilder::default()
+		.enable_http2(Http2Config::new().keep_alive_interval(Duration::from_secs(10)).initial_max_send_streams(16))
+		.pool_max_idle_per_host(1)
+		.pool_idle_timeout(Duration::from_secs(30))
+		.set_http_middleware(middleware)
+		.build(&uri)
+		.unwrap();
+
+	let calls: FuturesUnordered<_> =
+		(0..32).map(|_| client.request::<String, ArrayParams>("say_hello", rpc_params![])).collect();
+	let responses: Vec<String> = calls.try_collect().await.unwrap();
+
+	assert!(responses.iter().all(|r| r == "hello"));
+	assert_eq!(versions.lock().unwrap().len(), 32);
+	assert!(versions.lock().unwrap().iter().all(|v| *v == hyper::Version::HTTP_2));
+}
+
+const TLS_CA: &[u8] = include_bytes!("certs/ca.pem");
+const TLS_SERVER_CERT: &[u8] = include_bytes!("certs/server.pem");
+const TLS_SERVER_KEY: &[u8] = include_bytes!("certs/server.key");
+const TLS_OTHER_CERT: &[u8] = include_bytes!("certs/other.pem");
+const TLS_OTHER_KEY: &[u8] = include_bytes!("certs/other.key");
+const TLS_CLIENT_CERT: &[u8] = include_bytes!("certs/client.pem");
+const TLS_CLIENT_KEY: &[u8] = include_bytes!("certs/client.key");
+
+fn pem_certs(pem: &[u8]) -> Vec<rustls::pki_types::CertificateDer<'static>> {
+	use rustls::pki_types::pem::PemObject;
+
+	rustls::pki_types::CertificateDer::pem_slice_iter(pem).collect::<Result<_, _>>().unwrap()
+}
+
+fn tls_client_config(client_auth: bool) -> rustls::ClientConfig {
+	use rustls::pki_types::PrivateKeyDer;
+	use rustls::pki_types::pem::PemObject;
+
+	let mut roots = rustls::RootCertStore::empty();
+	roots.add_parsable_certificates(pem_certs(TLS_CA));
+
+	let builder = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+		.with_safe_default_protocol_versions()
+		.unwrap()
+		.with_root_certificates(roots);
+
+	if client_auth {
+		let key = PrivateKeyDer::from_pem_slice(TLS_CLIENT_KEY).unwrap();
+		builder.with_client_auth_cert(pem_certs(TLS_CLIENT_CERT), key).unwrap()
+	} else {
+		builder.with_no_client_auth()
+	}
+}
+
+async fn tls_server(tls: jsonrpsee::server::TlsConfig) -> (SocketAddr, ServerHandle) {
+	use jsonrpsee::server::PeerCertificates;
+
+	let server = ServerBuilder::default().tls(tls).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	module.register_method("has_peer_cert", |_, _, ext| ext.get::<PeerCertificates>().is_some()).unwrap();
+
+	let addr = server.local_addr().unwrap();
+	(addr, server.start(module))
+}
+
+#[tokio::test]
+async fn tls_server_works() {
+	use jsonrpsee::server::TlsConfig;
+
+	init_logger();
+
+	let (addr, _handle) = tls_server(TlsConfig::from_pem(TLS_SERVER_CERT, TLS_SERVER_KEY).unwrap()).await;
+
+	let http_client = HttpClientBuilder::default()
+		.with_custom_cert_store(tls_client_config(false))
+		.build(format!("https://{addr}"))
+		.unwrap();
+	let response: String = http_client.request("say_hello", rpc_params![]).await.unwrap();
+	assert_eq!(response, "hello");
+
+	let ws_client = WsClientBuilder::default()
+		.with_custom_cert_store(tls_client_config(false))
+		.build(format!("wss://{addr}"))
+		.await
+		.unwrap();
+	let response: String = ws_client.request("say_hello", rpc_params![]).await.unwrap();
+	assert_eq!(response, "hello");
+
+	// Plaintext connections are rejected.
+	let plain_client = HttpClientBuilder::default().build(format!("http://{addr}")).unwrap();
+	assert!(plain_client.request::<String, _>("say_hello", rpc_params![]).await.is_err());
+}
+
+#[tokio::test]
+async fn tls_handshake_is_aborted_on_stop() {
+	use jsonrpsee::server::TlsConfig;
+
+	init_logger();
+
+	let tls = TlsConfig::from_pem(TLS_SERVER_CERT, TLS_SERVER_KEY).unwrap().handshake_timeout(Duration::from_secs(60));
+	let (addr, handle) = tls_server(tls).await;
+
+	// The client never sends its hello.
+	let _socket = tokio::net::TcpStream::connect(addr).await.unwrap();
+	tokio::time::sleep(Duration::from_millis(100)).await;
+
+	handle.stop().unwrap();
+	assert!(tokio::time::timeout(Duration::from_secs(5), handle.stopped()).await.is_ok());
+}
+
+#[tokio::test]
+async fn tls_certificate_reload_works() {
+	use jsonrpsee::server::TlsConfig;
+
+	init_logger();
+
+	// The certificate is issued for another host name and is rejected by the client.
+	let tls = TlsConfig::from_pem(TLS_OTHER_CERT, TLS_OTHER_KEY).unwrap();
+	let (addr, _handle) = tls_server(tls.clone()).await;
+	let uri = format!("https://{addr}");
+
+	let client = HttpClientBuilder::default().with_custom_cert_store(tls_client_config(false)).build(&uri).unwrap();
+	assert!(client.request::<String, _>("say_hello", rpc_params![]).await.is_err());
+
+	tls.reload_pem(TLS_SERVER_CERT, TLS_SERVER_KEY).unwrap();
+
+	let client = HttpClientBuilder::default().with_custom_cert_store(tls_client_config(false)).build(&uri).unwrap();
+	let response: String = client.request("say_hello", rpc_params![]).await.unwrap();
+	assert_eq!(response, "hello");
+}
+
+#[tokio::test]
+async fn tls_client_auth_works() {
+	use jsonrpsee::server::TlsConfig;
+
+	init_logger();
+
+	let tls =
+		TlsConfig::from_pem(TLS_SERVER_CERT, TLS_SERVER_KEY).unwrap().require_client_auth(pem_certs(TLS_CA)).unwrap();
+	let (addr, _handle) = tls_server(tls).await;
+	let uri = format!("https://{addr}");
+
+	let client = HttpClientBuilder::default().with_custom_cert_store(tls_client_config(true)).build(&uri).unwrap();
+	let has_peer_cert: bool = client.request("has_peer_cert", rpc_params![]).await.unwrap();
+	assert!(has_peer_cert);
+
+	let client = HttpClientBuilder::default().with_custom_cert_store(tls_client_config(false)).build(&uri).unwrap();
+	assert!(client.request::<bool, _>("has_peer_cert", rpc_params![]).await.is_err());
+}
+
+#[tokio::test]
+async fn client_certificate_works() {
+	use jsonrpsee::client_transport::ws::WsHandshakeError;
+	use jsonrpsee::http_client::ClientCertificate as HttpClientCertificate;
+	use jsonrpsee::http_client::transport::Error as HttpTransportError;
+	use jsonrpsee::server::TlsConfig;
+	use jsonrpsee::ws_client::ClientCertificate as WsClientCertificate;
+
+	init_logger();
+
+	let tls =
+		TlsConfig::from_pem(TLS_SERVER_CERT, TLS_SERVER_KEY).unwrap().require_client_auth(pem_certs(TLS_CA)).unwrap();
+	let (addr, _handle) = tls_server(tls).await;
+
+	let http_client = HttpClientBuilder::default()
+		.with_custom_cert_store(tls_client_config(false))
+		.with_client_certificate(HttpClientCertificate::from_pem(TLS_CLIENT_CERT, TLS_CLIENT_KEY).unwrap())
+		.build(format!("https://{addr}"))
+		.unwrap();
+	let has_peer_cert: bool = http_client.request("has_peer_cert", rpc_params![]).await.unwrap();
+	assert!(has_peer_cert);
+
+	let ws_client = WsClientBuilder::default()
+		.with_custom_cert_store(tls_client_config(false))
+		.with_client_certificate(WsClientCertificate::from_pem(TLS_CLIENT_CERT, TLS_CLIENT_KEY).unwrap())
+		.build(format!("wss://{addr}"))
+		.await
+		.unwrap();
+	let has_peer_cert: bool = ws_client.request("has_peer_cert", rpc_params![]).await.unwrap();
+	assert!(has_peer_cert);
+
+	// The server requires a client certificate.
+	let err = WsClientBuilder::default()
+		.with_custom_cert_store(tls_client_config(false))
+		.build(format!("wss://{addr}"))
+		.await
+		.unwrap_err();
+	let Error::Transport(err) = err else { panic!("Expected transport error, got: {err:?}") };
+	assert!(matches!(
+		err.downcast_ref::<WsHandshakeError>(),
+		Some(WsHandshakeError::ClientCertificateRejected(rustls::AlertDescription::CertificateRequired))
+	));
+
+	// The certificate isn't issued by the CA the server trusts.
+	let err = WsClientBuilder::default()
+		.with_custom_cert_store(tls_client_config(false))
+		.with_client_certificate(WsClientCertificate::from_pem(TLS_OTHER_CERT, TLS_OTHER_KEY).unwrap())
+		.build(format!("wss://{addr}"))
+		.await
+		.unwrap_err();
+	let Error::Transport(err) = err else { panic!("Expected transport error, got: {err:?}") };
+	assert!(matches!(err.downcast_ref::<WsHandshakeError>(), Some(WsHandshakeError::ClientCertificateRejected(_))));
+
+	// The HTTP client reports the rejections with the same distinct error.
+	let http_client = HttpClientBuilder::default()
+		.with_custom_cert_store(tls_client_config(false))
+		.build(format!("https://{addr}"))
+		.unwrap();
+	let err = http_client.request::<bool, _>("has_peer_cert", rpc_params![]).await.unwrap_err();
+	let Error::Transport(err) = err else { panic!("Expected transport error, got: {err:?}") };
+	assert!(matches!(
+		err.downcast_ref::<HttpTransportError>(),
+		Some(HttpTransportError::ClientCertificateRejected(rustls::AlertDescription::CertificateRequired))
+	));
+
+	let http_client = HttpClientBuilder::default()
+		.with_custom_cert_store(tls_client_config(false))
+		.with_client_certificate(HttpClientCertificate::from_pem(TLS_OTHER_CERT, TLS_OTHER_KEY).unwrap())
+		.build(format!("https://{addr}"))
+		.unwrap();
+	let err = http_client.request::<bool, _>("has_peer_cert", rpc_params![]).await.unwrap_err();
+	let Error::Transport(err) = err else { panic!("Expected transport error, got: {err:?}") };
+	assert!(matches!(err.downcast_ref::<HttpTransportError>(), Some(HttpTransportError::ClientCertificateRejected(_))));
+}
+
 #[tokio::test]
 async fn http_method_call_str_id_works() {
 	init_logger();
@@ -861,6 +1098,205 @@ async fn ws_server_limit_subs_per_conn_works() {
 	);
 }
 
+#[tokio::test]
+async fn ws_resumable_subscription_works() {
+	use jsonrpsee::core::client::SubscriptionKind;
+	use jsonrpsee::core::to_json_raw_value;
+	use jsonrpsee::server::ReplayConfig;
+	use jsonrpsee::types::Sequenced;
+	use jsonrpsee::types::error::SUBSCRIPTION_NOT_RESUMABLE_CODE;
+
+	init_logger();
+
+	let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+
+	let mut module = RpcModule::new(());
+	for (sub, unsub, resume, config) in [
+		("subscribe_counter", "unsubscribe_counter", "resume_counter", ReplayConfig::new()),
+		(
+			"subscribe_short",
+			"unsubscribe_short",
+			"resume_short",
+			ReplayConfig::new().grace_period(Duration::from_millis(50)),
+		),
+		("subscribe_none", "unsubscribe_none", "resume_none", ReplayConfig::new().max_detached(0)),
+	] {
+		module
+			.register_resumable_subscription(sub, "counter", unsub, resume, config, |_, pending, _, _| async move {
+				let sink = pending.accept().await?;
+				for i in 0_u64.. {
+					sink.send(to_json_raw_value(&i)?).await?;
+					tokio::time::sleep(Duration::from_millis(10)).await;
+				}
+				Ok(())
+			})
+			.unwrap();
+	}
+	let _handle = server.start(module);
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let mut sub = client
+		.subscribe::<Sequenced<u64>, ArrayParams>("subscribe_counter", rpc_params![], "unsubscribe_counter")
+		.await
+		.unwrap();
+	for seq in 1..=3 {
+		assert_eq!(sub.next().await.unwrap().unwrap(), Sequenced { seq, value: seq - 1 });
+	}
+	let SubscriptionKind::Subscription(sub_id) = sub.kind().clone() else { panic!("Expected a subscription ID") };
+
+	// The messages sent while the client is disconnected are buffered.
+	// The subscription is dropped after the connection is closed, otherwise the client unsubscribes.
+	drop(client);
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	drop(sub);
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let mut sub = client
+		.subscribe::<Sequenced<u64>, ArrayParams>("resume_counter", rpc_params![&sub_id, 3], "unsubscribe_counter")
+		.await
+		.unwrap();
+	for seq in 4..=20 {
+		assert_eq!(sub.next().await.unwrap().unwrap(), Sequenced { seq, value: seq - 1 });
+	}
+
+	// The subscription is continued on the new connection.
+	let unsubscribed: bool = client.request("unsubscribe_counter", rpc_params![&sub_id]).await.unwrap();
+	assert!(unsubscribed);
+
+	let err = client
+		.subscribe::<Sequenced<u64>, ArrayParams>("resume_counter", rpc_params![&sub_id, 20], "unsubscribe_counter")
+		.await
+		.unwrap_err();
+	assert!(matches!(err, Error::Call(e) if e.code() == SUBSCRIPTION_NOT_RESUMABLE_CODE));
+
+	// The subscription expires if it's not resumed within the grace period.
+	let mut sub = client
+		.subscribe::<Sequenced<u64>, ArrayParams>("subscribe_short", rpc_params![], "unsubscribe_short")
+		.await
+		.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap().seq, 1);
+	let SubscriptionKind::Subscription(sub_id) = sub.kind().clone() else { panic!("Expected a subscription ID") };
+	drop(client);
+	tokio::time::sleep(Duration::from_millis(300)).await;
+	drop(sub);
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let err = client
+		.subscribe::<Sequenced<u64>, ArrayParams>("resume_short", rpc_params![&sub_id, 1], "unsubscribe_short")
+		.await
+		.unwrap_err();
+	assert!(matches!(err, Error::Call(e) if e.code() == SUBSCRIPTION_NOT_RESUMABLE_CODE));
+
+	// The subscription is closed right away if too many subscriptions are detached.
+	let mut sub = client
+		.subscribe::<Sequenced<u64>, ArrayParams>("subscribe_none", rpc_params![], "unsubscribe_none")
+		.await
+		.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap().seq, 1);
+	let SubscriptionKind::Subscription(sub_id) = sub.kind().clone() else { panic!("Expected a subscription ID") };
+	drop(client);
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	drop(sub);
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let err = client
+		.subscribe::<Sequenced<u64>, ArrayParams>("resume_none", rpc_params![&sub_id, 1], "unsubscribe_none")
+		.await
+		.unwrap_err();
+	assert!(matches!(err, Error::Call(e) if e.code() == SUBSCRIPTION_NOT_RESUMABLE_CODE));
+}
+
+#[tokio::test]
+async fn ws_resumable_subscription_is_protected() {
+	use jsonrpsee::core::client::SubscriptionKind;
+	use jsonrpsee::core::to_json_raw_value;
+	use jsonrpsee::server::{Authenticator, Extensions, Principal, ReplayConfig};
+	use jsonrpsee::types::Sequenced;
+	use jsonrpsee::types::error::{SUBSCRIPTION_NOT_RESUMABLE_CODE, UNAUTHORIZED_CODE};
+
+	/// Authenticates the caller named in the `x-user` header with the `ops` role.
+	#[derive(Debug)]
+	struct HeaderAuthenticator;
+
+	impl Authenticator for HeaderAuthenticator {
+		fn authenticate(&self, headers: &hyper::HeaderMap, _: &Extensions) -> Option<Principal> {
+			let name = headers.get("x-user")?.to_str().ok()?;
+			Some(Principal::new(name).with_role("ops"))
+		}
+	}
+
+	init_logger();
+
+	let config = ServerConfig::builder().set_authenticator(HeaderAuthenticator).build();
+	let server = ServerBuilder::with_config(config).build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+
+	let mut module = RpcModule::new(());
+	module
+		.register_resumable_subscription(
+			"subscribe_counter",
+			"counter",
+			"unsubscribe_counter",
+			"resume_counter",
+			ReplayConfig::new(),
+			|_, pending, _, _| async move {
+				let sink = pending.accept().await?;
+				for i in 0_u64.. {
+					sink.send(to_json_raw_value(&i)?).await?;
+					tokio::time::sleep(Duration::from_millis(10)).await;
+				}
+				Ok(())
+			},
+		)
+		.unwrap();
+	module.set_required_roles("subscribe_counter", ["ops"]).unwrap();
+	assert_eq!(module.required_roles("resume_counter"), Some(&["ops"][..]));
+	let _handle = server.start(module);
+
+	let client = |user: Option<&'static str>| {
+		let mut headers = hyper::HeaderMap::new();
+		if let Some(user) = user {
+			headers.insert("x-user", HeaderValue::from_static(user));
+		}
+		WsClientBuilder::default().set_headers(headers).build(&server_url)
+	};
+
+	let alice = client(Some("alice")).await.unwrap();
+	let mut sub = alice
+		.subscribe::<Sequenced<u64>, ArrayParams>("subscribe_counter", rpc_params![], "unsubscribe_counter")
+		.await
+		.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap().seq, 1);
+	let SubscriptionKind::Subscription(sub_id) = sub.kind().clone() else { panic!("Expected a subscription ID") };
+	drop(alice);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	drop(sub);
+
+	// Anonymous callers lack the roles of the subscribe method.
+	let anonymous = client(None).await.unwrap();
+	let err = anonymous
+		.subscribe::<Sequenced<u64>, ArrayParams>("resume_counter", rpc_params![&sub_id, 1], "unsubscribe_counter")
+		.await
+		.unwrap_err();
+	assert!(matches!(err, Error::Call(e) if e.code() == UNAUTHORIZED_CODE));
+
+	// Other principals can't take over the subscription.
+	let mallory = client(Some("mallory")).await.unwrap();
+	let err = mallory
+		.subscribe::<Sequenced<u64>, ArrayParams>("resume_counter", rpc_params![&sub_id, 1], "unsubscribe_counter")
+		.await
+		.unwrap_err();
+	assert!(matches!(err, Error::Call(e) if e.code() == SUBSCRIPTION_NOT_RESUMABLE_CODE));
+
+	let alice = client(Some("alice")).await.unwrap();
+	let mut sub = alice
+		.subscribe::<Sequenced<u64>, ArrayParams>("resume_counter", rpc_params![&sub_id, 1], "unsubscribe_counter")
+		.await
+		.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), Sequenced { seq: 2, value: 1 });
+}
+
 #[tokio::test]
 async fn ws_server_unsub_methods_should_ignore_sub_limit() {
 	use futures::StreamExt;
@@ -1057,6 +1493,141 @@ fn comma_separated_header_values(headers: &hyper::HeaderMap, header: &str) -> Ve
 		.collect()
 }
 
+#[tokio::test]
+async fn origin_policy_works() {
+	use hyper::{Method, Request, StatusCode};
+	use hyper_util::client::legacy::Client;
+	use jsonrpsee::server::OriginPolicy;
+
+	init_logger();
+
+	let policy = OriginPolicy::allow_only(["https://foo.com", "https://*.bar.com"]).unwrap().allow_credentials();
+	let server = ServerBuilder::default()
+		.set_config(ServerConfig::builder().set_origin_policy(policy).build())
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	let _handle = server.start(module);
+
+	let http_client = Client::builder(TokioExecutor::new()).build_http();
+	let uri = format!("http://{}", addr);
+
+	let preflight_req = Request::builder()
+		.method(Method::OPTIONS)
+		.uri(&uri)
+		.header("origin", "https://api.bar.com")
+		.header("access-control-request-method", "POST")
+		.header("access-control-request-headers", "content-type")
+		.body(HttpBody::default())
+		.unwrap();
+	let res = http_client.request(preflight_req).await.unwrap();
+	assert_eq!(res.status(), StatusCode::NO_CONTENT);
+	assert_eq!(comma_separated_header_values(res.headers(), "access-control-allow-origin"), ["https://api.bar.com"]);
+	assert_eq!(comma_separated_header_values(res.headers(), "access-control-allow-methods"), ["get", "post"]);
+	assert_eq!(comma_separated_header_values(res.headers(), "access-control-allow-headers"), ["content-type"]);
+	assert_eq!(comma_separated_header_values(res.headers(), "access-control-allow-credentials"), ["true"]);
+
+	let call = |origin: &'static str| {
+		Request::builder()
+			.method(Method::POST)
+			.uri(&uri)
+			.header("origin", origin)
+			.header("content-type", "application/json")
+			.body(HttpBody::from(r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#))
+			.unwrap()
+	};
+
+	let res = http_client.request(call("https://foo.com")).await.unwrap();
+	assert_eq!(res.status(), StatusCode::OK);
+	assert_eq!(comma_separated_header_values(res.headers(), "access-control-allow-origin"), ["https://foo.com"]);
+	assert_eq!(comma_separated_header_values(res.headers(), "vary"), ["origin"]);
+
+	let res = http_client.request(call("https://evil.com")).await.unwrap();
+	assert_eq!(res.status(), StatusCode::FORBIDDEN);
+	assert!(res.headers().get("access-control-allow-origin").is_none());
+
+	// Clients which are not browsers don't send an origin.
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	assert_eq!(client.request::<String, _>("say_hello", rpc_params![]).await.unwrap(), "hello");
+
+	let ws_client = |origin: &'static str| {
+		let mut headers = hyper::HeaderMap::new();
+		headers.insert(hyper::header::ORIGIN, HeaderValue::from_static(origin));
+		WsClientBuilder::default().set_headers(headers).build(format!("ws://{}", addr))
+	};
+
+	let client = ws_client("https://foo.com").await.unwrap();
+	assert_eq!(client.request::<String, _>("say_hello", rpc_params![]).await.unwrap(), "hello");
+	assert!(ws_client("https://bar.com").await.is_err());
+}
+
+#[tokio::test]
+async fn origin_policy_applies_before_http_middleware() {
+	use hyper::{Method, Request, StatusCode};
+	use hyper_util::client::legacy::Client;
+	use jsonrpsee::server::OriginPolicy;
+	use jsonrpsee::server::middleware::http::AuthLayer;
+
+	init_logger();
+
+	let policy = OriginPolicy::allow_only(["https://foo.com"])
+		.unwrap()
+		.allow_headers([hyper::header::CONTENT_TYPE, hyper::header::AUTHORIZATION]);
+	let server = ServerBuilder::default()
+		.set_config(ServerConfig::builder().set_origin_policy(policy).build())
+		.set_http_middleware(tower::ServiceBuilder::new().layer(AuthLayer::hs256(b"secret")))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	let _handle = server.start(module);
+
+	let http_client = Client::builder(TokioExecutor::new()).build_http();
+	let uri = format!("http://{}", addr);
+
+	// Preflight requests don't have credentials and are answered before the authentication.
+	let preflight_req = Request::builder()
+		.method(Method::OPTIONS)
+		.uri(&uri)
+		.header("origin", "https://foo.com")
+		.header("access-control-request-method", "POST")
+		.header("access-control-request-headers", "authorization, content-type")
+		.body(HttpBody::default())
+		.unwrap();
+	let res = http_client.request(preflight_req).await.unwrap();
+	assert_eq!(res.status(), StatusCode::NO_CONTENT);
+	assert_eq!(comma_separated_header_values(res.headers(), "access-control-allow-origin"), ["https://foo.com"]);
+	assert_eq!(
+		comma_separated_header_values(res.headers(), "access-control-allow-headers"),
+		["content-type", "authorization"]
+	);
+
+	let call = |origin: &'static str| {
+		Request::builder()
+			.method(Method::POST)
+			.uri(&uri)
+			.header("origin", origin)
+			.header("content-type", "application/json")
+			.body(HttpBody::from(r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#))
+			.unwrap()
+	};
+
+	// The rejections of the HTTP middleware have the CORS headers, such that browsers can read them.
+	let res = http_client.request(call("https://foo.com")).await.unwrap();
+	assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+	assert_eq!(comma_separated_header_values(res.headers(), "access-control-allow-origin"), ["https://foo.com"]);
+
+	// Denied origins are rejected before the authentication.
+	let res = http_client.request(call("https://evil.com")).await.unwrap();
+	assert_eq!(res.status(), StatusCode::FORBIDDEN);
+	assert!(res.headers().get("access-control-allow-origin").is_none());
+}
+
 #[tokio::test]
 async fn ws_subscribe_with_bad_params() {
 	init_logger();
@@ -1095,18 +1666,194 @@ async fn http_health_api_works() {
 }
 
 #[tokio::test]
-async fn ws_host_filtering_wildcard_works() {
-	use jsonrpsee::server::*;
+async fn http_proxy_routes_work() {
+	use hyper::{Method, Request, StatusCode};
+	use hyper_util::client::legacy::Client;
+	use jsonrpsee::server::middleware::http::{ProxyGetRequestLayer, ProxyResponseFormat, ProxyRoute};
 
 	init_logger();
 
-	let middleware =
-		tower::ServiceBuilder::new().layer(HostFilterLayer::new(["http://localhost:*", "http://127.0.0.1:*"]).unwrap());
-
-	let server = ServerBuilder::default().set_http_middleware(middleware).build("127.0.0.1:0").await.unwrap();
+	let proxy = ProxyGetRequestLayer::from_routes([
+		ProxyRoute::get("/block/{number}", "get_block").positional(["number", "full"]).query_params(),
+		ProxyRoute::new(Method::POST, "/accounts/{name}", "set_account").response_format(ProxyResponseFormat::Envelope),
+		ProxyRoute::get("/missing", "does_not_exist"),
+	])
+	.unwrap()
+	.error_status(-32099, StatusCode::NOT_FOUND);
+
+	let server = ServerBuilder::default()
+		.set_http_middleware(tower::ServiceBuilder::new().layer(proxy))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
 	let mut module = RpcModule::new(());
 	let addr = server.local_addr().unwrap();
-	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	module
+		.register_method("get_block", |params, _, _| {
+			let (number, full): (u64, Option<bool>) = params.parse()?;
+			if number > 100 {
+				return Err(ErrorObject::owned(-32099, "Unknown block", None::<()>));
+			}
+			Ok(serde_json::json!({ "number": number, "full": full.unwrap_or(false) }))
+		})
+		.unwrap();
+	module.register_method("set_account", |params, _, _| params.parse::<JsonValue>().unwrap()).unwrap();
+
+	let _handle = server.start(module);
+
+	let http_client = Client::builder(TokioExecutor::new()).build_http();
+	let request = |method: Method, path: &str, content_type: &'static str, body: &'static str| {
+		let req = Request::builder()
+			.method(method)
+			.uri(format!("http://{}{}", addr, path))
+			.header(hyper::header::CONTENT_TYPE, content_type)
+			.body(HttpBody::from(body))
+			.expect("request builder");
+		let res = http_client.request(req);
+		async move {
+			let res = res.await.unwrap();
+			let status = res.status();
+			let bytes = res.into_body().collect().await.unwrap().to_bytes();
+			(status, String::from_utf8(bytes.to_vec()).unwrap())
+		}
+	};
+
+	let (status, body) = request(Method::GET, "/block/42?full=true", "application/json", "").await;
+	assert_eq!(status, StatusCode::OK);
+	assert_eq!(body, r#"{"full":true,"number":42}"#);
+
+	let (status, body) = request(Method::GET, "/block/101", "application/json", "").await;
+	assert_eq!(status, StatusCode::NOT_FOUND);
+	assert_eq!(body, r#"{"code":-32099,"message":"Unknown block"}"#);
+
+	let (status, _) = request(Method::GET, "/block/latest", "application/json", "").await;
+	assert_eq!(status, StatusCode::BAD_REQUEST);
+
+	let (status, _) = request(Method::GET, "/missing", "application/json", "").await;
+	assert_eq!(status, StatusCode::NOT_FOUND);
+
+	let (status, body) = request(Method::POST, "/accounts/alice", "application/json", r#"{"balance":1}"#).await;
+	assert_eq!(status, StatusCode::OK);
+	assert_eq!(
+		serde_json::from_str::<JsonValue>(&body).unwrap(),
+		serde_json::json!({ "jsonrpc": "2.0", "id": 0, "result": { "name": "alice", "balance": 1 } })
+	);
+
+	let (status, _) = request(Method::POST, "/accounts/alice", "application/json", "[1]").await;
+	assert_eq!(status, StatusCode::BAD_REQUEST);
+
+	// A body which isn't declared as JSON is rejected, such as the one of a cross-origin form.
+	let (status, _) = request(Method::POST, "/accounts/alice", "text/plain", r#"{"balance":1}"#).await;
+	assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+	// Requests which don't match a route are not proxied.
+	let (status, _) = request(Method::GET, "/accounts/alice", "application/json", "").await;
+	assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[tokio::test]
+async fn http_sse_subscription_works() {
+	use hyper::Request;
+	use hyper_util::client::legacy::Client;
+
+	init_logger();
+
+	let (tx, mut rx) = futures::channel::mpsc::channel(1);
+	let server_addr = server_with_sleeping_subscription(tx).await;
+
+	let http_client = Client::builder(TokioExecutor::new()).build_http();
+	let uri = format!("http://{}", server_addr);
+	let body = r#"{"jsonrpc":"2.0","method":"subscribe_sleep","params":[],"id":1}"#;
+
+	let req = Request::post(&uri)
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.header(hyper::header::ACCEPT, "application/json, text/event-stream")
+		.body(HttpBody::from(body))
+		.expect("request builder");
+	let res = http_client.request(req).await.unwrap();
+
+	assert!(res.status().is_success());
+	assert_eq!(res.headers().get(hyper::header::CONTENT_TYPE).unwrap(), "text/event-stream");
+
+	let mut body = res.into_body();
+	let mut events = String::new();
+
+	while events.matches("\n\n").count() < 2 {
+		let frame = body.frame().with_default_timeout().await.unwrap().unwrap().unwrap();
+		events.push_str(std::str::from_utf8(frame.data_ref().unwrap()).unwrap());
+	}
+
+	let events: Vec<JsonValue> = events
+		.split_terminator("\n\n")
+		.map(|event| serde_json::from_str(event.strip_prefix("data: ").unwrap()).unwrap())
+		.collect();
+
+	let sub_id = events[0]["result"].clone();
+	assert_eq!(events[0]["id"], 1);
+	assert_eq!(events[1]["method"], "n");
+	assert_eq!(events[1]["params"]["subscription"], sub_id);
+	assert_eq!(events[1]["params"]["result"], 1);
+
+	// Closing the stream must close the subscription.
+	drop(body);
+
+	let res = rx.next().with_default_timeout().await.expect("Test must complete in 1 min");
+	assert!(res.is_some());
+}
+
+#[tokio::test]
+async fn http_sse_method_call_is_plain_json() {
+	use hyper::Request;
+	use hyper_util::client::legacy::Client;
+
+	init_logger();
+
+	let server_addr = server_with_subscription().await;
+
+	let http_client = Client::builder(TokioExecutor::new()).build_http();
+	let uri = format!("http://{}", server_addr);
+	let body = r#"{"jsonrpc":"2.0","method":"say_hello","params":[],"id":1}"#;
+
+	let req = Request::post(&uri)
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.header(hyper::header::ACCEPT, "text/event-stream")
+		.body(HttpBody::from(body))
+		.expect("request builder");
+	let res = http_client.request(req).await.unwrap();
+
+	assert!(res.status().is_success());
+	assert_eq!(res.headers().get(hyper::header::CONTENT_TYPE).unwrap(), "application/json; charset=utf-8");
+
+	let bytes = res.into_body().collect().await.unwrap().to_bytes();
+	assert_eq!(bytes, r#"{"jsonrpc":"2.0","id":1,"result":"hello"}"#);
+
+	// A batch without subscriptions isn't streamed either.
+	let body = r#"[{"jsonrpc":"2.0","method":"say_hello","params":[],"id":1}]"#;
+	let req = Request::post(&uri)
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.header(hyper::header::ACCEPT, "text/event-stream")
+		.body(HttpBody::from(body))
+		.expect("request builder");
+	let res = http_client.request(req).await.unwrap();
+
+	assert_eq!(res.headers().get(hyper::header::CONTENT_TYPE).unwrap(), "application/json; charset=utf-8");
+	let bytes = res.into_body().collect().await.unwrap().to_bytes();
+	assert_eq!(bytes, r#"[{"jsonrpc":"2.0","id":1,"result":"hello"}]"#);
+}
+
+#[tokio::test]
+async fn ws_host_filtering_wildcard_works() {
+	use jsonrpsee::server::*;
+
+	init_logger();
+
+	let middleware =
+		tower::ServiceBuilder::new().layer(HostFilterLayer::new(["http://localhost:*", "http://127.0.0.1:*"]).unwrap());
+
+	let server = ServerBuilder::default().set_http_middleware(middleware).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
 
 	let _handle = server.start(module);
 
@@ -1199,6 +1946,158 @@ async fn disable_host_filter_works() {
 	}
 }
 
+#[tokio::test]
+async fn jwt_auth_works() {
+	use jsonrpsee::http_client::BearerAuthLayer;
+	use jsonrpsee::server::middleware::http::{AuthLayer, JwtClaims};
+	use jsonrpsee::server::*;
+	use jsonwebtoken::{EncodingKey, Header, encode};
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::time::{SystemTime, UNIX_EPOCH};
+
+	init_logger();
+
+	const SECRET: &[u8] = b"secret";
+
+	fn token(sub: &str, expires_in: i64) -> String {
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+		let claims = serde_json::json!({ "sub": sub, "exp": now + expires_in });
+		encode(&Header::default(), &claims, &EncodingKey::from_secret(SECRET)).unwrap()
+	}
+
+	let middleware = tower::ServiceBuilder::new().layer(AuthLayer::hs256(SECRET).query_param("token"));
+	let server = Server::builder().set_http_middleware(middleware).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module
+		.register_method("whoami", |_, _, ext| ext.get::<JwtClaims>().unwrap().subject().unwrap().to_string())
+		.unwrap();
+	let _handle = server.start(module);
+
+	let http_url = format!("http://{addr}");
+	let ws_url = format!("ws://{addr}");
+
+	// Missing, expired or forged tokens are rejected.
+	let client = HttpClientBuilder::default().build(&http_url).unwrap();
+	let err = client.request::<String, _>("whoami", rpc_params![]).await.unwrap_err();
+	assert!(err.to_string().contains("401"));
+
+	let forged = encode(
+		&Header::default(),
+		&serde_json::json!({ "sub": "eve", "exp": u32::MAX }),
+		&EncodingKey::from_secret(b"other"),
+	)
+	.unwrap();
+	for token in [token("alice", -120), forged] {
+		let mut headers = hyper::HeaderMap::new();
+		headers.insert(hyper::header::AUTHORIZATION, HeaderValue::try_from(format!("Bearer {token}")).unwrap());
+		let client = HttpClientBuilder::default().set_headers(headers).build(&http_url).unwrap();
+		let err = client.request::<String, _>("whoami", rpc_params![]).await.unwrap_err();
+		assert!(err.to_string().contains("401"));
+	}
+
+	// Tokens are cached until they are about to expire.
+	let fetched = Arc::new(AtomicUsize::new(0));
+	let auth = BearerAuthLayer::new({
+		let fetched = fetched.clone();
+		move || {
+			fetched.fetch_add(1, Ordering::SeqCst);
+			async { Ok(token("alice", 3600)) }
+		}
+	});
+	let client = HttpClientBuilder::default()
+		.set_http_middleware(tower::ServiceBuilder::new().layer(auth.clone()))
+		.build(&http_url)
+		.unwrap();
+	for _ in 0..2 {
+		assert_eq!(client.request::<String, _>("whoami", rpc_params![]).await.unwrap(), "alice");
+	}
+	assert_eq!(fetched.load(Ordering::SeqCst), 1);
+
+	let auth = BearerAuthLayer::new({
+		let fetched = fetched.clone();
+		move || {
+			fetched.fetch_add(1, Ordering::SeqCst);
+			async { Ok(token("alice", 10)) }
+		}
+	});
+	let client = HttpClientBuilder::default()
+		.set_http_middleware(tower::ServiceBuilder::new().layer(auth))
+		.build(&http_url)
+		.unwrap();
+	for _ in 0..2 {
+		assert_eq!(client.request::<String, _>("whoami", rpc_params![]).await.unwrap(), "alice");
+	}
+	assert_eq!(fetched.load(Ordering::SeqCst), 3);
+
+	// Concurrent requests wait for the same fetch.
+	let fetched = Arc::new(AtomicUsize::new(0));
+	let auth = BearerAuthLayer::new({
+		let fetched = fetched.clone();
+		move || {
+			fetched.fetch_add(1, Ordering::SeqCst);
+			async {
+				tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+				Ok(token("alice", 3600))
+			}
+		}
+	});
+	let client = HttpClientBuilder::default()
+		.set_http_middleware(tower::ServiceBuilder::new().layer(auth))
+		.build(&http_url)
+		.unwrap();
+	let calls = (0..4).map(|_| client.request::<String, _>("whoami", rpc_params![]));
+	for res in futures::future::join_all(calls).await {
+		assert_eq!(res.unwrap(), "alice");
+	}
+	assert_eq!(fetched.load(Ordering::SeqCst), 1);
+
+	// A rejected token is fetched again and the request is sent once more.
+	let fetched = Arc::new(AtomicUsize::new(0));
+	let auth = BearerAuthLayer::new({
+		let fetched = fetched.clone();
+		move || {
+			let n = fetched.fetch_add(1, Ordering::SeqCst);
+			async move { Ok(if n == 0 { "invalid".to_string() } else { token("bob", 3600) }) }
+		}
+	});
+	let client = HttpClientBuilder::default()
+		.set_http_middleware(tower::ServiceBuilder::new().layer(auth.clone()))
+		.build(&http_url)
+		.unwrap();
+	assert_eq!(client.request::<String, _>("whoami", rpc_params![]).await.unwrap(), "bob");
+	assert_eq!(fetched.load(Ordering::SeqCst), 2);
+
+	// The request is only sent once more.
+	let fetched = Arc::new(AtomicUsize::new(0));
+	let invalid = BearerAuthLayer::new({
+		let fetched = fetched.clone();
+		move || {
+			fetched.fetch_add(1, Ordering::SeqCst);
+			async { Ok("invalid".to_string()) }
+		}
+	});
+	let client = HttpClientBuilder::default()
+		.set_http_middleware(tower::ServiceBuilder::new().layer(invalid))
+		.build(&http_url)
+		.unwrap();
+	let err = client.request::<String, _>("whoami", rpc_params![]).await.unwrap_err();
+	assert!(err.to_string().contains("401"));
+	assert_eq!(fetched.load(Ordering::SeqCst), 2);
+
+	// WebSocket with the token in the handshake headers or in the query.
+	let mut headers = hyper::HeaderMap::new();
+	headers.insert(hyper::header::AUTHORIZATION, auth.header_value().await.unwrap());
+	let client = WsClientBuilder::default().set_headers(headers).build(&ws_url).await.unwrap();
+	assert_eq!(client.request::<String, _>("whoami", rpc_params![]).await.unwrap(), "bob");
+
+	let client = WsClientBuilder::default().build(format!("{ws_url}/?token={}", token("carol", 3600))).await.unwrap();
+	assert_eq!(client.request::<String, _>("whoami", rpc_params![]).await.unwrap(), "carol");
+
+	let err = WsClientBuilder::default().build(&ws_url).await.unwrap_err();
+	assert!(matches!(err, Error::Transport(e) if e.to_string().contains("Connection rejected with status code: 401")));
+}
+
 #[tokio::test]
 async fn subscription_option_err_is_not_sent() {
 	init_logger();
@@ -1600,3 +2499,637 @@ async fn http_connection_guard_works() {
 		assert_eq!(conn_count, 1);
 	}
 }
+
+#[cfg(unix)]
+fn unix_socket_path(name: &str) -> std::path::PathBuf {
+	std::env::temp_dir().join(format!("jsonrpsee-{}-{}.sock", name, std::process::id()))
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn ipc_server_and_client_works() {
+	use jsonrpsee::client_transport::ipc::IpcTransportClientBuilder;
+	use jsonrpsee::core::client::ClientBuilder;
+	use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+	init_logger();
+
+	let path = unix_socket_path("ipc");
+	let server = ServerBuilder::default().build_ipc(&path).await.unwrap();
+	assert!(server.local_addr().is_err());
+	assert_eq!(server.local_path().as_deref(), Some(path.as_path()));
+
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	module
+		.register_subscription("subscribe_5_ints", "n", "unsubscribe_5_ints", |_, pending, _, _| async move {
+			let stream = futures::stream::iter(1..=5_usize);
+			pipe_from_stream_and_drop(pending, stream).await.map_err(Into::into)
+		})
+		.unwrap();
+	let handle = server.start(module);
+
+	let (tx, rx) = IpcTransportClientBuilder::default().build(&path).await.unwrap();
+	let client = ClientBuilder::default().build_with_tokio(tx, rx);
+
+	let response: String = client.request("say_hello", rpc_params![]).await.unwrap();
+	assert_eq!(&response, "hello");
+
+	let mut batch = BatchRequestBuilder::new();
+	batch.insert("say_hello", rpc_params![]).unwrap();
+	batch.insert("say_hello", rpc_params![]).unwrap();
+	let responses = client.batch_request::<String>(batch).await.unwrap();
+	assert_eq!(responses.num_successful_calls(), 2);
+
+	let sub: Subscription<usize> =
+		client.subscribe("subscribe_5_ints", rpc_params![], "unsubscribe_5_ints").await.unwrap();
+	let items: Vec<usize> = sub.take(5).map(|i| i.unwrap()).collect().await;
+	assert_eq!(items, vec![1, 2, 3, 4, 5]);
+
+	// Raw newline-delimited JSON-RPC, including an invalid line which doesn't close the connection.
+	let stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+	let (read, mut write) = stream.into_split();
+	let mut lines = BufReader::new(read).lines();
+
+	write.write_all(b"foo\n{\"jsonrpc\":\"2.0\",\"method\":\"say_hello\",\"id\":1}\n").await.unwrap();
+	let parse_err = lines.next_line().await.unwrap().unwrap();
+	assert!(parse_err.contains("-32700"));
+	let hello = lines.next_line().await.unwrap().unwrap();
+	assert_eq!(hello, r#"{"jsonrpc":"2.0","id":1,"result":"hello"}"#);
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+	assert!(!path.exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn ipc_connections_are_authenticated_without_headers() {
+	use jsonrpsee::client_transport::ipc::IpcTransportClientBuilder;
+	use jsonrpsee::core::client::ClientBuilder;
+	use jsonrpsee::server::{Authenticator, Extensions, Principal};
+	use jsonrpsee::types::error::UNAUTHORIZED_CODE;
+
+	/// Grants the `admin` role to the local callers, which have no headers.
+	#[derive(Debug)]
+	struct LocalAuthenticator;
+
+	impl Authenticator for LocalAuthenticator {
+		fn authenticate(&self, headers: &hyper::HeaderMap, _: &Extensions) -> Option<Principal> {
+			headers.is_empty().then(|| Principal::new("local").with_role("admin"))
+		}
+	}
+
+	fn module() -> RpcModule<()> {
+		let mut module = RpcModule::new(());
+		module.register_method("admin", |_, _, _| "ok").unwrap();
+		module.set_required_roles("admin", ["admin"]).unwrap();
+		module
+	}
+
+	init_logger();
+
+	let path = unix_socket_path("ipc-auth");
+	let config = ServerConfig::builder().set_authenticator(LocalAuthenticator).build();
+	let handle = ServerBuilder::with_config(config).build_ipc(&path).await.unwrap().start(module());
+
+	let (tx, rx) = IpcTransportClientBuilder::default().build(&path).await.unwrap();
+	let client = ClientBuilder::default().build_with_tokio(tx, rx);
+	let response: String = client.request("admin", rpc_params![]).await.unwrap();
+	assert_eq!(response, "ok");
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+
+	// Without an authenticator the callers are anonymous.
+	let path = unix_socket_path("ipc-anonymous");
+	let handle = ServerBuilder::default().build_ipc(&path).await.unwrap().start(module());
+
+	let (tx, rx) = IpcTransportClientBuilder::default().build(&path).await.unwrap();
+	let client = ClientBuilder::default().build_with_tokio(tx, rx);
+	let err = client.request::<String, _>("admin", rpc_params![]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(e) if e.code() == UNAUTHORIZED_CODE));
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix_socket_http_and_ws_works() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+	use tokio::net::UnixStream;
+
+	init_logger();
+
+	let path = unix_socket_path("http-ws");
+	let server = ServerBuilder::default().build_unix(&path).await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	let handle = server.start(module);
+
+	// WebSocket over the Unix socket.
+	let stream = UnixStream::connect(&path).await.unwrap();
+	let client = WsClientBuilder::default().build_with_stream("ws://localhost", stream).await.unwrap();
+	let response: String = client.request("say_hello", rpc_params![]).await.unwrap();
+	assert_eq!(&response, "hello");
+
+	// HTTP over the Unix socket.
+	let body = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let req = format!(
+		"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+		body.len(),
+		body
+	);
+	let mut stream = UnixStream::connect(&path).await.unwrap();
+	stream.write_all(req.as_bytes()).await.unwrap();
+	let mut response = String::new();
+	stream.read_to_string(&mut response).await.unwrap();
+	assert!(response.starts_with("HTTP/1.1 200 OK"));
+	assert!(response.ends_with(r#"{"jsonrpc":"2.0","id":1,"result":"hello"}"#));
+
+	// A stale socket file is replaced, but a live one is not.
+	assert!(ServerBuilder::default().build_unix(&path).await.is_err());
+	handle.stop().unwrap();
+	handle.stopped().await;
+	assert!(!path.exists());
+
+	let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+	drop(listener);
+	let server = ServerBuilder::default().build_unix(&path).await.unwrap();
+	drop(server);
+	std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn ws_compression_works() {
+	use jsonrpsee::server::DeflateConfig as ServerDeflateConfig;
+	use jsonrpsee::ws_client::DeflateConfig;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	init_logger();
+
+	let server_cfg = ServerConfig::builder()
+		.enable_ws_compression(ServerDeflateConfig::new().max_window_bits(10).threshold(64))
+		.build();
+	let server = ServerBuilder::with_config(server_cfg).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("echo", |params, _, _| params.one::<String>().unwrap()).unwrap();
+	let _handle = server.start(module);
+
+	// The server accepts the extension and limits its own window size.
+	let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+	let req = format!(
+		"GET / HTTP/1.1\r\nHost: {addr}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+		Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\
+		Sec-WebSocket-Extensions: permessage-deflate; client_max_window_bits\r\n\r\n"
+	);
+	stream.write_all(req.as_bytes()).await.unwrap();
+	let mut buf = [0; 1024];
+	let n = stream.read(&mut buf).await.unwrap();
+	let response = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+	assert!(response.starts_with("http/1.1 101"));
+	assert!(response.contains("permessage-deflate"));
+	assert!(response.contains("server_max_window_bits=10"));
+
+	// Clients such as browsers use context takeover unless the server asks them not to,
+	// thus the later messages refer to the previous ones.
+	assert!(!response.contains("client_no_context_takeover"));
+	let mut compress = flate2::Compress::new(flate2::Compression::default(), false);
+	for id in 0..3 {
+		let req = format!(r#"{{"jsonrpc":"2.0","method":"echo","params":["hello"],"id":{id}}}"#);
+		let mut payload = Vec::with_capacity(req.len() + 64);
+		compress.compress_vec(req.as_bytes(), &mut payload, flate2::FlushCompress::Sync).unwrap();
+		payload.truncate(payload.len() - 4);
+
+		// Final text frame with RSV1 set, masked with a zero key.
+		let mut frame = vec![0xC1, 0x80 | payload.len() as u8, 0, 0, 0, 0];
+		frame.extend_from_slice(&payload);
+		stream.write_all(&frame).await.unwrap();
+
+		// Small responses are sent uncompressed.
+		let mut header = [0; 2];
+		stream.read_exact(&mut header).await.unwrap();
+		assert_eq!(header[0], 0x81);
+		let mut body = vec![0; header[1] as usize];
+		stream.read_exact(&mut body).await.unwrap();
+		let response: JsonValue = serde_json::from_slice(&body).unwrap();
+		assert_eq!(response, serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": "hello" }));
+	}
+
+	// Small and large messages in both directions.
+	let client = WsClientBuilder::default()
+		.enable_ws_compression(DeflateConfig::new().threshold(64))
+		.build(format!("ws://{addr}"))
+		.await
+		.unwrap();
+
+	for msg in ["small".to_string(), "large".repeat(10_000)] {
+		let response: String = client.request("echo", rpc_params![&msg]).await.unwrap();
+		assert_eq!(response, msg);
+	}
+}
+
+#[tokio::test]
+async fn server_requests_to_ws_client_works() {
+	use jsonrpsee::server::{ClientHandle, ClientRequestError};
+	use jsonrpsee::types::ErrorObjectOwned;
+	use jsonrpsee::types::error::METHOD_NOT_FOUND_CODE;
+
+	init_logger();
+
+	let server_cfg = ServerConfig::builder().enable_client_requests().build();
+	let server = ServerBuilder::with_config(server_cfg).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("transfer", |params, _, ext| async move {
+			let amount: u64 = params.one()?;
+			let Some(client) = ext.get::<ClientHandle>().cloned() else {
+				return Ok("unsupported".to_string());
+			};
+
+			client.notification("progress", rpc_params!["confirming"]).await.unwrap();
+			match client.request::<bool, _>("confirm", rpc_params![amount]).await {
+				Ok(true) => Ok("done".to_string()),
+				Ok(false) => Ok("cancelled".to_string()),
+				Err(ClientRequestError::JsonRpc(e)) => Ok(format!("error {}", e.code())),
+				Err(e) => Err(ErrorObjectOwned::owned(1, e.to_string(), None::<()>)),
+			}
+		})
+		.unwrap();
+	let _handle = server.start(module);
+
+	let mut local = RpcModule::new(());
+	local.register_method("confirm", |params, _, _| params.one::<u64>().map(|amount| amount < 100)).unwrap();
+	let client = WsClientBuilder::default().set_methods(local).build(format!("ws://{addr}")).await.unwrap();
+	let mut progress = client.subscribe_to_method::<Vec<String>>("progress").await.unwrap();
+
+	let response: String = client.request("transfer", rpc_params![10]).await.unwrap();
+	assert_eq!(response, "done");
+	assert_eq!(progress.next().await.unwrap().unwrap(), ["confirming"]);
+	let response: String = client.request("transfer", rpc_params![1000]).await.unwrap();
+	assert_eq!(response, "cancelled");
+
+	// Concurrent calls are matched with the responses of the client.
+	let calls: Vec<String> =
+		futures::future::try_join_all((0..10).map(|i| client.request::<String, _>("transfer", rpc_params![i * 20])))
+			.await
+			.unwrap();
+	assert_eq!(calls.iter().filter(|c| *c == "done").count(), 5);
+
+	// Clients without local methods answer with an error.
+	let client = WsClientBuilder::default().build(format!("ws://{addr}")).await.unwrap();
+	let response: String = client.request("transfer", rpc_params![10]).await.unwrap();
+	assert_eq!(response, format!("error {METHOD_NOT_FOUND_CODE}"));
+
+	// No client handle over HTTP.
+	let client = HttpClientBuilder::default().build(format!("http://{addr}")).unwrap();
+	let response: String = client.request("transfer", rpc_params![10]).await.unwrap();
+	assert_eq!(response, "unsupported");
+}
+
+#[tokio::test]
+async fn server_requests_to_ws_client_are_limited() {
+	use jsonrpsee::server::{ClientHandle, ClientRequestError};
+	use jsonrpsee::types::ErrorObjectOwned;
+	use jsonrpsee::types::error::SERVER_IS_BUSY_CODE;
+
+	init_logger();
+
+	let server_cfg = ServerConfig::builder().enable_client_requests().build();
+	let server = ServerBuilder::with_config(server_cfg).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("ask", |_, _, ext| async move {
+			let client = ext.get::<ClientHandle>().cloned().unwrap();
+			match client.request::<bool, _>("confirm", rpc_params![]).await {
+				Ok(_) => Ok("done".to_string()),
+				Err(ClientRequestError::JsonRpc(e)) => Ok(format!("error {}", e.code())),
+				Err(e) => Err(ErrorObjectOwned::owned(1, e.to_string(), None::<()>)),
+			}
+		})
+		.unwrap();
+	let _handle = server.start(module);
+
+	let mut local = RpcModule::new(());
+	local
+		.register_async_method("confirm", |_, _, _| async {
+			tokio::time::sleep(Duration::from_millis(200)).await;
+			true
+		})
+		.unwrap();
+	let client = WsClientBuilder::default()
+		.max_concurrent_requests(1)
+		.set_methods(local)
+		.build(format!("ws://{addr}"))
+		.await
+		.unwrap();
+
+	// The second request of the server is rejected while the first one is answered.
+	let (first, second) = tokio::join!(
+		client.request::<String, _>("ask", rpc_params![]),
+		client.request::<String, _>("ask", rpc_params![])
+	);
+	let mut responses = [first.unwrap(), second.unwrap()];
+	responses.sort();
+	assert_eq!(responses, ["done".to_string(), format!("error {SERVER_IS_BUSY_CODE}")]);
+
+	// Requests are answered again once the previous ones are done.
+	let response: String = client.request("ask", rpc_params![]).await.unwrap();
+	assert_eq!(response, "done");
+}
+
+#[tokio::test]
+async fn reconnecting_subscription_ends_when_closed_by_server() {
+	use jsonrpsee::ws_client::ReconnectPolicy;
+
+	init_logger();
+
+	let server_addr = server_with_subscription().await;
+	let client = WsClientBuilder::default()
+		.build_with_reconnect(format!("ws://{server_addr}"), ReconnectPolicy::default())
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	let mut sub = client
+		.subscribe::<usize, _>("subscribe_5_ints", rpc_params![], "unsubscribe_5_ints")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	for i in 1..=5 {
+		assert_eq!(sub.next().with_default_timeout().await.unwrap().unwrap().unwrap(), i);
+	}
+
+	// The stream ends although the connection is still up.
+	assert!(sub.next().with_default_timeout().await.unwrap().is_none());
+	assert!(matches!(sub.close_reason(), Some(SubscriptionCloseReason::ConnectionClosed)));
+	assert!(client.is_connected());
+	assert_eq!(client.reconnect_count(), 0);
+}
+
+/// Server of a [`PoolClient`](jsonrpsee::core::client::PoolClient) test which identifies itself with `id`.
+async fn pool_node(id: usize) -> (String, ServerHandle) {
+	let server = ServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("node_id", move |_, _, _| id).unwrap();
+	module.register_alias("record_node_id", "node_id").unwrap();
+	module
+		.register_subscription("subscribe_node", "node", "unsubscribe_node", move |_, pending, _, _| async move {
+			let stream = IntervalStream::new(interval(Duration::from_millis(50))).map(move |_| id);
+			pipe_from_stream_and_drop(pending, stream).await.map_err(Into::into)
+		})
+		.unwrap();
+	module
+		.register_subscription(
+			"subscribe_node_twice",
+			"node",
+			"unsubscribe_node_twice",
+			move |_, pending, _, _| async move {
+				let stream = futures::stream::iter([id, id]);
+				pipe_from_stream_and_drop(pending, stream).await.map_err(Into::into)
+			},
+		)
+		.unwrap();
+	let url = format!("ws://{}", server.local_addr().unwrap());
+	(url, server.start(module))
+}
+
+#[tokio::test]
+async fn pool_client_fails_over_and_migrates_subscriptions() {
+	use jsonrpsee::core::client::{LoadBalancing, PoolClientBuilder};
+
+	init_logger();
+
+	let nodes = [pool_node(0).await, pool_node(1).await];
+	let connector = |url: String| async move { WsClientBuilder::default().build(url).await };
+	let client = PoolClientBuilder::new()
+		.load_balancing(LoadBalancing::RoundRobin)
+		.idempotent_methods(["node_id"])
+		.build(nodes.iter().map(|(url, _)| url.clone()), connector)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	// The calls are distributed over both nodes.
+	let mut ids = Vec::new();
+	for _ in 0..4 {
+		ids.push(client.request::<usize, _>("node_id", rpc_params![]).with_default_timeout().await.unwrap().unwrap());
+	}
+	ids.sort();
+	assert_eq!(ids, [0, 0, 1, 1]);
+
+	// A subscription closed by the server ends without taking down its endpoint.
+	let mut sub: Subscription<usize> = client
+		.subscribe("subscribe_node_twice", rpc_params![], "unsubscribe_node_twice")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let id = sub.next().with_default_timeout().await.unwrap().unwrap().unwrap();
+	assert_eq!(sub.next().with_default_timeout().await.unwrap().unwrap().unwrap(), id);
+	assert!(sub.next().with_default_timeout().await.unwrap().is_none());
+	assert_eq!(client.healthy_endpoints().len(), 2);
+
+	let mut sub: Subscription<usize> = client
+		.subscribe("subscribe_node", rpc_params![], "unsubscribe_node")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let first = sub.next().with_default_timeout().await.unwrap().unwrap().unwrap();
+
+	// Take down the node which serves the subscription.
+	let (_, handle) = &nodes[first];
+	handle.stop().unwrap();
+	handle.clone().stopped().with_default_timeout().await.unwrap();
+
+	// The subscription is migrated to the other node.
+	let other = 1 - first;
+	loop {
+		let id = sub.next().with_default_timeout().await.unwrap().unwrap().unwrap();
+		if id == other {
+			break;
+		}
+	}
+	assert!(sub.close_reason().is_none());
+
+	// The calls fail over to the healthy node.
+	for _ in 0..4 {
+		let id = client.request::<usize, _>("node_id", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(id, other);
+	}
+	assert_eq!(client.healthy_endpoints(), [nodes[other].0.as_str()]);
+}
+
+#[tokio::test]
+async fn pool_client_only_retries_idempotent_methods() {
+	use jsonrpsee::core::client::PoolClientBuilder;
+
+	init_logger();
+
+	let nodes = [pool_node(0).await, pool_node(1).await];
+	let pool = || async {
+		let connector = |url: String| async move { WsClientBuilder::default().build(url).await };
+		PoolClientBuilder::new()
+			.health_check_interval(Duration::from_secs(60))
+			.idempotent_methods(["node_id"])
+			.build(nodes.iter().map(|(url, _)| url.clone()), connector)
+			.with_default_timeout()
+			.await
+			.unwrap()
+			.unwrap()
+	};
+	let clients = [pool().await, pool().await];
+
+	let (_, handle) = &nodes[0];
+	handle.stop().unwrap();
+	handle.clone().stopped().with_default_timeout().await.unwrap();
+
+	// One of the calls is made on the node which is down and isn't sent again.
+	let mut results = Vec::new();
+	for _ in 0..2 {
+		let res = clients[0].request::<usize, _>("record_node_id", rpc_params![]).with_default_timeout().await;
+		results.push(res.unwrap());
+	}
+	assert_eq!(results.iter().filter(|res| res.is_err()).count(), 1);
+	assert!(results.iter().any(|res| matches!(res, Ok(1))));
+
+	// Idempotent calls fail over to the healthy node.
+	for _ in 0..2 {
+		let id =
+			clients[1].request::<usize, _>("node_id", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(id, 1);
+	}
+	assert_eq!(clients[1].healthy_endpoints(), [nodes[1].0.as_str()]);
+}
+
+#[tokio::test]
+async fn access_log_records_http_requests_and_ws_sessions() {
+	use jsonrpsee::server::middleware::http::{AccessLogLayer, AccessLogRecord, AccessLogTransport};
+
+	init_logger();
+
+	let (tx, mut rx) = mpsc::unbounded::<AccessLogRecord>();
+	let access_log =
+		AccessLogLayer::new(move |record| tx.unbounded_send(record).unwrap()).with_params().redact_field("password");
+
+	let server = ServerBuilder::default()
+		.set_http_middleware(tower::ServiceBuilder::new().layer(access_log))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("login", |_, _, _| true).unwrap();
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	module
+		.register_subscription("subscribe_hello", "hello", "unsubscribe_hello", |_, pending, _, _| async move {
+			let sink = pending.accept().await?;
+			sink.send(serde_json::value::to_raw_value(&"hello")?).await?;
+			sink.closed().await;
+			Ok(())
+		})
+		.unwrap();
+
+	let _handle = server.start(module.clone());
+
+	let mut headers = hyper::HeaderMap::new();
+	headers.insert(hyper::header::USER_AGENT, HeaderValue::from_static("access-log-test"));
+	let client = HttpClientBuilder::default().set_headers(headers).build(format!("http://{}", addr)).unwrap();
+
+	let login = serde_json::json!({ "user": "alice", "password": "hunter2" });
+	assert!(client.request::<bool, _>("login", rpc_params![login]).await.unwrap());
+
+	let record = rx.next().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(record.transport, AccessLogTransport::Http);
+	assert_eq!(record.status, hyper::StatusCode::OK);
+	assert_eq!(record.user_agent.as_deref(), Some("access-log-test"));
+	assert!(record.connection_id.is_some());
+	assert_eq!(record.remote_addr.map(|a| a.ip()), Some(addr.ip()));
+	assert_eq!(record.calls.len(), 1);
+	assert_eq!(record.calls[0].method, "login");
+	assert!(!record.calls[0].params.as_ref().unwrap().contains("hunter2"));
+	assert!(record.batch_sizes.is_empty());
+	assert_eq!(record.response_bytes, r#"{"jsonrpc":"2.0","id":0,"result":true}"#.len());
+
+	let mut batch = BatchRequestBuilder::new();
+	batch.insert("say_hello", rpc_params![]).unwrap();
+	batch.insert("say_hello", rpc_params![]).unwrap();
+	client.batch_request::<String>(batch).await.unwrap();
+
+	let record = rx.next().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(record.batch_sizes, vec![2]);
+	assert_eq!(record.calls.iter().map(|c| c.method.as_str()).collect::<Vec<_>>(), ["say_hello", "say_hello"]);
+
+	// The record of a Server-Sent Events stream is written once the stream has ended.
+	let http_client = hyper_util::client::legacy::Client::builder(TokioExecutor::new()).build_http();
+	let req = hyper::Request::post(format!("http://{}", addr))
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.header(hyper::header::ACCEPT, "text/event-stream")
+		.body(HttpBody::from(r#"{"jsonrpc":"2.0","method":"subscribe_hello","params":[],"id":1}"#))
+		.unwrap();
+	let mut body = http_client.request(req).await.unwrap().into_body();
+	body.frame().with_default_timeout().await.unwrap().unwrap().unwrap();
+	assert!(rx.try_recv().is_err());
+	drop(body);
+
+	let record = rx.next().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(record.transport, AccessLogTransport::Http);
+	assert_eq!(record.calls.iter().map(|c| c.method.as_str()).collect::<Vec<_>>(), ["subscribe_hello"]);
+
+	// The calls of a WebSocket session are in the record of the session.
+	let client = WsClientBuilder::default().build(format!("ws://{}", addr)).await.unwrap();
+	for _ in 0..2 {
+		assert_eq!(client.request::<String, _>("say_hello", rpc_params![]).await.unwrap(), "hello");
+	}
+	assert!(rx.try_recv().is_err());
+	drop(client);
+
+	let record = rx.next().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(record.transport, AccessLogTransport::WebSocket);
+	assert_eq!(record.status, hyper::StatusCode::SWITCHING_PROTOCOLS);
+	assert!(record.connection_id.is_some());
+	assert_eq!(record.calls.iter().map(|c| c.method.as_str()).collect::<Vec<_>>(), ["say_hello", "say_hello"]);
+	assert_eq!(
+		record.response_bytes,
+		(0..2).map(|id| format!(r#"{{"jsonrpc":"2.0","id":{id},"result":"hello"}}"#).len()).sum::<usize>()
+	);
+
+	// With the records of the WebSocket messages enabled, each message has its own record.
+	let (tx, mut rx) = mpsc::unbounded::<AccessLogRecord>();
+	let access_log = AccessLogLayer::new(move |record| tx.unbounded_send(record).unwrap()).with_websocket_messages();
+	let server = ServerBuilder::default()
+		.set_http_middleware(tower::ServiceBuilder::new().layer(access_log))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let client = WsClientBuilder::default().build(format!("ws://{}", addr)).await.unwrap();
+	for id in 0..2 {
+		assert_eq!(client.request::<String, _>("say_hello", rpc_params![]).await.unwrap(), "hello");
+
+		let record = rx.next().with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(record.transport, AccessLogTransport::WebSocketMessage);
+		assert_eq!(record.status, hyper::StatusCode::SWITCHING_PROTOCOLS);
+		assert!(record.connection_id.is_some());
+		assert_eq!(record.calls.len(), 1);
+		assert_eq!(record.calls[0].method, "say_hello");
+		assert_eq!(record.response_bytes, format!(r#"{{"jsonrpc":"2.0","id":{id},"result":"hello"}}"#).len());
+	}
+	drop(client);
+
+	let record = rx.next().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(record.transport, AccessLogTransport::WebSocket);
+	assert!(record.calls.is_empty());
+	assert_eq!(record.response_bytes, 0);
+}
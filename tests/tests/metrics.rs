@@ -35,14 +35,17 @@ use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use helpers::init_logger;
+use jsonrpsee::core::ClientError;
+use jsonrpsee::core::client::{ClientT, Subscription, SubscriptionClientT, SubscriptionKind};
+use jsonrpsee::core::middleware::layer::PrometheusRecorder;
 use jsonrpsee::core::middleware::{Batch, Notification, Request, RpcServiceBuilder, RpcServiceT};
-use jsonrpsee::core::{ClientError, client::ClientT};
 use jsonrpsee::http_client::HttpClientBuilder;
 use jsonrpsee::proc_macros::rpc;
 use jsonrpsee::server::{Server, ServerHandle};
 use jsonrpsee::types::{ErrorObject, ErrorObjectOwned, Id};
 use jsonrpsee::ws_client::WsClientBuilder;
 use jsonrpsee::{MethodResponse, RpcModule, rpc_params};
+use jsonrpsee_test_utils::TimeoutFutureExt;
 use tokio::time::sleep;
 
 #[derive(Default, Clone)]
@@ -228,3 +231,239 @@ async fn http_server_logger() {
 	server_handle.stop().unwrap();
 	server_handle.stopped().await;
 }
+
+#[tokio::test]
+async fn ws_server_and_client_rpc_metrics() {
+	init_logger();
+
+	let server_recorder = Arc::new(PrometheusRecorder::new());
+	let client_recorder = Arc::new(PrometheusRecorder::new());
+
+	let mut module = test_module();
+	module
+		.register_subscription("subscribe_hello", "hello", "unsubscribe_hello", |_, pending, _, _| async move {
+			let sink = pending.accept().await?;
+			sink.closed().await;
+			Ok(())
+		})
+		.unwrap();
+	module
+		.register_subscription("subscribe_once", "once", "unsubscribe_once", |_, pending, _, _| async move {
+			let sink = pending.accept().await?;
+			sink.send(serde_json::value::to_raw_value(&"hello")?).await?;
+			Ok(())
+		})
+		.unwrap();
+	module.register_method("ping", |_, _, _| "pong").unwrap();
+
+	let rpc_middleware = RpcServiceBuilder::new().rpc_metrics(server_recorder.clone());
+	let server = Server::builder().set_rpc_middleware(rpc_middleware).build("127.0.0.1:0").await.unwrap();
+	let server_addr = server.local_addr().unwrap();
+	let server_url = format!("ws://{}", server_addr);
+	let server_handle = server.start(module);
+
+	let client = WsClientBuilder::default()
+		.set_rpc_middleware(RpcServiceBuilder::new().rpc_metrics(client_recorder.clone()))
+		.build(&server_url)
+		.await
+		.unwrap();
+
+	let res: String = client.request("say_hello", rpc_params![]).await.unwrap();
+	assert_eq!(res, "hello");
+	let res: Result<String, ClientError> = client.request("unknown_method", rpc_params![]).await;
+	assert!(res.is_err());
+	let res: Result<String, ClientError> = client.request("err", rpc_params![]).await;
+	assert!(res.is_err());
+
+	let sub: Subscription<String> =
+		client.subscribe("subscribe_hello", rpc_params![], "unsubscribe_hello").await.unwrap();
+
+	// A call with the subscription ID as parameter isn't regarded as an unsubscribe.
+	let SubscriptionKind::Subscription(sub_id) = sub.kind() else { panic!("Expected a subscription") };
+	let res: String = client.request("ping", rpc_params![sub_id]).await.unwrap();
+	assert_eq!(res, "pong");
+
+	// HTTP requests aren't accounted as connections.
+	let http_client = HttpClientBuilder::default().build(format!("http://{}", server_addr)).unwrap();
+	let res: String = http_client.request("ping", rpc_params![]).await.unwrap();
+	assert_eq!(res, "pong");
+
+	let metrics = server_recorder.render();
+	assert!(metrics.contains("jsonrpsee_calls_total{method=\"say_hello\",kind=\"method_call\"} 1\n"));
+	assert!(
+		metrics.contains("jsonrpsee_call_errors_total{method=\"<unknown>\",kind=\"method_call\",code=\"-32601\"} 1\n")
+	);
+	assert!(metrics.contains("jsonrpsee_call_errors_total{method=\"err\",kind=\"method_call\",code=\"1\"} 1\n"));
+	assert!(metrics.contains("jsonrpsee_call_duration_seconds_count{method=\"say_hello\",kind=\"method_call\"} 1\n"));
+	assert!(metrics.contains("jsonrpsee_active_connections 1\n"));
+	assert!(metrics.contains("jsonrpsee_active_subscriptions{method=\"subscribe_hello\"} 1\n"));
+
+	let metrics = client_recorder.render();
+	assert!(metrics.contains("jsonrpsee_calls_total{method=\"say_hello\",kind=\"method_call\"} 1\n"));
+	assert!(metrics.contains("jsonrpsee_call_errors_total{method=\"err\",kind=\"method_call\",code=\"1\"} 1\n"));
+	assert!(metrics.contains("jsonrpsee_active_subscriptions{method=\"subscribe_hello\"} 1\n"));
+
+	// The unsubscribe call is sent in the background.
+	sub.unsubscribe().await.unwrap();
+	assert!(client_recorder.render().contains("jsonrpsee_active_subscriptions{method=\"subscribe_hello\"} 0\n"));
+	async {
+		while !server_recorder.render().contains("jsonrpsee_active_subscriptions{method=\"subscribe_hello\"} 0\n") {
+			sleep(Duration::from_millis(10)).await;
+		}
+	}
+	.with_default_timeout()
+	.await
+	.unwrap();
+
+	// Subscriptions closed by the server are accounted as well.
+	let mut sub: Subscription<String> =
+		client.subscribe("subscribe_once", rpc_params![], "unsubscribe_once").await.unwrap();
+	assert_eq!(sub.next().with_default_timeout().await.unwrap().unwrap().unwrap(), "hello");
+	async {
+		while !server_recorder.render().contains("jsonrpsee_active_subscriptions{method=\"subscribe_once\"} 0\n") {
+			sleep(Duration::from_millis(10)).await;
+		}
+	}
+	.with_default_timeout()
+	.await
+	.unwrap();
+
+	drop(client);
+	server_handle.stop().unwrap();
+	server_handle.stopped().await;
+
+	assert!(server_recorder.render().contains("jsonrpsee_active_connections 0\n"));
+}
+
+#[tokio::test]
+async fn trace_context_is_propagated() {
+	use jsonrpsee::core::middleware::layer::{RpcTracingLayer, TRACEPARENT, TraceContext};
+	use jsonrpsee::core::params::BatchRequestBuilder;
+	use jsonrpsee::http_client::HeaderMap;
+
+	init_logger();
+
+	fn module() -> RpcModule<()> {
+		let mut module = RpcModule::new(());
+		module
+			.register_method("traceparent", |_, _, ext| ext.get::<TraceContext>().map(TraceContext::to_traceparent))
+			.unwrap();
+		module
+	}
+
+	let server = Server::builder()
+		.set_rpc_middleware(RpcServiceBuilder::new().rpc_tracing(RpcTracingLayer::server()))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module());
+
+	let parse = |value: Option<String>| TraceContext::from_traceparent(&value.unwrap()).unwrap();
+
+	// HTTP sends the trace context in the header.
+	let client = HttpClientBuilder::default()
+		.set_rpc_middleware(RpcServiceBuilder::new().rpc_tracing(RpcTracingLayer::client()))
+		.build(format!("http://{addr}"))
+		.unwrap();
+	let first = parse(client.request("traceparent", rpc_params![]).await.unwrap());
+	let second = parse(client.request("traceparent", rpc_params![]).await.unwrap());
+	assert_ne!(first.trace_id(), second.trace_id());
+
+	// WebSocket sends the trace context in the reserved member of each call.
+	let client = WsClientBuilder::default()
+		.set_rpc_middleware(RpcServiceBuilder::new().rpc_tracing(RpcTracingLayer::client()))
+		.build(format!("ws://{addr}"))
+		.await
+		.unwrap();
+	parse(client.request("traceparent", rpc_params![]).await.unwrap());
+
+	let mut batch = BatchRequestBuilder::new();
+	batch.insert("traceparent", rpc_params![]).unwrap();
+	batch.insert("traceparent", rpc_params![]).unwrap();
+	let calls: Vec<_> =
+		client.batch_request::<Option<String>>(batch).await.unwrap().into_ok().unwrap().map(parse).collect();
+	assert_eq!(calls[0], calls[1]);
+
+	// The server continues the trace of the client in its own span.
+	let remote = TraceContext::new_root(true);
+	let mut headers = HeaderMap::new();
+	headers.insert(TRACEPARENT, remote.to_traceparent().parse().unwrap());
+	let client = HttpClientBuilder::default().set_headers(headers.clone()).build(format!("http://{addr}")).unwrap();
+
+	let cx = parse(client.request("traceparent", rpc_params![]).await.unwrap());
+	assert_eq!(cx.trace_id(), remote.trace_id());
+	assert_ne!(cx.span_id(), remote.span_id());
+
+	// The layer continues the trace however it's added.
+	let server = Server::builder()
+		.set_rpc_middleware(RpcServiceBuilder::new().layer(RpcTracingLayer::server()))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module());
+
+	let client = HttpClientBuilder::default().set_headers(headers.clone()).build(format!("http://{addr}")).unwrap();
+	let cx = parse(client.request("traceparent", rpc_params![]).await.unwrap());
+	assert_eq!(cx.trace_id(), remote.trace_id());
+
+	// The trace context is ignored without a tracing layer in the server.
+	let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module());
+
+	let client = HttpClientBuilder::default().set_headers(headers).build(format!("http://{addr}")).unwrap();
+	assert_eq!(client.request::<Option<String>, _>("traceparent", rpc_params![]).await.unwrap(), None);
+
+	let client = WsClientBuilder::default()
+		.set_rpc_middleware(RpcServiceBuilder::new().rpc_tracing(RpcTracingLayer::client()))
+		.build(format!("ws://{addr}"))
+		.await
+		.unwrap();
+	assert_eq!(client.request::<Option<String>, _>("traceparent", rpc_params![]).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn trace_context_continues_opentelemetry_spans() {
+	use jsonrpsee::core::middleware::layer::{RpcTracingLayer, TraceContext};
+	use opentelemetry::trace::{TraceContextExt, TracerProvider};
+	use tracing::Instrument;
+	use tracing_opentelemetry::OpenTelemetrySpanExt;
+	use tracing_subscriber::layer::SubscriberExt;
+
+	let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder().build();
+	let subscriber =
+		tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(provider.tracer("jsonrpsee")));
+	let _guard = tracing::subscriber::set_default(subscriber);
+
+	let mut module = RpcModule::new(());
+	module
+		.register_method("traceparent", |_, _, ext| {
+			let cx = ext.get::<TraceContext>().unwrap();
+			// The context of the call is the one of the OpenTelemetry span of the server.
+			let span_cx = tracing::Span::current().context().span().span_context().clone();
+			assert_eq!(cx.span_id(), span_cx.span_id().to_string());
+			cx.to_traceparent()
+		})
+		.unwrap();
+
+	let server = Server::builder()
+		.set_rpc_middleware(RpcServiceBuilder::new().rpc_tracing(RpcTracingLayer::server()))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let client = HttpClientBuilder::default()
+		.set_rpc_middleware(RpcServiceBuilder::new().rpc_tracing(RpcTracingLayer::client()))
+		.build(format!("http://{addr}"))
+		.unwrap();
+
+	// The call continues the trace of the current span of the client.
+	let span = tracing::info_span!("caller");
+	let trace_id = span.context().span().span_context().trace_id().to_string();
+	let cx: String = client.request("traceparent", rpc_params![]).instrument(span).await.unwrap();
+	assert_eq!(TraceContext::from_traceparent(&cx).unwrap().trace_id(), trace_id);
+}
@@ -64,7 +64,7 @@ mod rpc_impl {
 		}
 	}
 
-	#[rpc(client, server, namespace = "foo")]
+	#[rpc(client, server, batch, namespace = "foo")]
 	pub trait Rpc {
 		#[method(name = "foo")]
 		async fn async_method(&self, param_a: u8, param_b: String) -> Result<u16, ErrorObjectOwned>;
@@ -478,3 +478,335 @@ async fn sync_sub_works() {
 
 	assert_eq!(sub.next().await.unwrap().unwrap(), "hello");
 }
+
+#[tokio::test]
+async fn openrpc_discover_works() {
+	use jsonrpsee::core::openrpc::{OpenRpc, ParamStructure};
+	use jsonrpsee::core::{RpcResult, SubscriptionResult, async_trait};
+	use jsonrpsee::proc_macros::rpc;
+	use jsonrpsee::server::PendingSubscriptionSink;
+
+	/// Documented API.
+	#[rpc(server, openrpc, namespace = "doc")]
+	pub trait Documented {
+		/// Adds two numbers.
+		///
+		/// Overflows wrap around.
+		#[method(name = "add", aliases = ["doc_sum"])]
+		fn add(&self, a: u32, b: Option<u32>) -> RpcResult<u32>;
+
+		/// Multiplies two numbers.
+		#[method(name = "mul", param_kind = map)]
+		fn mul(&self, a: u32, b: u32) -> RpcResult<u32>;
+
+		/// Streams numbers.
+		#[subscription(name = "subscribeNumbers" => "numbers", unsubscribe = "unsubscribeNumbers", item = Vec<u64>)]
+		async fn numbers(&self) -> SubscriptionResult;
+	}
+
+	struct DocumentedImpl;
+
+	#[async_trait]
+	impl DocumentedServer for DocumentedImpl {
+		fn add(&self, a: u32, b: Option<u32>) -> RpcResult<u32> {
+			Ok(a.wrapping_add(b.unwrap_or_default()))
+		}
+
+		fn mul(&self, a: u32, b: u32) -> RpcResult<u32> {
+			Ok(a.wrapping_mul(b))
+		}
+
+		async fn numbers(&self, pending: PendingSubscriptionSink) -> SubscriptionResult {
+			pending.accept().await?;
+			Ok(())
+		}
+	}
+
+	let module = DocumentedImpl.into_rpc();
+	let doc: OpenRpc = module.call("rpc.discover", rpc_params![]).await.unwrap();
+
+	assert_eq!(doc, <DocumentedImpl as DocumentedServer>::openrpc());
+	assert_eq!(doc.info.title, "Documented");
+	assert_eq!(doc.info.description.as_deref(), Some("Documented API."));
+	assert_eq!(doc.methods.len(), 3);
+
+	let add = &doc.methods[0];
+	assert_eq!(add.name, "doc_add");
+	assert_eq!(add.description.as_deref(), Some("Adds two numbers.\n\nOverflows wrap around."));
+	assert_eq!(add.aliases, vec!["doc_sum".to_string()]);
+	assert_eq!(add.params.len(), 2);
+	assert_eq!(add.params[0].name, "a");
+	assert!(add.params[0].required);
+	assert_eq!(add.params[0].schema, json!({ "type": "integer", "minimum": 0 }));
+	assert_eq!(add.params[1].name, "b");
+	assert!(!add.params[1].required);
+	assert_eq!(add.result.as_ref().unwrap().schema, json!({ "type": "integer", "minimum": 0 }));
+	// The server accepts both params by name and by position regardless of `param_kind`.
+	assert_eq!(add.param_structure, ParamStructure::Either);
+	assert_eq!(serde_json::to_value(add).unwrap()["paramStructure"], "either");
+
+	let mul = &doc.methods[1];
+	assert_eq!(mul.name, "doc_mul");
+	assert_eq!(mul.param_structure, ParamStructure::Either);
+	assert_eq!(serde_json::to_value(mul).unwrap()["paramStructure"], "either");
+
+	let sub = &doc.methods[2];
+	assert_eq!(sub.name, "doc_subscribeNumbers");
+	assert_eq!(sub.result.as_ref().unwrap().schema["type"], "array");
+	assert_eq!(sub.param_structure, ParamStructure::Either);
+	let info = sub.subscription.as_ref().unwrap();
+	assert_eq!(info.notification, "doc_numbers");
+	assert_eq!(info.unsubscribe, "doc_unsubscribeNumbers");
+}
+
+#[tokio::test]
+async fn method_timeout_works() {
+	use std::time::Duration;
+
+	use jsonrpsee::core::{RpcResult, async_trait};
+	use jsonrpsee::proc_macros::rpc;
+	use jsonrpsee::types::error::METHOD_TIMEOUT_CODE;
+
+	#[rpc(server, namespace = "timeout")]
+	pub trait Slow {
+		#[method(name = "slow", aliases = ["timeout_sluggish"], timeout = "100ms")]
+		async fn slow(&self) -> RpcResult<u32>;
+
+		#[method(name = "blocking", blocking, timeout = "2m")]
+		fn blocking(&self) -> RpcResult<u32>;
+	}
+
+	struct SlowImpl;
+
+	#[async_trait]
+	impl SlowServer for SlowImpl {
+		async fn slow(&self) -> RpcResult<u32> {
+			tokio::time::sleep(Duration::from_secs(60)).await;
+			Ok(1)
+		}
+
+		fn blocking(&self) -> RpcResult<u32> {
+			Ok(2)
+		}
+	}
+
+	let module = SlowImpl.into_rpc();
+	assert_eq!(module.method_timeout("timeout_slow"), Some(Duration::from_millis(100)));
+	assert_eq!(module.method_timeout("timeout_sluggish"), Some(Duration::from_millis(100)));
+	assert_eq!(module.method_timeout("timeout_blocking"), Some(Duration::from_secs(120)));
+
+	let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("http://{}", server.local_addr().unwrap());
+	let _handle = server.start(module);
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+
+	let err = client.request::<u32, _>("timeout_sluggish", rpc_params![]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(e) if e.code() == METHOD_TIMEOUT_CODE));
+	assert_eq!(client.request::<u32, _>("timeout_blocking", rpc_params![]).await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn method_requires_works() {
+	use jsonrpsee::core::server::PendingSubscriptionSink;
+	use jsonrpsee::core::{RpcResult, SubscriptionResult, async_trait};
+	use jsonrpsee::proc_macros::rpc;
+	use jsonrpsee::server::{Authenticator, Extensions, Principal, ServerConfig};
+	use jsonrpsee::types::error::UNAUTHORIZED_CODE;
+
+	#[rpc(server, namespace = "admin")]
+	pub trait Admin {
+		#[method(name = "shutdown", aliases = ["admin_stop"], requires = "admin")]
+		fn shutdown(&self) -> RpcResult<bool>;
+
+		#[method(name = "stats", requires = ["admin", "ops"])]
+		async fn stats(&self) -> RpcResult<u32>;
+
+		#[method(name = "version")]
+		fn version(&self) -> RpcResult<u32>;
+
+		#[subscription(name = "subscribeEvents" => "event", unsubscribe = "unsubscribeEvents", item = u32, requires = "ops")]
+		async fn events(&self) -> SubscriptionResult;
+	}
+
+	struct AdminImpl;
+
+	#[async_trait]
+	impl AdminServer for AdminImpl {
+		fn shutdown(&self) -> RpcResult<bool> {
+			Ok(true)
+		}
+
+		async fn stats(&self) -> RpcResult<u32> {
+			Ok(7)
+		}
+
+		fn version(&self) -> RpcResult<u32> {
+			Ok(1)
+		}
+
+		async fn events(&self, pending: PendingSubscriptionSink) -> SubscriptionResult {
+			let _sink = pending.accept().await?;
+			Ok(())
+		}
+	}
+
+	/// Grants the roles listed in the `x-roles` header.
+	#[derive(Debug)]
+	struct HeaderAuthenticator;
+
+	impl Authenticator for HeaderAuthenticator {
+		fn authenticate(&self, headers: &HeaderMap, _: &Extensions) -> Option<Principal> {
+			let roles = headers.get("x-roles")?.to_str().ok()?;
+			Some(roles.split(',').fold(Principal::new("test"), |principal, role| principal.with_role(role)))
+		}
+	}
+
+	let module = AdminImpl.into_rpc();
+	assert_eq!(module.required_roles("admin_shutdown"), Some(&["admin"][..]));
+	assert_eq!(module.required_roles("admin_stop"), Some(&["admin"][..]));
+	assert_eq!(module.required_roles("admin_stats"), Some(&["admin", "ops"][..]));
+	assert_eq!(module.required_roles("admin_version"), None);
+	assert_eq!(module.required_roles("admin_subscribeEvents"), Some(&["ops"][..]));
+
+	let config = ServerConfig::builder().set_authenticator(HeaderAuthenticator).build();
+	let server = ServerBuilder::with_config(config).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let headers = |roles: &'static str| {
+		let mut headers = HeaderMap::new();
+		headers.insert("x-roles", HeaderValue::from_static(roles));
+		headers
+	};
+
+	let http_admin =
+		HttpClientBuilder::default().set_headers(headers("admin")).build(format!("http://{addr}")).unwrap();
+	let ws_anonymous = WsClientBuilder::default().build(format!("ws://{addr}")).await.unwrap();
+	let ws_ops =
+		WsClientBuilder::default().set_headers(headers("admin,ops")).build(format!("ws://{addr}")).await.unwrap();
+
+	assert!(http_admin.request::<bool, _>("admin_stop", rpc_params![]).await.unwrap());
+	let err = http_admin.request::<u32, _>("admin_stats", rpc_params![]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(e) if e.code() == UNAUTHORIZED_CODE));
+
+	assert_eq!(ws_anonymous.request::<u32, _>("admin_version", rpc_params![]).await.unwrap(), 1);
+	let err = ws_anonymous.request::<bool, _>("admin_shutdown", rpc_params![]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(e) if e.code() == UNAUTHORIZED_CODE));
+
+	assert_eq!(ws_ops.request::<u32, _>("admin_stats", rpc_params![]).await.unwrap(), 7);
+
+	let err = ws_anonymous
+		.subscribe::<u32, _>("admin_subscribeEvents", rpc_params![], "admin_unsubscribeEvents")
+		.await
+		.unwrap_err();
+	assert!(matches!(err, Error::Call(e) if e.code() == UNAUTHORIZED_CODE));
+	assert!(
+		ws_ops.subscribe::<u32, _>("admin_subscribeEvents", rpc_params![], "admin_unsubscribeEvents").await.is_ok()
+	);
+}
+
+#[tokio::test]
+async fn idempotent_methods_are_retried() {
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::time::Duration;
+
+	use jsonrpsee::core::RpcResult;
+	use jsonrpsee::core::middleware::RpcServiceBuilder;
+	use jsonrpsee::core::middleware::layer::RetryPolicy;
+	use jsonrpsee::http_client::HttpClient;
+	use jsonrpsee::proc_macros::rpc;
+	use jsonrpsee::types::ErrorObjectOwned;
+
+	const BUSY_CODE: i32 = -32099;
+
+	#[rpc(client, server)]
+	pub trait Flaky {
+		#[method(name = "get", idempotent)]
+		fn get(&self) -> RpcResult<usize>;
+
+		#[method(name = "put")]
+		fn put(&self) -> RpcResult<usize>;
+	}
+
+	/// Fails every call until it has been called `fail_first` times.
+	struct FlakyImpl {
+		calls: Arc<AtomicUsize>,
+		fail_first: usize,
+	}
+
+	impl FlakyImpl {
+		fn next(&self) -> RpcResult<usize> {
+			let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+			if n <= self.fail_first { Err(ErrorObjectOwned::owned(BUSY_CODE, "busy", None::<()>)) } else { Ok(n) }
+		}
+	}
+
+	impl FlakyServer for FlakyImpl {
+		fn get(&self) -> RpcResult<usize> {
+			self.next()
+		}
+
+		fn put(&self) -> RpcResult<usize> {
+			self.next()
+		}
+	}
+
+	init_logger();
+
+	assert_eq!(<HttpClient as FlakyClient>::IDEMPOTENT_METHODS, &["get"]);
+
+	let calls = Arc::new(AtomicUsize::new(0));
+	let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(FlakyImpl { calls: calls.clone(), fail_first: 2 }.into_rpc());
+
+	let policy = RetryPolicy::new()
+		.jitter(false)
+		.initial_delay(Duration::from_millis(10))
+		.retry_on_codes([BUSY_CODE])
+		.idempotent_methods(<HttpClient as FlakyClient>::IDEMPOTENT_METHODS.iter().copied());
+	let client = HttpClientBuilder::default()
+		.set_rpc_middleware(RpcServiceBuilder::new().rpc_retry(policy))
+		.build(format!("http://{addr}"))
+		.unwrap();
+
+	assert_eq!(client.get().await.unwrap(), 3);
+	assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+	calls.store(0, Ordering::SeqCst);
+	let err = client.put().await.unwrap_err();
+	assert!(matches!(err, Error::Call(e) if e.code() == BUSY_CODE));
+	assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn typed_batch_requests_work() {
+	use rpc_impl::RpcBatch;
+
+	init_logger();
+
+	let server_addr = server().await;
+	let client = HttpClientBuilder::default().build(format!("http://{server_addr}")).unwrap();
+
+	let mut batch = RpcBatch::new();
+	let foo = batch.async_method(10, "a".into());
+	let bar = batch.sync_method();
+	let params = batch.params(1, "x");
+	let err = batch.custom_error();
+	assert_eq!(batch.len(), 4);
+
+	let response = batch.execute(&client).await.unwrap();
+	assert_eq!(response.num_successful_calls(), 3);
+
+	assert_eq!(foo.response(&response).unwrap(), 42);
+	assert_eq!(bar.response(&response).unwrap(), 10);
+	assert_eq!(params.response(&response).unwrap(), "Called with: 1, x");
+	assert!(matches!(err.response(&response), Err(Error::Call(e)) if e.message() == "my_error"));
+
+	// The handles of another batch can't read the response.
+	let mut other = RpcBatch::new();
+	let other_foo = other.async_method(10, "a".into());
+	assert_eq!(other_foo.index(), foo.index());
+	assert!(matches!(other_foo.response(&response), Err(Error::Custom(_))));
+}
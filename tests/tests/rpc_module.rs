@@ -93,6 +93,28 @@ fn rpc_register_alias() {
 	assert!(module.method("hello_foobar").is_some());
 }
 
+#[test]
+fn rpc_alias_shares_roles_and_timeout() {
+	let mut module = RpcModule::new(());
+
+	module.register_method("hello_world", |_, _, _| RpcResult::Ok(())).unwrap();
+	module.register_alias("hello_foobar", "hello_world").unwrap();
+	module.register_alias("hello_baz", "hello_foobar").unwrap();
+
+	// Set after the aliases were registered.
+	module.set_required_roles("hello_world", ["admin"]).unwrap();
+	module.set_method_timeout("hello_foobar", Duration::from_secs(1)).unwrap();
+
+	for name in ["hello_world", "hello_foobar", "hello_baz"] {
+		assert_eq!(module.required_roles(name), Some(&["admin"][..]));
+		assert_eq!(module.method_timeout(name), Some(Duration::from_secs(1)));
+	}
+
+	// The alias is still protected if the method is removed.
+	module.remove_method("hello_world");
+	assert_eq!(module.required_roles("hello_foobar"), Some(&["admin"][..]));
+}
+
 #[tokio::test]
 async fn calling_method_without_server() {
 	// Call sync method with no params
@@ -611,6 +633,75 @@ async fn subscription_close_response_works() {
 	}
 }
 
+#[tokio::test]
+async fn topic_hub_works() {
+	init_logger();
+
+	let hub = TopicHub::new(16);
+	let mut module = RpcModule::new(());
+	module.register_topic("my_sub", "my_sub", "my_unsub", hub.clone()).unwrap();
+
+	let mut sub1 = module.subscribe_unbounded("my_sub", EmptyServerParams::new()).await.unwrap();
+	let mut sub2 = module.subscribe_unbounded("my_sub", EmptyServerParams::new()).await.unwrap();
+	assert_eq!(hub.subscriber_count(), 2);
+
+	for n in 0..3 {
+		assert_eq!(hub.send(&n).unwrap(), 2);
+	}
+
+	for exp in 0..3 {
+		let (item, _) = sub1.next::<usize>().await.unwrap().unwrap();
+		assert_eq!(item, exp);
+		let (item, _) = sub2.next::<usize>().await.unwrap().unwrap();
+		assert_eq!(item, exp);
+	}
+}
+
+#[tokio::test]
+async fn topic_hub_lag_policy_works() {
+	init_logger();
+
+	let close_notif = SubscriptionMessage::from(serde_json::value::to_raw_value(&"lagged").unwrap());
+	let policies = [
+		LagPolicy::DropOldest,
+		LagPolicy::DropSubscriber,
+		LagPolicy::Close(SubscriptionCloseResponse::Notif(close_notif)),
+	];
+
+	for policy in policies {
+		let hub = TopicHub::new(2).lag_policy(policy.clone());
+		let mut module = RpcModule::new(());
+		module.register_topic("my_sub", "my_sub", "my_unsub", hub.clone()).unwrap();
+
+		let mut sub = module.subscribe("my_sub", EmptyServerParams::new(), 1).await.unwrap();
+
+		// The subscription can't keep up because the hub only keeps the last two items.
+		for n in 0..10 {
+			hub.send(&n).unwrap();
+		}
+
+		match policy {
+			LagPolicy::DropOldest => {
+				for exp in 8..10 {
+					let (item, _) = sub.next::<usize>().await.unwrap().unwrap();
+					assert_eq!(item, exp);
+				}
+				assert_eq!(hub.subscriber_count(), 1);
+			}
+			LagPolicy::DropSubscriber => {
+				assert!(sub.next::<usize>().await.is_none());
+				assert_eq!(hub.subscriber_count(), 0);
+			}
+			LagPolicy::Close(_) => {
+				let (item, _) = sub.next::<String>().await.unwrap().unwrap();
+				assert_eq!(item, "lagged");
+				assert!(sub.next::<String>().await.is_none());
+				assert_eq!(hub.subscriber_count(), 0);
+			}
+		}
+	}
+}
+
 #[tokio::test]
 async fn method_response_notify_on_completion() {
 	use jsonrpsee::server::ResponsePayload;
@@ -145,6 +145,18 @@ pub const SERVER_IS_BUSY_CODE: i32 = -32009;
 pub const TOO_BIG_BATCH_REQUEST_CODE: i32 = -32010;
 /// Batch response limit was exceed.
 pub const TOO_BIG_BATCH_RESPONSE_CODE: i32 = -32011;
+/// Rate limit exceeded error code.
+pub const RATE_LIMITED_CODE: i32 = -32012;
+/// Method call timed out error code.
+pub const METHOD_TIMEOUT_CODE: i32 = -32013;
+/// Subscription can't be resumed error code.
+pub const SUBSCRIPTION_NOT_RESUMABLE_CODE: i32 = -32014;
+/// Batch budget exceeded error code.
+pub const BATCH_BUDGET_EXCEEDED_CODE: i32 = -32015;
+/// Unauthorized error code.
+pub const UNAUTHORIZED_CODE: i32 = -32016;
+/// Request cancelled error code, the same code as used by the Language Server Protocol.
+pub const REQUEST_CANCELLED_CODE: i32 = -32800;
 
 /// Parse error message
 pub const PARSE_ERROR_MSG: &str = "Parse error";
@@ -172,6 +184,18 @@ pub const TOO_MANY_SUBSCRIPTIONS_MSG: &str = "Too many subscriptions on the conn
 pub const TOO_BIG_BATCH_REQUEST_MSG: &str = "The batch request was too large";
 /// Batch request response limit was exceed.
 pub const TOO_BIG_BATCH_RESPONSE_MSG: &str = "The batch response was too large";
+/// Rate limit exceeded error message.
+pub const RATE_LIMITED_MSG: &str = "Rate limit exceeded, try again later";
+/// Method call timed out error message.
+pub const METHOD_TIMEOUT_MSG: &str = "Method call timed out";
+/// Subscription can't be resumed error message.
+pub const SUBSCRIPTION_NOT_RESUMABLE_MSG: &str = "Subscription can't be resumed";
+/// Batch budget exceeded error message.
+pub const BATCH_BUDGET_EXCEEDED_MSG: &str = "The budget of the batch request was exceeded";
+/// Unauthorized error message.
+pub const UNAUTHORIZED_MSG: &str = "Unauthorized";
+/// Request cancelled error message.
+pub const REQUEST_CANCELLED_MSG: &str = "Request cancelled";
 
 /// JSONRPC error code
 #[derive(Error, Debug, PartialEq, Eq, Copy, Clone)]
@@ -303,6 +327,40 @@ pub fn reject_too_big_batch_response(limit: usize) -> ErrorObjectOwned {
 	)
 }
 
+/// Helper to get a `JSON-RPC` error object when a rate limit has been exceeded.
+pub fn reject_rate_limited(retry_after: std::time::Duration) -> ErrorObjectOwned {
+	ErrorObjectOwned::owned(
+		RATE_LIMITED_CODE,
+		RATE_LIMITED_MSG,
+		Some(format!("Retry after {} ms", retry_after.as_millis())),
+	)
+}
+
+/// Helper to get a `JSON-RPC` error object when a method call exceeded its execution timeout.
+pub fn reject_method_timeout(timeout: std::time::Duration) -> ErrorObjectOwned {
+	ErrorObjectOwned::owned(
+		METHOD_TIMEOUT_CODE,
+		METHOD_TIMEOUT_MSG,
+		Some(format!("Timed out after {} ms", timeout.as_millis())),
+	)
+}
+
+/// Helper to get a `JSON-RPC` error object when a subscription couldn't be resumed.
+pub fn reject_subscription_not_resumable(reason: impl Into<String>) -> ErrorObjectOwned {
+	ErrorObjectOwned::owned(SUBSCRIPTION_NOT_RESUMABLE_CODE, SUBSCRIPTION_NOT_RESUMABLE_MSG, Some(reason.into()))
+}
+
+/// Helper to get a `JSON-RPC` error object when a call of a batch request wasn't executed
+/// because the budget of the batch was exhausted.
+pub fn reject_batch_budget_exceeded(reason: impl Into<String>) -> ErrorObjectOwned {
+	ErrorObjectOwned::owned(BATCH_BUDGET_EXCEEDED_CODE, BATCH_BUDGET_EXCEEDED_MSG, Some(reason.into()))
+}
+
+/// Helper to get a `JSON-RPC` error object when the caller isn't allowed to call a method.
+pub fn reject_unauthorized(reason: impl Into<String>) -> ErrorObjectOwned {
+	ErrorObjectOwned::owned(UNAUTHORIZED_CODE, UNAUTHORIZED_MSG, Some(reason.into()))
+}
+
 #[cfg(test)]
 mod tests {
 	use super::{ErrorCode, ErrorObject};
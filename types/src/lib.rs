@@ -47,4 +47,6 @@ pub use error::{ErrorCode, ErrorObject, ErrorObjectOwned};
 pub use http::Extensions;
 pub use params::{Id, InvalidRequestId, Params, ParamsSequence, SubscriptionId, TwoPointZero};
 pub use request::{InvalidRequest, Notification, Request};
-pub use response::{Response, ResponsePayload, SubscriptionPayload, SubscriptionResponse, Success as ResponseSuccess};
+pub use response::{
+	Response, ResponsePayload, Sequenced, SubscriptionPayload, SubscriptionResponse, Success as ResponseSuccess,
+};
@@ -133,6 +133,18 @@ pub struct SubscriptionPayload<'a, T> {
 	pub result: T,
 }
 
+/// Result of a notification on a resumable subscription.
+///
+/// The sequence number is assigned by the server and is used to resume the subscription
+/// from the last message that was received.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Sequenced<T> {
+	/// Sequence number of the message, starting at 1.
+	pub seq: u64,
+	/// Value of the message.
+	pub value: T,
+}
+
 /// Subscription response object, embedding a [`SubscriptionPayload`] in the `params` member along with `result` field.
 pub type SubscriptionResponse<'a, T> = Notification<'a, SubscriptionPayload<'a, T>>;
 /// Subscription response object, embedding a [`SubscriptionPayload`] in the `params` member along with `error` field.